@@ -4,232 +4,1380 @@
 //! Each file will be processed, even if there is an error for the other files.
 //!
 
+pub mod archives;
+pub mod audit;
+pub mod budget;
+pub mod compression;
+pub mod copy_truncate;
+pub mod dedup;
+pub mod file_copy;
+pub mod file_meta;
+pub mod fingerprint;
+pub mod hooks;
+pub mod line_count;
+pub mod manifest;
+pub mod metrics;
+pub mod open_files;
+pub mod plan;
+pub mod redact;
+pub mod report;
+pub mod retry;
+pub mod sha256;
+pub mod shred;
+pub mod signal;
+pub mod symlink;
+pub mod transfer;
+pub mod trim;
+pub mod undo;
+pub mod upload;
+
 use std::fs;
-use std::io;
-use std::path::Path;
-use std::time::SystemTime;
+use std::io::{self, ErrorKind};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use crate::cleaner::audit::AuditSession;
+use crate::cleaner::compression::Compressor;
+use crate::cleaner::file_copy::CopyOptions;
+use crate::cleaner::hooks::GroupHooks;
+use crate::cleaner::metrics::OperationMetric;
+use crate::cleaner::plan::{Action, FilePlan, GroupLimit, Plan};
+use crate::cleaner::report::{GroupReport, RunReport, TaskReport};
+use crate::cleaner::undo::{UndoJournal, UndoStep};
+use crate::config::{Compression, Config, ErrorPolicy, RotationPolicy, UploadConfig};
+use crate::lock::{self, RunLock};
+use crate::output::{self, Color, events, json::JsonValue, run_id, table};
+use crate::platform::{self, priority};
+use crate::resolver::{self, FileTask};
+use crate::state::{self, FileAction, StateStore};
+use crate::stats;
+
+/// Run all cleanup tasks for a given yalc config and the resolved file tasks.
+/// The results are returned as report, only the progress of the tasks is logged.
+pub fn run_cleanup(config: &Config, tasks: &[FileTask]) -> Result<RunReport, io::Error> {
+    let run_started: Instant = Instant::now();
 
-use crate::config::{CleanUpMode, Config};
+    //Overlapping runs would race on the same rotation sequence, dry runs do not change files
+    let _run_lock: Option<RunLock> = acquire_run_lock(config, config.dry_run)?;
 
-/// Run all cleanup tasks for a given yalc config
-pub fn run_cleanup(config: &Config) -> Result<(), io::Error> {
-    //Log the execution start for the cleanup
-    println!(
-        "Starting cleanup tasks for: {} files",
-        config.file_list.len()
-    );
-    println!("----------------");
+    //The audit log records the operations independent of the console output
+    let _audit_session: Option<AuditSession> = open_audit_log(config, config.dry_run)?;
+
+    //Hooks and compressors inherit the priorities of yalc
+    priority::apply_priority(&config.priority);
 
     //Task status counter
-    let mut tasks_executed: usize = 0;
-    let mut tasks_success: usize = 0;
-    let mut tasks_failure: usize = 0;
+    let mut report = RunReport {
+        run_id: run_id::current(),
+        started_at: state::unix_time_now(),
+        ..RunReport::default()
+    };
+
+    //Log the execution start for the cleanup, the id is shown once for the search in central logs
+    output::print_status(&format!(
+        "Starting cleanup tasks for: {} files{}",
+        tasks.len(),
+        report
+            .run_id
+            .as_ref()
+            .map(|run_id| format!(" [run {}]", run_id))
+            .unwrap_or_default()
+    ));
+
+    //Shared hooks of the groups are executed once for all files of a group
+    let mut group_hooks = GroupHooks::new(config);
+
+    //The state of previous runs is required for the frequency limits and is updated by every run
+    let mut state_store: StateStore = load_state_store(config);
+
+    //The steps of every changed file are recorded, so the run can be restored
+    let mut undo_journal: UndoJournal = load_undo_journal(config);
 
     //Check if the file list is empty
-    if config.file_list.is_empty() {
-        println!("File list is empty - nothing to do");
+    if tasks.is_empty() {
+        output::print_status("File list is empty - nothing to do");
     } else {
+        //The order of the files is decided before the first file is changed
+        let ordered_tasks: Vec<(&FileTask, Option<GroupLimit>)> = plan::order_tasks(config, tasks);
+
+        //Bytes of the files that were rotated or trimmed, limited by max_bytes_per_run
+        let mut processed_bytes: u64 = 0;
+
         //Run the cleanup task for each individual file
-        for (idx_task, file) in config.file_list.iter().enumerate() {
-            let task_nr = idx_task + 1;
-            println!("[{}] Running task for: {}", task_nr, file);
-
-            match run_file_cleanup(idx_task, &config) {
-                Ok(_) => {
-                    println!("[{}] Task was successfully executed", task_nr);
-                    tasks_success += 1;
+        for (idx_task, (task, group_limit)) in ordered_tasks.iter().enumerate() {
+            let task_nr: usize = idx_task + 1;
+
+            //A signal stops the run between two files, the current file is always finished
+            if let Some(signal_number) = platform::shutdown_signal() {
+                output::print_status(&format!(
+                    "Run interrupted by {}, {} tasks were not executed",
+                    platform::signal_name(signal_number),
+                    ordered_tasks.len() - idx_task
+                ));
+                report.interrupted_by = Some(signal_number);
+                break;
+            }
+
+            //The conditions are checked right before the file is changed, earlier files of
+            //the run can change its archives or the state
+            let mut file_plan: FilePlan =
+                plan::plan_file(task_nr, task, config, &state_store, group_limit.as_ref());
+            let state_path: PathBuf = file_plan.path.clone();
+
+            //The size is read before the file is changed by the task
+            let metadata: Option<fs::Metadata> = fs::metadata(&state_path).ok();
+            let file_size: Option<u64> = metadata.as_ref().map(|m| m.len());
+
+            //Files that are copied or compressed are deferred when they would exceed the byte
+            //limit, a file above the whole limit is only processed as the first file of a run
+            let mut changes_file: bool = file_plan.actions.iter().any(Action::changes_file);
+            if let Some(max_bytes) = config.max_bytes_per_run
+                && changes_file
+                && processed_bytes > 0
+                && processed_bytes.saturating_add(file_size.unwrap_or(0)) > max_bytes
+            {
+                output::print_status(&format!(
+                    "[{}] Deferring '{}' to the next run: {} of {} bytes of the run are used",
+                    task_nr,
+                    state_path.display(),
+                    processed_bytes,
+                    max_bytes
+                ));
+                report.tasks_deferred += 1;
+
+                //The archives are still cleaned up, the removal frees space without copying
+                file_plan.defer_file_changes();
+                changes_file = false;
+                if file_plan.actions.is_empty() {
+                    continue;
+                }
+            }
+
+            //The tasks are ordered by group, so every group starts exactly once
+            let group: Option<String> = file_plan.task.group.clone();
+            if let Some(name) = &group
+                && report.groups.last().is_none_or(|g| &g.name != name)
+            {
+                let group_size: usize = tasks
+                    .iter()
+                    .filter(|task| task.group.as_ref() == Some(name))
+                    .count();
+                output::print_status(&format!(
+                    "Processing group '{}' with {} files",
+                    name, group_size
+                ));
+                report.groups.push(GroupReport {
+                    name: name.clone(),
+                    tasks_success: 0,
+                    tasks_failure: 0,
+                });
+            }
+
+            output::print_status(&format!(
+                "[{}] Running task for: {}",
+                task_nr,
+                state_path.display()
+            ));
+            events::emit("task_started", task_fields(task_nr, &state_path));
+
+            //A file without writes for a long time may belong to a service that logs to a new path
+            let stale_since: Option<u64> = match (&metadata, config.stale_after_s) {
+                (Some(metadata), Some(stale_after_s)) => stale_since(metadata, stale_after_s),
+                _ => None,
+            };
+            if let Some(last_write) = stale_since {
+                output::warn(&format!(
+                    "[{}] Warning: '{}' was not written for {} days, the service may log to another path",
+                    task_nr,
+                    state_path.display(),
+                    state::unix_time_now().saturating_sub(last_write) / (24 * 60 * 60)
+                ));
+            }
+
+            if changes_file {
+                processed_bytes = processed_bytes.saturating_add(file_size.unwrap_or(0));
+            }
+
+            let started: Instant = Instant::now();
+            let trigger: Option<String> = file_plan.trigger();
+            let planned_action: FileAction = file_plan.result;
+            let warning: Option<String> = file_plan.warning.clone();
+            let dry_run: bool = file_plan.policy.dry_run;
+            let mut operations: Vec<OperationMetric> = Vec::new();
+
+            let (action, error): (FileAction, Option<String>) = match apply_file_plan(
+                file_plan,
+                config,
+                &mut group_hooks,
+                &mut state_store,
+                &mut undo_journal,
+                &mut operations,
+            ) {
+                Ok(action) => {
+                    output::print_colored(
+                        &format!("[{}] Task was successfully executed", task_nr),
+                        Color::Green,
+                    );
+                    report.tasks_success += 1;
+                    (action, None)
                 }
                 Err(e) => {
-                    eprintln!("[{}] Task error: {}", idx_task, e);
-                    tasks_failure += 1;
+                    output::error(&format!("[{}] Task error: {}", task_nr, e));
+                    report.tasks_failure += 1;
+                    (FileAction::Failed, Some(e.to_string()))
+                }
+            };
+
+            let mut finished_fields = task_fields(task_nr, &state_path);
+            finished_fields.push(("result".to_string(), JsonValue::string(action.name())));
+            finished_fields.push((
+                "error".to_string(),
+                JsonValue::optional_string(error.clone()),
+            ));
+            finished_fields.push(("stale".to_string(), JsonValue::Bool(stale_since.is_some())));
+            finished_fields.push((
+                "warning".to_string(),
+                JsonValue::optional_string(warning.clone()),
+            ));
+            finished_fields.push((
+                "operations".to_string(),
+                JsonValue::Array(operations.iter().map(OperationMetric::to_json).collect()),
+            ));
+            events::emit("task_finished", finished_fields);
+
+            let remaining_size: u64 = fs::metadata(&state_path).map_or(0, |m| m.len());
+            report.tasks.push(TaskReport {
+                path: state_path.clone(),
+                trigger,
+                action: planned_action,
+                size: file_size,
+                stale_since,
+                warning,
+                bytes_freed: file_size.unwrap_or(0).saturating_sub(remaining_size),
+                duration: started.elapsed(),
+                dry_run,
+                error,
+                operations,
+            });
+
+            state_store.record_run(&state_path, action, file_size);
+
+            if let Some(group_report) = report.groups.last_mut()
+                && group.as_ref() == Some(&group_report.name)
+            {
+                match action {
+                    FileAction::Failed => group_report.tasks_failure += 1,
+                    _ => group_report.tasks_success += 1,
                 }
             }
 
-            //Log separation for better readability
-            tasks_executed += 1;
-            println!("----------------");
+            report.tasks_executed += 1;
+
+            //Critical environments stop at the first failure
+            if action == FileAction::Failed && config.on_error == ErrorPolicy::Abort {
+                output::print_status(&format!(
+                    "Run aborted after the failure of task {}, {} tasks were not executed",
+                    task_nr,
+                    ordered_tasks.len() - task_nr
+                ));
+                report.aborted = true;
+                break;
+            }
         }
     }
 
-    //Calculate percentage rates
-    let success_rate: usize = tasks_success * 100 / tasks_executed;
-    let failure_rate: usize = tasks_failure * 100 / tasks_executed;
+    //The postrotate hooks run after all files of the groups are processed
+    report.hook_failures = group_hooks.run_postrotate_hooks();
 
-    //Print task stats
-    println!(
-        "Successful tasks: {}/{} [{}%]",
-        tasks_success, tasks_executed, success_rate
-    );
-    println!(
-        "Failure tasks:    {}/{} [{}%]",
-        tasks_failure, tasks_executed, failure_rate
-    );
+    //A dry run never changes the state of the files
+    if !config.dry_run
+        && state_store.has_changed()
+        && let Err(e) = state_store.save()
+    {
+        output::error(&format!(
+            "Failed to save state file '{}': {}",
+            state_store.path().display(),
+            e
+        ));
+    }
 
-    //Log that all tasks have finished
-    println!("All tasks done");
-    Ok(())
-}
+    if !config.dry_run
+        && undo_journal.has_changed()
+        && let Err(e) = undo_journal.save()
+    {
+        output::error(&format!(
+            "Failed to save undo journal '{}': {}",
+            undo_journal.path().display(),
+            e
+        ));
+    }
 
-/// Execute a single file cleanup task for a given config
-/// The task_idx is the 0-based index for the file in the config's file_list.
-fn run_file_cleanup(task_idx: usize, config: &Config) -> Result<(), io::Error> {
-    let task_nr = task_idx + 1;
+    //The budget is checked after the rotations, so the new archives are included
+    if let Some(budget_bytes) = config.total_budget_bytes
+        && !report.aborted
+        && report.interrupted_by.is_none()
+    {
+        report.budget_exceeded = budget::enforce_total_budget(
+            tasks,
+            budget_bytes,
+            config.shred,
+            config.allocated_size,
+            config.symlinks,
+            config.dry_run,
+        )?;
 
-    //1. Get file path from the config's file list
-    let file_path_str = &config.file_list[task_idx];
-    let file_path = Path::new(file_path_str);
+        //The manifests must not list the archives that were removed for the budget
+        if report
+            .budget_exceeded
+            .as_ref()
+            .is_some_and(|excess| excess.removed_count > 0)
+            && !config.dry_run
+        {
+            for (idx, task) in tasks.iter().enumerate() {
+                if task.overrides.manifest.unwrap_or(config.manifest)
+                    && task.overrides.dry_run != Some(true)
+                {
+                    let rotated_task: FileTask = symlink::rotated_task(task, config.symlinks);
+                    let rotation_base: PathBuf = archives::archive_base_path(&rotated_task)?;
+                    manifest::update_manifest(idx + 1, &rotation_base, &task.path)?;
+                }
+            }
+        }
+    }
 
-    //2. Check for file existence and type
-    if !file_path.exists() {
-        if config.missing_files_ok {
-            println!(
-                "[{}] File not found, missing file is configured as okay",
-                task_nr,
-            );
-            return Ok(());
-        } else {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                format!("File not found: {}", file_path.display()),
+    //A run of selected groups or patterns does not cover all files of the watched directories
+    if let Some(min_size_bytes) = config.unmanaged_min_size_bytes
+        && config.selected_groups.is_empty()
+        && config.selected_patterns.is_empty()
+    {
+        report.unmanaged_files = resolver::find_unmanaged_files(config, tasks, min_size_bytes);
+
+        for file in report.unmanaged_files.iter() {
+            output::warn(&format!(
+                "Warning: '{}' [{}] is matched by no entry of the config",
+                file.path.display(),
+                table::format_bytes(file.size)
             ));
         }
     }
 
-    //Check that the path is a file
-    if !file_path.is_file() {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            format!("Path is not a file: {}", file_path.display()),
-        ));
+    //The statistics still contain the previous runs, the current run is recorded afterwards
+    report.changes = stats::changes_since_previous(config, &report);
+
+    report.duration = run_started.elapsed();
+    Ok(report)
+}
+
+/// Get the unix time of the last write of a file that was not written within the stale time, None for an active file
+fn stale_since(metadata: &fs::Metadata, stale_after_s: u64) -> Option<u64> {
+    let last_write: u64 = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+
+    match state::unix_time_now().saturating_sub(last_write) >= stale_after_s {
+        true => Some(last_write),
+        false => None,
+    }
+}
+
+/// Get the json fields that identify the task of an event
+fn task_fields(task_nr: usize, path: &Path) -> Vec<(String, JsonValue)> {
+    vec![
+        ("task".to_string(), JsonValue::uint(task_nr as u64)),
+        (
+            "path".to_string(),
+            JsonValue::string(path.display().to_string()),
+        ),
+    ]
+}
+
+/// Emit the action_executed event of an action, in a dry run the action was only logged
+fn emit_action_event(file_plan: &FilePlan, action: &Action, dry_run: bool) {
+    if !events::is_enabled() {
+        return;
+    }
+
+    let mut fields = task_fields(file_plan.task_nr, &file_plan.path);
+    fields.push(("dry_run".to_string(), JsonValue::Bool(dry_run)));
+
+    if let JsonValue::Object(entries) = action.to_json() {
+        fields.extend(entries);
+    }
+
+    events::emit("action_executed", fields);
+}
+
+/// Restore the most recent run of the files, all recorded files are restored without any paths
+pub fn run_restore(
+    config: &Config,
+    file_paths: &[PathBuf],
+    dry_run: bool,
+) -> Result<RunReport, io::Error> {
+    let run_started: Instant = Instant::now();
+
+    //A restore must not race with a run on the same rotation sequence
+    let _run_lock: Option<RunLock> = acquire_run_lock(config, dry_run)?;
+    let _audit_session: Option<AuditSession> = open_audit_log(config, dry_run)?;
+
+    let mut undo_journal: UndoJournal =
+        UndoJournal::load(&undo::journal_path(&state_path(config)))?;
+    let copy_options = CopyOptions::from_config(config);
+
+    let selected_paths: Vec<PathBuf> = match file_paths.is_empty() {
+        true => undo_journal.file_paths(),
+        false => file_paths.to_vec(),
+    };
+
+    output::print_status(&format!(
+        "Restoring the last run of: {} files",
+        selected_paths.len()
+    ));
+    output::print_status("----------------");
+
+    let mut report = RunReport {
+        run_id: run_id::current(),
+        started_at: state::unix_time_now(),
+        ..RunReport::default()
+    };
+
+    for (idx, file_path) in selected_paths.iter().enumerate() {
+        let task_nr: usize = idx + 1;
+        output::print_status(&format!("[{}] Restoring: {}", task_nr, file_path.display()));
+
+        let result: Result<(), io::Error> = match undo_journal.get(file_path) {
+            Some(file_undo) => undo::restore_file(task_nr, file_undo, dry_run, &copy_options),
+            None => Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("No run of '{}' was recorded", file_path.display()),
+            )),
+        };
+
+        match result {
+            Ok(()) => {
+                output::print_status(&format!("[{}] File was successfully restored", task_nr));
+                report.tasks_success += 1;
+
+                //A restored run can not be restored again
+                if !dry_run {
+                    undo_journal.remove(file_path);
+                }
+            }
+            Err(e) => {
+                output::error(&format!("[{}] Restore error: {}", task_nr, e));
+                report.tasks_failure += 1;
+            }
+        }
+
+        report.tasks_executed += 1;
+        output::print_status("----------------");
     }
 
-    //3. Check if a cleanup is needed for the current file
-    let cleanup_needed: bool = check_cleanup_conditions(task_nr, &file_path, &config)?;
+    if undo_journal.has_changed() {
+        undo_journal.save()?;
+    }
+
+    report.duration = run_started.elapsed();
+    Ok(report)
+}
 
-    //4. If no cleanup conditions are met, we are done with this file.
-    if !cleanup_needed {
-        println!("[{}] No cleanup conditions met", task_nr,);
+/// Build the plan of the cleanup run without executing it
+pub fn plan_cleanup(config: &Config, tasks: &[FileTask]) -> Plan {
+    plan::build_plan(config, tasks, &load_state_store(config))
+}
+
+/// Acquire the lock that prevents overlapping runs, None for runs that do not change files
+fn acquire_run_lock(config: &Config, dry_run: bool) -> Result<Option<RunLock>, io::Error> {
+    if dry_run {
+        return Ok(None);
+    }
+
+    let lock_path: PathBuf = config
+        .lock_file
+        .as_ref()
+        .map_or_else(lock::default_lock_path, PathBuf::from);
+
+    lock::acquire(&lock_path, config.lock_wait_s.map(Duration::from_secs)).map(Some)
+}
+
+/// Open the configured audit log, None without an audit log or for runs that do not change files
+fn open_audit_log(config: &Config, dry_run: bool) -> Result<Option<AuditSession>, io::Error> {
+    match (&config.audit_log, dry_run) {
+        (Some(audit_log), false) => audit::open(Path::new(audit_log)).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Get the path of the state file of the config
+pub fn state_path(config: &Config) -> PathBuf {
+    config
+        .state_file
+        .as_ref()
+        .map_or_else(state::default_state_path, PathBuf::from)
+}
+
+/// Load the undo journal next to the state file, an invalid journal results in an empty journal
+fn load_undo_journal(config: &Config) -> UndoJournal {
+    let journal_path: PathBuf = undo::journal_path(&state_path(config));
+
+    UndoJournal::load(&journal_path).unwrap_or_else(|e| {
+        output::warn(&format!("{}, starting with an empty undo journal", e));
+        UndoJournal::new(&journal_path)
+    })
+}
+
+/// Load the state of previous runs, an invalid state file results in an empty state
+fn load_state_store(config: &Config) -> StateStore {
+    let state_path: PathBuf = state_path(config);
+
+    StateStore::load(&state_path).unwrap_or_else(|e| {
+        output::warn(&format!("{}, starting with an empty state", e));
+        StateStore::new(&state_path)
+    })
+}
+
+/// Execute the actions of a single file plan for a given config.
+/// In a dry run the actions are only logged. Returns the action that was performed for the file.
+fn apply_file_plan(
+    file_plan: FilePlan,
+    config: &Config,
+    group_hooks: &mut GroupHooks,
+    state_store: &mut StateStore,
+    undo_journal: &mut UndoJournal,
+    metrics: &mut Vec<OperationMetric>,
+) -> Result<FileAction, io::Error> {
+    let task_nr: usize = file_plan.task_nr;
+
+    for note in file_plan.notes.iter() {
+        output::print_status(&format!("[{}] {}", task_nr, note));
+
+        if let Some(condition) = note.strip_prefix("Condition met: ") {
+            let mut fields = task_fields(task_nr, &file_plan.path);
+            fields.push(("condition".to_string(), JsonValue::string(condition)));
+            events::emit("condition_met", fields);
+        }
+    }
+
+    //A dry run never changes any file, single files can be in a dry run of their own
+    if file_plan.policy.dry_run {
+        for action in file_plan.actions.iter() {
+            output::print_status(&format!("[{}] DRY RUN: Would {}", task_nr, action));
+            emit_action_event(&file_plan, action, true);
+        }
+
+        return match file_plan.error {
+            Some(e) => Err(e),
+            None => Ok(FileAction::Skipped),
+        };
+    }
+
+    let mut undo_steps: Vec<UndoStep> = Vec::new();
+    let applied: Result<(), io::Error> = file_plan.actions.iter().try_for_each(|action| {
+        apply_action(
+            &file_plan,
+            action,
+            config,
+            group_hooks,
+            state_store,
+            &mut undo_steps,
+            metrics,
+        )?;
+        emit_action_event(&file_plan, action, false);
+        Ok(())
+    });
+
+    //The steps of a failed task are recorded too, so a partial run can be restored
+    undo_journal.record_run(&file_plan.path, &file_plan.task.path, undo_steps);
+    applied?;
+
+    match file_plan.error {
+        Some(e) => Err(e),
+        None => Ok(file_plan.result),
+    }
+}
+
+/// Remove the archive directory of the task when the removed archive was its last file.
+/// Only the directory of the archives itself is removed, its parents are always kept.
+fn remove_empty_archive_dir(
+    task_nr: usize,
+    task: &FileTask,
+    config: &Config,
+    archive_path: &Path,
+) -> Result<(), io::Error> {
+    if !config.remove_empty_dirs {
         return Ok(());
     }
 
-    //5. Handle dry run: log action and exit without changes
-    if config.dry_run {
-        println!(
-            "[{}] DRY RUN: Would cleanup file '{}'",
+    let rotation_base: PathBuf = archives::archive_base_path(task)?;
+    if let Some(archive_dir) = rotation_base.parent() {
+        archives::remove_empty_dirs(
             task_nr,
-            file_path.display()
-        );
-        return Ok(());
+            archive_path,
+            archive_dir.parent().unwrap_or(Path::new("")),
+        )?;
     }
 
-    //6. Perform the actual file operations
-    perform_file_cleanup(task_nr, &file_path, &config)?;
     Ok(())
 }
 
-/// Check if the cleanup should be performed for a given file and config
-fn check_cleanup_conditions(
+/// Execute a single action of a file plan
+fn apply_action(
+    file_plan: &FilePlan,
+    action: &Action,
+    config: &Config,
+    group_hooks: &mut GroupHooks,
+    state_store: &mut StateStore,
+    undo_steps: &mut Vec<UndoStep>,
+    metrics: &mut Vec<OperationMetric>,
+) -> Result<(), io::Error> {
+    let task_nr: usize = file_plan.task_nr;
+    let task: &FileTask = &file_plan.task;
+    let policy: &RotationPolicy = &file_plan.policy;
+    let file_path: &Path = &task.path;
+
+    //Prepare the group of the file before the first file of the group is changed
+    if action.changes_file()
+        && let Some(group) = &task.group
+    {
+        group_hooks.before_rotation(task_nr, group)?;
+    }
+
+    match action {
+        Action::RemoveExpiredArchive(archive_path) => {
+            output::print_status(&format!(
+                "[{}] Removing expired archive '{}'",
+                task_nr,
+                archive_path.display()
+            ));
+            shred::remove_file(archive_path, policy.shred)?;
+            undo_steps.push(UndoStep::Delete {
+                path: archive_path.clone(),
+            });
+            remove_empty_archive_dir(task_nr, task, config, archive_path)?;
+        }
+        Action::RemoveStrayArchive(archive_path) => {
+            output::print_status(&format!(
+                "[{}] Removing stray archive '{}' beyond keep_rotate",
+                task_nr,
+                archive_path.display()
+            ));
+            shred::remove_file(archive_path, policy.shred)?;
+            undo_steps.push(UndoStep::Delete {
+                path: archive_path.clone(),
+            });
+            remove_empty_archive_dir(task_nr, task, config, archive_path)?;
+        }
+        Action::RemoveGroupArchive(archive_path) => {
+            output::print_status(&format!(
+                "[{}] Removing archive '{}' for the size limit of the group",
+                task_nr,
+                archive_path.display()
+            ));
+            shred::remove_file(archive_path, policy.shred)?;
+            undo_steps.push(UndoStep::Delete {
+                path: archive_path.clone(),
+            });
+            remove_empty_archive_dir(task_nr, task, config, archive_path)?;
+        }
+        Action::RemoveFile => {
+            output::print_status(&format!("[{}] Removing file: keep_rotate is zero", task_nr));
+            retry::with_retry(task_nr, &config.retry, "Remove", || {
+                shred::remove_file(file_path, policy.shred)
+            })?;
+            undo_steps.push(UndoStep::Delete {
+                path: file_path.to_path_buf(),
+            });
+
+            //The directory of the log file is only removed with its last file
+            if config.remove_empty_dirs
+                && let Some(log_dir) = file_path.parent()
+            {
+                archives::remove_empty_dirs(
+                    task_nr,
+                    file_path,
+                    log_dir.parent().unwrap_or(Path::new("")),
+                )?;
+            }
+        }
+        Action::Rotate => rotate_file(
+            task_nr,
+            task,
+            config,
+            policy,
+            file_plan.checked_size,
+            undo_steps,
+            metrics,
+        )?,
+        Action::Trim => {
+            let copy_options = CopyOptions::from_config(config);
+            retry::with_retry(task_nr, &config.retry, "Trim", || {
+                audit::track("trim", &[("path", &file_path)], || {
+                    trim::trim_file(task_nr, file_path, &policy.trim, &copy_options)
+                })
+            })?;
+            undo_steps.push(UndoStep::Modify {
+                path: file_path.to_path_buf(),
+            });
+        }
+        Action::EnforceArchiveSize(max_size_bytes) => {
+            let removed_archives: Vec<PathBuf> = archives::enforce_size_limit(
+                task_nr,
+                &archives::archive_base_path(task)?,
+                *max_size_bytes,
+                policy.shred,
+                false,
+            )?;
+
+            for archive_path in removed_archives {
+                undo_steps.push(UndoStep::Delete {
+                    path: archive_path.clone(),
+                });
+                remove_empty_archive_dir(task_nr, task, config, &archive_path)?;
+            }
+        }
+        Action::UploadArchive(_) => {
+            if let Some(upload) = &config.upload {
+                upload_finished_archive(task_nr, task, config, policy, upload, undo_steps)?;
+            }
+        }
+        Action::UpdateManifest => {
+            manifest::update_manifest(
+                task_nr,
+                &archives::archive_base_path(task)?,
+                &file_plan.path,
+            )?;
+        }
+        Action::SignalProcess(target) => audit::track(
+            "signal",
+            &[("signal", &target.signal), ("pidfile", &target.pidfile)],
+            || signal::signal_process(task_nr, target),
+        )?,
+    }
+
+    if action.changes_file() {
+        state_store.record_rotation(&file_plan.path, state::unix_time_now());
+
+        //The fingerprint is taken from the file that remains after the rotation
+        if config.skip_unchanged {
+            let fingerprint: Option<String> = match file_path.exists() {
+                true => Some(fingerprint::file_fingerprint(file_path)?),
+                false => None,
+            };
+            state_store.record_fingerprint(&file_plan.path, fingerprint);
+        }
+
+        if let Some(group) = &task.group {
+            group_hooks.after_rotation(group);
+        }
+    }
+
+    Ok(())
+}
+
+/// Rotate a file into its archives.
+/// The checked size is compared with the current size to detect files that are still growing.
+fn rotate_file(
     task_nr: usize,
-    file_path: &Path,
+    task: &FileTask,
     config: &Config,
-) -> Result<bool, io::Error> {
-    //Evaluate if a cleanup is required based on the mode
-    let metadata = fs::metadata(file_path)?;
-    let mut cleanup_needed = false;
-
-    //Check file size condition
-    if matches!(config.mode, CleanUpMode::FileSize | CleanUpMode::All) {
-        let size_limit_bytes: u64 = config.retention.file_size_mib * 1024 * 1024;
-
-        if metadata.len() > size_limit_bytes {
-            println!(
-                "[{}] Condition met: File size ({} MiB) exceeds limit ({} MiB)",
+    policy: &RotationPolicy,
+    checked_size: Option<u64>,
+    undo_steps: &mut Vec<UndoStep>,
+    metrics: &mut Vec<OperationMetric>,
+) -> Result<(), io::Error> {
+    let file_path: &Path = &task.path;
+
+    //Copies that are not done by the kernel use the configured buffer size
+    let copy_options = CopyOptions::from_config(config);
+
+    //Rotated files are stored next to the log file or in the olddir
+    let rotation_base: PathBuf = rotation_base_path(task_nr, task, policy.copy_truncate)?;
+
+    //Rotated files are stored with the extension of the compression algorithm
+    let compressor: Option<Box<dyn Compressor>> =
+        compression::create_compressor(config.compression, config.compression_level);
+    let extension: &str = compressor.as_ref().map_or("", |c| c.extension());
+
+    //Gaps in the indices are closed, so keep_rotate counts the archives that exist
+    if config.compact_rotations {
+        for (from, to) in archives::compact_rotations(&rotation_base, policy.rotate_start)? {
+            output::print_status(&format!(
+                "[{}] Compacting: {} -> {}",
                 task_nr,
-                metadata.len() / 1024 / 1024,
-                config.retention.file_size_mib
-            );
-            cleanup_needed = true;
+                from.display(),
+                to.display()
+            ));
+
+            metrics::measure(metrics, "rename", &from, || {
+                retry::with_retry(task_nr, &config.retry, "Rename", || {
+                    transfer::move_file(task_nr, &from, &to, &copy_options)
+                })
+            })?;
+            undo_steps.push(UndoStep::Rename { from, to });
         }
     }
 
-    //Check last write time condition, only if not already triggered
-    if !cleanup_needed && matches!(config.mode, CleanUpMode::LastWrite | CleanUpMode::All) {
-        let modified_time = metadata.modified()?;
+    //With delayed compression the previous rotation is compressed before it is shifted.
+    //A single kept rotation is never compressed, it would be overwritten right away.
+    let delay_compress: bool = config.delay_compress && compressor.is_some();
 
-        if let Ok(duration_since_write) = SystemTime::now().duration_since(modified_time) {
-            let time_limit_duration =
-                std::time::Duration::from_secs(config.retention.last_write_h * 3600);
+    //A file that was still growing left its rotation uncompressed in the previous run
+    if let Some(compressor) = &compressor
+        && (delay_compress || config.defer_growing)
+        && policy.keep_rotate > 1
+    {
+        compress_rotated_file(
+            task_nr,
+            compressor.as_ref(),
+            &rotation_base,
+            policy,
+            &copy_options,
+            undo_steps,
+            metrics,
+        )?;
+    }
 
-            //Check if the age of the file exceeds the limit
-            if duration_since_write > time_limit_duration {
-                //Calculate hours for readable output
-                let duration_since_write_h: u64 = duration_since_write.as_secs() / 3600;
-                let time_limit_duration_h: u64 = time_limit_duration.as_secs() / 3600;
+    //The oldest rotation would be overwritten by the shift, so it is shredded beforehand
+    if policy.shred {
+        shred_oldest_rotation(
+            task_nr,
+            &rotation_base,
+            policy.rotate_start + policy.keep_rotate - 1,
+            undo_steps,
+        )?;
+    }
 
-                println!(
-                    "[{}] Condition met: Last write age ({} h) exceeds limit ({} h)",
-                    task_nr, duration_since_write_h, time_limit_duration_h
-                );
-                cleanup_needed = true;
+    //Rotate files by shifting them: file.1 -> file.2, file.0 -> file.1, etc.
+    //This loop starts from the second to last possible rotation and moves
+    //everything up one index, overwriting the oldest file in the process.
+    //The indices are offset by the start, so the most recent file can be '.1'.
+    //Archives of a previous compression format are shifted with the same indices.
+    let start: u64 = policy.rotate_start;
+    for i in (1..policy.keep_rotate).rev() {
+        let source_paths: Vec<(PathBuf, PathBuf)> = compression::ROTATED_SUFFIXES
+            .iter()
+            .map(|suffix| {
+                (
+                    rotated_path(&rotation_base, start + i - 1, suffix),
+                    rotated_path(&rotation_base, start + i, suffix),
+                )
+            })
+            .filter(|(source_path, _)| source_path.exists())
+            .collect();
+
+        //The oldest rotation expires, also when it has another format than the shifted file
+        if !source_paths.is_empty() {
+            for suffix in compression::ROTATED_SUFFIXES.iter() {
+                let expired_path: PathBuf = rotated_path(&rotation_base, start + i, suffix);
+
+                if expired_path.is_file() {
+                    output::debug(&format!(
+                        "[{}] Removing expired rotation '{}'",
+                        task_nr,
+                        expired_path.display()
+                    ));
+                    shred::remove_file(&expired_path, policy.shred)?;
+                    undo_steps.push(UndoStep::Delete { path: expired_path });
+                }
             }
         }
+
+        for (source_path, dest_path) in source_paths {
+            output::debug(&format!(
+                "[{}] Rotating: {} -> {}",
+                task_nr,
+                source_path.display(),
+                dest_path.display()
+            ));
+
+            metrics::measure(metrics, "rename", &source_path, || {
+                retry::with_retry(task_nr, &config.retry, "Rename", || {
+                    transfer::move_file(task_nr, &source_path, &dest_path, &copy_options)
+                })
+            })?;
+            undo_steps.push(UndoStep::Rename {
+                from: source_path,
+                to: dest_path,
+            });
+        }
+    }
+
+    //Writes that are still in flight can end up in the fresh archive
+    let grown_bytes: u64 = match checked_size {
+        Some(size) if config.defer_growing => fs::metadata(file_path)?.len().saturating_sub(size),
+        _ => 0,
+    };
+
+    //Handle the original file, moving it to the position of the start like '.0'
+    let new_rotated_path = rotated_path(&rotation_base, start, "");
+    if policy.copy_truncate {
+        output::print_status(&format!(
+            "[{}] Copying original to '{}' and truncating",
+            task_nr,
+            new_rotated_path.display()
+        ));
+        //The metadata is read before the truncate changes the modification time
+        let file_metadata = fs::metadata(file_path)?;
+        //The file is truncated through the open file, so the inode is preserved
+        //Only the copy is retried, the truncate is the last step and runs once
+        metrics::measure(metrics, "copy", file_path, || {
+            audit::track(
+                "copy_truncate",
+                &[("from", &file_path), ("to", &new_rotated_path)],
+                || {
+                    copy_truncate::copy_and_truncate(
+                        task_nr,
+                        file_path,
+                        &new_rotated_path,
+                        config.copy_truncate_lock,
+                        &copy_options,
+                        &policy.redact,
+                        &config.retry,
+                    )
+                },
+            )
+        })?;
+        undo_steps.push(UndoStep::CopyTruncate {
+            from: file_path.to_path_buf(),
+            to: new_rotated_path.clone(),
+        });
+        file_meta::replicate_metadata(task_nr, &file_metadata, &new_rotated_path)?;
+    } else {
+        output::print_status(&format!(
+            "[{}] Renaming original to '{}'",
+            task_nr,
+            new_rotated_path.display()
+        ));
+        let file_metadata = fs::metadata(file_path)?;
+        metrics::measure(metrics, "rename", file_path, || {
+            retry::with_retry(task_nr, &config.retry, "Rename", || {
+                audit::track(
+                    "rename",
+                    &[("from", &file_path), ("to", &new_rotated_path)],
+                    || fs::rename(file_path, &new_rotated_path),
+                )
+            })
+        })?;
+        undo_steps.push(UndoStep::Rename {
+            from: file_path.to_path_buf(),
+            to: new_rotated_path.clone(),
+        });
+
+        //Processes that do not recreate their log file continue with the new file
+        if config.create {
+            output::print_status(&format!("[{}] Creating new empty log file", task_nr));
+            audit::track("create", &[("path", &file_path)], || {
+                file_meta::create_empty_file(
+                    task_nr,
+                    file_path,
+                    &file_metadata,
+                    &config.create_permissions,
+                )
+            })?;
+            undo_steps.push(UndoStep::Create {
+                path: file_path.to_path_buf(),
+            });
+        }
+    }
+
+    //Secrets of a renamed file are masked in the rotated file, copies are redacted while copying
+    if !policy.redact.is_empty() && !policy.copy_truncate {
+        audit::track("redact", &[("path", &new_rotated_path)], || {
+            redact::redact_file(task_nr, &new_rotated_path, &policy.redact, &copy_options)
+        })?;
+        undo_steps.push(UndoStep::Modify {
+            path: new_rotated_path.clone(),
+        });
+    }
+
+    //Replace the rotated file with the compressed archive
+    if compressor.is_some() && !delay_compress && grown_bytes > 0 {
+        output::print_status(&format!(
+            "[{}] File grew by {} bytes since the check, compression is deferred to the next run",
+            task_nr, grown_bytes
+        ));
+    } else if let Some(compressor) = &compressor
+        && !delay_compress
+    {
+        compress_rotated_file(
+            task_nr,
+            compressor.as_ref(),
+            &rotation_base,
+            policy,
+            &copy_options,
+            undo_steps,
+            metrics,
+        )?;
     }
 
-    Ok(cleanup_needed)
+    //Archives can have stricter permissions than the live log file
+    let archive_path: PathBuf = match rotated_path(&rotation_base, start, "") {
+        uncompressed if uncompressed.exists() => uncompressed,
+        _ => rotated_path(&rotation_base, start, extension),
+    };
+
+    file_meta::apply_permissions(task_nr, &archive_path, &config.archive_permissions)?;
+
+    //An archive with the content of the previous rotation is only stored once
+    if config.dedup_archives && policy.keep_rotate > 1 {
+        let extension: &str = match archive_path == rotated_path(&rotation_base, start, "") {
+            true => "",
+            false => extension,
+        };
+        dedup::link_duplicate(
+            task_nr,
+            &archive_path,
+            &rotated_path(&rotation_base, start + 1, extension),
+        )?;
+    }
+
+    Ok(())
 }
 
-/// Execute the cleanup or rotate operation for a file
-fn perform_file_cleanup(
+/// Upload the archive that was finished by the rotation, the local archive is removed when configured
+fn upload_finished_archive(
     task_nr: usize,
-    file_path: &Path,
+    task: &FileTask,
     config: &Config,
+    policy: &RotationPolicy,
+    upload: &UploadConfig,
+    undo_steps: &mut Vec<UndoStep>,
 ) -> Result<(), io::Error> {
-    if config.keep_rotate == 0 {
-        //If keep_rotate is 0, we just delete the file.
-        println!("[{}] Removing file: keep_rotate is zero", task_nr);
-        fs::remove_file(file_path)?;
-    } else {
-        //Rotate files by shifting them: file.1 -> file.2, file.0 -> file.1, etc.
-        //This loop starts from the second to last possible rotation and moves
-        //everything up one index, overwriting the oldest file in the process.
-        for i in (1..config.keep_rotate).rev() {
-            let source_path_str = format!("{}.{}", file_path.display(), i - 1);
-            let source_path = Path::new(&source_path_str);
-
-            if source_path.exists() {
-                let dest_path_str = format!("{}.{}", file_path.display(), i);
-                println!(
-                    "[{}] Rotating: {} -> {}",
-                    task_nr,
-                    source_path.display(),
-                    dest_path_str
-                );
-                fs::rename(source_path, &dest_path_str)?;
+    let Some(archive_path) = finished_archive_path(task, config, policy)? else {
+        output::debug(&format!("[{}] No finished archive to upload", task_nr));
+        return Ok(());
+    };
+
+    output::print_status(&format!(
+        "[{}] Uploading '{}' to '{}'",
+        task_nr,
+        archive_path.display(),
+        upload.backend.location()
+    ));
+
+    let log_name: String = task
+        .path
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+    let app: String = match &task.group {
+        Some(group) => group.clone(),
+        None => task
+            .path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().to_string()),
+    };
+    let hostname: String = platform::hostname();
+    let tokens = upload::NameTokens {
+        name: &log_name,
+        app: &app,
+        hostname: &hostname,
+    };
+    let remote: String = upload::upload_archive(task_nr, &archive_path, &tokens, upload)?;
+    output::print_status(&format!("[{}] Uploaded archive as '{}'", task_nr, remote));
+
+    //The fresh archive of a growing file can still receive writes, so it is kept until the next run
+    let deferred: bool = config.defer_growing
+        && !config.delay_compress
+        && config.compression != Compression::None
+        && archive_path
+            == rotated_path(&archives::archive_base_path(task)?, policy.rotate_start, "");
+
+    if upload.delete_local && deferred {
+        output::print_status(&format!(
+            "[{}] Keeping the uploaded archive '{}', the file was still growing",
+            task_nr,
+            archive_path.display()
+        ));
+    } else if upload.delete_local {
+        output::print_status(&format!(
+            "[{}] Removing the uploaded archive '{}'",
+            task_nr,
+            archive_path.display()
+        ));
+        shred::remove_file(&archive_path, policy.shred)?;
+        undo_steps.push(UndoStep::Delete { path: archive_path });
+    }
+
+    Ok(())
+}
+
+/// Get the path of the archive that was finished by the rotation, None when it does not exist.
+/// With delayed compression the previous rotation is the archive that was finished.
+/// The compression of a growing file can be deferred, then the uncompressed rotation is used.
+fn finished_archive_path(
+    task: &FileTask,
+    config: &Config,
+    policy: &RotationPolicy,
+) -> Result<Option<PathBuf>, io::Error> {
+    let rotation_base: PathBuf = archives::archive_base_path(task)?;
+    let compressor: Option<Box<dyn Compressor>> =
+        compression::create_compressor(config.compression, config.compression_level);
+
+    let start: u64 = policy.rotate_start;
+
+    let archive_path: PathBuf = match &compressor {
+        Some(compressor) if config.delay_compress && policy.keep_rotate > 1 => {
+            rotated_path(&rotation_base, start + 1, compressor.extension())
+        }
+        Some(compressor) if !config.delay_compress => {
+            match rotated_path(&rotation_base, start, compressor.extension()) {
+                compressed if !compressed.exists() && config.defer_growing => {
+                    rotated_path(&rotation_base, start, "")
+                }
+                compressed => compressed,
             }
         }
+        _ => rotated_path(&rotation_base, start, ""),
+    };
+
+    Ok(Some(archive_path).filter(|path| path.is_file()))
+}
+
+/// Get the base path of the rotated files like '/var/log/archive/app.log'.
+/// The olddir of the task is created when it does not exist yet.
+fn rotation_base_path(
+    task_nr: usize,
+    task: &FileTask,
+    copy_truncate: bool,
+) -> Result<PathBuf, io::Error> {
+    let rotation_base: PathBuf = archives::archive_base_path(task)?;
+
+    let Some(olddir) = &task.olddir else {
+        return Ok(rotation_base);
+    };
+
+    if !olddir.is_dir() {
+        output::print_status(&format!(
+            "[{}] Creating olddir: {}",
+            task_nr,
+            olddir.display()
+        ));
+        fs::create_dir_all(olddir)?;
+    }
+
+    //The live file can only be renamed within the same filesystem
+    if !copy_truncate && fs::metadata(&task.path)?.dev() != fs::metadata(olddir)?.dev() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Olddir '{}' is not on the same filesystem, use copy_truncate instead",
+                olddir.display()
+            ),
+        ));
+    }
+
+    Ok(rotation_base)
+}
 
-        //Handle the original file, moving it to the '.0' position
-        let new_rotated_path_str = format!("{}.0", file_path.display());
-        if config.copy_truncate {
-            println!(
-                "[{}] Copying original to '{}' and truncating",
-                task_nr, new_rotated_path_str
-            );
-            fs::copy(file_path, &new_rotated_path_str)?;
+/// Shred the rotation with the highest kept index, which is replaced by the next shift.
+/// The uncompressed and the compressed files of all formats of the index are shredded.
+fn shred_oldest_rotation(
+    task_nr: usize,
+    rotation_base: &Path,
+    oldest_idx: u64,
+    undo_steps: &mut Vec<UndoStep>,
+) -> Result<(), io::Error> {
+    for suffix in compression::ROTATED_SUFFIXES.iter() {
+        let oldest_path: PathBuf = rotated_path(rotation_base, oldest_idx, suffix);
 
-            //Re-open the file with truncate option to clear its content while preserving the inode
-            let _file = fs::OpenOptions::new()
-                .write(true)
-                .truncate(true)
-                .open(file_path)?;
-        } else {
-            println!(
-                "[{}] Renaming original to '{}'",
-                task_nr, new_rotated_path_str
-            );
-            fs::rename(file_path, &new_rotated_path_str)?;
+        if oldest_path.is_file() {
+            output::print_status(&format!(
+                "[{}] Shredding oldest rotation '{}'",
+                task_nr,
+                oldest_path.display()
+            ));
+            shred::remove_file(&oldest_path, true)?;
+            undo_steps.push(UndoStep::Delete { path: oldest_path });
         }
     }
 
     Ok(())
 }
+
+/// Compress the most recent rotated file at the rotate start like '.0' and remove the uncompressed file.
+/// The uncompressed file is shredded when enabled. Nothing is done when it does not exist.
+fn compress_rotated_file(
+    task_nr: usize,
+    compressor: &dyn Compressor,
+    rotation_base: &Path,
+    policy: &RotationPolicy,
+    copy_options: &CopyOptions,
+    undo_steps: &mut Vec<UndoStep>,
+    metrics: &mut Vec<OperationMetric>,
+) -> Result<(), io::Error> {
+    let idx: u64 = policy.rotate_start;
+    let rotated_file_path = rotated_path(rotation_base, idx, "");
+
+    if !rotated_file_path.exists() {
+        return Ok(());
+    }
+
+    let archive_path = rotated_path(rotation_base, idx, compressor.extension());
+    output::print_status(&format!(
+        "[{}] Compressing '{}' with {}",
+        task_nr,
+        rotated_file_path.display(),
+        compressor.name()
+    ));
+
+    metrics::measure(metrics, "compress", &rotated_file_path, || {
+        audit::track(
+            "compress",
+            &[("from", &rotated_file_path), ("to", &archive_path)],
+            || compressor.compress(&rotated_file_path, &archive_path, copy_options),
+        )
+    })?;
+
+    //The archive replaces the rotated file, so it gets the same metadata
+    let rotated_metadata = fs::metadata(&rotated_file_path)?;
+    file_meta::replicate_metadata(task_nr, &rotated_metadata, &archive_path)?;
+    shred::remove_file(&rotated_file_path, policy.shred)?;
+    undo_steps.push(UndoStep::Compress {
+        from: rotated_file_path,
+        to: archive_path,
+    });
+    Ok(())
+}
+
+/// Get the path of a rotated file like 'app.log.2.gz'
+fn rotated_path(file_path: &Path, idx: u64, extension: &str) -> PathBuf {
+    PathBuf::from(format!("{}.{}{}", file_path.display(), idx, extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CleanUpMode, FileOverrides};
+    use crate::constants::{EXIT_ABORTED, EXIT_TASK_FAILURE};
+    use std::env;
+
+    fn create_task(path: PathBuf) -> FileTask {
+        FileTask {
+            path,
+            olddir: None,
+            group: None,
+            signal: None,
+            priority: 0,
+            overrides: FileOverrides::default(),
+        }
+    }
+
+    #[test]
+    fn test_abort_after_failed_task() {
+        let dir = env::temp_dir().join(format!("yalc_test_abort_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("app.log"), "line\n").unwrap();
+
+        //The first task fails, because the file does not exist
+        let tasks: Vec<FileTask> = vec![
+            create_task(dir.join("missing.log")),
+            create_task(dir.join("app.log")),
+        ];
+
+        let mut config: Config = Config::test_config();
+        config.mode = CleanUpMode::FileSize;
+        config.retention.file_size_mib = 0;
+        config.state_file = Some(dir.join("state.toml").display().to_string());
+        config.lock_file = Some(dir.join("yalc.lock").display().to_string());
+        config.on_error = ErrorPolicy::Abort;
+
+        let report: RunReport = run_cleanup(&config, &tasks).unwrap();
+        assert!(report.aborted);
+        assert_eq!(report.tasks_executed, 1);
+        assert_eq!(report.exit_code(), EXIT_ABORTED);
+        assert!(!dir.join("app.log.0").exists());
+
+        //With the default policy the remaining tasks are still executed
+        config.on_error = ErrorPolicy::Continue;
+        let report: RunReport = run_cleanup(&config, &tasks).unwrap();
+        assert!(!report.aborted);
+        assert_eq!(report.tasks_executed, 2);
+        assert_eq!(report.exit_code(), EXIT_TASK_FAILURE);
+        assert!(dir.join("app.log.0").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_defer_after_byte_limit() {
+        let dir = env::temp_dir().join(format!("yalc_test_defer_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.log"), "x".repeat(100)).unwrap();
+        fs::write(dir.join("b.log"), "x".repeat(100)).unwrap();
+        fs::write(dir.join("b.log.99"), "stray").unwrap();
+
+        let tasks: Vec<FileTask> = vec![
+            create_task(dir.join("a.log")),
+            create_task(dir.join("b.log")),
+        ];
+
+        let mut config: Config = Config::test_config();
+        config.mode = CleanUpMode::FileSize;
+        config.retention.file_size_mib = 0;
+        config.state_file = Some(dir.join("state.toml").display().to_string());
+        config.lock_file = Some(dir.join("yalc.lock").display().to_string());
+        config.max_bytes_per_run = Some(150);
+        config.remove_stray_archives = true;
+
+        //The second file would exceed the limit, only its stray archive is removed
+        let report: RunReport = run_cleanup(&config, &tasks).unwrap();
+        assert_eq!(report.tasks_deferred, 1);
+        assert!(dir.join("a.log.0").exists());
+        assert!(!dir.join("b.log.0").exists());
+        assert!(!dir.join("b.log.99").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotate_previous_format() {
+        let dir = env::temp_dir().join(format!("yalc_test_rotate_format_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        //Archives of a previous gzip config and an even older zstd config
+        let log_path: PathBuf = dir.join("app.log");
+        for (name, content) in [
+            ("app.log", "new"),
+            ("app.log.0.gz", "zero"),
+            ("app.log.1.gz", "one"),
+            ("app.log.2.zst", "two"),
+        ] {
+            fs::write(dir.join(name), content).unwrap();
+        }
+
+        let config: Config = Config::test_config();
+        let task: FileTask = create_task(log_path.clone());
+        let policy: RotationPolicy = task.overrides.resolve(&config);
+        let mut undo_steps: Vec<UndoStep> = Vec::new();
+        let mut metrics: Vec<OperationMetric> = Vec::new();
+
+        rotate_file(
+            1,
+            &task,
+            &config,
+            &policy,
+            None,
+            &mut undo_steps,
+            &mut metrics,
+        )
+        .unwrap();
+
+        //The gzip archives are shifted and the oldest archive expired
+        assert_eq!(fs::read_to_string(dir.join("app.log.0")).unwrap(), "new");
+        assert_eq!(
+            fs::read_to_string(dir.join("app.log.1.gz")).unwrap(),
+            "zero"
+        );
+        assert_eq!(fs::read_to_string(dir.join("app.log.2.gz")).unwrap(), "one");
+        assert!(!dir.join("app.log.0.gz").exists());
+        assert!(!dir.join("app.log.2.zst").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
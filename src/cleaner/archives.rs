@@ -0,0 +1,493 @@
+//! Module for the rotated archives of a log file
+//!
+//! Archives are the siblings of the rotation base that start with an index
+//! or a date, like 'app.log.2', 'app.log.3.gz' or 'app.log-20240101'. They
+//! are expired independent of the rotation count, so archives of logs that
+//! are rarely rotated do not stay on the disk forever.
+//!
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::cleaner::shred;
+use crate::output;
+use crate::resolver::FileTask;
+
+/// Represents a rotated file of a log file
+#[derive(Debug)]
+pub struct Archive {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// Get the base path of the rotated files of a task like '/var/log/archive/app.log'
+pub fn archive_base_path(task: &FileTask) -> Result<PathBuf, io::Error> {
+    let Some(olddir) = &task.olddir else {
+        return Ok(task.path.clone());
+    };
+
+    match task.path.file_name() {
+        Some(file_name) => Ok(olddir.join(file_name)),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Path has no file name: {}", task.path.display()),
+        )),
+    }
+}
+
+/// Find all archives of a rotation base, sorted from the oldest to the newest archive
+pub fn find_archives(rotation_base: &Path) -> Result<Vec<Archive>, io::Error> {
+    let (Some(dir), Some(base_name)) = (
+        rotation_base.parent(),
+        rotation_base.file_name().and_then(|n| n.to_str()),
+    ) else {
+        return Ok(Vec::new());
+    };
+
+    //The directory of the archives does not exist before the first rotation
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut archives: Vec<Archive> = Vec::new();
+
+    for dir_entry in fs::read_dir(dir)? {
+        let path: PathBuf = dir_entry?.path();
+
+        let is_archive: bool = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|name| is_archive_name(base_name, name));
+
+        if !is_archive || !path.is_file() {
+            continue;
+        }
+
+        let metadata = fs::metadata(&path)?;
+        archives.push(Archive {
+            path,
+            size: metadata.len(),
+            modified: metadata.modified()?,
+        });
+    }
+
+    archives.sort_by_key(|archive| archive.modified);
+    Ok(archives)
+}
+
+/// Returns true when the file name is an archive name of the base name
+fn is_archive_name(base_name: &str, name: &str) -> bool {
+    let Some(suffix) = name.strip_prefix(base_name) else {
+        return false;
+    };
+
+    let mut suffix_chars = suffix.chars();
+    matches!(suffix_chars.next(), Some('.' | '-'))
+        && suffix_chars.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+/// Get the rotation index and the remaining extension of an archive name,
+/// like (3, ".gz") for 'app.log.3.gz'. None for names without an index like dated archives.
+fn rotation_index(base_name: &str, name: &str) -> Option<(u64, String)> {
+    let suffix: &str = name.strip_prefix(base_name)?.strip_prefix('.')?;
+    let digits_len: usize = suffix
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(suffix.len());
+    let (digits, extension) = suffix.split_at(digits_len);
+
+    if digits.is_empty() || !(extension.is_empty() || extension.starts_with('.')) {
+        return None;
+    }
+
+    Some((digits.parse().ok()?, extension.to_string()))
+}
+
+/// Find the renames that renumber the indexed archives from the start into a dense sequence,
+/// so gaps after manual deletions like '.0, .2, .5' become '.0, .1, .2'. Returns the pairs
+/// of the old and the new path in the order the archives can be renamed.
+pub fn compact_rotations(
+    rotation_base: &Path,
+    start: u64,
+) -> Result<Vec<(PathBuf, PathBuf)>, io::Error> {
+    let (Some(dir), Some(base_name)) = (
+        rotation_base.parent(),
+        rotation_base.file_name().and_then(|n| n.to_str()),
+    ) else {
+        return Ok(Vec::new());
+    };
+
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    //The uncompressed and the compressed file of an index are moved together
+    let mut indexed: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+
+    for dir_entry in fs::read_dir(dir)? {
+        let path: PathBuf = dir_entry?.path();
+
+        if let Some((idx, extension)) = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|name| rotation_index(base_name, name))
+            && idx >= start
+            && path.is_file()
+        {
+            indexed.entry(idx).or_default().push(extension);
+        }
+    }
+
+    //The new index is never above the old index, so the renames in this order can not overwrite an archive
+    let mut renamed: Vec<(PathBuf, PathBuf)> = Vec::new();
+
+    for (new_idx, (idx, extensions)) in (start..).zip(indexed) {
+        if idx == new_idx {
+            continue;
+        }
+
+        for extension in extensions {
+            let from = PathBuf::from(format!("{}.{}{}", rotation_base.display(), idx, extension));
+            let to = PathBuf::from(format!(
+                "{}.{}{}",
+                rotation_base.display(),
+                new_idx,
+                extension
+            ));
+            renamed.push((from, to));
+        }
+    }
+
+    Ok(renamed)
+}
+
+/// Find the indexed archives outside of the window of the kept rotations, sorted from the oldest.
+/// These are left by earlier configs with a higher keep_rotate or another start index.
+pub fn find_stray_archives(
+    rotation_base: &Path,
+    start: u64,
+    keep_rotate: u64,
+) -> Result<Vec<Archive>, io::Error> {
+    let Some(base_name) = rotation_base.file_name().and_then(|n| n.to_str()) else {
+        return Ok(Vec::new());
+    };
+    let window = start..start.saturating_add(keep_rotate);
+
+    Ok(find_archives(rotation_base)?
+        .into_iter()
+        .filter(|archive| {
+            archive
+                .path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|name| rotation_index(base_name, name))
+                .is_some_and(|(idx, _)| !window.contains(&idx))
+        })
+        .collect())
+}
+
+/// Find the archives with a last write older than the maximum age, sorted from the oldest
+pub fn find_expired_archives(
+    rotation_base: &Path,
+    max_age_s: u64,
+) -> Result<Vec<Archive>, io::Error> {
+    let max_age = Duration::from_secs(max_age_s);
+
+    //Archives with a modification time in the future are never expired
+    Ok(find_archives(rotation_base)?
+        .into_iter()
+        .filter(|archive| {
+            SystemTime::now()
+                .duration_since(archive.modified)
+                .is_ok_and(|age| age > max_age)
+        })
+        .collect())
+}
+
+/// Remove the empty directories of a removed file from its parent directory upwards.
+/// The stop directory and its parents are always kept, a directory that still contains
+/// files ends the removal. Returns the number of removed directories.
+pub fn remove_empty_dirs(
+    task_nr: usize,
+    removed_file: &Path,
+    stop_dir: &Path,
+) -> Result<usize, io::Error> {
+    let mut removed_count: usize = 0;
+    let mut dir: Option<&Path> = removed_file.parent();
+
+    while let Some(current) = dir {
+        if current == stop_dir || !current.starts_with(stop_dir) {
+            break;
+        }
+
+        match fs::remove_dir(current) {
+            Ok(()) => {
+                output::print_status(&format!(
+                    "[{}] Removed empty directory '{}'",
+                    task_nr,
+                    current.display()
+                ));
+                removed_count += 1;
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::DirectoryNotEmpty | io::ErrorKind::NotFound
+                ) =>
+            {
+                break;
+            }
+            Err(e) => return Err(e),
+        }
+
+        dir = current.parent();
+    }
+
+    Ok(removed_count)
+}
+
+/// Remove the oldest archives until the total size of all archives is within the maximum size.
+/// Returns the paths of the removed or, in a dry run, of the archives that would be removed.
+pub fn enforce_size_limit(
+    task_nr: usize,
+    rotation_base: &Path,
+    max_size_bytes: u64,
+    shred: bool,
+    dry_run: bool,
+) -> Result<Vec<PathBuf>, io::Error> {
+    let archives: Vec<Archive> = find_archives(rotation_base)?;
+    let mut total_size: u64 = archives.iter().map(|archive| archive.size).sum();
+    let mut removed: Vec<PathBuf> = Vec::new();
+
+    for archive in archives.iter() {
+        if total_size <= max_size_bytes {
+            break;
+        }
+
+        if dry_run {
+            output::print_status(&format!(
+                "[{}] DRY RUN: Would remove archive '{}' to stay within the size limit",
+                task_nr,
+                archive.path.display()
+            ));
+        } else {
+            output::print_status(&format!(
+                "[{}] Removing archive '{}' to stay within the size limit",
+                task_nr,
+                archive.path.display()
+            ));
+            shred::remove_file(&archive.path, shred)?;
+        }
+
+        total_size -= archive.size;
+        removed.push(archive.path.clone());
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs::{File, FileTimes};
+
+    #[test]
+    fn test_is_archive_name() {
+        assert!(is_archive_name("app.log", "app.log.0"));
+        assert!(is_archive_name("app.log", "app.log.3.gz"));
+        assert!(is_archive_name("app.log", "app.log-20240101"));
+        assert!(!is_archive_name("app.log", "app.log"));
+        assert!(!is_archive_name("app.log", "app.log.old"));
+        assert!(!is_archive_name("app.log", "other.log.1"));
+    }
+
+    #[test]
+    fn test_rotation_index() {
+        assert_eq!(
+            rotation_index("app.log", "app.log.0"),
+            Some((0, String::new()))
+        );
+        assert_eq!(
+            rotation_index("app.log", "app.log.12.gz"),
+            Some((12, ".gz".to_string()))
+        );
+        assert_eq!(rotation_index("app.log", "app.log-20240101"), None);
+        assert_eq!(rotation_index("app.log", "app.log.2024-01-01"), None);
+        assert_eq!(rotation_index("app.log", "app.log.old"), None);
+    }
+
+    #[test]
+    fn test_compact_rotations() {
+        let dir = env::temp_dir().join(format!("yalc_test_compact_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let base = dir.join("app.log");
+        for name in ["app.log.0", "app.log.2", "app.log.2.gz", "app.log.5.gz"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+
+        let renamed = compact_rotations(&base, 0).unwrap();
+        assert_eq!(renamed.len(), 3);
+        for (from, to) in renamed {
+            fs::rename(from, to).unwrap();
+        }
+        assert_eq!(
+            fs::read_to_string(dir.join("app.log.0")).unwrap(),
+            "app.log.0"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("app.log.1")).unwrap(),
+            "app.log.2"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("app.log.1.gz")).unwrap(),
+            "app.log.2.gz"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("app.log.2.gz")).unwrap(),
+            "app.log.5.gz"
+        );
+        assert!(!dir.join("app.log.5.gz").exists());
+
+        //A dense sequence is not changed
+        assert!(compact_rotations(&base, 0).unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_stray_archives() {
+        let dir = env::temp_dir().join(format!("yalc_test_stray_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let base = dir.join("app.log");
+        for name in [
+            "app.log.0",
+            "app.log.1",
+            "app.log.3.gz",
+            "app.log.7",
+            "app.log-20240101",
+        ] {
+            fs::write(dir.join(name), "line\n").unwrap();
+        }
+
+        let stray = |start, keep_rotate| {
+            let mut names: Vec<String> = find_stray_archives(&base, start, keep_rotate)
+                .unwrap()
+                .into_iter()
+                .map(|archive| {
+                    archive
+                        .path
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(stray(0, 3), vec!["app.log.3.gz", "app.log.7"]);
+        assert_eq!(stray(1, 3), vec!["app.log.0", "app.log.7"]);
+        assert!(stray(0, 10).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_expired_archives() {
+        let dir = env::temp_dir().join(format!("yalc_test_archives_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let base = dir.join("app.log");
+        for name in ["app.log", "app.log.0", "app.log.1.gz"] {
+            fs::write(dir.join(name), "line\n").unwrap();
+        }
+
+        //Only the oldest archive is older than one day
+        let old_time = SystemTime::now() - Duration::from_secs(2 * 86400);
+        File::options()
+            .write(true)
+            .open(dir.join("app.log.1.gz"))
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(old_time))
+            .unwrap();
+
+        assert_eq!(find_archives(&base).unwrap().len(), 2);
+        let expired = find_expired_archives(&base, 86400).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].path, dir.join("app.log.1.gz"));
+        assert!(find_expired_archives(&base, 3 * 86400).unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_enforce_size_limit() {
+        let dir = env::temp_dir().join(format!("yalc_test_archive_size_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        //Each archive has 100 bytes, the oldest archive has the highest index
+        let base = dir.join("app.log");
+        for idx in 0..3u64 {
+            let path = dir.join(format!("app.log.{}", idx));
+            fs::write(&path, "x".repeat(100)).unwrap();
+
+            let modified = SystemTime::now() - Duration::from_secs(idx * 3600);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_times(FileTimes::new().set_modified(modified))
+                .unwrap();
+        }
+
+        assert!(
+            enforce_size_limit(1, &base, 300, false, false)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            enforce_size_limit(1, &base, 150, false, false).unwrap(),
+            vec![dir.join("app.log.2"), dir.join("app.log.1")]
+        );
+        assert!(dir.join("app.log.0").exists());
+        assert!(!dir.join("app.log.1").exists());
+        assert!(!dir.join("app.log.2").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_remove_empty_dirs() {
+        let dir = env::temp_dir().join(format!("yalc_test_empty_dirs_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("archive/app")).unwrap();
+        fs::write(dir.join("archive/other.log.1"), "other").unwrap();
+
+        //The emptied directory is removed, the directory with other files is kept
+        let removed_file = dir.join("archive/app/app.log.1");
+        assert_eq!(remove_empty_dirs(1, &removed_file, &dir).unwrap(), 1);
+        assert!(!dir.join("archive/app").exists());
+        assert!(dir.join("archive").is_dir());
+
+        //The stop directory is never removed
+        fs::remove_file(dir.join("archive/other.log.1")).unwrap();
+        let removed_file = dir.join("archive/other.log.1");
+        assert_eq!(
+            remove_empty_dirs(1, &removed_file, &dir.join("archive")).unwrap(),
+            0
+        );
+        assert!(dir.join("archive").is_dir());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+//! Module for the persistent audit log of the executed operations
+//!
+//! The console output of unattended runs is often discarded. With an audit
+//! log every operation that changes a file, like a rename, copy, truncate,
+//! delete or hook, is appended as a single line to the configured file.
+//! Each line has the time, the process id, the id of the run, the operation,
+//! its paths and the result, so the changes of a run can be traced after an
+//! incident:
+//!
+//! 2024-03-01T12:30:00Z pid=812 run=3f9a0c2e81d4b7a6 op=rename from="/var/log/app.log" to="/var/log/app.log.0" result=ok
+//!
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::process;
+use std::sync::Mutex;
+
+use crate::config::config_timestamp;
+use crate::output::run_id;
+use crate::state;
+
+/// Mode of a newly created audit log, only the owner and the group can read it
+const AUDIT_LOG_MODE: u32 = 0o640;
+
+/// Audit log of the current run, no operations are recorded when it is not opened
+static AUDIT_LOG: Mutex<Option<File>> = Mutex::new(None);
+
+/// Represents the open audit log of a run, the recording stops when it is dropped
+#[derive(Debug)]
+pub struct AuditSession {
+    _private: (),
+}
+
+//Implement the Drop trait
+impl Drop for AuditSession {
+    fn drop(&mut self) {
+        *AUDIT_LOG.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// Open the audit log for appending, it is created when it does not exist
+pub fn open(path: &Path) -> Result<AuditSession, io::Error> {
+    let file: File = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .mode(AUDIT_LOG_MODE)
+        .open(path)
+        .map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Failed to open audit log '{}': {}", path.display(), e),
+            )
+        })?;
+
+    *AUDIT_LOG.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+    Ok(AuditSession { _private: () })
+}
+
+/// Execute the operation and record it with its result in the audit log
+pub fn track<T>(
+    operation: &str,
+    fields: &[(&str, &dyn AsRef<Path>)],
+    execute: impl FnOnce() -> Result<T, io::Error>,
+) -> Result<T, io::Error> {
+    let result: Result<T, io::Error> = execute();
+    record(operation, fields, result.as_ref().err());
+    result
+}
+
+/// Append a line for the operation, the error is None for a successful operation
+pub fn record(operation: &str, fields: &[(&str, &dyn AsRef<Path>)], error: Option<&io::Error>) {
+    let mut audit_log = AUDIT_LOG.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(file) = audit_log.as_mut() else {
+        return;
+    };
+
+    let mut line: String = format!(
+        "{} pid={} {}op={}",
+        format_utc_time(state::unix_time_now()),
+        process::id(),
+        run_id::field(),
+        operation
+    );
+
+    for (key, value) in fields.iter() {
+        line.push_str(&format!(
+            " {}={}",
+            key,
+            quote_value(&value.as_ref().display().to_string())
+        ));
+    }
+
+    match error {
+        None => line.push_str(" result=ok"),
+        Some(e) => line.push_str(&format!(
+            " result=error error={}",
+            quote_value(&e.to_string())
+        )),
+    }
+    line.push('\n');
+
+    //The line is written with a single append, so concurrent writers never mix their lines
+    if let Err(e) = file.write_all(line.as_bytes()) {
+        eprintln!("Failed to write the audit log: {}", e);
+    }
+}
+
+/// Quote a value, quotes and backslashes inside of the value are escaped
+fn quote_value(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// Format a unix time as UTC timestamp like '2024-03-01T12:30:00Z'
+pub fn format_utc_time(unix_s: u64) -> String {
+    let (year, month, day) = config_timestamp::civil_from_days((unix_s / 86400) as i64);
+    let time_of_day: u64 = unix_s % 86400;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day % 3600 / 60,
+        time_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_audit_log() {
+        assert_eq!(format_utc_time(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_utc_time(1709296200), "2024-03-01T12:30:00Z");
+        assert_eq!(format_utc_time(951782400), "2000-02-29T00:00:00Z");
+
+        let dir = env::temp_dir().join(format!("yalc_test_audit_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let audit_path = dir.join("audit.log");
+        let source = dir.join("app.log");
+        fs::write(&source, "line\n").unwrap();
+
+        let session: AuditSession = open(&audit_path).unwrap();
+        let renamed = track(
+            "rename",
+            &[("from", &source), ("to", &dir.join("app.log.0"))],
+            || fs::rename(&source, dir.join("app.log.0")),
+        );
+        let failed = track("delete", &[("path", &source)], || fs::remove_file(&source));
+        drop(session);
+
+        //Operations after the end of the session are not recorded
+        record("delete", &[("path", &source)], None);
+
+        assert!(renamed.is_ok());
+        assert!(failed.is_err());
+
+        //Tests of other modules can record their operations at the same time
+        let content: String = fs::read_to_string(&audit_path).unwrap();
+        let dir_name: String = dir.display().to_string();
+        let lines: Vec<&str> = content.lines().filter(|l| l.contains(&dir_name)).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(&format!(
+            "op=rename from=\"{}\" to=\"{}\" result=ok",
+            source.display(),
+            dir.join("app.log.0").display()
+        )));
+        assert!(lines[1].contains("op=delete"));
+        assert!(lines[1].contains("result=error error=\""));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+//! Module for the disk usage budget of all managed log files
+//!
+//! The budget includes the log files and all their rotated archives. When
+//! the budget is exceeded, the oldest archives of all files are removed
+//! first, independent of the file they belong to. The live log files and
+//! the archives of files in a dry run are never removed to meet the budget.
+//! With allocated sizes the disk blocks of sparse files are counted instead
+//! of their length, so the reclaimed space matches the actual disk usage.
+//!
+use std::fs;
+use std::io;
+
+use crate::cleaner::archives::{self, Archive};
+use crate::cleaner::shred;
+use crate::cleaner::symlink;
+use crate::config::SymlinkPolicy;
+use crate::output;
+use crate::platform;
+use crate::resolver::FileTask;
+
+/// Represents an exceeded budget and the archives that were removed to meet it
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetExcess {
+    pub budget_bytes: u64,
+
+    /// Total disk usage of the files and archives before any archive was removed
+    pub used_bytes: u64,
+
+    /// Number of the removed archives, in a dry run the archives that would be removed
+    pub removed_count: usize,
+
+    pub removed_bytes: u64,
+}
+
+/// Remove the oldest archives of all tasks until the total disk usage is within the budget.
+/// The removed archives are reported, in a dry run they are only logged. Archives are
+/// shredded when it is enabled for their file, the global shred value is the default.
+/// The archives of followed symbolic links are searched next to the link target.
+/// Returns None when the budget was not exceeded.
+pub fn enforce_total_budget(
+    tasks: &[FileTask],
+    budget_bytes: u64,
+    shred: bool,
+    allocated_size: bool,
+    symlinks: SymlinkPolicy,
+    dry_run: bool,
+) -> Result<Option<BudgetExcess>, io::Error> {
+    let mut all_archives: Vec<(Archive, bool)> = Vec::new();
+    let mut total_size: u64 = 0;
+
+    for task in tasks.iter() {
+        let task: &FileTask = &symlink::rotated_task(task, symlinks);
+
+        //Missing log files do not use any disk space
+        if let Ok(metadata) = fs::metadata(&task.path) {
+            total_size += platform::file_size(&metadata, allocated_size);
+        }
+
+        //The archives of files in a dry run are counted, but never removed
+        if task.overrides.dry_run == Some(true) {
+            total_size += find_task_archives(task, allocated_size)?
+                .iter()
+                .map(|archive| archive.size)
+                .sum::<u64>();
+            continue;
+        }
+
+        let task_shred: bool = task.overrides.shred.unwrap_or(shred);
+        all_archives.extend(
+            find_task_archives(task, allocated_size)?
+                .into_iter()
+                .map(|archive| (archive, task_shred)),
+        );
+    }
+
+    //Files with the same olddir and file name share the same archives
+    //Shared archives are shredded when one of their files requires it
+    all_archives.sort_by(|(a, a_shred), (b, b_shred)| {
+        a.modified
+            .cmp(&b.modified)
+            .then(a.path.cmp(&b.path))
+            .then(b_shred.cmp(a_shred))
+    });
+    all_archives.dedup_by(|(a, _), (b, _)| a.path == b.path);
+    total_size += all_archives.iter().map(|(a, _)| a.size).sum::<u64>();
+
+    if total_size <= budget_bytes {
+        return Ok(None);
+    }
+
+    let used_bytes: u64 = total_size;
+
+    output::print_status(&format!(
+        "Total budget of {} bytes exceeded by {} bytes",
+        budget_bytes,
+        total_size - budget_bytes
+    ));
+
+    let mut removed_count: usize = 0;
+    let mut removed_size: u64 = 0;
+
+    for (archive, archive_shred) in all_archives.iter() {
+        if total_size <= budget_bytes {
+            break;
+        }
+
+        if dry_run {
+            output::print_status(&format!(
+                "  DRY RUN: Would remove '{}' ({} bytes)",
+                archive.path.display(),
+                archive.size
+            ));
+        } else {
+            output::print_status(&format!(
+                "  Removing '{}' ({} bytes)",
+                archive.path.display(),
+                archive.size
+            ));
+            shred::remove_file(&archive.path, *archive_shred)?;
+        }
+
+        total_size -= archive.size;
+        removed_count += 1;
+        removed_size += archive.size;
+    }
+
+    output::print_status(&format!(
+        "{} {} archives with {} bytes for the total budget",
+        if dry_run { "Would remove" } else { "Removed" },
+        removed_count,
+        removed_size
+    ));
+
+    if total_size > budget_bytes {
+        output::warn(&format!(
+            "Total budget is still exceeded by {} bytes, the log files themselves exceed the budget",
+            total_size - budget_bytes
+        ));
+    }
+
+    Ok(Some(BudgetExcess {
+        budget_bytes,
+        used_bytes,
+        removed_count,
+        removed_bytes: removed_size,
+    }))
+}
+
+/// Find the archives of a task, the archive sizes are the allocated sizes when requested
+fn find_task_archives(task: &FileTask, allocated_size: bool) -> Result<Vec<Archive>, io::Error> {
+    let mut archives: Vec<Archive> = archives::find_archives(&archives::archive_base_path(task)?)?;
+
+    if allocated_size {
+        for archive in archives.iter_mut() {
+            archive.size = platform::file_size(&fs::metadata(&archive.path)?, true);
+        }
+    }
+
+    Ok(archives)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FileOverrides;
+    use std::env;
+    use std::fs::{File, FileTimes};
+    use std::os::unix::fs::symlink;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    fn create_task(path: PathBuf) -> FileTask {
+        FileTask {
+            path,
+            olddir: None,
+            group: None,
+            signal: None,
+            priority: 0,
+            overrides: FileOverrides::default(),
+        }
+    }
+
+    #[test]
+    fn test_enforce_total_budget() {
+        let dir = env::temp_dir().join(format!("yalc_test_budget_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        //The archive of b.log is the oldest archive of both files
+        for (name, age_h) in [("a.log", 0), ("a.log.0", 1), ("b.log", 0), ("b.log.0", 2)] {
+            let path = dir.join(name);
+            fs::write(&path, "x".repeat(100)).unwrap();
+
+            let modified = SystemTime::now() - Duration::from_secs(age_h * 3600);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_times(FileTimes::new().set_modified(modified))
+                .unwrap();
+        }
+
+        let tasks = vec![
+            create_task(dir.join("a.log")),
+            create_task(dir.join("b.log")),
+        ];
+
+        enforce_total_budget(&tasks, 300, false, false, SymlinkPolicy::Follow, true).unwrap();
+        assert!(dir.join("b.log.0").exists());
+
+        assert_eq!(
+            enforce_total_budget(&tasks, 300, false, false, SymlinkPolicy::Follow, false).unwrap(),
+            Some(BudgetExcess {
+                budget_bytes: 300,
+                used_bytes: 400,
+                removed_count: 1,
+                removed_bytes: 100,
+            })
+        );
+        assert!(!dir.join("b.log.0").exists());
+        assert!(dir.join("a.log.0").exists());
+        assert!(dir.join("b.log").exists());
+        assert_eq!(
+            enforce_total_budget(&tasks, 300, false, false, SymlinkPolicy::Follow, false).unwrap(),
+            None
+        );
+
+        //The archives of a followed link are stored next to its target
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::write(dir.join("target/c.log"), "x".repeat(100)).unwrap();
+        fs::write(dir.join("target/c.log.0"), "x".repeat(100)).unwrap();
+        symlink(dir.join("target/c.log"), dir.join("c.log")).unwrap();
+
+        let link_tasks = vec![create_task(dir.join("c.log"))];
+        enforce_total_budget(&link_tasks, 100, false, false, SymlinkPolicy::Follow, false).unwrap();
+        assert!(!dir.join("target/c.log.0").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
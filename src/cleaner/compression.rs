@@ -0,0 +1,253 @@
+//! Module for compressing rotated log files
+//!
+//! The rotation only depends on the Compressor trait, so the algorithms
+//! can be exchanged without changing the rotation logic. The algorithms
+//! are implemented by calling the installed compression programs. The
+//! content is streamed into the stdin of the program by yalc, so the
+//! buffer size of the reads is controlled by the config.
+//!
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+
+use crate::cleaner::file_copy::{self, CopyOptions};
+use crate::config::Compression;
+
+/// File extensions of all supported compression algorithms
+pub const COMPRESSED_EXTENSIONS: &[&str] = &["gz", "zst", "xz", "bz2"];
+
+/// Suffixes of rotated files of all formats including the uncompressed files.
+/// Archives of a previously configured format are still shifted and expired.
+pub const ROTATED_SUFFIXES: &[&str] = &["", ".gz", ".zst", ".xz", ".bz2"];
+
+/// Common interface of all compression algorithms
+pub trait Compressor {
+    /// Name of the algorithm used for logging
+    fn name(&self) -> &str;
+
+    /// File extension of compressed files including the leading dot
+    fn extension(&self) -> &str;
+
+    /// Compress the source file into the destination file
+    fn compress(&self, source: &Path, dest: &Path, options: &CopyOptions) -> Result<(), io::Error>;
+
+    /// Decompress the source archive into the destination file
+    fn decompress(
+        &self,
+        source: &Path,
+        dest: &Path,
+        options: &CopyOptions,
+    ) -> Result<(), io::Error>;
+}
+
+/// Compressor that runs an external program like 'gzip'.
+/// The program must support '-c' to write to stdout and '-N' for the level.
+pub struct ExternalCompressor {
+    program: &'static str,
+    extension: &'static str,
+    level: Option<u32>,
+}
+
+impl Compressor for ExternalCompressor {
+    fn name(&self) -> &str {
+        self.program
+    }
+
+    fn extension(&self) -> &str {
+        self.extension
+    }
+
+    fn compress(&self, source: &Path, dest: &Path, options: &CopyOptions) -> Result<(), io::Error> {
+        let mut command = Command::new(self.program);
+        command.arg("-c");
+
+        if let Some(level) = self.level {
+            command.arg(format!("-{}", level));
+        }
+
+        self.stream_through(command, source, dest, options)
+    }
+
+    fn decompress(
+        &self,
+        source: &Path,
+        dest: &Path,
+        options: &CopyOptions,
+    ) -> Result<(), io::Error> {
+        let mut command = Command::new(self.program);
+        command.arg("-d").arg("-c");
+
+        self.stream_through(command, source, dest, options)
+    }
+}
+
+impl ExternalCompressor {
+    /// Stream the source file through the program into the destination file
+    fn stream_through(
+        &self,
+        mut command: Command,
+        source: &Path,
+        dest: &Path,
+        options: &CopyOptions,
+    ) -> Result<(), io::Error> {
+        let mut source_file = File::open(source)?;
+        let output_file = File::create(dest)?;
+        let mut child: Child = match command.stdin(Stdio::piped()).stdout(output_file).spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                //The empty destination would look like a finished archive
+                let _ = fs::remove_file(dest);
+
+                return Err(io::Error::new(
+                    e.kind(),
+                    format!("Failed to start compressor '{}': {}", self.program, e),
+                ));
+            }
+        };
+
+        //Closing stdin after the copy signals the end of the input to the program
+        let copy_result = match child.stdin.take() {
+            Some(mut stdin) => file_copy::stream_copy(&mut source_file, &mut stdin, options),
+            None => Err(io::Error::other("Compressor stdin is not available")),
+        };
+        let status: ExitStatus = match child.wait() {
+            Ok(status) => status,
+            Err(e) => {
+                let _ = fs::remove_file(dest);
+                return Err(e);
+            }
+        };
+
+        if let Err(e) = copy_result {
+            let _ = fs::remove_file(dest);
+            return Err(e);
+        }
+
+        if !status.success() {
+            //Never keep an incomplete archive
+            let _ = fs::remove_file(dest);
+
+            return Err(io::Error::other(format!(
+                "Compressor '{}' failed with {}",
+                self.program, status
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Create the compressor that can decompress the archive, None for uncompressed archives
+pub fn compressor_for_archive(path: &Path) -> Option<Box<dyn Compressor>> {
+    let compression: Compression = match path.extension().and_then(|e| e.to_str()) {
+        Some("gz") => Compression::Gzip,
+        Some("zst") => Compression::Zstd,
+        Some("xz") => Compression::Xz,
+        Some("bz2") => Compression::Bzip2,
+        _ => return None,
+    };
+
+    create_compressor(compression, None)
+}
+
+/// Create the compressor of the configured algorithm, None for no compression
+pub fn create_compressor(
+    compression: Compression,
+    level: Option<u32>,
+) -> Option<Box<dyn Compressor>> {
+    let (program, extension) = match compression {
+        Compression::None => return None,
+        Compression::Gzip => ("gzip", ".gz"),
+        Compression::Zstd => ("zstd", ".zst"),
+        Compression::Xz => ("xz", ".xz"),
+        Compression::Bzip2 => ("bzip2", ".bz2"),
+    };
+
+    Some(Box::new(ExternalCompressor {
+        program,
+        extension,
+        level,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_create_compressor() {
+        assert!(create_compressor(Compression::None, None).is_none());
+
+        let compressor = create_compressor(Compression::Zstd, Some(3)).unwrap();
+        assert_eq!(compressor.name(), "zstd");
+        assert_eq!(compressor.extension(), ".zst");
+
+        assert_eq!(
+            compressor_for_archive(Path::new("app.log.1.xz"))
+                .unwrap()
+                .name(),
+            "xz"
+        );
+        assert!(compressor_for_archive(Path::new("app.log.1")).is_none());
+    }
+
+    #[test]
+    fn test_gzip_compress() {
+        let dir = env::temp_dir().join(format!("yalc_test_compress_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let source = dir.join("app.log.0");
+        let dest = dir.join("app.log.0.gz");
+        fs::write(&source, "line\n".repeat(1000)).unwrap();
+
+        //The test requires an installed gzip program
+        let compressor = create_compressor(Compression::Gzip, Some(9)).unwrap();
+        let options = CopyOptions {
+            buffer_size: 4096,
+            rate_limit: None,
+        };
+        compressor.compress(&source, &dest, &options).unwrap();
+
+        let compressed = fs::read(&dest).unwrap();
+        assert_eq!(&compressed[..2], &[0x1f, 0x8b]);
+        assert!(compressed.len() < 5000);
+
+        //The compressor of the archive restores the original content
+        let restored = dir.join("app.log.restored");
+        let decompressor = compressor_for_archive(&dest).unwrap();
+        decompressor.decompress(&dest, &restored, &options).unwrap();
+        assert_eq!(fs::read(&restored).unwrap(), fs::read(&source).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_compressor() {
+        let dir = env::temp_dir().join(format!("yalc_test_no_compressor_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let source = dir.join("app.log.0");
+        let dest = dir.join("app.log.0.missing");
+        fs::write(&source, "line\n").unwrap();
+
+        let compressor = ExternalCompressor {
+            program: "yalc-missing-compressor",
+            extension: ".missing",
+            level: None,
+        };
+        let options = CopyOptions {
+            buffer_size: 4096,
+            rate_limit: None,
+        };
+
+        //No empty archive is left behind when the program can not be started
+        assert!(compressor.compress(&source, &dest, &options).is_err());
+        assert!(!dest.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
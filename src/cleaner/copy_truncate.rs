@@ -0,0 +1,268 @@
+//! Module for the copy and truncate rotation
+//!
+//! The process that writes the log keeps its file open, so the content is
+//! copied into the rotated file and the original file is truncated. Writes
+//! between the copy and the truncate would be lost. To keep this window as
+//! small as possible, the bytes written during the copy are copied again
+//! as tail diff until the file stops growing, and the file is truncated
+//! right after the last diff. The slow sync of the copy happens before the
+//! tail diffs, so it does not extend the window. Writers that use flock
+//! can be blocked for the whole operation with the optional advisory lock.
+//!
+//! With redaction expressions the copy is redacted while it is written,
+//! so the unredacted content never reaches the archive. A failed copy is
+//! removed and the original file is only truncated after a complete copy.
+//! Only the copy is retried after transient errors. Every attempt writes a
+//! new copy and never replaces an existing file, the truncate is the last
+//! step and runs once.
+//!
+use std::fs::{self, File, TryLockError};
+use std::io::{self, Seek, SeekFrom};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cleaner::file_copy::{self, CopyOptions};
+use crate::cleaner::{redact, retry};
+use crate::config::RetryConfig;
+use crate::config::config_regex::Regex;
+use crate::output;
+
+/// Maximum number of tail diffs, a writer that never pauses can not delay the truncate forever
+const MAX_TAIL_PASSES: usize = 3;
+
+/// Maximum time to wait for the advisory lock of the log file
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time between two attempts to get the advisory lock
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Copy the content of the source into the destination and truncate the source afterwards.
+/// With advisory locking the source is locked exclusively while it is copied and truncated.
+/// The matches of the redaction expressions are masked in the copy.
+pub fn copy_and_truncate(
+    task_nr: usize,
+    source: &Path,
+    dest: &Path,
+    advisory_lock: bool,
+    options: &CopyOptions,
+    redact: &[Regex],
+    retry_config: &RetryConfig,
+) -> Result<(), io::Error> {
+    let mut source_file: File = File::options().read(true).write(true).open(source)?;
+
+    if advisory_lock {
+        lock_with_timeout(task_nr, &source_file)?;
+    }
+
+    let dest_file: File = retry::with_retry(task_nr, retry_config, "Copy", || {
+        copy_to_new_file(task_nr, &mut source_file, dest, options, redact)
+    })?;
+
+    //The truncate is not retried, the copy is complete at this point
+    source_file.set_len(0)?;
+
+    //Only the small tail diffs are synced after the truncate
+    dest_file.sync_all()?;
+
+    if !redact.is_empty() {
+        output::print_status(&format!(
+            "[{}] Redacted the copy '{}'",
+            task_nr,
+            dest.display()
+        ));
+    }
+
+    //The advisory lock is released when the file is closed
+    Ok(())
+}
+
+/// Copy the source into a new destination file, a partial copy is removed on errors.
+/// An existing destination is never opened, so a retry can not truncate an earlier copy.
+fn copy_to_new_file(
+    task_nr: usize,
+    source_file: &mut File,
+    dest: &Path,
+    options: &CopyOptions,
+    redact: &[Regex],
+) -> Result<File, io::Error> {
+    let mut dest_file: File = File::options().write(true).create_new(true).open(dest)?;
+
+    let result: Result<(), io::Error> = source_file
+        .seek(SeekFrom::Start(0))
+        .and_then(|_| copy_content(task_nr, source_file, &mut dest_file, options, redact));
+
+    match result {
+        Ok(()) => Ok(dest_file),
+        Err(e) => {
+            drop(dest_file);
+            let _ = fs::remove_file(dest);
+            Err(e)
+        }
+    }
+}
+
+/// Copy the content and the bytes that were written during the copy into the destination
+fn copy_content(
+    task_nr: usize,
+    source_file: &mut File,
+    dest_file: &mut File,
+    options: &CopyOptions,
+    redact: &[Regex],
+) -> Result<(), io::Error> {
+    let mut redacted_count: usize = 0;
+    let mut copy_pass =
+        |source_file: &mut File, dest_file: &mut File, final_pass: bool| match redact.is_empty() {
+            true => file_copy::copy_to_end(source_file, dest_file, options),
+            false => redact::copy_redacted(
+                source_file,
+                dest_file,
+                redact,
+                options,
+                final_pass,
+                &mut redacted_count,
+            ),
+        };
+
+    //A reflink only shares the blocks, so even large files are copied instantly.
+    //The shared blocks would contain the unredacted content, so redacted copies are streamed.
+    let reflinked: bool = redact.is_empty() && file_copy::try_reflink(source_file, dest_file)?;
+
+    let mut copied_bytes: u64 = if reflinked {
+        output::print_status(&format!(
+            "[{}] Cloned the file content with reflink",
+            task_nr
+        ));
+        let cloned_bytes: u64 = dest_file.metadata()?.len();
+        source_file.seek(SeekFrom::Start(cloned_bytes))?;
+        dest_file.seek(SeekFrom::End(0))?;
+        cloned_bytes
+    } else {
+        copy_pass(source_file, dest_file, false)?
+    };
+    dest_file.sync_all()?;
+
+    //Copy the bytes that were written while the file was copied
+    for _ in 0..MAX_TAIL_PASSES {
+        let current_size: u64 = source_file.metadata()?.len();
+
+        if current_size <= copied_bytes {
+            break;
+        }
+
+        source_file.seek(SeekFrom::Start(copied_bytes))?;
+        let tail_bytes: u64 = copy_pass(source_file, dest_file, false)?;
+        copied_bytes += tail_bytes;
+
+        output::print_status(&format!(
+            "[{}] Copied {} bytes that were written during the copy",
+            task_nr, tail_bytes
+        ));
+    }
+
+    //The last line without line end is only redacted right before the truncate
+    if !redact.is_empty() {
+        source_file.seek(SeekFrom::Start(copied_bytes))?;
+        copy_pass(source_file, dest_file, true)?;
+
+        output::debug(&format!(
+            "[{}] Masked {} matches in the copy",
+            task_nr, redacted_count
+        ));
+    }
+
+    Ok(())
+}
+
+/// Lock the file exclusively, continues without the lock when the timeout is reached
+fn lock_with_timeout(task_nr: usize, file: &File) -> Result<(), io::Error> {
+    let lock_start = Instant::now();
+
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(()),
+            Err(TryLockError::WouldBlock) if lock_start.elapsed() < LOCK_TIMEOUT => {
+                thread::sleep(LOCK_POLL_INTERVAL);
+            }
+            Err(TryLockError::WouldBlock) => {
+                output::print_status(&format!(
+                    "[{}] Warning: The file is still locked after {} seconds, continuing without the lock",
+                    task_nr,
+                    LOCK_TIMEOUT.as_secs()
+                ));
+                return Ok(());
+            }
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config_regex;
+    use std::env;
+
+    #[test]
+    fn test_copy_and_truncate() {
+        let dir = env::temp_dir().join(format!("yalc_test_copy_truncate_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let source = dir.join("app.log");
+        let dest = dir.join("app.log.0");
+        fs::write(&source, "line\n".repeat(1000)).unwrap();
+
+        let options = CopyOptions {
+            buffer_size: 4096,
+            rate_limit: None,
+        };
+        let retry_config = RetryConfig {
+            attempts: 3,
+            backoff_ms: 1,
+        };
+        copy_and_truncate(1, &source, &dest, true, &options, &[], &retry_config).unwrap();
+
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "line\n".repeat(1000));
+        assert_eq!(fs::metadata(&source).unwrap().len(), 0);
+
+        //The copy is redacted while it is written, the incomplete last line is also redacted
+        fs::write(&source, "card 4111111111111111\ntoken 4111111111111111").unwrap();
+        let redact = vec![config_regex::parse_regex(r"\d{16}").unwrap()];
+        fs::remove_file(&dest).unwrap();
+        copy_and_truncate(1, &source, &dest, false, &options, &redact, &retry_config).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&dest).unwrap(),
+            "card [REDACTED]\ntoken [REDACTED]"
+        );
+        assert_eq!(fs::metadata(&source).unwrap().len(), 0);
+
+        //A failed copy is removed and the source is not truncated
+        fs::write(&source, "line\n").unwrap();
+        let missing_dir_dest = dir.join("missing").join("app.log.0");
+        assert!(
+            copy_and_truncate(
+                1,
+                &source,
+                &missing_dir_dest,
+                false,
+                &options,
+                &[],
+                &retry_config
+            )
+            .is_err()
+        );
+        assert_eq!(fs::metadata(&source).unwrap().len(), 5);
+
+        //An existing copy is never replaced or truncated
+        assert!(copy_and_truncate(1, &source, &dest, false, &options, &[], &retry_config).is_err());
+        assert_eq!(
+            fs::read_to_string(&dest).unwrap(),
+            "card [REDACTED]\ntoken [REDACTED]"
+        );
+        assert_eq!(fs::metadata(&source).unwrap().len(), 5);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
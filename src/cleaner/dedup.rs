@@ -0,0 +1,89 @@
+//! Module for the deduplication of identical archives
+//!
+//! Logs that rarely change but are rotated on a schedule create the same
+//! archive again and again. A new archive with the same SHA-256 checksum as
+//! the previous rotation is replaced with a hard link to it, so the content
+//! is only stored once. The links survive the renames of later shifts and
+//! the removal of one link keeps the content of the other one.
+//!
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::cleaner::sha256;
+use crate::output;
+
+/// Replace the archive with a hard link to the previous archive when both have the same content.
+/// Returns true when the archive was replaced, archives that are already linked are kept.
+pub fn link_duplicate(task_nr: usize, archive: &Path, previous: &Path) -> Result<bool, io::Error> {
+    let (Ok(archive_metadata), Ok(previous_metadata)) =
+        (fs::metadata(archive), fs::metadata(previous))
+    else {
+        return Ok(false);
+    };
+
+    //The size is compared first, so different archives are rarely hashed
+    if !previous_metadata.is_file()
+        || archive_metadata.len() != previous_metadata.len()
+        || is_same_file(&archive_metadata, &previous_metadata)
+        || sha256::file_sha256(archive)? != sha256::file_sha256(previous)?
+    {
+        return Ok(false);
+    }
+
+    output::print_status(&format!(
+        "[{}] Archive '{}' is identical to '{}', replacing it with a hard link",
+        task_nr,
+        archive.display(),
+        previous.display()
+    ));
+
+    //The link is created next to the archive and renamed, so the archive is never missing
+    let link_path = PathBuf::from(format!("{}.dedup", archive.display()));
+    fs::hard_link(previous, &link_path)?;
+
+    if let Err(e) = fs::rename(&link_path, archive) {
+        let _ = fs::remove_file(&link_path);
+        return Err(e);
+    }
+
+    Ok(true)
+}
+
+/// Returns true when both metadata belong to the same inode
+fn is_same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_link_duplicate() {
+        let dir = env::temp_dir().join(format!("yalc_test_dedup_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let archive = dir.join("app.log.0.gz");
+        let previous = dir.join("app.log.1.gz");
+        fs::write(&archive, "same").unwrap();
+        fs::write(&previous, "diff").unwrap();
+
+        //Archives with different content are kept
+        assert!(!link_duplicate(1, &archive, &previous).unwrap());
+
+        fs::write(&previous, "same").unwrap();
+        assert!(link_duplicate(1, &archive, &previous).unwrap());
+        assert_eq!(fs::metadata(&archive).unwrap().nlink(), 2);
+        assert_eq!(fs::read_to_string(&archive).unwrap(), "same");
+
+        //Linked archives are not linked again and missing archives are ignored
+        assert!(!link_duplicate(1, &archive, &previous).unwrap());
+        assert!(!link_duplicate(1, &archive, &dir.join("app.log.2.gz")).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
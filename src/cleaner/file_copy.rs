@@ -0,0 +1,233 @@
+//! Module for fast copies of large log files
+//!
+//! Copying a log of several gigabytes through a user space buffer costs a
+//! lot of time and I/O. On filesystems like btrfs and XFS the copy shares
+//! the data blocks with the original via reflink. Otherwise the data is
+//! copied within the kernel with copy_file_range. Filesystems without
+//! support for both use a streamed copy with the configured buffer size.
+//! With an I/O limit every copy is streamed, so the throughput can be
+//! throttled between the buffers. Copies that transform the content, like
+//! the redaction of archives, are streamed line by line. All copy loops
+//! report their copied bytes to the progress of the running operation.
+//!
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::config::config_validator::MAX_IO_BUFFER_BYTES;
+use crate::output::progress;
+use crate::platform;
+
+/// Maximum number of bytes per copy_file_range call
+const CHUNK_SIZE: usize = 1 << 30;
+
+/// Represents the settings of streamed copies
+#[derive(Debug, Clone, Copy)]
+pub struct CopyOptions {
+    /// Size of the buffer in bytes
+    pub buffer_size: usize,
+
+    /// Maximum bytes per second, not limited when not set
+    pub rate_limit: Option<u64>,
+}
+
+impl CopyOptions {
+    /// Get the copy settings of the config, the buffer size is clamped to the valid range
+    pub fn from_config(config: &Config) -> CopyOptions {
+        let buffer_bytes: u64 = config.io_buffer_bytes.clamp(1, MAX_IO_BUFFER_BYTES);
+
+        CopyOptions {
+            buffer_size: usize::try_from(buffer_bytes).unwrap_or(usize::MAX),
+            rate_limit: config.io_limit_bytes_per_s,
+        }
+    }
+}
+
+/// Represents the rate limit of a running streamed copy
+struct Throttle {
+    rate_limit: Option<u64>,
+    copy_start: Instant,
+    copied_bytes: u64,
+}
+
+impl Throttle {
+    fn new(options: &CopyOptions) -> Throttle {
+        Throttle {
+            rate_limit: options.rate_limit,
+            copy_start: Instant::now(),
+            copied_bytes: 0,
+        }
+    }
+
+    /// Add the copied bytes and wait until the average rate of the copy is within the limit
+    fn record(&mut self, bytes: usize) {
+        self.copied_bytes += bytes as u64;
+        progress::advance(bytes as u64);
+
+        if let Some(rate_limit) = self.rate_limit {
+            let target =
+                Duration::from_secs_f64(self.copied_bytes as f64 / rate_limit.max(1) as f64);
+            if let Some(remaining) = target.checked_sub(self.copy_start.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
+    }
+}
+
+/// Returns true when the error means that the filesystem does not support the operation
+fn is_unsupported(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::Unsupported | ErrorKind::CrossesDevices | ErrorKind::InvalidInput
+    ) || error.raw_os_error() == Some(95) //EOPNOTSUPP
+}
+
+/// Clone the whole source into the empty destination, returns false without reflink support
+pub fn try_reflink(source: &File, dest: &File) -> Result<bool, io::Error> {
+    match platform::clone_file(source, dest) {
+        Ok(()) => Ok(true),
+        Err(e) if is_unsupported(&e) || e.raw_os_error() == Some(25) => Ok(false), //ENOTTY
+        Err(e) => Err(e),
+    }
+}
+
+/// Copy everything from the current offset of the source to the current offset of the
+/// destination. Returns the number of copied bytes.
+pub fn copy_to_end(
+    source: &mut File,
+    dest: &mut File,
+    options: &CopyOptions,
+) -> Result<u64, io::Error> {
+    //Copies within the kernel can not be throttled
+    if options.rate_limit.is_some() {
+        return stream_copy(source, dest, options);
+    }
+
+    let mut copied_bytes: u64 = 0;
+
+    loop {
+        match platform::copy_file_chunk(source, dest, CHUNK_SIZE) {
+            Ok(0) => return Ok(copied_bytes),
+            Ok(chunk_bytes) => {
+                copied_bytes += chunk_bytes;
+                progress::advance(chunk_bytes);
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+
+            //The offsets are only moved by copied chunks, so the fallback continues there
+            Err(e) if is_unsupported(&e) => {
+                return Ok(copied_bytes + stream_copy(source, dest, options)?);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Copy the remaining bytes of the reader into the writer through a buffer of the configured size
+pub fn stream_copy(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    options: &CopyOptions,
+) -> Result<u64, io::Error> {
+    let mut buffer: Vec<u8> = vec![0; options.buffer_size.max(1)];
+    let mut throttle = Throttle::new(options);
+
+    loop {
+        let read_bytes: usize = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(throttle.copied_bytes),
+            Ok(read_bytes) => read_bytes,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        writer.write_all(&buffer[..read_bytes])?;
+        throttle.record(read_bytes);
+    }
+}
+
+/// Copy the remaining lines of the reader into the writer, every line is passed through the
+/// transform first. The line end is part of the line. With complete lines only, a last line
+/// without line end is not copied, because it can still be written. Returns the number of
+/// copied bytes of the reader.
+pub fn stream_lines(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    options: &CopyOptions,
+    complete_lines_only: bool,
+    transform: &mut dyn FnMut(&[u8], &mut Vec<u8>),
+) -> Result<u64, io::Error> {
+    let mut reader = BufReader::with_capacity(options.buffer_size.max(1), reader);
+    let mut writer = BufWriter::with_capacity(options.buffer_size.max(1), writer);
+    let mut line: Vec<u8> = Vec::new();
+    let mut transformed: Vec<u8> = Vec::new();
+    let mut throttle = Throttle::new(options);
+
+    loop {
+        line.clear();
+        let read_bytes: usize = reader.read_until(b'\n', &mut line)?;
+
+        if read_bytes == 0 || (complete_lines_only && !line.ends_with(b"\n")) {
+            writer.flush()?;
+            return Ok(throttle.copied_bytes);
+        }
+
+        transformed.clear();
+        transform(&line, &mut transformed);
+        writer.write_all(&transformed)?;
+        throttle.record(read_bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::io::{Seek, SeekFrom};
+
+    #[test]
+    fn test_copy_to_end() {
+        let dir = env::temp_dir().join(format!("yalc_test_file_copy_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let source_path = dir.join("app.log");
+        let dest_path = dir.join("app.log.0");
+        let content: String = "line\n".repeat(100_000);
+        fs::write(&source_path, &content).unwrap();
+
+        let options = CopyOptions {
+            buffer_size: 4096,
+            rate_limit: None,
+        };
+        let mut source = File::open(&source_path).unwrap();
+        let mut dest = File::create(&dest_path).unwrap();
+        assert_eq!(
+            copy_to_end(&mut source, &mut dest, &options).unwrap(),
+            500_000
+        );
+        assert_eq!(fs::read_to_string(&dest_path).unwrap(), content);
+
+        //The fallback continues at the offsets of the files
+        source.seek(SeekFrom::Start(499_995)).unwrap();
+        assert_eq!(stream_copy(&mut source, &mut dest, &options).unwrap(), 5);
+        assert_eq!(fs::metadata(&dest_path).unwrap().len(), 500_005);
+
+        //A limit of 100 KB/s requires about 0.2 seconds for 20 KB
+        let limited = CopyOptions {
+            buffer_size: 4096,
+            rate_limit: Some(100_000),
+        };
+        let copy_start = Instant::now();
+        source.seek(SeekFrom::Start(480_000)).unwrap();
+        assert_eq!(
+            copy_to_end(&mut source, &mut dest, &limited).unwrap(),
+            20_000
+        );
+        assert!(copy_start.elapsed() >= Duration::from_millis(150));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+//! Module for the metadata of rotated and created files
+//!
+//! Copies and compressed archives are new files, so they are owned by the
+//! user of yalc and have the current time as timestamps. The metadata of
+//! the original file is applied explicitly, so readers of the rotated
+//! files keep their access and tools that sort by time keep working.
+//! Configured permissions are applied on top of the replicated metadata.
+//!
+use std::fs::{self, File, FileTimes, Metadata};
+use std::io::{self, ErrorKind};
+use std::os::unix::fs::{MetadataExt, PermissionsExt, chown};
+use std::path::Path;
+
+use crate::config::FilePermissions;
+use crate::output;
+use crate::platform;
+
+/// Apply the mode, owner and timestamps of the source metadata to the file.
+/// A missing permission to change the owner is only reported as warning.
+pub fn replicate_metadata(task_nr: usize, source: &Metadata, dest: &Path) -> Result<(), io::Error> {
+    let times = FileTimes::new()
+        .set_accessed(source.accessed()?)
+        .set_modified(source.modified()?);
+
+    //The times are set first, a read-only mode would prevent opening the file for writing
+    File::options().write(true).open(dest)?.set_times(times)?;
+
+    fs::set_permissions(dest, source.permissions())?;
+    change_owner(task_nr, dest, Some(source.uid()), Some(source.gid()))
+}
+
+/// Create a new empty file with the mode and owner of the rotated file.
+/// The configured permissions are applied afterwards.
+pub fn create_empty_file(
+    task_nr: usize,
+    path: &Path,
+    rotated: &Metadata,
+    permissions: &FilePermissions,
+) -> Result<(), io::Error> {
+    File::create_new(path)?;
+    fs::set_permissions(path, rotated.permissions())?;
+    change_owner(task_nr, path, Some(rotated.uid()), Some(rotated.gid()))?;
+
+    apply_permissions(task_nr, path, permissions)
+}
+
+/// Apply the configured mode, owner and group to the file, unset values are not changed
+pub fn apply_permissions(
+    task_nr: usize,
+    path: &Path,
+    permissions: &FilePermissions,
+) -> Result<(), io::Error> {
+    let uid: Option<u32> = match &permissions.owner {
+        Some(owner) => Some(platform::lookup_user_id(owner).ok_or_else(|| {
+            io::Error::new(ErrorKind::NotFound, format!("Unknown user: '{}'", owner))
+        })?),
+        None => None,
+    };
+
+    let gid: Option<u32> = match &permissions.group {
+        Some(group) => Some(platform::lookup_group_id(group).ok_or_else(|| {
+            io::Error::new(ErrorKind::NotFound, format!("Unknown group: '{}'", group))
+        })?),
+        None => None,
+    };
+
+    if uid.is_some() || gid.is_some() {
+        change_owner(task_nr, path, uid, gid)?;
+    }
+
+    if let Some(mode) = permissions.mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+
+    Ok(())
+}
+
+/// Change the owner of the file, a missing permission is only reported as warning
+fn change_owner(
+    task_nr: usize,
+    path: &Path,
+    uid: Option<u32>,
+    gid: Option<u32>,
+) -> Result<(), io::Error> {
+    match chown(path, uid, gid) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+            output::print_status(&format!(
+                "[{}] Warning: Missing permission to change the owner of '{}'",
+                task_nr,
+                path.display()
+            ));
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_replicate_metadata() {
+        let dir = env::temp_dir().join(format!("yalc_test_meta_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let source = dir.join("app.log");
+        let dest = dir.join("app.log.0");
+        fs::write(&source, "content").unwrap();
+        fs::write(&dest, "content").unwrap();
+
+        //Prepare a source with a distinct mode and modification time
+        let modified = SystemTime::now() - Duration::from_secs(3600);
+        fs::set_permissions(&source, fs::Permissions::from_mode(0o640)).unwrap();
+        File::options()
+            .write(true)
+            .open(&source)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let source_meta = fs::metadata(&source).unwrap();
+        replicate_metadata(1, &source_meta, &dest).unwrap();
+
+        let dest_meta = fs::metadata(&dest).unwrap();
+        assert_eq!(dest_meta.permissions().mode() & 0o777, 0o640);
+        assert_eq!(dest_meta.modified().unwrap(), modified);
+        assert_eq!(dest_meta.uid(), source_meta.uid());
+
+        //A read-only mode is applied after the times
+        fs::set_permissions(&source, fs::Permissions::from_mode(0o440)).unwrap();
+        let source_meta = fs::metadata(&source).unwrap();
+        replicate_metadata(1, &source_meta, &dest).unwrap();
+
+        let dest_meta = fs::metadata(&dest).unwrap();
+        assert_eq!(dest_meta.permissions().mode() & 0o777, 0o440);
+        assert_eq!(dest_meta.modified().unwrap(), modified);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_create_empty_file() {
+        let dir = env::temp_dir().join(format!("yalc_test_create_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let rotated = dir.join("app.log.0");
+        let path = dir.join("app.log");
+        fs::write(&rotated, "content").unwrap();
+        fs::set_permissions(&rotated, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let permissions = FilePermissions {
+            mode: Some(0o600),
+            owner: None,
+            group: None,
+        };
+
+        let rotated_meta = fs::metadata(&rotated).unwrap();
+        create_empty_file(1, &path, &rotated_meta, &permissions).unwrap();
+
+        let meta = fs::metadata(&path).unwrap();
+        assert_eq!(meta.len(), 0);
+        assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+
+        //Existing files are never replaced
+        assert!(create_empty_file(1, &path, &rotated_meta, &permissions).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,96 @@
+//! Module for the content fingerprints of log files
+//!
+//! A fingerprint combines the size, the modification time and a hash of the
+//! head and the tail of a file. It is stored after every rotation, so a later
+//! run can detect that an idle service did not write anything since then.
+//! Only the edges of the file are read, the cost does not grow with the size.
+//!
+use std::fs::{File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// Number of bytes that are hashed at the start and at the end of the file
+const EDGE_SIZE: u64 = 4096;
+
+/// Offset basis of the 64-bit FNV-1a hash
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+/// Prime of the 64-bit FNV-1a hash
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Compute the fingerprint of a file, the result is stored as text in the state file
+pub fn file_fingerprint(path: &Path) -> Result<String, io::Error> {
+    let mut file: File = File::open(path)?;
+    let metadata: Metadata = file.metadata()?;
+    let size: u64 = metadata.len();
+
+    let mut edges: Vec<u8> = Vec::new();
+    (&file).take(EDGE_SIZE).read_to_end(&mut edges)?;
+
+    //The tail is only read separately when it does not overlap the head
+    if size > EDGE_SIZE * 2 {
+        file.seek(SeekFrom::End(-(EDGE_SIZE as i64)))?;
+        (&file).take(EDGE_SIZE).read_to_end(&mut edges)?;
+    } else if size > EDGE_SIZE {
+        (&file).read_to_end(&mut edges)?;
+    }
+
+    Ok(format!(
+        "{}-{}.{}-{:016x}",
+        size,
+        metadata.mtime(),
+        metadata.mtime_nsec(),
+        fnv1a_hash(&edges)
+    ))
+}
+
+/// Hash the bytes with the 64-bit FNV-1a hash function
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs::{self, FileTimes};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_file_fingerprint() {
+        let dir = env::temp_dir().join(format!("yalc_test_fingerprint_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("app.log");
+        let times =
+            FileTimes::new().set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1000));
+
+        fs::write(&path, "line\n".repeat(10_000)).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_times(times)
+            .unwrap();
+        let fingerprint = file_fingerprint(&path).unwrap();
+        assert_eq!(file_fingerprint(&path).unwrap(), fingerprint);
+
+        //Same size and time, but a different tail
+        let mut content = "line\n".repeat(10_000);
+        content.replace_range(content.len() - 5.., "tail\n");
+        fs::write(&path, content).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_times(times)
+            .unwrap();
+        assert_ne!(file_fingerprint(&path).unwrap(), fingerprint);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
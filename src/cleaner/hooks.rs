@@ -0,0 +1,134 @@
+//! Module for the shared hooks of file groups
+//!
+//! The hooks of a group run once per cleanup run, independent of the number
+//! of files in the group. The prerotate hook runs before the first file of the
+//! group is rotated. The postrotate hook runs after all tasks are done, but
+//! only for groups where at least one file was rotated.
+//!
+use std::collections::HashSet;
+use std::io;
+use std::process::Command;
+
+use crate::cleaner::audit;
+use crate::config::{Config, GroupConfig};
+use crate::output;
+
+/// Tracks which hooks of the groups were already executed in the current run
+pub struct GroupHooks<'a> {
+    config: &'a Config,
+
+    /// Groups where the prerotate hook was executed successfully
+    prepared_groups: HashSet<String>,
+
+    /// Groups where the prerotate hook has failed, no files are rotated for them
+    failed_groups: HashSet<String>,
+
+    /// Groups with at least one rotated file, in the order of the first rotation
+    rotated_groups: Vec<String>,
+}
+
+impl<'a> GroupHooks<'a> {
+    pub fn new(config: &'a Config) -> GroupHooks<'a> {
+        GroupHooks {
+            config,
+            prepared_groups: HashSet::new(),
+            failed_groups: HashSet::new(),
+            rotated_groups: Vec::new(),
+        }
+    }
+
+    /// Must be called before a file of the group is rotated.
+    /// Runs the prerotate hook when this is the first rotated file of the group.
+    pub fn before_rotation(&mut self, task_nr: usize, group_name: &str) -> Result<(), io::Error> {
+        if self.failed_groups.contains(group_name) {
+            return Err(io::Error::other(format!(
+                "Skipped, the prerotate hook of group '{}' has failed",
+                group_name
+            )));
+        }
+
+        if !self.prepared_groups.contains(group_name) {
+            if let Some(command) = self
+                .find_group(group_name)
+                .and_then(|g| g.prerotate.as_ref())
+            {
+                output::print_status(&format!(
+                    "[{}] Running prerotate hook of group '{}'",
+                    task_nr, group_name
+                ));
+
+                if let Err(e) = run_hook(command, group_name) {
+                    self.failed_groups.insert(group_name.to_string());
+                    return Err(e);
+                }
+            }
+
+            self.prepared_groups.insert(group_name.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Must be called after a file of the group was rotated
+    pub fn after_rotation(&mut self, group_name: &str) {
+        if !self.rotated_groups.iter().any(|g| g == group_name) {
+            self.rotated_groups.push(group_name.to_string());
+        }
+    }
+
+    /// Run the postrotate hooks of all groups with rotated files.
+    /// Returns the number of failed hooks, the errors are logged.
+    pub fn run_postrotate_hooks(&self) -> usize {
+        let mut failed_hooks: usize = 0;
+
+        for group_name in self.rotated_groups.iter() {
+            if let Some(command) = self
+                .find_group(group_name)
+                .and_then(|g| g.postrotate.as_ref())
+            {
+                output::print_status(&format!(
+                    "Running postrotate hook of group '{}'",
+                    group_name
+                ));
+
+                if let Err(e) = run_hook(command, group_name) {
+                    output::error(&format!(
+                        "Postrotate hook of group '{}' failed: {}",
+                        group_name, e
+                    ));
+                    failed_hooks += 1;
+                }
+            }
+        }
+
+        failed_hooks
+    }
+
+    fn find_group(&self, group_name: &str) -> Option<&'a GroupConfig> {
+        self.config.groups.iter().find(|g| g.name == group_name)
+    }
+}
+
+/// Run a hook command with the shell, the group name is passed as env variable
+fn run_hook(command: &str, group_name: &str) -> Result<(), io::Error> {
+    audit::track(
+        "hook",
+        &[("group", &group_name), ("command", &command)],
+        || {
+            let status = Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .env("YALC_GROUP", group_name)
+                .status()?;
+
+            if status.success() {
+                Ok(())
+            } else {
+                Err(io::Error::other(format!(
+                    "Hook '{}' failed with {}",
+                    command, status
+                )))
+            }
+        },
+    )
+}
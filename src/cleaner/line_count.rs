@@ -0,0 +1,65 @@
+//! Module for counting the lines of log files
+//!
+//! The file is read in chunks, so large files are never loaded into the
+//! memory at once. Counting stops after the limit was exceeded, because
+//! the exact number of lines is not required to decide about the cleanup.
+//!
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+/// Size of the chunks that are read from the file
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Count the newlines of a file, counting stops as soon as the limit is exceeded.
+/// A result above the limit means that the file has at least that many lines.
+pub fn count_lines_capped(path: &Path, limit: u64) -> Result<u64, io::Error> {
+    let mut reader = BufReader::with_capacity(CHUNK_SIZE, File::open(path)?);
+    let mut buffer: Vec<u8> = vec![0; CHUNK_SIZE];
+    let mut line_count: u64 = 0;
+
+    loop {
+        let read_bytes: usize = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read_bytes) => read_bytes,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        line_count += buffer[..read_bytes].iter().filter(|b| **b == b'\n').count() as u64;
+
+        if line_count > limit {
+            break;
+        }
+    }
+
+    Ok(line_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_count_lines_capped() {
+        let dir = env::temp_dir().join(format!("yalc_test_lines_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("app.log");
+        fs::write(&path, "line\n".repeat(100_000)).unwrap();
+
+        assert_eq!(count_lines_capped(&path, u64::MAX).unwrap(), 100_000);
+
+        //Counting stops after the chunk that exceeds the limit
+        let capped = count_lines_capped(&path, 10).unwrap();
+        assert!(capped > 10 && capped < 100_000);
+
+        fs::write(&path, "").unwrap();
+        assert_eq!(count_lines_capped(&path, 10).unwrap(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,289 @@
+//! Module for the archive manifests
+//!
+//! Compliance audits need a record of what each archive contains and that
+//! it was not changed afterwards. With the manifest enabled, a toml file
+//! next to the archives like 'app.log.manifest.toml' lists every archive
+//! with its original path, rotation time, size and SHA-256 checksum. The
+//! manifest is updated after every change of the archives. Entries of
+//! archives that were only renamed by the shift are kept, so the checksum
+//! of an archive is computed once. A rename keeps the device and inode of
+//! the archive, so these identify the entry of a shifted archive.
+//!
+use std::fs::{self, Metadata};
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::cleaner::archives::{self, Archive};
+use crate::cleaner::sha256;
+use crate::config::toml_parser::{self, Table, TopLevelTable, Value};
+use crate::config::toml_writer;
+use crate::output;
+use crate::state;
+
+/// Suffix of the manifest file after the rotation base
+pub const MANIFEST_SUFFIX: &str = ".manifest.toml";
+
+/// Name of the array of tables with an entry for every archive
+const ARCHIVE_KEY: &str = "archive";
+
+/// Represents the manifest entry of a single archive
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    /// File name of the archive in the directory of the manifest
+    pub archive: String,
+
+    /// Path of the log file that the archive was rotated from
+    pub original: String,
+
+    /// Unix time in seconds when the archive was first recorded
+    pub rotated_at: u64,
+
+    /// Unix time in seconds of the last write of the archive
+    pub modified: u64,
+
+    /// Device and inode of the archive, identify renamed archives. Zero for older manifests.
+    pub device: u64,
+    pub inode: u64,
+
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Get the path of the manifest of a rotation base like '/var/log/app.log.manifest.toml'
+pub fn manifest_path(rotation_base: &Path) -> PathBuf {
+    PathBuf::from(format!("{}{}", rotation_base.display(), MANIFEST_SUFFIX))
+}
+
+/// Load the entries of a manifest file, a missing file has no entries
+pub fn load_manifest(path: &Path) -> Result<Vec<ManifestEntry>, io::Error> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let root: TopLevelTable = toml_parser::load_toml_table(path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Failed to load manifest '{}': {}", path.display(), e),
+        )
+    })?;
+
+    let Some(Value::Array(entries)) = root.get(ARCHIVE_KEY) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Value::Table(table) => parse_entry(table),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Write the manifest of the current archives of the rotation base.
+/// Archives without an entry are recorded as rotated from the original path now.
+pub fn update_manifest(
+    task_nr: usize,
+    rotation_base: &Path,
+    original: &Path,
+) -> Result<(), io::Error> {
+    let path: PathBuf = manifest_path(rotation_base);
+    let previous: Vec<ManifestEntry> = load_manifest(&path)?;
+    let now_s: u64 = state::unix_time_now();
+    let mut entries: Vec<ManifestEntry> = Vec::new();
+
+    //The newest archive is listed first, like the rotation indexes
+    for archive in archives::find_archives(rotation_base)?.iter().rev() {
+        let Some(archive_name) = archive.path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let metadata: Metadata = fs::metadata(&archive.path)?;
+        let modified: u64 = archive
+            .modified
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        //A shifted archive keeps its inode under the new name, a rewritten archive gets a new one
+        let known_entry: Option<&ManifestEntry> = previous.iter().find(|entry| {
+            entry.device == metadata.dev()
+                && entry.inode == metadata.ino()
+                && entry.size == archive.size
+        });
+
+        let entry: ManifestEntry = match known_entry {
+            Some(known_entry) => ManifestEntry {
+                archive: archive_name.to_string(),
+                modified,
+                ..known_entry.clone()
+            },
+            None => new_entry(archive, archive_name, original, now_s, modified, &metadata)?,
+        };
+
+        entries.push(entry);
+    }
+
+    save_manifest(&path, &entries)?;
+    output::print_status(&format!(
+        "[{}] Updated manifest '{}' with {} archives",
+        task_nr,
+        path.display(),
+        entries.len()
+    ));
+    Ok(())
+}
+
+/// Create the entry of an archive that is not in the manifest yet
+fn new_entry(
+    archive: &Archive,
+    archive_name: &str,
+    original: &Path,
+    now_s: u64,
+    modified: u64,
+    metadata: &Metadata,
+) -> Result<ManifestEntry, io::Error> {
+    Ok(ManifestEntry {
+        archive: archive_name.to_string(),
+        original: original.display().to_string(),
+        rotated_at: now_s,
+        modified,
+        device: metadata.dev(),
+        inode: metadata.ino(),
+        size: archive.size,
+        sha256: sha256::file_sha256(&archive.path)?,
+    })
+}
+
+/// Write the entries to the manifest file, the file is replaced atomically
+fn save_manifest(path: &Path, entries: &[ManifestEntry]) -> Result<(), io::Error> {
+    let mut root: TopLevelTable = TopLevelTable::new();
+    root.insert(
+        ARCHIVE_KEY.to_string(),
+        Value::Array(
+            entries
+                .iter()
+                .map(|entry| Value::Table(format_entry(entry)))
+                .collect(),
+        ),
+    );
+
+    let tmp_path: PathBuf = PathBuf::from(format!("{}.tmp", path.display()));
+    fs::write(&tmp_path, toml_writer::to_toml_string(&root))?;
+    fs::rename(&tmp_path, path)
+}
+
+/// Read the values of a manifest entry, entries with missing values are ignored
+fn parse_entry(table: &Table) -> Option<ManifestEntry> {
+    let get_string = |key: &str| match table.get(key) {
+        Some(Value::String(value)) => Some(value.clone()),
+        _ => None,
+    };
+    let get_int = |key: &str| match table.get(key) {
+        Some(Value::Integer(value)) => Some(*value),
+        _ => None,
+    };
+
+    Some(ManifestEntry {
+        archive: get_string("archive")?,
+        original: get_string("original")?,
+        rotated_at: u64::try_from(get_int("rotated_at")?).ok()?,
+        modified: u64::try_from(get_int("modified")?).ok()?,
+        device: get_int("device")
+            .and_then(|v| u64::try_from(v).ok())
+            .unwrap_or(0),
+        inode: get_int("inode")
+            .and_then(|v| u64::try_from(v).ok())
+            .unwrap_or(0),
+        size: u64::try_from(get_int("size")?).ok()?,
+        sha256: get_string("sha256")?,
+    })
+}
+
+/// Convert a manifest entry into a toml table
+fn format_entry(entry: &ManifestEntry) -> Table {
+    let to_int = |value: u64| Value::Integer(i64::try_from(value).unwrap_or(i64::MAX));
+
+    let mut table: Table = Table::new();
+    table.insert("archive".to_string(), Value::String(entry.archive.clone()));
+    table.insert(
+        "original".to_string(),
+        Value::String(entry.original.clone()),
+    );
+    table.insert("rotated_at".to_string(), to_int(entry.rotated_at));
+    table.insert("modified".to_string(), to_int(entry.modified));
+    table.insert("device".to_string(), to_int(entry.device));
+    table.insert("inode".to_string(), to_int(entry.inode));
+    table.insert("size".to_string(), to_int(entry.size));
+    table.insert("sha256".to_string(), Value::String(entry.sha256.clone()));
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs::{File, FileTimes};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_update_manifest() {
+        let dir = env::temp_dir().join(format!("yalc_test_manifest_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let base = dir.join("app.log");
+        fs::write(dir.join("app.log.0"), "abc").unwrap();
+        update_manifest(1, &base, &base).unwrap();
+
+        let entries = load_manifest(&manifest_path(&base)).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].archive, "app.log.0");
+        assert_eq!(entries[0].size, 3);
+        assert_eq!(
+            entries[0].sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        //A shifted archive keeps its entry, the new archive gets its own entry
+        let rotated_at: u64 = entries[0].rotated_at;
+        fs::rename(dir.join("app.log.0"), dir.join("app.log.1")).unwrap();
+        fs::write(dir.join("app.log.0"), "newer").unwrap();
+        File::options()
+            .write(true)
+            .open(dir.join("app.log.0"))
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(SystemTime::now() + Duration::from_secs(10)))
+            .unwrap();
+        update_manifest(1, &base, Path::new("/var/log/app.log")).unwrap();
+
+        let entries = load_manifest(&manifest_path(&base)).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].archive, "app.log.0");
+        assert_eq!(entries[0].original, "/var/log/app.log");
+        assert_eq!(entries[1].archive, "app.log.1");
+        assert_eq!(entries[1].rotated_at, rotated_at);
+        assert_eq!(entries[1].original, base.display().to_string());
+
+        //A replaced archive with the same size and modification time gets a new entry
+        let times =
+            FileTimes::new().set_modified(UNIX_EPOCH + Duration::from_secs(entries[1].modified));
+        fs::write(dir.join("replaced.tmp"), "xyz").unwrap();
+        File::options()
+            .write(true)
+            .open(dir.join("replaced.tmp"))
+            .unwrap()
+            .set_times(times)
+            .unwrap();
+        fs::rename(dir.join("replaced.tmp"), dir.join("app.log.1")).unwrap();
+        update_manifest(1, &base, &base).unwrap();
+
+        let entries = load_manifest(&manifest_path(&base)).unwrap();
+        assert_eq!(
+            entries[1].sha256,
+            "3608bca1e44ea6c4d268eb6db02260269892c0b42b86bbf1e77a6fa16c3c9282"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
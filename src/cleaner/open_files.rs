@@ -0,0 +1,158 @@
+//! Module for log files that are held open for writing
+//!
+//! A process that writes a renamed log file continues to write into the
+//! rotated file, so new lines end up in the archive or get lost after the
+//! compression. The writers of a file are found with the file descriptors
+//! in '/proc', a descriptor is a writer when its access mode is not read only.
+//! Platforms without '/proc' never report a writer.
+//!
+use std::fs::{self, DirEntry};
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Root of the process information of the kernel
+const PROC_ROOT: &str = "/proc";
+
+/// Mask of the access mode in the flags of a file descriptor
+const O_ACCMODE: u32 = 0o3;
+
+/// Access mode of a file descriptor that is opened read only
+const O_RDONLY: u32 = 0o0;
+
+/// Represents a process that has a file open for writing
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileWriter {
+    pub pid: u32,
+
+    /// Command name of the process, empty when it can not be read
+    pub name: String,
+}
+
+/// Find all processes that have the file open for writing.
+/// Processes of other users without permissions to their descriptors are not found.
+pub fn find_writers(path: &Path) -> Result<Vec<FileWriter>, io::Error> {
+    let proc_root = Path::new(PROC_ROOT);
+    if !proc_root.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let metadata = fs::metadata(path)?;
+    let file_id: (u64, u64) = (metadata.dev(), metadata.ino());
+    let mut writers: Vec<FileWriter> = Vec::new();
+
+    for entry in fs::read_dir(proc_root)?.flatten() {
+        let pid: u32 = match entry.file_name().to_str().and_then(|s| s.parse().ok()) {
+            Some(pid) => pid,
+            None => continue,
+        };
+
+        //Processes can exit during the scan, so their errors are ignored
+        if process_writes_file(&entry, file_id).unwrap_or(false) {
+            writers.push(FileWriter {
+                pid,
+                name: process_name(&entry.path()),
+            });
+        }
+    }
+
+    Ok(writers)
+}
+
+/// Format the writers for a log line like "nginx (1234), java (5678)"
+pub fn describe_writers(writers: &[FileWriter]) -> String {
+    writers
+        .iter()
+        .map(|writer| match writer.name.is_empty() {
+            true => writer.pid.to_string(),
+            false => format!("{} ({})", writer.name, writer.pid),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Returns true when one of the file descriptors of the process writes the file
+fn process_writes_file(process: &DirEntry, file_id: (u64, u64)) -> Result<bool, io::Error> {
+    let process_dir: PathBuf = process.path();
+
+    for fd_entry in fs::read_dir(process_dir.join("fd"))?.flatten() {
+        //The metadata of the descriptor link is the metadata of the opened file
+        let fd_metadata = match fs::metadata(fd_entry.path()) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+
+        if (fd_metadata.dev(), fd_metadata.ino()) != file_id {
+            continue;
+        }
+
+        let fd_info: PathBuf = process_dir.join("fdinfo").join(fd_entry.file_name());
+        if let Ok(content) = fs::read_to_string(fd_info)
+            && parse_fd_flags(&content).is_some_and(|flags| flags & O_ACCMODE != O_RDONLY)
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Parse the octal flags of a '/proc/PID/fdinfo/FD' file
+fn parse_fd_flags(content: &str) -> Option<u32> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("flags:"))
+        .and_then(|flags| u32::from_str_radix(flags.trim(), 8).ok())
+}
+
+/// Get the command name of a process
+fn process_name(process_dir: &Path) -> String {
+    fs::read_to_string(process_dir.join("comm"))
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn test_parse_fd_flags() {
+        assert_eq!(
+            parse_fd_flags("pos:\t0\nflags:\t0100002\nmnt_id:\t28\n"),
+            Some(0o100002)
+        );
+        assert_eq!(parse_fd_flags("pos:\t0\n"), None);
+    }
+
+    #[test]
+    fn test_find_writers() {
+        let dir = std::env::temp_dir().join(format!("yalc_open_files_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        fs::write(&path, "line\n").unwrap();
+
+        //A reader of the file is no writer
+        let reader = File::open(&path).unwrap();
+        assert!(find_writers(&path).unwrap().is_empty());
+
+        let writer = File::options().append(true).open(&path).unwrap();
+        let writers = find_writers(&path).unwrap();
+        assert_eq!(writers.len(), 1);
+        assert_eq!(writers[0].pid, std::process::id());
+
+        drop(writer);
+        drop(reader);
+        assert!(find_writers(&path).unwrap().is_empty());
+        assert_eq!(
+            describe_writers(&[FileWriter {
+                pid: 42,
+                name: "nginx".to_string()
+            }]),
+            "nginx (42)"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
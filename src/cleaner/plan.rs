@@ -0,0 +1,1577 @@
+//! Module for the plan of a cleanup run
+//!
+//! A run is split into two phases. First the plan is built from the config,
+//! the resolved file tasks and the state of earlier runs. Building the plan
+//! only reads the files, it decides which actions are executed for every
+//! file and records the reasons of the decisions. Afterwards the actions of
+//! the plan are executed, or only logged in a dry run. A run decides the
+//! order of the files up front, but plans every file right before it is
+//! changed, so earlier files of the run can not make its decisions stale.
+//! The complete plan is only built for '--plan'.
+//!
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::cleaner::{archives, fingerprint, line_count, open_files, symlink};
+use crate::config::config_condition::{ConditionExpr, ConditionField};
+use crate::config::{
+    CleanUpAction, CleanUpMode, Config, OpenFilePolicy, RotationPolicy, SignalTarget,
+};
+use crate::output::json::JsonValue;
+use crate::platform;
+use crate::resolver::{FileTask, glob};
+use crate::state::{self, FileAction, StateStore};
+
+/// Enum representing a single typed operation of a file plan
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Remove an archive with a last write older than the maximum archive age
+    RemoveExpiredArchive(PathBuf),
+
+    /// Remove an archive with an index outside of the kept rotations
+    RemoveStrayArchive(PathBuf),
+
+    /// Remove one of the oldest archives of a group that exceeds its total size limit
+    RemoveGroupArchive(PathBuf),
+
+    /// Remove the log file, because no rotations are kept
+    RemoveFile,
+
+    /// Rotate the log file into the archives
+    Rotate,
+
+    /// Trim the log file in place
+    Trim,
+
+    /// Remove the oldest archives until their total size is within the limit in bytes
+    EnforceArchiveSize(u64),
+
+    /// Upload the archive that was finished by the rotation to the remote location
+    UploadArchive(String),
+
+    /// Write the manifest of the archives
+    UpdateManifest,
+
+    /// Signal the process of the log file to reopen it
+    SignalProcess(SignalTarget),
+}
+
+impl Action {
+    /// Returns true when the action changes the log file itself
+    pub fn changes_file(&self) -> bool {
+        matches!(self, Action::RemoveFile | Action::Rotate | Action::Trim)
+    }
+
+    /// Returns true when the action removes an archive without touching the log file
+    pub fn removes_archive(&self) -> bool {
+        matches!(
+            self,
+            Action::RemoveExpiredArchive(_)
+                | Action::RemoveStrayArchive(_)
+                | Action::RemoveGroupArchive(_)
+        )
+    }
+
+    /// Name of the action in machine readable output
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::RemoveExpiredArchive(_) => "remove_expired_archive",
+            Action::RemoveStrayArchive(_) => "remove_stray_archive",
+            Action::RemoveGroupArchive(_) => "remove_group_archive",
+            Action::RemoveFile => "remove_file",
+            Action::Rotate => "rotate",
+            Action::Trim => "trim",
+            Action::EnforceArchiveSize(_) => "enforce_archive_size",
+            Action::UploadArchive(_) => "upload_archive",
+            Action::UpdateManifest => "update_manifest",
+            Action::SignalProcess(_) => "signal_process",
+        }
+    }
+
+    /// Convert the action into a json object with its name and parameters
+    pub fn to_json(&self) -> JsonValue {
+        let mut entries: Vec<(String, JsonValue)> =
+            vec![("action".to_string(), JsonValue::string(self.name()))];
+
+        match self {
+            Action::RemoveExpiredArchive(path)
+            | Action::RemoveStrayArchive(path)
+            | Action::RemoveGroupArchive(path) => entries.push((
+                "path".to_string(),
+                JsonValue::string(path.display().to_string()),
+            )),
+            Action::EnforceArchiveSize(max_size_bytes) => entries.push((
+                "max_size_bytes".to_string(),
+                JsonValue::uint(*max_size_bytes),
+            )),
+            Action::UploadArchive(location) => {
+                entries.push(("location".to_string(), JsonValue::string(location)))
+            }
+            Action::SignalProcess(target) => {
+                entries.push(("signal".to_string(), JsonValue::string(&target.signal)));
+                entries.push(("pidfile".to_string(), JsonValue::string(&target.pidfile)));
+            }
+            Action::RemoveFile | Action::Rotate | Action::Trim | Action::UpdateManifest => {}
+        }
+
+        JsonValue::Object(entries)
+    }
+}
+
+//Implement the Display trait
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::RemoveExpiredArchive(path) => {
+                write!(f, "remove expired archive '{}'", path.display())
+            }
+            Action::RemoveStrayArchive(path) => {
+                write!(f, "remove stray archive '{}'", path.display())
+            }
+            Action::RemoveGroupArchive(path) => {
+                write!(
+                    f,
+                    "remove archive '{}' for the group size limit",
+                    path.display()
+                )
+            }
+            Action::RemoveFile => write!(f, "remove the file, keep_rotate is zero"),
+            Action::Rotate => write!(f, "rotate the file"),
+            Action::Trim => write!(f, "trim the file"),
+            Action::EnforceArchiveSize(max_size_bytes) => {
+                write!(f, "limit the archives to {} bytes", max_size_bytes)
+            }
+            Action::UploadArchive(location) => {
+                write!(f, "upload the finished archive to '{}'", location)
+            }
+            Action::UpdateManifest => write!(f, "update the archive manifest"),
+            Action::SignalProcess(target) => {
+                write!(
+                    f,
+                    "send {} to the process of '{}'",
+                    target.signal, target.pidfile
+                )
+            }
+        }
+    }
+}
+
+/// Represents the cleanup of a group member that is selected by the total size limit of its group
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GroupLimit {
+    /// Note of the met condition when the file itself is cleaned up
+    pub note: Option<String>,
+
+    /// Oldest archives of the file that are removed for the limit
+    pub archives: Vec<PathBuf>,
+}
+
+/// Represents the planned actions of a single file task
+#[derive(Debug)]
+pub struct FilePlan {
+    /// The 1-based number of the task used for logging
+    pub task_nr: usize,
+
+    /// Configured path of the file, the state is kept for this path
+    pub path: PathBuf,
+
+    /// Task of the file that is changed, the target of a followed symbolic link
+    pub task: FileTask,
+
+    pub policy: RotationPolicy,
+
+    /// Size of the file in bytes when its conditions were checked
+    pub checked_size: Option<u64>,
+
+    /// Reasons of the decisions in the order they were made
+    pub notes: Vec<String>,
+
+    pub actions: Vec<Action>,
+
+    /// Result of the file when all actions were executed
+    pub result: FileAction,
+
+    /// Warning of a missing or skipped file that fails a run with 'fail_on = "warn"'
+    pub warning: Option<String>,
+
+    /// Error that fails the task after the planned actions
+    pub error: Option<io::Error>,
+}
+
+impl FilePlan {
+    /// Get the name of the planned result, a plan with an error always fails
+    pub fn result_name(&self) -> &'static str {
+        match self.error {
+            Some(_) => FileAction::Failed.name(),
+            None => self.result.name(),
+        }
+    }
+
+    /// Defer the changes of the log file to the next run. The removal of archives is kept,
+    /// it frees space without copying or compressing the file.
+    pub fn defer_file_changes(&mut self) {
+        self.actions.retain(Action::removes_archive);
+        self.result = FileAction::Skipped;
+        add_manifest_update(self);
+    }
+
+    /// Get the first condition that triggered the cleanup, None when no condition was met
+    pub fn trigger(&self) -> Option<String> {
+        self.notes
+            .iter()
+            .find_map(|note| note.strip_prefix("Condition met: "))
+            .map(str::to_string)
+    }
+
+    /// Convert the file plan into a json object
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            ("task".to_string(), JsonValue::uint(self.task_nr as u64)),
+            (
+                "path".to_string(),
+                JsonValue::string(self.path.display().to_string()),
+            ),
+            (
+                "target".to_string(),
+                JsonValue::string(self.task.path.display().to_string()),
+            ),
+            (
+                "group".to_string(),
+                JsonValue::optional_string(self.task.group.clone()),
+            ),
+            (
+                "priority".to_string(),
+                JsonValue::Integer(self.task.priority),
+            ),
+            ("dry_run".to_string(), JsonValue::Bool(self.policy.dry_run)),
+            ("result".to_string(), JsonValue::string(self.result_name())),
+            (
+                "notes".to_string(),
+                JsonValue::Array(self.notes.iter().map(JsonValue::string).collect()),
+            ),
+            (
+                "actions".to_string(),
+                JsonValue::Array(self.actions.iter().map(Action::to_json).collect()),
+            ),
+            (
+                "error".to_string(),
+                JsonValue::optional_string(self.error.as_ref().map(|e| e.to_string())),
+            ),
+        ])
+    }
+}
+
+/// Represents the planned actions of all file tasks of a run
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub files: Vec<FilePlan>,
+
+    /// When set to true the actions of the plan are only logged
+    pub dry_run: bool,
+}
+
+impl Plan {
+    /// Returns true when the plan of at least one file has failed
+    pub fn has_errors(&self) -> bool {
+        self.files.iter().any(|file_plan| file_plan.error.is_some())
+    }
+
+    /// Convert the plan into a json document
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            ("dry_run".to_string(), JsonValue::Bool(self.dry_run)),
+            (
+                "files".to_string(),
+                JsonValue::Array(self.files.iter().map(FilePlan::to_json).collect()),
+            ),
+        ])
+    }
+
+    /// Print the plan as readable text
+    pub fn print_text(&self) {
+        println!("Plan for {} files:", self.files.len());
+
+        for file_plan in self.files.iter() {
+            let group: String = match &file_plan.task.group {
+                Some(name) => format!(" (group '{}')", name),
+                None => String::new(),
+            };
+
+            println!(
+                "[{}] {}{}: {}",
+                file_plan.task_nr,
+                file_plan.path.display(),
+                group,
+                file_plan.result_name()
+            );
+
+            for note in file_plan.notes.iter() {
+                println!("    {}", note);
+            }
+
+            for action in file_plan.actions.iter() {
+                println!("    - {}", action);
+            }
+
+            if let Some(e) = &file_plan.error {
+                println!("    Error: {}", e);
+            }
+        }
+    }
+}
+
+/// Build the plan of all file tasks, no file is changed.
+/// Only the files that match a pattern of '--match' are planned.
+pub fn build_plan(config: &Config, tasks: &[FileTask], state_store: &StateStore) -> Plan {
+    Plan {
+        dry_run: config.dry_run,
+        files: order_tasks(config, tasks)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (task, group_limit))| {
+                plan_file(idx + 1, task, config, state_store, group_limit.as_ref())
+            })
+            .collect(),
+    }
+}
+
+/// Get the selected tasks in the order of the run with the cleanup of their group size limit.
+/// The files are ordered by their priority, the files of a group stay together.
+pub fn order_tasks<'a>(
+    config: &Config,
+    tasks: &'a [FileTask],
+) -> Vec<(&'a FileTask, Option<GroupLimit>)> {
+    let mut ordered_tasks: Vec<&FileTask> = tasks
+        .iter()
+        .filter(|task| is_selected(&task.path, &config.selected_patterns))
+        .collect();
+    ordered_tasks.sort_by_key(|task| task_order(config, task));
+
+    //The size limit of a group includes the files that are not selected
+    let group_limits: HashMap<PathBuf, GroupLimit> = find_group_limits(config, tasks);
+
+    ordered_tasks
+        .into_iter()
+        .map(|task| (task, group_limits.get(&task.path).cloned()))
+        .collect()
+}
+
+/// Find the cleanup of the groups that exceed their total size limit. The total includes the
+/// archives of the members, the oldest archives are removed first. When the live files alone
+/// exceed the limit, the oldest files are selected until the remaining size is within the limit.
+/// Returns the cleanup of the selected members by their path.
+fn find_group_limits(config: &Config, tasks: &[FileTask]) -> HashMap<PathBuf, GroupLimit> {
+    let mut selected: HashMap<PathBuf, GroupLimit> = HashMap::new();
+
+    for group in config.groups.iter() {
+        let Some(max_total_size_bytes) = group.max_total_size_bytes else {
+            continue;
+        };
+        let group_tasks: Vec<&FileTask> = tasks
+            .iter()
+            .filter(|task| task.group.as_ref() == Some(&group.name))
+            .collect();
+
+        //Missing files do not use any disk space and are never selected
+        let mut members: Vec<(SystemTime, u64, &PathBuf)> = group_tasks
+            .iter()
+            .filter_map(|task| {
+                let metadata: Metadata = fs::metadata(&task.path).ok()?;
+                let size: u64 = platform::file_size(&metadata, config.allocated_size);
+                Some((metadata.modified().ok()?, size, &task.path))
+            })
+            .collect();
+
+        //Members with the same olddir and file name share their archives
+        let mut group_archives: Vec<(SystemTime, u64, PathBuf, &PathBuf)> = group_tasks
+            .iter()
+            .flat_map(|task| {
+                find_member_archives(config, task)
+                    .into_iter()
+                    .map(|(modified, size, path)| (modified, size, path, &task.path))
+            })
+            .collect();
+        group_archives.sort();
+        group_archives.dedup_by(|a, b| a.2 == b.2);
+
+        let total_size: u64 = members.iter().map(|(_, size, _)| size).sum::<u64>()
+            + group_archives
+                .iter()
+                .map(|(_, size, _, _)| size)
+                .sum::<u64>();
+        if total_size <= max_total_size_bytes {
+            continue;
+        }
+
+        let mut remaining_size: u64 = total_size;
+
+        for (_, size, archive_path, member_path) in group_archives {
+            if remaining_size <= max_total_size_bytes {
+                break;
+            }
+
+            remaining_size -= size;
+            selected
+                .entry(member_path.clone())
+                .or_default()
+                .archives
+                .push(archive_path);
+        }
+
+        //The live files are only cleaned up when the archives alone can not meet the limit
+        members.sort();
+
+        for (_, size, path) in members {
+            if remaining_size <= max_total_size_bytes {
+                break;
+            }
+
+            remaining_size -= size;
+            selected.entry(path.clone()).or_default().note = Some(format!(
+                "Condition met: Group '{}' size ({} bytes) exceeds limit ({} bytes)",
+                group.name, total_size, max_total_size_bytes
+            ));
+        }
+    }
+
+    selected
+}
+
+/// Find the archives of a group member with their modification time and size.
+/// Archives that can not be read are not counted.
+fn find_member_archives(config: &Config, task: &FileTask) -> Vec<(SystemTime, u64, PathBuf)> {
+    let rotated_task: FileTask = symlink::rotated_task(task, config.symlinks);
+    let Ok(rotation_base) = archives::archive_base_path(&rotated_task) else {
+        return Vec::new();
+    };
+
+    archives::find_archives(&rotation_base)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|archive| {
+            let metadata: Metadata = fs::metadata(&archive.path).ok()?;
+            let size: u64 = platform::file_size(&metadata, config.allocated_size);
+            Some((archive.modified, size, archive.path))
+        })
+        .collect()
+}
+
+/// Returns true when the path is matched by any of the selected patterns or no pattern is selected.
+/// Patterns without '/' are matched against the file name, others against the whole path.
+fn is_selected(path: &Path, patterns: &[String]) -> bool {
+    let file_name: String = path
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+    let full_path: String = path.display().to_string();
+
+    patterns.is_empty()
+        || patterns.iter().any(|pattern| match pattern.contains('/') {
+            true => glob::matches(pattern, &full_path),
+            false => glob::matches(pattern, &file_name),
+        })
+}
+
+/// Check that the path is inside of one of the allowed roots.
+/// Existing directories are canonicalized, so links and '..' can not leave a root.
+fn check_allowed_roots(path: &Path, allowed_roots: &[String]) -> Result<(), io::Error> {
+    let resolved: PathBuf = resolve_parent_dir(path)?;
+
+    for root in allowed_roots.iter() {
+        let root_path: PathBuf =
+            fs::canonicalize(root).unwrap_or_else(|_| normalize_path(Path::new(root)));
+
+        if resolved.starts_with(&root_path) {
+            return Ok(());
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!(
+            "Path '{}' is outside of the allowed roots: {}",
+            path.display(),
+            allowed_roots.join(", ")
+        ),
+    ))
+}
+
+/// Resolve the path with the canonical path of its parent directory, the file itself is kept.
+/// The '..' components are resolved by the filesystem after the links, a parent directory
+/// that can not be canonicalized is only accepted when the path contains no '..' component.
+fn resolve_parent_dir(path: &Path) -> Result<PathBuf, io::Error> {
+    let absolute: PathBuf = std::path::absolute(path)?;
+
+    if let (Some(parent), Some(name)) = (absolute.parent(), absolute.file_name())
+        && let Ok(canonical_parent) = fs::canonicalize(parent)
+    {
+        return Ok(canonical_parent.join(name));
+    }
+
+    match absolute
+        .components()
+        .any(|component| component == Component::ParentDir)
+    {
+        true => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Path '{}' contains '..' and its parent directory can not be resolved",
+                path.display()
+            ),
+        )),
+        false => Ok(absolute),
+    }
+}
+
+/// Remove the '.' and '..' components of the path without accessing the filesystem
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+/// Get the sort key of a task, a group is ordered by the priority of the group
+/// and a file without a group by its own priority. The sort is stable, so files
+/// with the same priority keep the order of the config.
+fn task_order(config: &Config, task: &FileTask) -> (Reverse<i64>, usize, Reverse<i64>) {
+    let group_position: Option<usize> = task
+        .group
+        .as_ref()
+        .and_then(|name| config.groups.iter().position(|g| &g.name == name));
+
+    match group_position {
+        Some(position) => (
+            Reverse(config.groups[position].priority.unwrap_or(0)),
+            position + 1,
+            Reverse(task.priority),
+        ),
+        None => (Reverse(task.priority), 0, Reverse(0)),
+    }
+}
+
+/// Build the plan of a single file task
+pub fn plan_file(
+    task_nr: usize,
+    task: &FileTask,
+    config: &Config,
+    state_store: &StateStore,
+    group_limit: Option<&GroupLimit>,
+) -> FilePlan {
+    let mut file_plan = FilePlan {
+        task_nr,
+        path: task.path.clone(),
+        task: task.clone(),
+        policy: task.overrides.resolve(config),
+        checked_size: None,
+        notes: Vec::new(),
+        actions: Vec::new(),
+        result: FileAction::Skipped,
+        warning: None,
+        error: None,
+    };
+
+    if let Err(e) = decide_actions(&mut file_plan, config, state_store, group_limit) {
+        file_plan.error = Some(e);
+    }
+
+    add_manifest_update(&mut file_plan);
+    file_plan
+}
+
+/// Removed archives must not stay in the manifest, even without a rotation
+fn add_manifest_update(file_plan: &mut FilePlan) {
+    let removes_archives: bool = file_plan.actions.iter().any(Action::removes_archive);
+
+    if file_plan.policy.manifest
+        && removes_archives
+        && !file_plan.actions.contains(&Action::UpdateManifest)
+    {
+        file_plan.actions.push(Action::UpdateManifest);
+    }
+}
+
+/// Decide the actions of the file plan, the checks stop at the first skip reason.
+/// Files that are selected by the size limit of their group are cleaned up without
+/// checking their own conditions, the note of the group limit is passed instead.
+fn decide_actions(
+    file_plan: &mut FilePlan,
+    config: &Config,
+    state_store: &StateStore,
+    group_limit: Option<&GroupLimit>,
+) -> Result<(), io::Error> {
+    let policy: &RotationPolicy = &file_plan.policy;
+    let notes: &mut Vec<String> = &mut file_plan.notes;
+
+    //Symbolic links are resolved to their final target before any operation
+    match symlink::resolve_target(&file_plan.path, policy.symlinks)? {
+        None => {
+            notes.push("File skipped: symbolic link".to_string());
+            file_plan.warning = Some("symbolic link skipped".to_string());
+            return Ok(());
+        }
+        Some(target) if target != file_plan.path => {
+            notes.push(format!("Following symbolic link to '{}'", target.display()));
+            file_plan.task.path = target;
+        }
+        Some(_) => {}
+    }
+    let task: &FileTask = &file_plan.task;
+    let file_path: &Path = &task.path;
+
+    //Files outside of the allowed roots are refused, also when they are reached by a link
+    if !config.allowed_roots.is_empty() {
+        check_allowed_roots(&file_plan.path, &config.allowed_roots)?;
+        check_allowed_roots(file_path, &config.allowed_roots)?;
+        check_allowed_roots(&archives::archive_base_path(task)?, &config.allowed_roots)?;
+    }
+
+    //Check for file existence and type
+    if !file_path.exists() {
+        if config.missing_files_ok {
+            notes.push("File not found, missing file is configured as okay".to_string());
+            file_plan.result = FileAction::Missing;
+            file_plan.warning = Some("file not found".to_string());
+            return Ok(());
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("File not found: {}", file_path.display()),
+            ));
+        }
+    }
+
+    //Check that the path is a file
+    if !file_path.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Path is not a file: {}", file_path.display()),
+        ));
+    }
+
+    //Expired archives are removed in every run, even when the file is not rotated
+    if let Some(max_age_s) = policy.max_archive_age_s {
+        let expired =
+            archives::find_expired_archives(&archives::archive_base_path(task)?, max_age_s)?;
+        file_plan.actions.extend(
+            expired
+                .into_iter()
+                .map(|archive| Action::RemoveExpiredArchive(archive.path)),
+        );
+    }
+
+    //Archives of earlier configs with more rotations would never be shifted out
+    if config.remove_stray_archives && policy.action == CleanUpAction::Rotate {
+        let stray = archives::find_stray_archives(
+            &archives::archive_base_path(task)?,
+            policy.rotate_start,
+            policy.keep_rotate,
+        )?;
+
+        for archive in stray {
+            if !file_plan
+                .actions
+                .contains(&Action::RemoveExpiredArchive(archive.path.clone()))
+            {
+                file_plan
+                    .actions
+                    .push(Action::RemoveStrayArchive(archive.path));
+            }
+        }
+    }
+
+    //The oldest archives of a group are removed before any file of the group is cleaned up
+    for archive_path in group_limit.iter().flat_map(|limit| limit.archives.iter()) {
+        let planned: bool = file_plan.actions.iter().any(|action| match action {
+            Action::RemoveExpiredArchive(path) | Action::RemoveStrayArchive(path) => {
+                path == archive_path
+            }
+            _ => false,
+        });
+
+        if !planned {
+            file_plan
+                .actions
+                .push(Action::RemoveGroupArchive(archive_path.clone()));
+        }
+    }
+
+    //The size is checked again before the rotation to detect files that are still growing
+    let checked_size: u64 = fs::metadata(file_path)?.len();
+    file_plan.checked_size = Some(checked_size);
+
+    //Empty placeholder files would only create empty archives
+    if !config.rotate_empty && checked_size == 0 {
+        notes.push("File skipped: empty".to_string());
+        return Ok(());
+    }
+
+    //Check if a cleanup is needed for the current file
+    match group_limit.and_then(|limit| limit.note.as_ref()) {
+        Some(note) => notes.push(note.clone()),
+        None => {
+            if !check_cleanup_conditions(file_path, policy, notes)? {
+                notes.push("No cleanup conditions met".to_string());
+                return Ok(());
+            }
+        }
+    }
+
+    //Rotations within the window of the frequency are skipped
+    if let Some(frequency) = policy.frequency
+        && let Some(last_rotation) = state_store
+            .get(&file_plan.path)
+            .and_then(|s| s.last_rotation)
+    {
+        let elapsed_s: u64 = state::unix_time_now().saturating_sub(last_rotation);
+
+        if elapsed_s < frequency.window_s() {
+            notes.push(format!(
+                "Rotation skipped: Last rotation ({} h ago) is within the {:?} frequency",
+                elapsed_s / 3600,
+                frequency
+            ));
+            return Ok(());
+        }
+    }
+
+    //Idle files would only create archives with the content of the last rotation
+    if config.skip_unchanged
+        && let Some(last_fingerprint) = state_store
+            .get(&file_plan.path)
+            .and_then(|s| s.fingerprint.as_ref())
+        && fingerprint::file_fingerprint(file_path)? == *last_fingerprint
+    {
+        notes.push("Rotation skipped: Content is unchanged since the last rotation".to_string());
+        return Ok(());
+    }
+
+    //A process that still writes a renamed file would write into the archive
+    let mut switch_to_copy_truncate: bool = false;
+    if policy.action == CleanUpAction::Rotate
+        && policy.keep_rotate > 0
+        && !policy.copy_truncate
+        && config.open_files != OpenFilePolicy::Ignore
+    {
+        let writers = open_files::find_writers(file_path)?;
+
+        if !writers.is_empty() {
+            let names: String = open_files::describe_writers(&writers);
+
+            match config.open_files {
+                OpenFilePolicy::Warn => {
+                    notes.push(format!("Warning: File is open for writing by {}", names));
+                    file_plan.warning = Some(format!("open for writing by {}", names));
+                }
+                OpenFilePolicy::CopyTruncate => {
+                    notes.push(format!(
+                        "Using copy_truncate: File is open for writing by {}",
+                        names
+                    ));
+                    switch_to_copy_truncate = true;
+                }
+                OpenFilePolicy::Skip => {
+                    notes.push(format!(
+                        "Rotation skipped: File is open for writing by {}",
+                        names
+                    ));
+                    file_plan.warning = Some(format!("open for writing by {}", names));
+                    return Ok(());
+                }
+                OpenFilePolicy::Ignore => {}
+            }
+        }
+    }
+
+    let (cleanup_action, result) = match (policy.action, policy.keep_rotate) {
+        (CleanUpAction::Rotate, 0) => (Action::RemoveFile, FileAction::Removed),
+        (CleanUpAction::Rotate, _) => (Action::Rotate, FileAction::Rotated),
+        (CleanUpAction::Trim, _) => (Action::Trim, FileAction::Trimmed),
+    };
+    file_plan.actions.push(cleanup_action);
+    file_plan.result = result;
+
+    //Let the process reopen its log file right after the rotation, so its writes are not
+    //delayed by the upload and the housekeeping, failures of these never skip the signal
+    if let Some(target) = &task.signal {
+        file_plan
+            .actions
+            .push(Action::SignalProcess(target.clone()));
+    }
+
+    //The archive is uploaded before the size limit can remove it
+    if let Some(upload) = &config.upload
+        && result == FileAction::Rotated
+    {
+        file_plan
+            .actions
+            .push(Action::UploadArchive(upload.backend.location()));
+    }
+
+    //The size limit also includes the archive of this rotation
+    if let Some(max_size_bytes) = policy.max_archive_size_bytes {
+        file_plan
+            .actions
+            .push(Action::EnforceArchiveSize(max_size_bytes));
+    }
+
+    //The manifest lists the archive of this rotation with its checksum
+    if policy.manifest && policy.action == CleanUpAction::Rotate {
+        file_plan.actions.push(Action::UpdateManifest);
+    }
+
+    if switch_to_copy_truncate {
+        file_plan.policy.copy_truncate = true;
+    }
+
+    Ok(())
+}
+
+/// Check if the cleanup should be performed for a given file and rotation policy
+fn check_cleanup_conditions(
+    file_path: &Path,
+    policy: &RotationPolicy,
+    notes: &mut Vec<String>,
+) -> Result<bool, io::Error> {
+    let metadata = fs::metadata(file_path)?;
+
+    //A condition expression of the file entry replaces the checks of the mode
+    let mut cleanup_needed: bool = match &policy.condition {
+        Some(condition) => check_condition_expr(
+            file_path,
+            &metadata,
+            condition,
+            policy.allocated_size,
+            notes,
+        )?,
+        None => check_mode_conditions(file_path, &metadata, policy, notes)?,
+    };
+
+    //Small files are kept, even when a condition was met
+    if cleanup_needed
+        && let Some(min_size_bytes) = policy.retention.min_size_bytes
+        && platform::file_size(&metadata, policy.allocated_size) < min_size_bytes
+    {
+        notes.push(format!(
+            "Cleanup skipped: File size ({} bytes) is below minimum size ({} bytes)",
+            platform::file_size(&metadata, policy.allocated_size),
+            min_size_bytes
+        ));
+        cleanup_needed = false;
+    }
+
+    //Fresh files are kept, for example a replayed log that already exceeds the size limit
+    if cleanup_needed && let Some(min_age_s) = policy.retention.min_age_s {
+        let file_age_s: u64 = SystemTime::now()
+            .duration_since(created_time(&metadata, notes)?)
+            .map_or(0, |duration| duration.as_secs());
+
+        if file_age_s < min_age_s {
+            notes.push(format!(
+                "Cleanup skipped: File age ({} s) is below minimum age ({} s)",
+                file_age_s, min_age_s
+            ));
+            cleanup_needed = false;
+        }
+    }
+
+    Ok(cleanup_needed)
+}
+
+/// Evaluate the condition expression of a file entry
+fn check_condition_expr(
+    file_path: &Path,
+    metadata: &Metadata,
+    condition: &ConditionExpr,
+    allocated_size: bool,
+    notes: &mut Vec<String>,
+) -> Result<bool, io::Error> {
+    let seconds_since = |time: SystemTime| {
+        SystemTime::now()
+            .duration_since(time)
+            .map_or(0, |duration| duration.as_secs())
+    };
+
+    let condition_met: bool = condition.root.evaluate(&mut |field, value| match field {
+        ConditionField::Size => Ok(platform::file_size(metadata, allocated_size)),
+        ConditionField::Age => Ok(seconds_since(metadata.modified()?)),
+        ConditionField::FileAge => Ok(seconds_since(created_time(metadata, notes)?)),
+        ConditionField::Lines => line_count::count_lines_capped(file_path, value),
+        ConditionField::FreePercent => Ok(platform::filesystem_space(file_path)?.free_percent()),
+    })?;
+
+    if condition_met {
+        notes.push(format!("Condition met: {}", condition));
+    }
+
+    Ok(condition_met)
+}
+
+/// Enum representing the single checks of the cleanup modes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ModeCheck {
+    FileSize,
+    LastWrite,
+    FileAge,
+    LineCount,
+    DiskFree,
+}
+
+/// Get the checks of the cleanup mode, combined modes only use checks with a threshold
+fn mode_checks(policy: &RotationPolicy) -> Vec<ModeCheck> {
+    match policy.mode {
+        CleanUpMode::FileSize => vec![ModeCheck::FileSize],
+        CleanUpMode::LastWrite => vec![ModeCheck::LastWrite],
+        CleanUpMode::FileAge => vec![ModeCheck::FileAge],
+        CleanUpMode::LineCount => vec![ModeCheck::LineCount],
+        CleanUpMode::DiskFree => vec![ModeCheck::DiskFree],
+        CleanUpMode::AnyOf | CleanUpMode::AllOf => {
+            let retention = &policy.retention;
+            let optional_checks = [
+                (ModeCheck::FileAge, retention.file_age_h.is_some()),
+                (ModeCheck::LineCount, retention.max_lines.is_some()),
+                (ModeCheck::DiskFree, retention.min_free_percent.is_some()),
+            ];
+
+            let mut checks: Vec<ModeCheck> = vec![ModeCheck::FileSize, ModeCheck::LastWrite];
+            checks.extend(
+                optional_checks
+                    .iter()
+                    .filter(|(_, enabled)| *enabled)
+                    .map(|(check, _)| *check),
+            );
+            checks
+        }
+    }
+}
+
+/// Check the conditions of the cleanup mode of the policy.
+/// The checks stop as soon as the result of the combination is known.
+fn check_mode_conditions(
+    file_path: &Path,
+    metadata: &Metadata,
+    policy: &RotationPolicy,
+    notes: &mut Vec<String>,
+) -> Result<bool, io::Error> {
+    let require_all: bool = policy.mode == CleanUpMode::AllOf;
+
+    for check in mode_checks(policy) {
+        let condition_met: bool = run_mode_check(file_path, metadata, policy, check, notes)?;
+
+        if condition_met != require_all {
+            return Ok(condition_met);
+        }
+    }
+
+    Ok(require_all)
+}
+
+/// Run a single check of a cleanup mode, met conditions are noted
+fn run_mode_check(
+    file_path: &Path,
+    metadata: &Metadata,
+    policy: &RotationPolicy,
+    check: ModeCheck,
+    notes: &mut Vec<String>,
+) -> Result<bool, io::Error> {
+    let retention = &policy.retention;
+
+    match check {
+        ModeCheck::FileSize => {
+            let size_limit_bytes: u64 = retention.file_size_mib * 1024 * 1024;
+
+            let file_size: u64 = platform::file_size(metadata, policy.allocated_size);
+
+            if file_size > size_limit_bytes {
+                notes.push(format!(
+                    "Condition met: File size ({} MiB) exceeds limit ({} MiB)",
+                    file_size / 1024 / 1024,
+                    retention.file_size_mib
+                ));
+                return Ok(true);
+            }
+        }
+        ModeCheck::LastWrite => {
+            let modified_time = metadata.modified()?;
+
+            if let Ok(duration_since_write) = SystemTime::now().duration_since(modified_time) {
+                let time_limit_duration = Duration::from_secs(retention.last_write_h * 3600);
+
+                //Check if the age of the file exceeds the limit
+                if duration_since_write > time_limit_duration {
+                    //Calculate hours for readable output
+                    let duration_since_write_h: u64 = duration_since_write.as_secs() / 3600;
+                    let time_limit_duration_h: u64 = time_limit_duration.as_secs() / 3600;
+
+                    notes.push(format!(
+                        "Condition met: Last write age ({} h) exceeds limit ({} h)",
+                        duration_since_write_h, time_limit_duration_h
+                    ));
+                    return Ok(true);
+                }
+            }
+        }
+        ModeCheck::FileAge => {
+            let Some(file_age_h) = retention.file_age_h else {
+                return Ok(false);
+            };
+
+            let created_time: SystemTime = created_time(metadata, notes)?;
+
+            if let Ok(file_age) = SystemTime::now().duration_since(created_time)
+                && file_age > Duration::from_secs(file_age_h * 3600)
+            {
+                notes.push(format!(
+                    "Condition met: File age ({} h) exceeds limit ({} h)",
+                    file_age.as_secs() / 3600,
+                    file_age_h
+                ));
+                return Ok(true);
+            }
+        }
+        ModeCheck::LineCount => {
+            let Some(max_lines) = retention.max_lines else {
+                return Ok(false);
+            };
+
+            let line_count: u64 = line_count::count_lines_capped(file_path, max_lines)?;
+
+            if line_count > max_lines {
+                notes.push(format!(
+                    "Condition met: Line count (at least {}) exceeds limit ({})",
+                    line_count, max_lines
+                ));
+                return Ok(true);
+            }
+        }
+        ModeCheck::DiskFree => {
+            let Some(min_free_percent) = retention.min_free_percent else {
+                return Ok(false);
+            };
+
+            let free_percent: u64 = platform::filesystem_space(file_path)?.free_percent();
+
+            if free_percent < min_free_percent {
+                notes.push(format!(
+                    "Condition met: Free disk space ({}%) is below limit ({}%)",
+                    free_percent, min_free_percent
+                ));
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+/// Get the creation time of a file.
+/// The last write is never before the creation, so the age is not overestimated
+/// when the last write is used on filesystems without creation time.
+fn created_time(metadata: &Metadata, notes: &mut Vec<String>) -> Result<SystemTime, io::Error> {
+    match metadata.created() {
+        Ok(created_time) => Ok(created_time),
+        Err(_) => {
+            notes.push("Creation time is not supported, using the last write instead".to_string());
+            metadata.modified()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FileOverrides;
+    use crate::config::config_parser;
+    use crate::config::toml_parser;
+    use crate::resolver;
+    use std::env;
+    use std::fs::{File, FileTimes};
+
+    #[test]
+    fn test_build_plan() {
+        let dir = env::temp_dir().join(format!("yalc_test_plan_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join("app.log"), "line\n").unwrap();
+        fs::write(dir.join("app.log.1"), "old\n").unwrap();
+        File::options()
+            .write(true)
+            .open(dir.join("app.log.1"))
+            .unwrap()
+            .set_times(
+                FileTimes::new().set_modified(SystemTime::now() - Duration::from_secs(3 * 86400)),
+            )
+            .unwrap();
+
+        let root = toml_parser::parse_toml(&format!(
+            r#"
+config_version = 2
+dry_run = false
+mode = "LastWrite"
+keep_rotate = 3
+missing_files_ok = false
+copy_truncate = false
+manifest = true
+max_archive_age = "1d"
+file_list = ["{0}/app.log", "{0}/missing.log"]
+
+[retention]
+file_size_mib = 1
+last_write_h = 0
+"#,
+            dir.display()
+        ))
+        .unwrap();
+        let config = config_parser::parse_config(&root).unwrap();
+        let tasks = resolver::resolve_tasks(&config);
+        let state_store = StateStore::new(&dir.join("state.toml"));
+
+        let plan = build_plan(&config, &tasks, &state_store);
+        assert_eq!(plan.files.len(), 2);
+
+        let file_plan = &plan.files[0];
+        assert_eq!(
+            file_plan.actions,
+            vec![
+                Action::RemoveExpiredArchive(dir.join("app.log.1")),
+                Action::Rotate,
+                Action::UpdateManifest,
+            ]
+        );
+        assert_eq!(file_plan.result, FileAction::Rotated);
+        assert!(file_plan.notes[0].starts_with("Condition met: Last write age"));
+        assert!(file_plan.error.is_none());
+
+        //Building the plan never changes a file
+        assert!(dir.join("app.log.1").exists());
+        assert!(!dir.join("app.log.0").exists());
+
+        let missing_plan = &plan.files[1];
+        assert!(missing_plan.actions.is_empty());
+        assert!(missing_plan.error.is_some());
+        assert!(plan.has_errors());
+
+        let json: String = plan.to_json().to_pretty_string();
+        assert!(json.contains("\"action\": \"rotate\""));
+        assert!(json.contains("\"result\": \"failed\""));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_plan_signal_after_rotation() {
+        let dir = env::temp_dir().join(format!("yalc_test_plan_signal_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("app.log"), "line\n").unwrap();
+
+        let root = toml_parser::parse_toml(&format!(
+            r#"
+config_version = 2
+dry_run = false
+mode = "LastWrite"
+keep_rotate = 3
+missing_files_ok = false
+copy_truncate = false
+manifest = true
+max_archive_size = "1MiB"
+file_list = []
+
+[retention]
+file_size_mib = 1
+last_write_h = 0
+
+[[files]]
+path = "{0}/app.log"
+signal = "HUP"
+pidfile = "{0}/app.pid"
+"#,
+            dir.display()
+        ))
+        .unwrap();
+        let config = config_parser::parse_config(&root).unwrap();
+        let tasks = resolver::resolve_tasks(&config);
+        let state_store = StateStore::new(&dir.join("state.toml"));
+
+        let plan = build_plan(&config, &tasks, &state_store);
+        let target = SignalTarget {
+            signal: "HUP".to_string(),
+            pidfile: format!("{}/app.pid", dir.display()),
+        };
+        assert_eq!(
+            plan.files[0].actions,
+            vec![
+                Action::Rotate,
+                Action::SignalProcess(target),
+                Action::EnforceArchiveSize(1024 * 1024),
+                Action::UpdateManifest,
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_plan_min_age() {
+        let dir = env::temp_dir().join(format!("yalc_test_plan_min_age_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("app.log"), "line\n").unwrap();
+
+        let root = toml_parser::parse_toml(&format!(
+            r#"
+config_version = 2
+dry_run = false
+mode = "LastWrite"
+keep_rotate = 3
+missing_files_ok = false
+copy_truncate = false
+file_list = ["{0}/app.log"]
+
+[retention]
+file_size_mib = 1
+last_write_h = 0
+min_age = "1h"
+"#,
+            dir.display()
+        ))
+        .unwrap();
+        let config = config_parser::parse_config(&root).unwrap();
+        let tasks = resolver::resolve_tasks(&config);
+        let state_store = StateStore::new(&dir.join("state.toml"));
+
+        //The condition is met, but the file was just created
+        let plan = build_plan(&config, &tasks, &state_store);
+        assert!(plan.files[0].actions.is_empty());
+        assert_eq!(plan.files[0].result, FileAction::Skipped);
+        assert!(
+            plan.files[0]
+                .notes
+                .iter()
+                .any(|note| note.contains("below minimum age"))
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_plan_file_when_applied() {
+        let dir = env::temp_dir().join(format!("yalc_test_plan_applied_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("app.log"), "line\n").unwrap();
+
+        let root = toml_parser::parse_toml(&format!(
+            r#"
+config_version = 2
+dry_run = false
+mode = "FileSize"
+keep_rotate = 3
+missing_files_ok = false
+copy_truncate = false
+file_list = ["{0}/app.log"]
+
+[retention]
+file_size_mib = 1
+last_write_h = 0
+"#,
+            dir.display()
+        ))
+        .unwrap();
+        let config = config_parser::parse_config(&root).unwrap();
+        let tasks = resolver::resolve_tasks(&config);
+        let state_store = StateStore::new(&dir.join("state.toml"));
+
+        //The order is decided up front, the conditions of the file are checked when it is planned
+        let ordered_tasks = order_tasks(&config, &tasks);
+        assert_eq!(ordered_tasks.len(), 1);
+        let (task, group_limit) = &ordered_tasks[0];
+        assert_eq!(
+            plan_file(1, task, &config, &state_store, group_limit.as_ref()).result,
+            FileAction::Skipped
+        );
+
+        fs::write(dir.join("app.log"), "x".repeat(2 * 1024 * 1024)).unwrap();
+        assert_eq!(
+            plan_file(1, task, &config, &state_store, group_limit.as_ref()).result,
+            FileAction::Rotated
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_plan_group_total_size() {
+        let dir = env::temp_dir().join(format!("yalc_test_plan_group_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        //The files are 100 bytes each, a.log is the oldest and c.log the newest
+        for (name, age_s) in [("c.log", 100), ("a.log", 300), ("b.log", 200)] {
+            let file = File::create(dir.join(name)).unwrap();
+            file.set_len(100).unwrap();
+            let modified = SystemTime::now() - Duration::from_secs(age_s);
+            file.set_times(FileTimes::new().set_modified(modified))
+                .unwrap();
+        }
+
+        let root = toml_parser::parse_toml(&format!(
+            r#"
+config_version = 2
+dry_run = false
+mode = "FileSize"
+keep_rotate = 3
+missing_files_ok = false
+copy_truncate = false
+file_list = []
+
+[retention]
+file_size_mib = 1
+last_write_h = 1
+
+[group.containers]
+paths = ["{0}/*.log"]
+max_total_size = "150B"
+"#,
+            dir.display()
+        ))
+        .unwrap();
+        let config = config_parser::parse_config(&root).unwrap();
+        let tasks = resolver::resolve_tasks(&config);
+        let state_store = StateStore::new(&dir.join("state.toml"));
+
+        //The two oldest files are rotated until the newest file is within the limit
+        let plan = build_plan(&config, &tasks, &state_store);
+        let rotated: Vec<&str> = plan
+            .files
+            .iter()
+            .filter(|file_plan| file_plan.result == FileAction::Rotated)
+            .map(|file_plan| file_plan.path.file_name().unwrap().to_str().unwrap())
+            .collect();
+
+        assert_eq!(rotated.len(), 2);
+        assert!(rotated.contains(&"a.log") && rotated.contains(&"b.log"));
+
+        //The archives count towards the limit and the oldest archive is removed first
+        let archive = File::create(dir.join("c.log.1")).unwrap();
+        archive.set_len(100).unwrap();
+        archive
+            .set_times(FileTimes::new().set_modified(SystemTime::now() - Duration::from_secs(400)))
+            .unwrap();
+
+        let plan = build_plan(&config, &tasks, &state_store);
+        let c_plan = plan
+            .files
+            .iter()
+            .find(|file_plan| file_plan.path.ends_with("c.log"))
+            .unwrap();
+        assert_eq!(
+            c_plan.actions,
+            vec![Action::RemoveGroupArchive(dir.join("c.log.1"))]
+        );
+        assert_eq!(
+            plan.files
+                .iter()
+                .filter(|file_plan| file_plan.result == FileAction::Rotated)
+                .count(),
+            2
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_selected() {
+        let path = Path::new("/var/log/nginx/access.log");
+        assert!(is_selected(path, &[]));
+        assert!(is_selected(path, &["access*".to_string()]));
+        assert!(is_selected(path, &["/var/log/nginx/*.log".to_string()]));
+        assert!(!is_selected(path, &["nginx*".to_string()]));
+        assert!(!is_selected(path, &["/var/log/*.log".to_string()]));
+        assert!(is_selected(
+            path,
+            &["error.log".to_string(), "*.log".to_string()]
+        ));
+    }
+
+    #[test]
+    fn test_check_allowed_roots() {
+        let roots: Vec<String> = vec!["/var/log".to_string(), "/opt/app/logs".to_string()];
+
+        assert!(check_allowed_roots(Path::new("/var/log/app.log"), &roots).is_ok());
+        assert!(check_allowed_roots(Path::new("/opt/app/logs/a/b.log"), &roots).is_ok());
+        assert!(check_allowed_roots(Path::new("/var/log/../../etc/passwd"), &roots).is_err());
+        assert!(check_allowed_roots(Path::new("/var/logs/app.log"), &roots).is_err());
+        assert!(check_allowed_roots(Path::new("/etc/passwd"), &roots).is_err());
+        assert!(check_allowed_roots(Path::new("/var/log/missing/../app.log"), &roots).is_err());
+        assert_eq!(
+            normalize_path(Path::new("/var/./log/../tmp/a.log")),
+            PathBuf::from("/var/tmp/a.log")
+        );
+    }
+
+    #[test]
+    fn test_combined_mode_checks() {
+        let mut config: Config = Config::test_config();
+        config.mode = CleanUpMode::AnyOf;
+
+        //Without optional thresholds only the size and the last write are checked
+        let policy: RotationPolicy = FileOverrides::default().resolve(&config);
+        assert_eq!(
+            mode_checks(&policy),
+            vec![ModeCheck::FileSize, ModeCheck::LastWrite]
+        );
+
+        config.mode = CleanUpMode::AllOf;
+        config.retention.max_lines = Some(10);
+        config.retention.min_free_percent = Some(5);
+        let policy: RotationPolicy = FileOverrides::default().resolve(&config);
+        assert_eq!(
+            mode_checks(&policy),
+            vec![
+                ModeCheck::FileSize,
+                ModeCheck::LastWrite,
+                ModeCheck::LineCount,
+                ModeCheck::DiskFree
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combined_mode_short_circuit() {
+        let dir = env::temp_dir().join(format!("yalc_test_mode_combined_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join("app.log"), "x".repeat(2 * 1024 * 1024)).unwrap();
+        let metadata: Metadata = fs::metadata(dir.join("app.log")).unwrap();
+
+        //Counting the lines of the missing file fails, so it must never be checked
+        let missing_path: PathBuf = dir.join("missing.log");
+        let mut config: Config = Config::test_config();
+        config.retention.file_size_mib = 1;
+        config.retention.max_lines = Some(10);
+
+        //Any of the checks: the exceeded size is enough
+        config.mode = CleanUpMode::AnyOf;
+        let policy: RotationPolicy = FileOverrides::default().resolve(&config);
+        let mut notes: Vec<String> = Vec::new();
+        assert!(check_mode_conditions(&missing_path, &metadata, &policy, &mut notes).unwrap());
+        assert_eq!(notes.len(), 1);
+
+        //All of the checks: the recent last write already fails the combination
+        config.mode = CleanUpMode::AllOf;
+        let policy: RotationPolicy = FileOverrides::default().resolve(&config);
+        let mut notes: Vec<String> = Vec::new();
+        assert!(!check_mode_conditions(&missing_path, &metadata, &policy, &mut notes).unwrap());
+
+        //All checks are met, the line count is read from the existing file
+        config.retention.last_write_h = 0;
+        config.retention.max_lines = Some(0);
+        fs::write(dir.join("app.log"), "line\n".repeat(1024 * 1024)).unwrap();
+        File::options()
+            .write(true)
+            .open(dir.join("app.log"))
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(SystemTime::now() - Duration::from_secs(60)))
+            .unwrap();
+        let metadata: Metadata = fs::metadata(dir.join("app.log")).unwrap();
+        let policy: RotationPolicy = FileOverrides::default().resolve(&config);
+        let mut notes: Vec<String> = Vec::new();
+        assert!(
+            check_mode_conditions(&dir.join("app.log"), &metadata, &policy, &mut notes).unwrap()
+        );
+        assert_eq!(notes.len(), 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_check_allowed_roots_link() {
+        let test_dir: PathBuf =
+            env::temp_dir().join(format!("yalc_test_roots_link_{}", std::process::id()));
+        let root: PathBuf = test_dir.join("root");
+        let outside: PathBuf = test_dir.join("outside");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(outside.join("nested")).unwrap();
+        std::os::unix::fs::symlink(outside.join("nested"), root.join("link")).unwrap();
+
+        //Lexically inside of the root, but the '..' of the link leads to the outside directory
+        let roots: Vec<String> = vec![root.display().to_string()];
+        let path: PathBuf = root.join("link").join("..").join("app.log");
+        assert!(check_allowed_roots(&path, &roots).is_err());
+        assert!(check_allowed_roots(&root.join("link").join("app.log"), &roots).is_err());
+        assert!(check_allowed_roots(&root.join("app.log"), &roots).is_ok());
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_plan_priority_order() {
+        let root = toml_parser::parse_toml(
+            r#"
+config_version = 2
+dry_run = true
+mode = "LastWrite"
+keep_rotate = 3
+missing_files_ok = true
+copy_truncate = false
+file_list = ["/var/log/first.log"]
+
+[retention]
+file_size_mib = 1
+last_write_h = 0
+
+[[files]]
+path = "/var/log/archive.log"
+priority = -5
+
+[[files]]
+path = "/var/log/nginx/error.log"
+group = "nginx"
+priority = 1
+
+[[files]]
+path = "/var/log/critical.log"
+priority = 50
+
+[group.nginx]
+paths = ["/var/log/nginx/access.log"]
+priority = 10
+"#,
+        )
+        .unwrap();
+        let config = config_parser::parse_config(&root).unwrap();
+        let tasks = resolver::resolve_tasks(&config);
+        let state_store = StateStore::new(&env::temp_dir().join("yalc_test_priority_state.toml"));
+
+        //The files of the group stay together and are ordered by their own priority
+        let plan = build_plan(&config, &tasks, &state_store);
+        let paths: Vec<&Path> = plan.files.iter().map(|f| f.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("/var/log/critical.log"),
+                Path::new("/var/log/nginx/error.log"),
+                Path::new("/var/log/nginx/access.log"),
+                Path::new("/var/log/first.log"),
+                Path::new("/var/log/archive.log"),
+            ]
+        );
+        assert_eq!(plan.files[0].task_nr, 1);
+    }
+}
@@ -0,0 +1,159 @@
+//! Module for the redaction of rotated files
+//!
+//! Logs can contain secrets or personal data like card numbers that must
+//! not be kept in the archives. The matches of the configured expressions
+//! are masked while the content is written into the archive. The copy of
+//! the copy truncate rotation is redacted while it is copied, so the
+//! unredacted content is never written again. A renamed file already is
+//! on disk, it is streamed line by line into a temporary file that
+//! replaces it. The live log file is never changed.
+//!
+use std::fs::{self, File, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::cleaner::file_copy::{self, CopyOptions};
+use crate::cleaner::{file_meta, transfer};
+use crate::config::config_regex::Regex;
+use crate::output;
+
+/// Replacement of every match of a redaction expression
+pub const REDACTION_MASK: &[u8] = b"[REDACTED]";
+
+/// Mask all matches of the expressions in the rotated file
+pub fn redact_file(
+    task_nr: usize,
+    path: &Path,
+    patterns: &[Regex],
+    options: &CopyOptions,
+) -> Result<(), io::Error> {
+    let metadata: Metadata = fs::metadata(path)?;
+    let temp_path: PathBuf = transfer::temp_path(path);
+    let mut redacted_count: usize = 0;
+
+    let redact_result = File::create(&temp_path)
+        .and_then(|mut temp_file| {
+            file_copy::stream_lines(
+                &mut File::open(path)?,
+                &mut temp_file,
+                options,
+                false,
+                &mut |line, output| redacted_count += redact_line(line, patterns, output),
+            )?;
+            temp_file.sync_all()
+        })
+        .and_then(|_| file_meta::replicate_metadata(task_nr, &metadata, &temp_path))
+        .and_then(|_| fs::rename(&temp_path, path));
+
+    if let Err(e) = redact_result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    output::print_status(&format!(
+        "[{}] Redacted {} matches in '{}'",
+        task_nr,
+        redacted_count,
+        path.display()
+    ));
+    Ok(())
+}
+
+/// Copy the lines from the offset of the source to the offset of the destination with all
+/// matches masked. Without the final pass, a last line without line end is kept for the
+/// next pass, so a line that is still written is never redacted in two parts.
+/// Returns the number of copied bytes of the source.
+pub fn copy_redacted(
+    source: &mut File,
+    dest: &mut File,
+    patterns: &[Regex],
+    options: &CopyOptions,
+    final_pass: bool,
+    redacted_count: &mut usize,
+) -> Result<u64, io::Error> {
+    file_copy::stream_lines(source, dest, options, !final_pass, &mut |line, output| {
+        *redacted_count += redact_line(line, patterns, output)
+    })
+}
+
+/// Write the line with all matches of the expressions masked into the output.
+/// The expressions are applied one after another, returns the number of masked matches.
+fn redact_line(line: &[u8], patterns: &[Regex], output: &mut Vec<u8>) -> usize {
+    //The line end is not matched, so '$' matches at the end of the content
+    let (content, line_end): (&[u8], &[u8]) = match line.strip_suffix(b"\n") {
+        Some(content) => (content, b"\n"),
+        None => (line, b""),
+    };
+
+    let mut current: Vec<u8> = content.to_vec();
+    let mut masked_count: usize = 0;
+
+    for pattern in patterns.iter() {
+        let mut redacted: Vec<u8> = Vec::with_capacity(current.len());
+        let mut pos: usize = 0;
+
+        while let Some((start, end)) = pattern.find_at(&current, pos) {
+            //Empty matches are never masked
+            if start == end {
+                if start >= current.len() {
+                    break;
+                }
+                redacted.extend_from_slice(&current[pos..=start]);
+                pos = start + 1;
+                continue;
+            }
+
+            redacted.extend_from_slice(&current[pos..start]);
+            redacted.extend_from_slice(REDACTION_MASK);
+            masked_count += 1;
+            pos = end;
+        }
+
+        redacted.extend_from_slice(&current[pos..]);
+        current = redacted;
+    }
+
+    output.extend_from_slice(&current);
+    output.extend_from_slice(line_end);
+    masked_count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config_regex;
+    use std::env;
+
+    #[test]
+    fn test_redact_file() {
+        let dir = env::temp_dir().join(format!("yalc_test_redact_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("app.log.0");
+        fs::write(
+            &path,
+            "paid with 4111111111111111\nlogin token=abc123 ok\nno secrets",
+        )
+        .unwrap();
+
+        let patterns = vec![
+            config_regex::parse_regex(r"\d{16}").unwrap(),
+            config_regex::parse_regex(r"token=\S*").unwrap(),
+            config_regex::parse_regex(r"x*").unwrap(),
+        ];
+        let options = CopyOptions {
+            buffer_size: 16,
+            rate_limit: None,
+        };
+        redact_file(1, &path, &patterns, &options).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "paid with [REDACTED]\nlogin [REDACTED] ok\nno secrets"
+        );
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,385 @@
+//! Module for the report of a cleanup run
+//!
+//! A run collects the outcome of every file task into a report instead of
+//! printing its results. The command layer decides how the report is
+//! presented, like the summary table or the run_finished event, and the
+//! daemon can use the same report for every scheduled run.
+//!
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::cleaner::audit;
+use crate::cleaner::budget::BudgetExcess;
+use crate::cleaner::metrics::OperationMetric;
+use crate::config::FailOn;
+use crate::constants::{EXIT_ABORTED, EXIT_SIGNAL_BASE, EXIT_TASK_FAILURE, EXIT_WARNING};
+use crate::output::json::JsonValue;
+use crate::platform;
+use crate::resolver::UnmanagedFile;
+use crate::state::FileAction;
+use crate::stats::FileChange;
+
+/// Represents the task results of a cleanup run
+#[derive(Debug, Default)]
+pub struct RunReport {
+    /// Id of the run that is part of its messages, None when no run id was started
+    pub run_id: Option<String>,
+
+    pub tasks_executed: usize,
+    pub tasks_success: usize,
+    pub tasks_failure: usize,
+
+    /// True when the run was stopped by the error policy before all tasks were executed
+    pub aborted: bool,
+
+    /// Number of the signal that stopped the run before all tasks were executed
+    pub interrupted_by: Option<i32>,
+
+    /// Number of tasks that were deferred to the next run by the byte limit of the run
+    pub tasks_deferred: usize,
+
+    /// Number of postrotate hooks of the groups that failed after the tasks
+    pub hook_failures: usize,
+
+    /// Task results of the groups in the order they were processed
+    pub groups: Vec<GroupReport>,
+
+    /// Results of the executed tasks in the order they were processed
+    pub tasks: Vec<TaskReport>,
+
+    /// Unix time in seconds when the run was started
+    pub started_at: u64,
+
+    /// Wall time of the whole run
+    pub duration: Duration,
+
+    /// Total budget that was exceeded by the files and archives, None when it was kept
+    pub budget_exceeded: Option<BudgetExcess>,
+
+    /// Files in the watched directories that are matched by no entry
+    pub unmanaged_files: Vec<UnmanagedFile>,
+
+    /// Files whose outcome differs from their previous recorded run
+    pub changes: Vec<FileChange>,
+}
+
+/// Represents the result of a single executed file task
+#[derive(Debug)]
+pub struct TaskReport {
+    pub path: PathBuf,
+
+    /// Condition that triggered the cleanup, None when no condition was met
+    pub trigger: Option<String>,
+
+    /// Planned action of the file, like rotated or skipped
+    pub action: FileAction,
+
+    /// Size of the log file in bytes before the task, None for a missing file
+    pub size: Option<u64>,
+
+    /// Unix time of the last write of a file without a write within 'stale_after', None otherwise
+    pub stale_since: Option<u64>,
+
+    /// Warning of a missing or skipped file, None without a warning
+    pub warning: Option<String>,
+
+    /// Number of bytes that the log file shrank by the task
+    pub bytes_freed: u64,
+
+    /// Wall time of the task
+    pub duration: Duration,
+
+    /// True when the file is in a dry run and no file was changed
+    pub dry_run: bool,
+
+    /// Error of a failed task, None for a successful task
+    pub error: Option<String>,
+
+    /// Timed copies, compressions and renames of the task
+    pub operations: Vec<OperationMetric>,
+}
+
+impl TaskReport {
+    /// Get the status of the task like 'ok', 'failed' or 'dry run'
+    pub fn status(&self) -> &'static str {
+        match (&self.error, self.dry_run) {
+            (Some(_), _) => "failed",
+            (None, true) => "dry run",
+            (None, false) => "ok",
+        }
+    }
+}
+
+/// Represents the task results of the files of a single group
+#[derive(Debug)]
+pub struct GroupReport {
+    pub name: String,
+    pub tasks_success: usize,
+    pub tasks_failure: usize,
+}
+
+impl RunReport {
+    /// Get the exit status of the process for the results of the run
+    pub fn exit_code(&self) -> u8 {
+        if let Some(signal_number) = self.interrupted_by {
+            EXIT_SIGNAL_BASE.saturating_add(u8::try_from(signal_number).unwrap_or(0))
+        } else if self.aborted {
+            EXIT_ABORTED
+        } else if self.tasks_failure > 0 || self.hook_failures > 0 {
+            EXIT_TASK_FAILURE
+        } else {
+            0
+        }
+    }
+
+    /// Get the exit status of the process for the results of the run and the 'fail_on' setting.
+    /// An interruption by a signal is always reported, it is not a result of the tasks.
+    pub fn exit_code_for(&self, fail_on: FailOn) -> u8 {
+        match (fail_on, self.exit_code()) {
+            (_, code) if self.interrupted_by.is_some() => code,
+            (FailOn::Never, _) => 0,
+            (FailOn::Warn, 0) if self.warnings() > 0 => EXIT_WARNING,
+            (_, code) => code,
+        }
+    }
+
+    /// Get the number of warnings of the run: missing, skipped, stale, deferred and unmanaged files
+    pub fn warnings(&self) -> usize {
+        let task_warnings: usize = self
+            .tasks
+            .iter()
+            .filter(|task| task.warning.is_some() || task.stale_since.is_some())
+            .count();
+
+        task_warnings + self.tasks_deferred + self.unmanaged_files.len()
+    }
+
+    /// Get the number of executed tasks with a missing or skipped file
+    pub fn tasks_warning(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|task| task.warning.is_some())
+            .count()
+    }
+
+    /// Get the number of executed tasks whose file was not written within 'stale_after'
+    pub fn tasks_stale(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|task| task.stale_since.is_some())
+            .count()
+    }
+
+    /// Get the number of bytes that all log files of the run shrank
+    pub fn bytes_freed(&self) -> u64 {
+        self.tasks.iter().map(|task| task.bytes_freed).sum()
+    }
+
+    /// Get the json fields of the totals of the run
+    pub fn total_fields(&self) -> Vec<(String, JsonValue)> {
+        vec![
+            (
+                "tasks_executed".to_string(),
+                JsonValue::uint(self.tasks_executed as u64),
+            ),
+            (
+                "tasks_success".to_string(),
+                JsonValue::uint(self.tasks_success as u64),
+            ),
+            (
+                "tasks_failure".to_string(),
+                JsonValue::uint(self.tasks_failure as u64),
+            ),
+            (
+                "tasks_deferred".to_string(),
+                JsonValue::uint(self.tasks_deferred as u64),
+            ),
+            (
+                "hook_failures".to_string(),
+                JsonValue::uint(self.hook_failures as u64),
+            ),
+            (
+                "tasks_warning".to_string(),
+                JsonValue::uint(self.tasks_warning() as u64),
+            ),
+            (
+                "tasks_stale".to_string(),
+                JsonValue::uint(self.tasks_stale() as u64),
+            ),
+            (
+                "budget_exceeded".to_string(),
+                self.budget_exceeded
+                    .as_ref()
+                    .map_or(JsonValue::Null, |excess| {
+                        JsonValue::Object(vec![
+                            (
+                                "budget_bytes".to_string(),
+                                JsonValue::uint(excess.budget_bytes),
+                            ),
+                            ("used_bytes".to_string(), JsonValue::uint(excess.used_bytes)),
+                            (
+                                "removed_count".to_string(),
+                                JsonValue::uint(excess.removed_count as u64),
+                            ),
+                            (
+                                "removed_bytes".to_string(),
+                                JsonValue::uint(excess.removed_bytes),
+                            ),
+                        ])
+                    }),
+            ),
+            (
+                "unmanaged_files".to_string(),
+                JsonValue::Array(
+                    self.unmanaged_files
+                        .iter()
+                        .map(|file| {
+                            JsonValue::Object(vec![
+                                (
+                                    "path".to_string(),
+                                    JsonValue::string(file.path.display().to_string()),
+                                ),
+                                ("size".to_string(), JsonValue::uint(file.size)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "changes".to_string(),
+                JsonValue::Array(
+                    self.changes
+                        .iter()
+                        .map(|change| {
+                            JsonValue::Object(vec![
+                                (
+                                    "path".to_string(),
+                                    JsonValue::string(change.path.display().to_string()),
+                                ),
+                                (
+                                    "change".to_string(),
+                                    JsonValue::string(change.kind.to_string()),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "bytes_freed".to_string(),
+                JsonValue::uint(self.bytes_freed()),
+            ),
+            (
+                "started_at".to_string(),
+                JsonValue::string(audit::format_utc_time(self.started_at)),
+            ),
+            (
+                "duration_ms".to_string(),
+                JsonValue::uint(self.duration.as_millis() as u64),
+            ),
+            ("aborted".to_string(), JsonValue::Bool(self.aborted)),
+            (
+                "interrupted_by".to_string(),
+                JsonValue::optional_string(self.interrupted_by.map(platform::signal_name)),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task_report(bytes_freed: u64, error: Option<&str>) -> TaskReport {
+        TaskReport {
+            path: PathBuf::from("/var/log/app.log"),
+            trigger: None,
+            action: FileAction::Rotated,
+            size: Some(bytes_freed),
+            stale_since: None,
+            warning: None,
+            bytes_freed,
+            duration: Duration::ZERO,
+            dry_run: false,
+            error: error.map(str::to_string),
+            operations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_run_report_totals() {
+        let report = RunReport {
+            tasks_executed: 2,
+            tasks_success: 1,
+            tasks_failure: 1,
+            tasks: vec![task_report(1024, None), task_report(512, Some("busy"))],
+            ..RunReport::default()
+        };
+
+        assert_eq!(report.bytes_freed(), 1536);
+        assert_eq!(report.exit_code(), EXIT_TASK_FAILURE);
+        assert_eq!(report.tasks[0].status(), "ok");
+        assert_eq!(report.tasks[1].status(), "failed");
+        assert_eq!(report.exit_code_for(FailOn::Never), 0);
+
+        //A failed postrotate hook fails the run even when all tasks were successful
+        let hook_failed = RunReport {
+            tasks_executed: 1,
+            tasks_success: 1,
+            hook_failures: 1,
+            ..RunReport::default()
+        };
+        assert_eq!(hook_failed.exit_code(), EXIT_TASK_FAILURE);
+    }
+
+    #[test]
+    fn test_exit_code_for_warnings() {
+        let mut missing = task_report(0, None);
+        missing.action = FileAction::Missing;
+        missing.warning = Some("file not found".to_string());
+
+        let report = RunReport {
+            tasks_executed: 1,
+            tasks_success: 1,
+            tasks: vec![missing],
+            ..RunReport::default()
+        };
+
+        assert_eq!(report.warnings(), 1);
+        assert_eq!(report.exit_code_for(FailOn::Warn), EXIT_WARNING);
+        assert_eq!(report.exit_code_for(FailOn::Error), 0);
+
+        let interrupted = RunReport {
+            interrupted_by: Some(15),
+            ..RunReport::default()
+        };
+        assert_eq!(interrupted.exit_code_for(FailOn::Never), 143);
+    }
+
+    #[test]
+    fn test_total_fields_budget_exceeded() {
+        let budget_field = |report: &RunReport| {
+            report
+                .total_fields()
+                .into_iter()
+                .find(|(key, _)| key == "budget_exceeded")
+                .map(|(_, value)| value)
+        };
+
+        assert_eq!(budget_field(&RunReport::default()), Some(JsonValue::Null));
+
+        let report = RunReport {
+            budget_exceeded: Some(BudgetExcess {
+                budget_bytes: 1000,
+                used_bytes: 1500,
+                removed_count: 2,
+                removed_bytes: 600,
+            }),
+            ..RunReport::default()
+        };
+        assert_eq!(
+            budget_field(&report).unwrap().to_compact_string(),
+            "{\"budget_bytes\":1000,\"used_bytes\":1500,\"removed_count\":2,\"removed_bytes\":600}"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Module for retrying file operations after transient errors
+//!
+//! Network filesystems can report errors like EBUSY for a short time,
+//! for example while another client holds the file. The operations of a
+//! rotation are repeated with a growing backoff in this case, all other
+//! errors are returned right away.
+//!
+use std::io::{self, ErrorKind};
+use std::thread;
+use std::time::Duration;
+
+use crate::config::RetryConfig;
+use crate::output;
+
+/// Error numbers of the transient errors: EINTR, EAGAIN, EBUSY, ETIMEDOUT
+const TRANSIENT_ERRNOS: [i32; 4] = [4, 11, 16, 110];
+
+/// Returns true when the error is expected to disappear on its own
+pub fn is_transient(error: &io::Error) -> bool {
+    match error.kind() {
+        ErrorKind::Interrupted
+        | ErrorKind::WouldBlock
+        | ErrorKind::ResourceBusy
+        | ErrorKind::TimedOut => true,
+        _ => error
+            .raw_os_error()
+            .is_some_and(|errno| TRANSIENT_ERRNOS.contains(&errno)),
+    }
+}
+
+/// Run the operation until it succeeds, fails with a permanent error or all attempts are used.
+/// The name of the operation is used to report the retries.
+pub fn with_retry<T>(
+    task_nr: usize,
+    retry: &RetryConfig,
+    name: &str,
+    operation: impl FnMut() -> Result<T, io::Error>,
+) -> Result<T, io::Error> {
+    with_retry_if(task_nr, retry, name, is_transient, operation)
+}
+
+/// Run the operation until it succeeds, fails with an error that is not retried or all attempts are used.
+/// The errors that are retried are selected by the predicate.
+pub fn with_retry_if<T>(
+    task_nr: usize,
+    retry: &RetryConfig,
+    name: &str,
+    should_retry: impl Fn(&io::Error) -> bool,
+    mut operation: impl FnMut() -> Result<T, io::Error>,
+) -> Result<T, io::Error> {
+    let mut backoff_ms: u64 = retry.backoff_ms;
+    let mut attempt: u64 = 1;
+
+    loop {
+        match operation() {
+            Err(e) if attempt < retry.attempts && should_retry(&e) => {
+                output::print_status(&format!(
+                    "[{}] {} failed (attempt {}/{}): {}, retrying in {} ms",
+                    task_nr, name, attempt, retry.attempts, e, backoff_ms
+                ));
+
+                thread::sleep(Duration::from_millis(backoff_ms));
+                backoff_ms = backoff_ms.saturating_mul(2);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_retry() {
+        let retry = RetryConfig {
+            attempts: 3,
+            backoff_ms: 1,
+        };
+
+        //Transient errors are retried until the operation succeeds
+        let mut calls: u64 = 0;
+        let result = with_retry(1, &retry, "Rename", || {
+            calls += 1;
+            match calls {
+                1 | 2 => Err(io::Error::from_raw_os_error(16)),
+                _ => Ok(calls),
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        //The last error is returned when all attempts are used
+        calls = 0;
+        let result: Result<(), io::Error> = with_retry(1, &retry, "Rename", || {
+            calls += 1;
+            Err(io::Error::from(ErrorKind::Interrupted))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+
+        //Permanent errors are not retried
+        calls = 0;
+        let result: Result<(), io::Error> = with_retry(1, &retry, "Rename", || {
+            calls += 1;
+            Err(io::Error::from(ErrorKind::NotFound))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,206 @@
+//! Module for the SHA-256 checksums of archives
+//!
+//! The archive manifest records a SHA-256 checksum of every archive, so
+//! the archives can be verified against tampering or bit rot later on.
+//! The hash follows FIPS 180-4 and works on streamed blocks, so archives
+//! of any size are hashed with a fixed amount of memory.
+//!
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Size of the buffer that the file content is read into
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Initial hash values, the fractional parts of the square roots of the first 8 primes
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Round constants, the fractional parts of the cube roots of the first 64 primes
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Represents a running SHA-256 hash computation
+#[derive(Debug, Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+
+    /// Bytes of the current block that is not complete yet
+    block: [u8; 64],
+    block_len: usize,
+
+    /// Total number of hashed bytes
+    total_len: u64,
+}
+
+impl Sha256 {
+    /// Create a hash computation without any input
+    pub fn new() -> Sha256 {
+        Sha256 {
+            state: INITIAL_STATE,
+            block: [0; 64],
+            block_len: 0,
+            total_len: 0,
+        }
+    }
+
+    /// Add the bytes to the hashed input
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        self.total_len += bytes.len() as u64;
+
+        while !bytes.is_empty() {
+            let take: usize = bytes.len().min(64 - self.block_len);
+            self.block[self.block_len..self.block_len + take].copy_from_slice(&bytes[..take]);
+            self.block_len += take;
+            bytes = &bytes[take..];
+
+            if self.block_len == 64 {
+                let block: [u8; 64] = self.block;
+                self.process_block(&block);
+                self.block_len = 0;
+            }
+        }
+    }
+
+    /// Finish the computation and get the hash as lowercase hex string
+    pub fn finish_hex(mut self) -> String {
+        let bit_len: u64 = self.total_len.wrapping_mul(8);
+
+        //The padding is a single one bit, zeros and the length of the input in bits
+        let padding_len: usize = match self.block_len < 56 {
+            true => 56 - self.block_len,
+            false => 120 - self.block_len,
+        };
+        let mut padding: Vec<u8> = vec![0; padding_len];
+        padding[0] = 0x80;
+        self.update(&padding);
+        self.update(&bit_len.to_be_bytes());
+
+        self.state
+            .iter()
+            .map(|word| format!("{:08x}", word))
+            .collect()
+    }
+
+    /// Apply the compression function to a complete block
+    fn process_block(&mut self, block: &[u8; 64]) {
+        let mut schedule: [u32; 64] = [0; 64];
+
+        for (idx, chunk) in block.chunks_exact(4).enumerate() {
+            schedule[idx] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        for idx in 16..64 {
+            let s0: u32 = schedule[idx - 15].rotate_right(7)
+                ^ schedule[idx - 15].rotate_right(18)
+                ^ (schedule[idx - 15] >> 3);
+            let s1: u32 = schedule[idx - 2].rotate_right(17)
+                ^ schedule[idx - 2].rotate_right(19)
+                ^ (schedule[idx - 2] >> 10);
+            schedule[idx] = schedule[idx - 16]
+                .wrapping_add(s0)
+                .wrapping_add(schedule[idx - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+
+        for (constant, word) in ROUND_CONSTANTS.iter().zip(schedule.iter()) {
+            let s1: u32 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice: u32 = (e & f) ^ (!e & g);
+            let temp1: u32 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(*constant)
+                .wrapping_add(*word);
+            let s0: u32 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority: u32 = (a & b) ^ (a & c) ^ (b & c);
+            let temp2: u32 = s0.wrapping_add(majority);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+
+        for (state_word, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state_word = state_word.wrapping_add(value);
+        }
+    }
+}
+
+//Implement the Default trait
+impl Default for Sha256 {
+    fn default() -> Self {
+        Sha256::new()
+    }
+}
+
+/// Compute the SHA-256 checksum of the file content as lowercase hex string
+pub fn file_sha256(path: &Path) -> Result<String, io::Error> {
+    let mut file: File = File::open(path)?;
+    let mut buffer: Vec<u8> = vec![0; READ_BUFFER_SIZE];
+    let mut hasher = Sha256::new();
+
+    loop {
+        let read_bytes: usize = file.read(&mut buffer)?;
+
+        if read_bytes == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..read_bytes]);
+    }
+
+    Ok(hasher.finish_hex())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_vectors() {
+        let hash = |input: &[u8]| {
+            let mut hasher = Sha256::new();
+            hasher.update(input);
+            hasher.finish_hex()
+        };
+
+        assert_eq!(
+            hash(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hash(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hash(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+
+        //Input that is split across several updates gives the same hash
+        let mut hasher = Sha256::new();
+        for _ in 0..1000 {
+            hasher.update(&[b'a'; 1000]);
+        }
+        assert_eq!(
+            hasher.finish_hex(),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Module for the secure deletion of archives
+//!
+//! Archives of logs with sensitive data can be subject to disposal policies.
+//! With shred enabled, the content of a file is overwritten with pseudo
+//! random data and synced before the file is unlinked. Copy-on-write
+//! filesystems and SSDs can still keep the old blocks, in this case the
+//! overwrite only prevents a recovery through the filesystem. Files with
+//! further hard links, like deduplicated archives, are only unlinked, the
+//! content is still used by the other links.
+//!
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cleaner::audit;
+
+/// Size of the buffer with the data that overwrites the content
+const SHRED_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Remove the file, the content is overwritten first when shred is enabled
+pub fn remove_file(path: &Path, shred: bool) -> Result<(), io::Error> {
+    let operation: &str = if shred { "shred" } else { "delete" };
+
+    audit::track(operation, &[("path", &path)], || {
+        if shred && fs::metadata(path)?.nlink() <= 1 {
+            overwrite_file(path)?;
+        }
+
+        fs::remove_file(path)
+    })
+}
+
+/// Overwrite the whole content of the file with pseudo random data and sync it to the disk
+fn overwrite_file(path: &Path) -> Result<(), io::Error> {
+    let mut file: File = File::options().write(true).open(path)?;
+    let mut remaining: u64 = file.metadata()?.len();
+    let mut buffer: Vec<u8> = vec![0; SHRED_BUFFER_SIZE];
+
+    //The xorshift state must never be zero
+    let mut state: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+        | 1;
+
+    while remaining > 0 {
+        let len: usize = remaining.min(buffer.len() as u64) as usize;
+
+        for chunk in buffer[..len].chunks_mut(8) {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            chunk.copy_from_slice(&state.to_le_bytes()[..chunk.len()]);
+        }
+
+        file.write_all(&buffer[..len])?;
+        remaining -= len as u64;
+    }
+
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_shred_file() {
+        let dir = env::temp_dir().join(format!("yalc_test_shred_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("app.log.0");
+        let content: String = "secret\n".repeat(20_000);
+        fs::write(&path, &content).unwrap();
+
+        overwrite_file(&path).unwrap();
+        let overwritten: Vec<u8> = fs::read(&path).unwrap();
+        assert_eq!(overwritten.len(), content.len());
+        assert!(!overwritten.windows(7).any(|w| w == b"secret\n"));
+
+        remove_file(&path, true).unwrap();
+        assert!(!path.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
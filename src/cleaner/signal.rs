@@ -0,0 +1,89 @@
+//! Module for signaling processes after a rotation
+//!
+//! Many daemons reopen their log files when they receive a signal like
+//! 'HUP'. The process id is read from the configured pidfile and the
+//! signal is sent directly, without starting a shell command.
+//!
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+use crate::config::SignalTarget;
+use crate::output;
+use crate::platform;
+
+/// Send the signal of the target to the process of the pidfile
+pub fn signal_process(task_nr: usize, target: &SignalTarget) -> Result<(), io::Error> {
+    let pid: i32 = read_pidfile(Path::new(&target.pidfile))?;
+
+    let signal: i32 = platform::signal_number(&target.signal).ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unknown signal: '{}'", target.signal),
+        )
+    })?;
+
+    output::print_status(&format!(
+        "[{}] Sending signal {} to process {}",
+        task_nr, target.signal, pid
+    ));
+
+    platform::send_signal(pid, signal).map_err(|e| match e.raw_os_error() {
+        Some(ESRCH) => io::Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "Stale pidfile '{}': process {} is not running",
+                target.pidfile, pid
+            ),
+        ),
+        _ => io::Error::new(
+            e.kind(),
+            format!("Failed to send signal to process {}: {}", pid, e),
+        ),
+    })
+}
+
+/// Error number of the kill call when the process does not exist
+const ESRCH: i32 = 3;
+
+/// Read the process id of a pidfile
+fn read_pidfile(path: &Path) -> Result<i32, io::Error> {
+    let content: String = fs::read_to_string(path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Failed to read pidfile '{}': {}", path.display(), e),
+        )
+    })?;
+
+    //Only positive process ids are valid, other values would signal process groups
+    match content.trim().parse::<i32>() {
+        Ok(pid) if pid > 0 => Ok(pid),
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid process id in pidfile '{}'", path.display()),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_read_pidfile() {
+        let dir = env::temp_dir().join(format!("yalc_test_pidfile_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let pidfile = dir.join("app.pid");
+        fs::write(&pidfile, "1234\n").unwrap();
+        assert_eq!(read_pidfile(&pidfile).unwrap(), 1234);
+
+        fs::write(&pidfile, "-1").unwrap();
+        assert!(read_pidfile(&pidfile).is_err());
+
+        assert!(read_pidfile(&dir.join("missing.pid")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,106 @@
+//! Module for log files that are symbolic links
+//!
+//! A rename of a symbolic link moves the link and not the log, while a copy
+//! and truncate changes the target. To avoid these surprising results, the
+//! link is resolved to its final target before any operation. Depending on
+//! the policy the target is rotated, the file is skipped or the task fails.
+//!
+use std::fs::{self, Metadata};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::config::SymlinkPolicy;
+use crate::resolver::FileTask;
+
+/// Get the path that is rotated for the file, returns None when the file is skipped.
+/// Paths that are no symbolic links and dangling links are returned unchanged.
+pub fn resolve_target(path: &Path, policy: SymlinkPolicy) -> Result<Option<PathBuf>, io::Error> {
+    let is_symlink: bool = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata.file_type().is_symlink(),
+        Err(e) if e.kind() == ErrorKind::NotFound => false,
+        Err(e) => return Err(e),
+    };
+
+    if !is_symlink {
+        return Ok(Some(path.to_path_buf()));
+    }
+
+    match policy {
+        SymlinkPolicy::Skip => Ok(None),
+        SymlinkPolicy::Error => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Path is a symbolic link: {}", path.display()),
+        )),
+        SymlinkPolicy::Follow => {
+            let target: PathBuf = match fs::canonicalize(path) {
+                Ok(target) => target,
+
+                //The missing target is reported by the existence check of the file
+                Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Some(path.to_path_buf())),
+                Err(e) => return Err(e),
+            };
+
+            let metadata: Metadata = fs::metadata(&target)?;
+            if !metadata.is_file() {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Target of the symbolic link '{}' is not a file: {}",
+                        path.display(),
+                        target.display()
+                    ),
+                ));
+            }
+
+            Ok(Some(target))
+        }
+    }
+}
+
+/// Get the task with the path that is rotated, the archives of a followed link are stored
+/// next to its target. Links that are skipped or fail keep their own path.
+pub fn rotated_task(task: &FileTask, default_policy: SymlinkPolicy) -> FileTask {
+    let policy: SymlinkPolicy = task.overrides.symlinks.unwrap_or(default_policy);
+    let mut rotated: FileTask = task.clone();
+
+    if let Ok(Some(target)) = resolve_target(&task.path, policy) {
+        rotated.path = target;
+    }
+
+    rotated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn test_resolve_target() {
+        let dir = env::temp_dir().join(format!("yalc_test_symlink_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let target = dir.join("app.log");
+        let link = dir.join("current.log");
+        fs::write(&target, "content").unwrap();
+        symlink(&target, &link).unwrap();
+
+        let resolved = resolve_target(&link, SymlinkPolicy::Follow).unwrap();
+        assert_eq!(resolved, Some(fs::canonicalize(&target).unwrap()));
+        assert_eq!(resolve_target(&link, SymlinkPolicy::Skip).unwrap(), None);
+        assert!(resolve_target(&link, SymlinkPolicy::Error).is_err());
+
+        //Regular files are never changed by the policy
+        let regular = resolve_target(&target, SymlinkPolicy::Error).unwrap();
+        assert_eq!(regular, Some(target.clone()));
+
+        //A link to a directory can not be rotated
+        let dir_link = dir.join("dir.log");
+        symlink(&dir, &dir_link).unwrap();
+        assert!(resolve_target(&dir_link, SymlinkPolicy::Follow).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+//! Module for moving archives between directories
+//!
+//! A rename only works within a single filesystem. When an archive is
+//! moved to another filesystem the rename fails with EXDEV, in this case
+//! the archive is copied, synced and then removed. The copy is written
+//! under a temporary name first, so an interrupted move never leaves a
+//! partial file with the name of a rotation behind. The live log file is
+//! never moved this way, its writer would keep writing into the removed
+//! file, copy_truncate is required for an olddir on another filesystem.
+//!
+use std::ffi::OsString;
+use std::fs::{self, File, Metadata};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::cleaner::audit;
+use crate::cleaner::file_copy::{self, CopyOptions};
+use crate::cleaner::file_meta;
+use crate::output;
+
+/// Suffix of the temporary file of a move between filesystems
+const TEMP_SUFFIX: &str = ".yalc-tmp";
+
+/// Get the path of the temporary file that is renamed to the destination
+pub fn temp_path(dest: &Path) -> PathBuf {
+    let mut temp_name: OsString = dest.as_os_str().to_os_string();
+    temp_name.push(TEMP_SUFFIX);
+    PathBuf::from(temp_name)
+}
+
+/// Move an archive like a rename, archives on another filesystem are copied and removed
+pub fn move_file(
+    task_nr: usize,
+    source: &Path,
+    dest: &Path,
+    options: &CopyOptions,
+) -> Result<(), io::Error> {
+    audit::track(
+        "rename",
+        &[("from", &source), ("to", &dest)],
+        || match fs::rename(source, dest) {
+            Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+                output::print_status(&format!(
+                    "[{}] Target is on another filesystem, copying '{}' instead",
+                    task_nr,
+                    source.display()
+                ));
+                move_across_filesystems(task_nr, source, dest, options)
+            }
+            result => result,
+        },
+    )
+}
+
+/// Copy the file with its metadata to the destination and remove the source afterwards
+fn move_across_filesystems(
+    task_nr: usize,
+    source: &Path,
+    dest: &Path,
+    options: &CopyOptions,
+) -> Result<(), io::Error> {
+    let metadata: Metadata = fs::metadata(source)?;
+
+    let temp_path: PathBuf = temp_path(dest);
+
+    let copy_result = File::create(&temp_path)
+        .and_then(|mut temp_file| {
+            file_copy::copy_to_end(&mut File::open(source)?, &mut temp_file, options)?;
+            temp_file.sync_all()
+        })
+        .and_then(|_| file_meta::replicate_metadata(task_nr, &metadata, &temp_path))
+        .and_then(|_| fs::rename(&temp_path, dest));
+
+    if let Err(e) = copy_result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    fs::remove_file(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs::FileTimes;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_move_across_filesystems() {
+        let dir = env::temp_dir().join(format!("yalc_test_transfer_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let source = dir.join("app.log");
+        let dest = dir.join("app.log.0");
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+
+        fs::write(&source, "content").unwrap();
+        File::options()
+            .write(true)
+            .open(&source)
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(modified))
+            .unwrap();
+
+        let options = CopyOptions {
+            buffer_size: 4096,
+            rate_limit: None,
+        };
+        move_across_filesystems(1, &source, &dest, &options).unwrap();
+
+        assert!(!source.exists());
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "content");
+        assert_eq!(fs::metadata(&dest).unwrap().modified().unwrap(), modified);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
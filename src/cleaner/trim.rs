@@ -0,0 +1,287 @@
+//! Module for the trim action
+//!
+//! Append-only debug logs often have no use for their history. Instead of a
+//! rotation, the file is rewritten in place and only the last lines or bytes
+//! are kept. With a maximum age, the lines with an older timestamp at the
+//! start are removed as well. The kept content is streamed into a temporary
+//! file that replaces the log with an atomic rename, so the log is never
+//! observed in a partial state. Writers that keep the file open continue to
+//! write into the replaced file, so they have to reopen it, for example after
+//! a signal.
+//!
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::cleaner::file_copy::{self, CopyOptions};
+use crate::cleaner::file_meta;
+use crate::cleaner::transfer;
+use crate::config::TrimConfig;
+use crate::config::config_timestamp::{self, DEFAULT_TIMESTAMP_FORMAT, TimestampFormat};
+use crate::output;
+use crate::state;
+
+/// Size of the blocks that are read backwards to find the kept lines
+const SCAN_BLOCK_SIZE: u64 = 64 * 1024;
+
+/// Trim the file to the content that is kept by the trim config.
+/// Returns the number of removed bytes, nothing is changed when the file is within the limits.
+pub fn trim_file(
+    task_nr: usize,
+    path: &Path,
+    trim: &TrimConfig,
+    options: &CopyOptions,
+) -> Result<u64, io::Error> {
+    let mut source: File = File::open(path)?;
+    let metadata: Metadata = source.metadata()?;
+    let keep_start: u64 = find_keep_start(&mut source, metadata.len(), trim)?;
+
+    if keep_start == 0 {
+        output::print_status(&format!(
+            "[{}] Nothing to trim: File is within the limits",
+            task_nr
+        ));
+        return Ok(0);
+    }
+
+    output::print_status(&format!(
+        "[{}] Trimming {} bytes, keeping the last {} bytes",
+        task_nr,
+        keep_start,
+        metadata.len() - keep_start
+    ));
+
+    let temp_path: PathBuf = transfer::temp_path(path);
+
+    let trim_result = File::create(&temp_path)
+        .and_then(|mut temp_file| {
+            source.seek(SeekFrom::Start(keep_start))?;
+            file_copy::copy_to_end(&mut source, &mut temp_file, options)?;
+            temp_file.sync_all()?;
+
+            //Lines that were written during the copy are kept as well
+            file_copy::copy_to_end(&mut source, &mut temp_file, options)?;
+            temp_file.sync_all()
+        })
+        .and_then(|_| file_meta::replicate_metadata(task_nr, &metadata, &temp_path))
+        .and_then(|_| fs::rename(&temp_path, path));
+
+    if let Err(e) = trim_result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    Ok(keep_start)
+}
+
+/// Get the offset of the first kept byte, the smaller result of all limits is kept
+fn find_keep_start(file: &mut File, len: u64, trim: &TrimConfig) -> Result<u64, io::Error> {
+    let mut keep_start: u64 = 0;
+
+    if let Some(keep_bytes) = trim.keep_bytes {
+        keep_start = keep_start.max(next_line_start(file, len.saturating_sub(keep_bytes))?);
+    }
+
+    if let Some(keep_lines) = trim.keep_lines {
+        keep_start = keep_start.max(last_lines_start(file, len, keep_lines)?);
+    }
+
+    //The lines before the start are removed anyway, so their timestamps are not read
+    if let Some(max_age_s) = trim.max_age_s {
+        let default_format: TimestampFormat;
+        let format: &TimestampFormat = match &trim.timestamp_format {
+            Some(format) => format,
+            None => {
+                default_format =
+                    config_timestamp::parse_timestamp_format(DEFAULT_TIMESTAMP_FORMAT)?;
+                &default_format
+            }
+        };
+
+        let now_s: u64 = state::unix_time_now();
+        keep_start = recent_lines_start(
+            file,
+            keep_start,
+            format,
+            now_s.saturating_sub(max_age_s),
+            now_s,
+        )?;
+    }
+
+    Ok(keep_start)
+}
+
+/// Get the start of the first full line at or after the offset
+fn next_line_start(file: &mut File, offset: u64) -> Result<u64, io::Error> {
+    if offset == 0 {
+        return Ok(0);
+    }
+
+    //The offset is a line start when the previous byte ends a line
+    file.seek(SeekFrom::Start(offset - 1))?;
+    let mut skipped: Vec<u8> = Vec::new();
+    let skipped_bytes: usize = BufReader::new(&mut *file).read_until(b'\n', &mut skipped)?;
+
+    Ok(offset - 1 + skipped_bytes as u64)
+}
+
+/// Get the start of the first line at or after the offset with a timestamp at or after the
+/// cutoff. Returns the end of the file when all timestamps are older than the cutoff and an
+/// error when no line has a timestamp of the format, so the file is not trimmed in this case.
+fn recent_lines_start(
+    file: &mut File,
+    offset: u64,
+    format: &TimestampFormat,
+    cutoff_s: u64,
+    now_s: u64,
+) -> Result<u64, io::Error> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut reader = BufReader::new(&mut *file);
+    let mut line: Vec<u8> = Vec::new();
+    let mut line_start: u64 = offset;
+    let mut timestamp_found: bool = false;
+
+    loop {
+        line.clear();
+        let line_bytes: usize = reader.read_until(b'\n', &mut line)?;
+
+        //A wrong timestamp format would otherwise remove the whole file
+        if line_bytes == 0 && !timestamp_found && line_start > offset {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "No line starts with a timestamp of the timestamp format",
+            ));
+        }
+
+        if line_bytes == 0 {
+            return Ok(line_start);
+        }
+
+        //Lines without timestamp like stack traces belong to the previous line
+        if let Some(timestamp_s) = format.parse_leading(&String::from_utf8_lossy(&line), now_s) {
+            timestamp_found = true;
+
+            if timestamp_s >= cutoff_s {
+                return Ok(line_start);
+            }
+        }
+
+        line_start += line_bytes as u64;
+    }
+}
+
+/// Get the start of the last lines of the file, 0 when the file has fewer lines
+fn last_lines_start(file: &mut File, len: u64, keep_lines: u64) -> Result<u64, io::Error> {
+    let mut block: Vec<u8> = Vec::new();
+    let mut line_ends: u64 = 0;
+    let mut block_end: u64 = len;
+
+    while block_end > 0 {
+        let block_start: u64 = block_end.saturating_sub(SCAN_BLOCK_SIZE);
+        block.clear();
+        file.seek(SeekFrom::Start(block_start))?;
+        (&mut *file)
+            .take(block_end - block_start)
+            .read_to_end(&mut block)?;
+
+        for (idx, byte) in block.iter().enumerate().rev() {
+            let position: u64 = block_start + idx as u64;
+
+            //The line end of the last line does not start another line
+            if *byte != b'\n' || position + 1 == len {
+                continue;
+            }
+
+            line_ends += 1;
+            if line_ends == keep_lines {
+                return Ok(position + 1);
+            }
+        }
+
+        block_end = block_start;
+    }
+
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_trim_file() {
+        let dir = env::temp_dir().join(format!("yalc_test_trim_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("debug.log");
+        let content: String = (1..=10_000).map(|i| format!("line {}\n", i)).collect();
+        fs::write(&path, &content).unwrap();
+
+        let options = CopyOptions {
+            buffer_size: 4096,
+            rate_limit: None,
+        };
+        let by_lines = TrimConfig {
+            keep_lines: Some(3),
+            ..TrimConfig::default()
+        };
+        trim_file(1, &path, &by_lines, &options).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "line 9998\nline 9999\nline 10000\n"
+        );
+
+        //The kept bytes start at the next full line
+        let by_bytes = TrimConfig {
+            keep_lines: Some(3),
+            keep_bytes: Some(15),
+            ..TrimConfig::default()
+        };
+        trim_file(1, &path, &by_bytes, &options).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line 10000\n");
+
+        //A file within the limits is not changed
+        assert_eq!(trim_file(1, &path, &by_lines, &options).unwrap(), 0);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_trim_by_timestamp() {
+        let dir = env::temp_dir().join(format!("yalc_test_trim_age_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("app.log");
+        fs::write(
+            &path,
+            "2020-01-01 10:00:00 old\n  stack trace\n2999-01-01 10:00:00 new\n  stack trace\n",
+        )
+        .unwrap();
+
+        let options = CopyOptions {
+            buffer_size: 4096,
+            rate_limit: None,
+        };
+        let by_age = TrimConfig {
+            max_age_s: Some(86400),
+            ..TrimConfig::default()
+        };
+        assert_eq!(trim_file(1, &path, &by_age, &options).unwrap(), 38);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "2999-01-01 10:00:00 new\n  stack trace\n"
+        );
+
+        //The file is kept when no line has a timestamp of the format
+        let unparsable = "[10:00] first line\n[10:01] second line\n";
+        fs::write(&path, unparsable).unwrap();
+        assert!(trim_file(1, &path, &by_age, &options).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), unparsable);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
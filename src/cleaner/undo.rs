@@ -0,0 +1,621 @@
+//! Module for the undo journal of the last run
+//!
+//! A rotation with wrong settings is hard to reverse by hand, the archives
+//! are already shifted and compressed. Every run records the steps that
+//! changed each file in a journal next to the state file, like
+//! 'state.undo.toml'. The restore command reverts the most recent run of a
+//! file with these steps in reverse order. A live file that was written
+//! since the run is never restored, its new content would be lost. Removed
+//! archives and masked content can not be restored, they are reported.
+//!
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cleaner::audit;
+use crate::cleaner::compression::{self, Compressor};
+use crate::cleaner::file_copy::{self, CopyOptions};
+use crate::cleaner::file_meta;
+use crate::cleaner::shred;
+use crate::cleaner::transfer;
+use crate::config::toml_parser::{self, Table, TopLevelTable, Value};
+use crate::config::toml_writer;
+use crate::output;
+use crate::state;
+
+/// Name of the array of tables with the recorded run of every file
+const FILE_KEY: &str = "file";
+
+/// Name of the array of tables with the steps of all files
+const STEP_KEY: &str = "step";
+
+/// Enum representing a step of a run that changed a file
+#[derive(Debug, Clone, PartialEq)]
+pub enum UndoStep {
+    /// A file was renamed or moved
+    Rename { from: PathBuf, to: PathBuf },
+
+    /// The content of a file was copied and the file was truncated
+    CopyTruncate { from: PathBuf, to: PathBuf },
+
+    /// A new empty file was created
+    Create { path: PathBuf },
+
+    /// A file was compressed into an archive and removed afterwards
+    Compress { from: PathBuf, to: PathBuf },
+
+    /// A file was removed, it can not be restored
+    Delete { path: PathBuf },
+
+    /// The content of a file was changed in place, it can not be restored
+    Modify { path: PathBuf },
+}
+
+impl UndoStep {
+    /// Name of the step in the journal
+    fn name(&self) -> &'static str {
+        match self {
+            UndoStep::Rename { .. } => "rename",
+            UndoStep::CopyTruncate { .. } => "copy_truncate",
+            UndoStep::Create { .. } => "create",
+            UndoStep::Compress { .. } => "compress",
+            UndoStep::Delete { .. } => "delete",
+            UndoStep::Modify { .. } => "modify",
+        }
+    }
+
+    /// Returns true when the step can be reverted
+    pub fn is_reversible(&self) -> bool {
+        !matches!(self, UndoStep::Delete { .. } | UndoStep::Modify { .. })
+    }
+
+    /// Read a step from a journal entry, entries with missing values are ignored
+    fn parse(table: &Table) -> Option<UndoStep> {
+        let get_path = |key: &str| match table.get(key) {
+            Some(Value::String(value)) => Some(PathBuf::from(value)),
+            _ => None,
+        };
+
+        let op: &str = match table.get("op") {
+            Some(Value::String(op)) => op,
+            _ => return None,
+        };
+
+        Some(match op {
+            "rename" => UndoStep::Rename {
+                from: get_path("from")?,
+                to: get_path("to")?,
+            },
+            "copy_truncate" => UndoStep::CopyTruncate {
+                from: get_path("from")?,
+                to: get_path("to")?,
+            },
+            "create" => UndoStep::Create {
+                path: get_path("path")?,
+            },
+            "compress" => UndoStep::Compress {
+                from: get_path("from")?,
+                to: get_path("to")?,
+            },
+            "delete" => UndoStep::Delete {
+                path: get_path("path")?,
+            },
+            "modify" => UndoStep::Modify {
+                path: get_path("path")?,
+            },
+            _ => return None,
+        })
+    }
+
+    /// Convert the step of a file into a journal entry
+    fn format(&self, file_path: &Path) -> Table {
+        let to_value = |path: &Path| Value::String(path.display().to_string());
+
+        let mut table: Table = Table::new();
+        table.insert("file".to_string(), to_value(file_path));
+        table.insert("op".to_string(), Value::String(self.name().to_string()));
+
+        match self {
+            UndoStep::Rename { from, to }
+            | UndoStep::CopyTruncate { from, to }
+            | UndoStep::Compress { from, to } => {
+                table.insert("from".to_string(), to_value(from));
+                table.insert("to".to_string(), to_value(to));
+            }
+            UndoStep::Create { path } | UndoStep::Delete { path } | UndoStep::Modify { path } => {
+                table.insert("path".to_string(), to_value(path));
+            }
+        }
+
+        table
+    }
+}
+
+//Implement the Display trait
+impl fmt::Display for UndoStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UndoStep::Rename { from, to } => {
+                write!(f, "rename '{}' back to '{}'", to.display(), from.display())
+            }
+            UndoStep::CopyTruncate { from, to } => {
+                write!(f, "copy '{}' back into '{}'", to.display(), from.display())
+            }
+            UndoStep::Create { path } => {
+                write!(f, "remove the created file '{}'", path.display())
+            }
+            UndoStep::Compress { from, to } => {
+                write!(f, "decompress '{}' into '{}'", to.display(), from.display())
+            }
+            UndoStep::Delete { path } => write!(
+                f,
+                "skip the removed file '{}', it can not be restored",
+                path.display()
+            ),
+            UndoStep::Modify { path } => write!(
+                f,
+                "skip the changed content of '{}', it can not be restored",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Represents the recorded steps of the most recent run of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileUndo {
+    /// Path of the live log file, the target when a symbolic link was followed
+    pub live_path: PathBuf,
+
+    /// Unix time in seconds of the run
+    pub run_at: u64,
+
+    /// Size of the live file directly after the run, None when it did not exist
+    pub live_size: Option<u64>,
+
+    /// Steps in the order of their execution
+    pub steps: Vec<UndoStep>,
+}
+
+impl FileUndo {
+    /// Returns true when the live file was renamed or truncated by the run
+    fn replaces_live_file(&self) -> bool {
+        self.steps.iter().any(|step| match step {
+            UndoStep::Rename { from, .. } | UndoStep::CopyTruncate { from, .. } => {
+                *from == self.live_path
+            }
+            _ => false,
+        })
+    }
+}
+
+/// Represents the loaded undo journal of all files
+#[derive(Debug)]
+pub struct UndoJournal {
+    path: PathBuf,
+    files: BTreeMap<PathBuf, FileUndo>,
+
+    /// When set to true the journal differs from the journal file
+    changed: bool,
+}
+
+/// Get the path of the undo journal next to the state file like '/var/lib/yalc/state.undo.toml'
+pub fn journal_path(state_path: &Path) -> PathBuf {
+    state_path.with_extension("undo.toml")
+}
+
+impl UndoJournal {
+    /// Create an empty journal that is saved to the path
+    pub fn new(path: &Path) -> UndoJournal {
+        UndoJournal {
+            path: path.to_path_buf(),
+            files: BTreeMap::new(),
+            changed: false,
+        }
+    }
+
+    /// Load the journal file, a missing file results in an empty journal
+    pub fn load(path: &Path) -> Result<UndoJournal, io::Error> {
+        let mut journal = UndoJournal::new(path);
+
+        if !path.exists() {
+            return Ok(journal);
+        }
+
+        let root: TopLevelTable = toml_parser::load_toml_table(path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Failed to load undo journal '{}': {}", path.display(), e),
+            )
+        })?;
+
+        let tables = |key: &str| -> Vec<Table> {
+            match root.get(key) {
+                Some(Value::Array(entries)) => entries
+                    .iter()
+                    .filter_map(|entry| match entry {
+                        Value::Table(table) => Some(table.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            }
+        };
+
+        for table in tables(FILE_KEY).iter() {
+            let get_uint = |key: &str| match table.get(key) {
+                Some(Value::Integer(value)) => u64::try_from(*value).ok(),
+                _ => None,
+            };
+
+            if let Some(Value::String(file_path)) = table.get("path")
+                && let Some(Value::String(live_path)) = table.get("live_path")
+            {
+                journal.files.insert(
+                    PathBuf::from(file_path),
+                    FileUndo {
+                        live_path: PathBuf::from(live_path),
+                        run_at: get_uint("run_at").unwrap_or(0),
+                        live_size: get_uint("live_size"),
+                        steps: Vec::new(),
+                    },
+                );
+            }
+        }
+
+        //The steps are listed in the order of their execution
+        for table in tables(STEP_KEY).iter() {
+            if let Some(Value::String(file_path)) = table.get("file")
+                && let Some(file_undo) = journal.files.get_mut(Path::new(file_path))
+                && let Some(step) = UndoStep::parse(table)
+            {
+                file_undo.steps.push(step);
+            }
+        }
+
+        Ok(journal)
+    }
+
+    /// Get the path of the journal file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the recorded run of a file, None for files without a recorded run
+    pub fn get(&self, file_path: &Path) -> Option<&FileUndo> {
+        self.files.get(file_path)
+    }
+
+    /// Get the paths of all files with a recorded run
+    pub fn file_paths(&self) -> Vec<PathBuf> {
+        self.files.keys().cloned().collect()
+    }
+
+    /// Store the steps of a run for the file, a run without steps keeps the previous run
+    pub fn record_run(&mut self, file_path: &Path, live_path: &Path, steps: Vec<UndoStep>) {
+        if steps.is_empty() {
+            return;
+        }
+
+        let file_undo = FileUndo {
+            live_path: live_path.to_path_buf(),
+            run_at: state::unix_time_now(),
+            live_size: fs::metadata(live_path).map(|m| m.len()).ok(),
+            steps,
+        };
+
+        self.files.insert(file_path.to_path_buf(), file_undo);
+        self.changed = true;
+    }
+
+    /// Remove the recorded run of a file after it was restored
+    pub fn remove(&mut self, file_path: &Path) {
+        if self.files.remove(file_path).is_some() {
+            self.changed = true;
+        }
+    }
+
+    /// Returns true when the journal must be saved
+    pub fn has_changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Write the journal file, the parent directory is created when it is missing
+    pub fn save(&self) -> Result<(), io::Error> {
+        let mut files: Vec<Value> = Vec::new();
+        let mut steps: Vec<Value> = Vec::new();
+
+        for (file_path, file_undo) in self.files.iter() {
+            let mut table: Table = Table::new();
+            table.insert(
+                "path".to_string(),
+                Value::String(file_path.display().to_string()),
+            );
+            table.insert(
+                "live_path".to_string(),
+                Value::String(file_undo.live_path.display().to_string()),
+            );
+            table.insert(
+                "run_at".to_string(),
+                Value::Integer(i64::try_from(file_undo.run_at).unwrap_or(i64::MAX)),
+            );
+
+            if let Some(live_size) = file_undo.live_size {
+                table.insert(
+                    "live_size".to_string(),
+                    Value::Integer(i64::try_from(live_size).unwrap_or(i64::MAX)),
+                );
+            }
+
+            files.push(Value::Table(table));
+            steps.extend(
+                file_undo
+                    .steps
+                    .iter()
+                    .map(|step| Value::Table(step.format(file_path))),
+            );
+        }
+
+        let mut root: TopLevelTable = TopLevelTable::new();
+        root.insert(FILE_KEY.to_string(), Value::Array(files));
+        root.insert(STEP_KEY.to_string(), Value::Array(steps));
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        //The journal is written to a temporary file first, so it is replaced atomically
+        let tmp_path: PathBuf = self.path.with_extension("toml.tmp");
+        fs::write(&tmp_path, toml_writer::to_toml_string(&root))?;
+        fs::rename(&tmp_path, &self.path)
+    }
+}
+
+/// Revert the recorded run of a file, the steps are reverted in reverse order.
+/// Nothing is changed when the live file has grown or a step can not be reverted.
+pub fn restore_file(
+    task_nr: usize,
+    file_undo: &FileUndo,
+    dry_run: bool,
+    options: &CopyOptions,
+) -> Result<(), io::Error> {
+    check_restorable(file_undo)?;
+
+    //Lines written after the run are appended to the restored content again
+    let pending_content: Vec<u8> = match file_undo.replaces_live_file() {
+        true => match fs::read(&file_undo.live_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        },
+        false => Vec::new(),
+    };
+
+    for step in file_undo.steps.iter().rev() {
+        if dry_run {
+            output::print_status(&format!("[{}] DRY RUN: Would {}", task_nr, step));
+            continue;
+        }
+
+        if step.is_reversible() {
+            output::print_status(&format!("[{}] Reverting: {}", task_nr, step));
+        } else {
+            output::print_status(&format!("[{}] Skipping: {}", task_nr, step));
+        }
+
+        revert_step(task_nr, step, options)?;
+    }
+
+    if !dry_run && !pending_content.is_empty() {
+        let live_path: &Path = &file_undo.live_path;
+        audit::track("append", &[("path", &live_path)], || {
+            OpenOptions::new()
+                .append(true)
+                .open(live_path)?
+                .write_all(&pending_content)
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Check that all steps of the run can be reverted without losing any content
+fn check_restorable(file_undo: &FileUndo) -> Result<(), io::Error> {
+    let live_path: &Path = &file_undo.live_path;
+
+    for step in file_undo.steps.iter() {
+        match step {
+            UndoStep::Delete { path } if path == live_path => {
+                return Err(io::Error::other(format!(
+                    "The last run removed '{}', it can not be restored",
+                    live_path.display()
+                )));
+            }
+            UndoStep::Modify { path } if path == live_path => {
+                return Err(io::Error::other(format!(
+                    "The last run changed the content of '{}' in place, it can not be restored",
+                    live_path.display()
+                )));
+            }
+            _ => {}
+        }
+    }
+
+    if file_undo.replaces_live_file() {
+        let recorded_size: u64 = file_undo.live_size.unwrap_or(0);
+        let current_size: u64 = fs::metadata(live_path).map_or(0, |m| m.len());
+
+        if current_size > recorded_size {
+            return Err(io::Error::other(format!(
+                "The live file '{}' has grown from {} to {} bytes since the last run",
+                live_path.display(),
+                recorded_size,
+                current_size
+            )));
+        }
+    }
+
+    //The steps are simulated in reverse order, so a restore never stops halfway
+    let mut simulated: HashMap<PathBuf, bool> = HashMap::new();
+    let exists = |simulated: &HashMap<PathBuf, bool>, path: &Path| {
+        simulated
+            .get(path)
+            .copied()
+            .unwrap_or_else(|| path.exists())
+    };
+
+    for step in file_undo.steps.iter().rev() {
+        match step {
+            UndoStep::Rename { from, to }
+            | UndoStep::CopyTruncate { from, to }
+            | UndoStep::Compress { from, to } => {
+                if !exists(&simulated, to) {
+                    return Err(io::Error::new(
+                        ErrorKind::NotFound,
+                        format!(
+                            "The file '{}' of the last run no longer exists",
+                            to.display()
+                        ),
+                    ));
+                }
+
+                if from != live_path && exists(&simulated, from) {
+                    return Err(io::Error::new(
+                        ErrorKind::AlreadyExists,
+                        format!(
+                            "The file '{}' would be overwritten by the restore",
+                            from.display()
+                        ),
+                    ));
+                }
+
+                simulated.insert(to.clone(), false);
+                simulated.insert(from.clone(), true);
+            }
+            UndoStep::Create { path } => {
+                simulated.insert(path.clone(), false);
+            }
+            UndoStep::Delete { .. } | UndoStep::Modify { .. } => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Revert a single step of the run
+fn revert_step(task_nr: usize, step: &UndoStep, options: &CopyOptions) -> Result<(), io::Error> {
+    match step {
+        UndoStep::Rename { from, to } => transfer::move_file(task_nr, to, from, options),
+        UndoStep::CopyTruncate { from, to } => {
+            //The content is written through the open file, so the inode is preserved
+            audit::track("restore", &[("from", &to), ("to", &from)], || {
+                let mut live_file: File = OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .open(from)?;
+                file_copy::copy_to_end(&mut File::open(to)?, &mut live_file, options)?;
+                live_file.sync_all()
+            })?;
+            shred::remove_file(to, false)
+        }
+        UndoStep::Create { path } => match path.exists() {
+            true => shred::remove_file(path, false),
+            false => Ok(()),
+        },
+        UndoStep::Compress { from, to } => {
+            let compressor: Box<dyn Compressor> = compression::compressor_for_archive(to)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Unknown compression of archive '{}'", to.display()),
+                    )
+                })?;
+
+            audit::track("decompress", &[("from", &to), ("to", &from)], || {
+                compressor.decompress(to, from, options)
+            })?;
+
+            let archive_metadata: Metadata = fs::metadata(to)?;
+            file_meta::replicate_metadata(task_nr, &archive_metadata, from)?;
+            shred::remove_file(to, false)
+        }
+        UndoStep::Delete { .. } | UndoStep::Modify { .. } => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_restore_file() {
+        let dir = env::temp_dir().join(format!("yalc_test_undo_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let live = dir.join("app.log");
+        let options = CopyOptions {
+            buffer_size: 4096,
+            rate_limit: None,
+        };
+
+        //Run with a shift of the previous archive and a copy truncate of the live file
+        fs::write(&live, "current\n").unwrap();
+        fs::write(dir.join("app.log.0"), "previous\n").unwrap();
+        fs::rename(dir.join("app.log.0"), dir.join("app.log.1")).unwrap();
+        fs::copy(&live, dir.join("app.log.0")).unwrap();
+        File::options()
+            .write(true)
+            .open(&live)
+            .unwrap()
+            .set_len(0)
+            .unwrap();
+
+        let journal_file = journal_path(&dir.join("state.toml"));
+        let mut journal = UndoJournal::load(&journal_file).unwrap();
+        journal.record_run(
+            &live,
+            &live,
+            vec![
+                UndoStep::Delete {
+                    path: dir.join("app.log.2"),
+                },
+                UndoStep::Rename {
+                    from: dir.join("app.log.0"),
+                    to: dir.join("app.log.1"),
+                },
+                UndoStep::CopyTruncate {
+                    from: live.clone(),
+                    to: dir.join("app.log.0"),
+                },
+            ],
+        );
+        journal.save().unwrap();
+
+        let loaded = UndoJournal::load(&journal_file).unwrap();
+        assert!(!loaded.has_changed());
+        assert_eq!(loaded.get(&live), journal.get(&live));
+        let file_undo: &FileUndo = loaded.get(&live).unwrap();
+        assert_eq!(file_undo.live_size, Some(0));
+
+        //A live file that has grown since the run is never restored
+        fs::write(&live, "new line\n").unwrap();
+        assert!(restore_file(1, file_undo, false, &options).is_err());
+        assert!(dir.join("app.log.1").exists());
+
+        fs::write(&live, "").unwrap();
+        restore_file(1, file_undo, false, &options).unwrap();
+        assert_eq!(fs::read_to_string(&live).unwrap(), "current\n");
+        assert_eq!(
+            fs::read_to_string(dir.join("app.log.0")).unwrap(),
+            "previous\n"
+        );
+        assert!(!dir.join("app.log.1").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
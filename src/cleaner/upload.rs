@@ -0,0 +1,222 @@
+//! Module for the upload of archives to remote storage
+//!
+//! Archives that are only kept on the host are lost together with it.
+//! With an upload, the archive that is finished by a rotation is copied to
+//! remote storage under a unique name with the upload time, like
+//! 'app.log-20240301T123000Z.gz'. The local names are shifted by every
+//! rotation, so they can not be used remotely. The name is built from a
+//! template, the host name and the application keep the archives of many
+//! machines apart on a shared storage. The uploads are done by the
+//! installed command line tools of the remote store, like the aws cli or
+//! scp. Every backend implements the RemoteStore trait in its own module,
+//! so all backends share the naming, the retries, the audit log and the
+//! removal of the local archive. The backends for GCS, Azure Blob Storage
+//! and WebDAV are optional features of the build. Failed uploads always
+//! keep the local archive.
+//!
+#[cfg(feature = "azure")]
+pub mod azure;
+#[cfg(feature = "gcs")]
+pub mod gcs;
+pub mod s3;
+pub mod ssh;
+#[cfg(feature = "webdav")]
+pub mod webdav;
+
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+use std::process::{Child, Command, Output, Stdio};
+
+use crate::cleaner::audit;
+use crate::cleaner::compression::COMPRESSED_EXTENSIONS;
+use crate::cleaner::retry;
+use crate::config::{UploadBackend, UploadConfig};
+use crate::state;
+
+/// Common interface of all remote storages of the upload
+pub trait RemoteStore {
+    /// Name of the backend used for logging
+    fn name(&self) -> &str;
+
+    /// Copy the archive to the remote location of the store with the remote name
+    fn upload(&self, archive: &Path, name: &str) -> Result<(), io::Error>;
+}
+
+/// Create the remote store of the configured upload backend
+pub fn create_remote_store(backend: &UploadBackend) -> Box<dyn RemoteStore + '_> {
+    match backend {
+        UploadBackend::S3(target) => Box::new(s3::S3Store { target }),
+        UploadBackend::Ssh(target) => Box::new(ssh::SshStore { target }),
+        #[cfg(feature = "gcs")]
+        UploadBackend::Gcs(target) => Box::new(gcs::GcsStore { target }),
+        #[cfg(feature = "azure")]
+        UploadBackend::Azure(target) => Box::new(azure::AzureStore { target }),
+        #[cfg(feature = "webdav")]
+        UploadBackend::WebDav(target) => Box::new(webdav::WebDavStore { target }),
+    }
+}
+
+/// Represents the values of the tokens in the name template of an archive
+#[derive(Debug)]
+pub struct NameTokens<'a> {
+    /// File name of the log file like 'app.log'
+    pub name: &'a str,
+
+    /// Name of the application, the group of the file or the log name without its extension
+    pub app: &'a str,
+
+    pub hostname: &'a str,
+}
+
+/// Get the remote name of an archive from the name template like 'app.log-20240301T123000Z.gz'.
+/// The extension of the compression is always appended.
+pub fn remote_name(archive: &Path, template: &str, tokens: &NameTokens, time_s: u64) -> String {
+    let timestamp: String = audit::format_utc_time(time_s).replace(['-', ':'], "");
+
+    let extension: String = match archive.extension().and_then(|e| e.to_str()) {
+        Some(extension) if COMPRESSED_EXTENSIONS.contains(&extension) => format!(".{}", extension),
+        _ => String::new(),
+    };
+
+    let name: String = template
+        .replace("{name}", tokens.name)
+        .replace("{app}", tokens.app)
+        .replace("{hostname}", tokens.hostname)
+        .replace("{time}", &timestamp);
+
+    format!("{}{}", name, extension)
+}
+
+/// Upload the archive of the log file with the retries of the upload config.
+/// Returns the remote location of the uploaded archive.
+pub fn upload_archive(
+    task_nr: usize,
+    archive: &Path,
+    tokens: &NameTokens,
+    upload: &UploadConfig,
+) -> Result<String, io::Error> {
+    let name: String = remote_name(
+        archive,
+        &upload.name_template,
+        tokens,
+        state::unix_time_now(),
+    );
+    let remote: String = format!("{}{}", upload.backend.location(), name);
+    let store: Box<dyn RemoteStore + '_> = create_remote_store(&upload.backend);
+
+    retry::with_retry_if(task_nr, &upload.retry, "Upload", is_retryable, || {
+        audit::track("upload", &[("path", &archive), ("to", &remote)], || {
+            store.upload(archive, &name)
+        })
+    })
+    .map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!(
+                "Failed to upload '{}' to '{}' with {}: {}",
+                archive.display(),
+                remote,
+                store.name(),
+                e
+            ),
+        )
+    })?;
+
+    Ok(remote)
+}
+
+/// Returns true when the upload can succeed with another attempt.
+/// Missing programs, files and credentials are not retried.
+fn is_retryable(error: &io::Error) -> bool {
+    !matches!(
+        error.kind(),
+        ErrorKind::NotFound | ErrorKind::InvalidInput | ErrorKind::PermissionDenied
+    )
+}
+
+/// Run the program of an upload with the optional input.
+/// The error contains the error output of the program.
+pub(crate) fn run_upload_program(
+    mut command: Command,
+    input: Option<&str>,
+) -> Result<(), io::Error> {
+    let program: String = command.get_program().to_string_lossy().to_string();
+    let start_error = |e: io::Error| {
+        io::Error::new(
+            e.kind(),
+            format!("Failed to start upload program '{}': {}", program, e),
+        )
+    };
+
+    let stdin: Stdio = match input {
+        Some(_) => Stdio::piped(),
+        None => Stdio::null(),
+    };
+
+    let mut child: Child = command
+        .stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(start_error)?;
+
+    //The input is closed after it was written, so the program sees its end
+    if let (Some(input), Some(mut child_stdin)) = (input, child.stdin.take()) {
+        child_stdin.write_all(input.as_bytes())?;
+    }
+
+    let output: Output = child.wait_with_output()?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    Err(io::Error::other(format!(
+        "Upload program '{}' failed with {}: {}",
+        program,
+        output.status,
+        String::from_utf8_lossy(&output.stderr).trim()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_name() {
+        let tokens = NameTokens {
+            name: "app.log",
+            app: "shop",
+            hostname: "web-01",
+        };
+
+        assert_eq!(
+            remote_name(
+                Path::new("/var/log/app.log.0.gz"),
+                "{name}-{time}",
+                &tokens,
+                1709296200
+            ),
+            "app.log-20240301T123000Z.gz"
+        );
+        assert_eq!(
+            remote_name(
+                Path::new("/var/log/app.log.1"),
+                "{name}-{time}",
+                &tokens,
+                1709296200
+            ),
+            "app.log-20240301T123000Z"
+        );
+        assert_eq!(
+            remote_name(
+                Path::new("/var/log/app.log.0.gz"),
+                "{hostname}/{app}/{name}-{time}",
+                &tokens,
+                1709296200
+            ),
+            "web-01/shop/app.log-20240301T123000Z.gz"
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! Remote store of an Azure Blob Storage container, the archives are
+//! copied with the az cli and the login of its current account.
+//!
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use crate::cleaner::upload::{RemoteStore, run_upload_program};
+use crate::config::AzureTarget;
+
+/// Program of the az cli that copies the archives into the container
+const AZ_PROGRAM: &str = "az";
+
+/// Remote store that copies the archives into the container of the target
+pub struct AzureStore<'a> {
+    pub target: &'a AzureTarget,
+}
+
+impl RemoteStore for AzureStore<'_> {
+    fn name(&self) -> &str {
+        "azure"
+    }
+
+    fn upload(&self, archive: &Path, name: &str) -> Result<(), io::Error> {
+        let mut command = Command::new(AZ_PROGRAM);
+        command
+            .arg("storage")
+            .arg("blob")
+            .arg("upload")
+            .arg("--account-name")
+            .arg(&self.target.account)
+            .arg("--container-name")
+            .arg(&self.target.container)
+            .arg("--name")
+            .arg(format!("{}{}", self.target.prefix, name))
+            .arg("--file")
+            .arg(archive)
+            .arg("--auth-mode")
+            .arg("login")
+            .arg("--only-show-errors")
+            .arg("--no-progress");
+
+        run_upload_program(command, None)
+    }
+}
@@ -0,0 +1,38 @@
+//! Remote store of a Google Cloud Storage bucket, the archives are copied
+//! with the gcloud cli and the credentials of its active account.
+//!
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use crate::cleaner::upload::{RemoteStore, run_upload_program};
+use crate::config::GcsTarget;
+
+/// Program of the gcloud cli that copies the archives into the bucket
+const GCLOUD_PROGRAM: &str = "gcloud";
+
+/// Remote store that copies the archives into the bucket of the target
+pub struct GcsStore<'a> {
+    pub target: &'a GcsTarget,
+}
+
+impl RemoteStore for GcsStore<'_> {
+    fn name(&self) -> &str {
+        "gcs"
+    }
+
+    fn upload(&self, archive: &Path, name: &str) -> Result<(), io::Error> {
+        let mut command = Command::new(GCLOUD_PROGRAM);
+        command
+            .arg("storage")
+            .arg("cp")
+            .arg(archive)
+            .arg(format!(
+                "gs://{}/{}{}",
+                self.target.bucket, self.target.prefix, name
+            ))
+            .arg("--quiet");
+
+        run_upload_program(command, None)
+    }
+}
@@ -0,0 +1,64 @@
+//! Remote store of an S3 bucket, the archives are copied with the aws cli
+//!
+use std::env;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use std::process::Command;
+
+use crate::cleaner::upload::{RemoteStore, run_upload_program};
+use crate::config::{S3Credentials, S3Target};
+
+/// Program of the aws cli that copies the archives into S3
+const AWS_PROGRAM: &str = "aws";
+
+/// Variables of the access keys that are required by the credentials source 'env'
+const AWS_KEY_VARIABLES: [&str; 2] = ["AWS_ACCESS_KEY_ID", "AWS_SECRET_ACCESS_KEY"];
+
+/// Remote store that copies the archives into the bucket of the target
+pub struct S3Store<'a> {
+    pub target: &'a S3Target,
+}
+
+impl RemoteStore for S3Store<'_> {
+    fn name(&self) -> &str {
+        "s3"
+    }
+
+    fn upload(&self, archive: &Path, name: &str) -> Result<(), io::Error> {
+        let target: &S3Target = self.target;
+        let mut command = Command::new(AWS_PROGRAM);
+        command
+            .arg("s3")
+            .arg("cp")
+            .arg(archive)
+            .arg(format!("s3://{}/{}{}", target.bucket, target.prefix, name))
+            .arg("--only-show-errors");
+
+        if let Some(region) = &target.region {
+            command.arg("--region").arg(region);
+        }
+
+        match &target.credentials {
+            S3Credentials::Default => {}
+            S3Credentials::Env => {
+                //The aws cli would silently fall back to other credentials
+                for variable in AWS_KEY_VARIABLES {
+                    if env::var_os(variable).is_none_or(|value| value.is_empty()) {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidInput,
+                            format!(
+                                "The S3 credentials 'env' require the variable '{}'",
+                                variable
+                            ),
+                        ));
+                    }
+                }
+            }
+            S3Credentials::Profile(profile) => {
+                command.arg("--profile").arg(profile);
+            }
+        }
+
+        run_upload_program(command, None)
+    }
+}
@@ -0,0 +1,92 @@
+//! Remote store of a directory on an ssh server, the archives are copied
+//! with scp or sftp in batch mode, so a missing key fails instead of
+//! waiting for a password.
+//!
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use crate::cleaner::upload::{RemoteStore, run_upload_program};
+use crate::config::{SshTarget, SshTransfer};
+
+/// Programs of the ssh transfers
+const SCP_PROGRAM: &str = "scp";
+const SFTP_PROGRAM: &str = "sftp";
+
+/// Remote store that copies the archives into the directory of the target
+pub struct SshStore<'a> {
+    pub target: &'a SshTarget,
+}
+
+impl RemoteStore for SshStore<'_> {
+    fn name(&self) -> &str {
+        match self.target.transfer {
+            SshTransfer::Scp => SCP_PROGRAM,
+            SshTransfer::Sftp => SFTP_PROGRAM,
+        }
+    }
+
+    fn upload(&self, archive: &Path, name: &str) -> Result<(), io::Error> {
+        let target: &SshTarget = self.target;
+        let remote_path: String = format!("{}{}", target.path, name);
+
+        let (mut command, input) = match target.transfer {
+            SshTransfer::Scp => {
+                let mut command = Command::new(SCP_PROGRAM);
+                command.arg("-B").arg("-q");
+                (command, None)
+            }
+            SshTransfer::Sftp => {
+                //The batch commands are read from the input, the paths are quoted for sftp
+                let mut command = Command::new(SFTP_PROGRAM);
+                command.arg("-b").arg("-").arg("-q");
+                let batch: String = format!(
+                    "put {} {}\n",
+                    quote_sftp_path(&archive.to_string_lossy()),
+                    quote_sftp_path(&remote_path)
+                );
+                (command, Some(batch))
+            }
+        };
+
+        if let Some(port) = target.port {
+            command.arg("-P").arg(port.to_string());
+        }
+
+        if let Some(identity_file) = &target.identity_file {
+            command.arg("-i").arg(identity_file);
+        }
+
+        command.arg("-o").arg("BatchMode=yes");
+
+        match target.transfer {
+            SshTransfer::Scp => {
+                command
+                    .arg(archive)
+                    .arg(format!("{}:{}", target.destination(), remote_path))
+            }
+            SshTransfer::Sftp => command.arg(target.destination()),
+        };
+
+        run_upload_program(command, input.as_deref())
+    }
+}
+
+/// Quote a path for the batch commands of sftp
+fn quote_sftp_path(path: &str) -> String {
+    format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_sftp_path() {
+        assert_eq!(
+            quote_sftp_path("/srv/logs/app.log"),
+            "\"/srv/logs/app.log\""
+        );
+        assert_eq!(quote_sftp_path("/srv/a \"b\""), "\"/srv/a \\\"b\\\"\"");
+    }
+}
@@ -0,0 +1,71 @@
+//! Remote store of a WebDAV collection, the archives are copied with a
+//! put request of curl. The credentials are read from a netrc file, so
+//! they never appear in the arguments of the process.
+//!
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use crate::cleaner::upload::{RemoteStore, run_upload_program};
+use crate::config::WebDavTarget;
+
+/// Program of curl that sends the put requests
+const CURL_PROGRAM: &str = "curl";
+
+/// Remote store that copies the archives into the collection of the target
+pub struct WebDavStore<'a> {
+    pub target: &'a WebDavTarget,
+}
+
+impl RemoteStore for WebDavStore<'_> {
+    fn name(&self) -> &str {
+        "webdav"
+    }
+
+    fn upload(&self, archive: &Path, name: &str) -> Result<(), io::Error> {
+        let mut command = Command::new(CURL_PROGRAM);
+        command
+            .arg("--fail")
+            .arg("--silent")
+            .arg("--show-error")
+            .arg("--upload-file")
+            .arg(archive);
+
+        if let Some(netrc_file) = &self.target.netrc_file {
+            command.arg("--netrc-file").arg(netrc_file);
+        }
+
+        command.arg(format!("{}{}", self.target.url, encode_path_segment(name)));
+        run_upload_program(command, None)
+    }
+}
+
+/// Percent-encode all bytes of a path segment except the unreserved characters
+fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_path_segment() {
+        assert_eq!(
+            encode_path_segment("app.log-20240301T123000Z.gz"),
+            "app.log-20240301T123000Z.gz"
+        );
+        assert_eq!(encode_path_segment("my app+1.log"), "my%20app%2B1.log");
+    }
+}
@@ -3,17 +3,86 @@
 //! Provides logic for parsing and executing commands.
 //! Other modules may be using to execute commands.
 //!
+pub mod history;
+pub mod report;
 
 use crate::{
-    cleaner, config,
-    constants::{DEFAULT_CONFIG_PATH, YALC_VERSION},
-    help,
+    cleaner,
+    config::{self, Config, ErrorPolicy, FailOn, config_deprecation, config_env},
+    constants::{EXIT_ERROR, EXIT_TASK_FAILURE, YALC_VERSION},
+    daemon, help, notify,
+    output::{self, ColorMode, LogLevel, OutputFormat, events, run_id},
+    platform, resolver, stats,
 };
 
-use std::{
-    io::{self, ErrorKind},
-    path::Path,
-};
+use std::io::{self, ErrorKind};
+use std::path::{self, Path, PathBuf};
+use std::process::ExitCode;
+
+/// Options that can be used with every command
+#[derive(Debug, Default)]
+pub struct GlobalArgs {
+    /// Path of the config file provided via '--config'
+    pub config_path: Option<PathBuf>,
+
+    /// Number of '--verbose' options, every option shows one more log level
+    pub verbosity: u8,
+
+    /// Color mode provided via '--color'
+    pub color: Option<ColorMode>,
+}
+
+impl GlobalArgs {
+    /// Remove the global options from the args and return the parsed options
+    pub fn extract_from(args: &mut Vec<String>) -> Result<GlobalArgs, io::Error> {
+        let mut global_args = GlobalArgs::default();
+        let mut idx: usize = 1;
+
+        while idx < args.len() {
+            if args[idx] == "--config" {
+                let value = args.get(idx + 1).filter(|value| !value.starts_with('-'));
+
+                let Some(value) = value else {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        "Missing value for argument: '--config'",
+                    ));
+                };
+
+                global_args.config_path = Some(PathBuf::from(value));
+                args.drain(idx..idx + 2);
+            } else if args[idx] == "--color" {
+                let Some(value) = args.get(idx + 1) else {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        "Missing value for argument: '--color'",
+                    ));
+                };
+
+                let mode: ColorMode = value
+                    .parse()
+                    .map_err(|e| io::Error::new(ErrorKind::InvalidInput, format!("{}", e)))?;
+                global_args.color = Some(mode);
+                args.drain(idx..idx + 2);
+            } else if args[idx] == "--verbose" {
+                global_args.verbosity = global_args.verbosity.saturating_add(1);
+                args.remove(idx);
+            } else {
+                idx += 1;
+            }
+        }
+
+        Ok(global_args)
+    }
+
+    /// Get the log level of the '--verbose' options, None when the option is not used
+    pub fn log_level(&self) -> Option<LogLevel> {
+        match self.verbosity {
+            0 => None,
+            count => Some(LogLevel::from_verbosity(count)),
+        }
+    }
+}
 
 /// Enum representing different commands that can be executed
 #[derive(Debug)]
@@ -29,6 +98,18 @@ pub enum Command {
 
     /// Run command to execute with additional arguments
     Run(Vec<RunArg>),
+
+    /// Daemon command to execute the run periodically
+    Daemon(Vec<RunArg>),
+
+    /// Restore command to revert the most recent run of the files
+    Restore(Vec<RestoreArg>),
+
+    /// History command to list the recorded runs
+    History(Vec<StatsArg>),
+
+    /// Stats command to summarize the recorded outcomes of every file
+    Stats(Vec<StatsArg>),
 }
 
 /// Enum representing different config command arguments
@@ -37,12 +118,18 @@ pub enum ConfigArg {
     /// Crates a new config file with default values
     Init,
 
-    /// Check if the config file exists and is valid
-    Check,
+    /// Check if the config file exists and is valid, strict checks also fail on warnings
+    Check { strict: bool },
+
+    /// Upgrade the config file to the current schema version
+    Migrate,
+
+    /// Print an example config with all supported keys
+    Example,
 }
 
 /// Enum representing different run arguments
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum RunArg {
     /// Overwrite the config value 'dry_run' with true
     DryRun,
@@ -52,6 +139,53 @@ pub enum RunArg {
 
     /// Overwrite the config value 'copy_truncate' with true
     Truncate,
+
+    /// Apply the values of a named config profile
+    Profile(String),
+
+    /// Overwrite the config value 'on_error'
+    OnError(ErrorPolicy),
+
+    /// Overwrite the config value 'fail_on'
+    FailOn(FailOn),
+
+    /// Only process the files of a named group
+    Group(String),
+
+    /// Only process the files whose path is matched by a glob pattern
+    Match(String),
+
+    /// Print the planned actions without executing them
+    Plan,
+
+    /// Format of the command result
+    Output(OutputFormat),
+
+    /// Only print the output of the run when a task has failed
+    Cron,
+}
+
+/// Enum representing different restore arguments
+#[derive(Debug, Clone)]
+pub enum RestoreArg {
+    /// Print the steps that would be reverted without changing any file
+    DryRun,
+
+    /// Apply the values of a named config profile
+    Profile(String),
+
+    /// Absolute path of a file to restore, all recorded files are restored without any path
+    File(PathBuf),
+}
+
+/// Enum representing the arguments of the history and stats commands
+#[derive(Debug, Clone)]
+pub enum StatsArg {
+    /// Apply the values of a named config profile
+    Profile(String),
+
+    /// Only use the most recent recorded runs
+    Limit(usize),
 }
 
 impl Command {
@@ -69,6 +203,25 @@ impl Command {
             "version" | "-v" | "v" => Command::Version,
             "config" | "-c" | "c" => Self::parse_config_command(&args),
             "run" => Self::parse_run_command(&args[1..]),
+            "daemon" => match Self::parse_run_command(&args[1..]) {
+                Command::Run(run_args) => Command::Daemon(run_args),
+                command => command,
+            },
+            "restore" => Self::parse_restore_command(&args[1..]),
+            "history" => match Self::parse_stats_args(&args[1..]) {
+                Ok(stats_args) => Command::History(stats_args),
+                Err(e) => {
+                    output::error(&e.to_string());
+                    Command::Help
+                }
+            },
+            "stats" => match Self::parse_stats_args(&args[1..]) {
+                Ok(stats_args) => Command::Stats(stats_args),
+                Err(e) => {
+                    output::error(&e.to_string());
+                    Command::Help
+                }
+            },
             _ => Self::parse_run_command(&args),
         }
     }
@@ -76,43 +229,51 @@ impl Command {
     fn parse_config_command(args: &Vec<String>) -> Command {
         //Use the check command when config is called without additional args
         if args.len() == 1 {
-            Command::Config(ConfigArg::Check)
+            Command::Config(ConfigArg::Check { strict: false })
+        } else if args.len() == 3 && args[1].eq_ignore_ascii_case("check") && args[2] == "--strict"
+        {
+            Command::Config(ConfigArg::Check { strict: true })
         } else if args.len() == 2 {
             //Parse the config argument command
             match args[1].to_lowercase().as_str() {
                 "init" => Command::Config(ConfigArg::Init),
-                "check" => Command::Config(ConfigArg::Check),
+                "check" => Command::Config(ConfigArg::Check { strict: false }),
+                "migrate" => Command::Config(ConfigArg::Migrate),
+                "example" => Command::Config(ConfigArg::Example),
                 _ => {
                     //Display help in case of invalid config arg
-                    eprintln!("Invalid config argument: {}", args[1]);
+                    output::error(&format!("Invalid config argument: {}", args[1]));
                     Command::Help
                 }
             }
         } else {
             //Invalid config argument length
-            eprintln!(
+            output::error(&format!(
                 "Invalid amount of config arguments provided: {}",
                 args.len()
-            );
+            ));
             Command::Help
         }
     }
 
     fn parse_run_command(args: &[String]) -> Command {
-        match Self::parse_run_args(&args.to_vec()) {
+        match Self::parse_run_args(args) {
             Ok(run_args) => Command::Run(run_args),
             Err(e) => {
-                eprintln!("{}", e);
+                output::error(&e.to_string());
                 Command::Help
             }
         }
     }
 
-    fn parse_run_args(args: &Vec<String>) -> Result<Vec<RunArg>, io::Error> {
-        let mut run_args: Vec<RunArg> = Vec::with_capacity(args.capacity());
+    fn parse_run_args(args: &[String]) -> Result<Vec<RunArg>, io::Error> {
+        let mut run_args: Vec<RunArg> = Vec::with_capacity(args.len());
+        let mut args_iter = args.iter();
 
         //Convert each argument
-        for arg in args.iter() {
+        while let Some(raw_arg) = args_iter.next() {
+            let arg: String = Self::replace_deprecated_flag(raw_arg);
+
             match arg.to_lowercase().as_str() {
                 "--dry" | "-d" => {
                     run_args.push(RunArg::DryRun);
@@ -123,6 +284,46 @@ impl Command {
                 "--trunc" | "-t" => {
                     run_args.push(RunArg::Truncate);
                 }
+                "--profile" | "-p" => {
+                    //The profile name is the next argument
+                    let name = Self::expect_arg_value(&arg, args_iter.next())?;
+                    run_args.push(RunArg::Profile(name));
+                }
+                "--on-error" | "-e" => {
+                    let value = Self::expect_arg_value(&arg, args_iter.next())?;
+                    let policy: ErrorPolicy = value
+                        .parse()
+                        .map_err(|e| io::Error::new(ErrorKind::InvalidInput, format!("{}", e)))?;
+                    run_args.push(RunArg::OnError(policy));
+                }
+                "--fail-on" => {
+                    let value = Self::expect_arg_value(&arg, args_iter.next())?;
+                    let fail_on: FailOn = value
+                        .parse()
+                        .map_err(|e| io::Error::new(ErrorKind::InvalidInput, format!("{}", e)))?;
+                    run_args.push(RunArg::FailOn(fail_on));
+                }
+                "--group" | "-g" => {
+                    let name = Self::expect_arg_value(&arg, args_iter.next())?;
+                    run_args.push(RunArg::Group(name));
+                }
+                "--match" | "-m" => {
+                    let pattern = Self::expect_arg_value(&arg, args_iter.next())?;
+                    run_args.push(RunArg::Match(pattern));
+                }
+                "--plan" => {
+                    run_args.push(RunArg::Plan);
+                }
+                "--cron" => {
+                    run_args.push(RunArg::Cron);
+                }
+                "--output" | "-o" => {
+                    let value = Self::expect_arg_value(&arg, args_iter.next())?;
+                    let format: OutputFormat = value
+                        .parse()
+                        .map_err(|e| io::Error::new(ErrorKind::InvalidInput, format!("{}", e)))?;
+                    run_args.push(RunArg::Output(format));
+                }
                 _ => {
                     //Invalid argument
                     return Err(io::Error::new(
@@ -136,50 +337,370 @@ impl Command {
         Ok(run_args)
     }
 
-    pub fn execute(&self) -> Result<(), Box<dyn std::error::Error>> {
+    fn parse_restore_command(args: &[String]) -> Command {
+        match Self::parse_restore_args(args) {
+            Ok(restore_args) => Command::Restore(restore_args),
+            Err(e) => {
+                output::error(&e.to_string());
+                Command::Help
+            }
+        }
+    }
+
+    fn parse_restore_args(args: &[String]) -> Result<Vec<RestoreArg>, io::Error> {
+        let mut restore_args: Vec<RestoreArg> = Vec::with_capacity(args.len());
+        let mut args_iter = args.iter();
+
+        while let Some(arg) = args_iter.next() {
+            match arg.to_lowercase().as_str() {
+                "--dry" | "-d" => {
+                    restore_args.push(RestoreArg::DryRun);
+                }
+                "--profile" | "-p" => {
+                    let name = Self::expect_arg_value(arg, args_iter.next())?;
+                    restore_args.push(RestoreArg::Profile(name));
+                }
+                _ if arg.starts_with('-') => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid restore argument: '{}'", arg),
+                    ));
+                }
+                _ => {
+                    //The journal records the files with absolute paths
+                    restore_args.push(RestoreArg::File(path::absolute(arg)?));
+                }
+            }
+        }
+
+        Ok(restore_args)
+    }
+
+    fn parse_stats_args(args: &[String]) -> Result<Vec<StatsArg>, io::Error> {
+        let mut stats_args: Vec<StatsArg> = Vec::with_capacity(args.len());
+        let mut args_iter = args.iter();
+
+        while let Some(arg) = args_iter.next() {
+            match arg.to_lowercase().as_str() {
+                "--profile" | "-p" => {
+                    let name = Self::expect_arg_value(arg, args_iter.next())?;
+                    stats_args.push(StatsArg::Profile(name));
+                }
+                "--limit" | "-l" => {
+                    let value = Self::expect_arg_value(arg, args_iter.next())?;
+                    let limit: usize = value.parse().map_err(|_| {
+                        io::Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Invalid value for '{}': '{}'", arg, value),
+                        )
+                    })?;
+                    stats_args.push(StatsArg::Limit(limit));
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid argument: '{}'", arg),
+                    ));
+                }
+            }
+        }
+
+        Ok(stats_args)
+    }
+
+    /// Replace a deprecated flag with its replacement and print a warning
+    fn replace_deprecated_flag(arg: &str) -> String {
+        let (flag, warning) = config_deprecation::replace_deprecated_flag(arg);
+
+        if let Some(warning) = warning {
+            output::warn(&format!("Warning: {}", warning));
+        }
+
+        flag
+    }
+
+    /// Return the value of an argument that requires a value
+    fn expect_arg_value(arg: &str, value: Option<&String>) -> Result<String, io::Error> {
+        match value {
+            Some(value) if !value.starts_with('-') => Ok(value.clone()),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Missing value for run argument: '{}'", arg),
+            )),
+        }
+    }
+
+    /// Get the selected profile, the run args have precedence over the env variable
+    fn selected_profile(run_args: &[RunArg]) -> Option<String> {
+        run_args
+            .iter()
+            .rev()
+            .find_map(|arg| match arg {
+                RunArg::Profile(name) => Some(name.clone()),
+                _ => None,
+            })
+            .or_else(config_env::profile)
+    }
+
+    /// Get the selected output format, the last run arg has precedence
+    fn selected_output(run_args: &[RunArg]) -> OutputFormat {
+        run_args
+            .iter()
+            .rev()
+            .find_map(|arg| match arg {
+                RunArg::Output(format) => Some(*format),
+                _ => None,
+            })
+            .unwrap_or(OutputFormat::Text)
+    }
+
+    /// Execute a cleanup run or print its plan, returns the exit status of the process
+    /// and whether the run has failed, independent of the 'fail_on' setting
+    fn execute_run(
+        run_args: &[RunArg],
+        global_args: &GlobalArgs,
+    ) -> Result<(ExitCode, bool), Box<dyn std::error::Error>> {
+        let plan_only: bool = run_args.iter().any(|arg| matches!(arg, RunArg::Plan));
+        let output_format: OutputFormat = Self::selected_output(run_args);
+
+        if output_format == OutputFormat::Json && !plan_only {
+            return Err(Box::new(io::Error::new(
+                ErrorKind::InvalidInput,
+                "The json output requires the run argument: '--plan'",
+            )));
+        }
+
+        if output_format == OutputFormat::Ndjson && plan_only {
+            return Err(Box::new(io::Error::new(
+                ErrorKind::InvalidInput,
+                "The ndjson output is only supported without the run argument: '--plan'",
+            )));
+        }
+
+        //Stdout only contains the json document or the events
+        match output_format {
+            OutputFormat::Json => output::redirect_status_to_stderr(),
+            OutputFormat::Ndjson => {
+                output::redirect_status_to_stderr();
+                events::enable();
+            }
+            OutputFormat::Text => {}
+        }
+
+        //A signal finishes the current file before the run stops
+        platform::install_shutdown_handler();
+
+        //Search the config file when no path is provided
+        let config_path = config::find_config_path(global_args.config_path.as_deref());
+
+        let profile: Option<String> = Self::selected_profile(run_args);
+
+        //Load the config
+        match config::load_config(&config_path, profile.as_deref()) {
+            Err(e) => {
+                output::print_status("Yalc config check: [ERROR]");
+                output::error(&format!("Config error: {}", e));
+                Ok((ExitCode::from(EXIT_ERROR), true))
+            }
+            Ok(raw_config) => {
+                output::print_status("Yalc config check: [VALID]");
+
+                //Adjust the config based on the env variables and cli args
+                let config = config::adjust_runner_config(raw_config, run_args)?;
+                output::apply_config(&config)?;
+
+                //Expand the configured paths to concrete files
+                let tasks = resolver::resolve_tasks(&config);
+                output::debug(&format!("Resolved {} file tasks", tasks.len()));
+
+                //The plan is only printed, no file is changed
+                if plan_only {
+                    let plan = cleaner::plan_cleanup(&config, &tasks);
+
+                    match output_format {
+                        OutputFormat::Json => {
+                            println!("{}", plan.to_json().to_pretty_string())
+                        }
+                        OutputFormat::Text | OutputFormat::Ndjson => plan.print_text(),
+                    }
+
+                    return match plan.has_errors() {
+                        true => Ok((ExitCode::from(EXIT_TASK_FAILURE), true)),
+                        false => Ok((ExitCode::SUCCESS, false)),
+                    };
+                }
+
+                //The messages of the run, its report and its notifications carry the id of the run
+                let _run_scope = run_id::start();
+
+                //Execute the cleanup tasks
+                let report = cleaner::run_cleanup(&config, &tasks)?;
+                report::print_run_report(&report);
+                stats::record_run(&config, &report);
+                notify::notify_run(&config.notify, &report);
+                Ok((
+                    ExitCode::from(report.exit_code_for(config.fail_on)),
+                    report.exit_code() != 0,
+                ))
+            }
+        }
+    }
+
+    /// Load the config and its recorded runs, the limit keeps only the most recent runs
+    fn load_run_records(
+        stats_args: &[StatsArg],
+        global_args: &GlobalArgs,
+    ) -> Result<(Config, Vec<stats::RunRecord>), Box<dyn std::error::Error>> {
+        let profile: Option<String> = stats_args
+            .iter()
+            .rev()
+            .find_map(|arg| match arg {
+                StatsArg::Profile(name) => Some(name.clone()),
+                _ => None,
+            })
+            .or_else(config_env::profile);
+        let limit: Option<usize> = stats_args.iter().rev().find_map(|arg| match arg {
+            StatsArg::Limit(limit) => Some(*limit),
+            _ => None,
+        });
+
+        //The statistics are stored next to the state file of the config
+        let config_path = config::find_config_path(global_args.config_path.as_deref());
+        let raw_config = config::load_config(&config_path, profile.as_deref())?;
+        let config = config::adjust_runner_config(raw_config, &[])?;
+        output::apply_config(&config)?;
+
+        let stats_path: PathBuf = stats::stats_path(&cleaner::state_path(&config));
+        let mut records: Vec<stats::RunRecord> = stats::load(&stats_path)?;
+
+        if records.is_empty() {
+            output::print_status(&format!("No runs recorded in: {}", stats_path.display()));
+        }
+
+        if let Some(limit) = limit {
+            records.drain(..records.len().saturating_sub(limit));
+        }
+
+        Ok((config, records))
+    }
+
+    /// Execute the command, returns the exit status of the process
+    pub fn execute(
+        &self,
+        global_args: &GlobalArgs,
+    ) -> Result<ExitCode, Box<dyn std::error::Error>> {
         match self {
             Command::Help => {
                 help::print_help();
-                Ok(())
+                Ok(ExitCode::SUCCESS)
             }
             Command::Version => {
                 println!("yalc version {}", YALC_VERSION);
-                Ok(())
+                Ok(ExitCode::SUCCESS)
             }
             Command::Config(config_arg) => match &config_arg {
                 ConfigArg::Init => {
-                    println!("Executing: Config init");
-                    config::execute_init_config_command()?;
-                    Ok(())
+                    output::print_status("Executing: Config init");
+                    config::execute_init_config_command(global_args.config_path.as_deref())?;
+                    Ok(ExitCode::SUCCESS)
+                }
+                ConfigArg::Check { strict } => {
+                    output::print_status("Executing: Config check");
+                    let path: Option<&Path> = global_args.config_path.as_deref();
+
+                    match config::execute_check_config_command(path, *strict)? {
+                        true => Ok(ExitCode::SUCCESS),
+                        false => Ok(ExitCode::from(EXIT_ERROR)),
+                    }
+                }
+                ConfigArg::Migrate => {
+                    output::print_status("Executing: Config migrate");
+                    config::execute_migrate_config_command(global_args.config_path.as_deref())?;
+                    Ok(ExitCode::SUCCESS)
                 }
-                ConfigArg::Check => {
-                    println!("Executing: Config check");
-                    config::execute_check_config_command()?;
-                    Ok(())
+                ConfigArg::Example => {
+                    //No status output, so the example can be redirected into a file
+                    config::execute_example_config_command();
+                    Ok(ExitCode::SUCCESS)
                 }
             },
             Command::Run(run_args) => {
-                //Always load from the default config path
-                let config_path = Path::new(DEFAULT_CONFIG_PATH);
-
-                //Load the config
-                match config::load_config(&config_path) {
-                    Err(e) => {
-                        println!("Yalc config check: [ERROR]");
-                        eprintln!("Config error: {}", e);
-                    }
-                    Ok(raw_config) => {
-                        println!("Yalc config check: [VALID]");
+                //Cron mails every output, so a successful run stays silent
+                let cron: bool = run_args.iter().any(|arg| matches!(arg, RunArg::Cron));
+                if cron {
+                    output::buffer_messages();
+                }
 
-                        //Adjust the config based on the provided cli args
-                        let config = config::adjust_runner_config(raw_config, &run_args);
+                let result = Self::execute_run(run_args, global_args);
 
-                        //Execute the cleanup tasks
-                        cleaner::run_cleanup(&config)?;
+                //A failed run is mailed even when 'fail_on' keeps the exit status at zero
+                if cron {
+                    match &result {
+                        Ok((_, false)) => output::discard_buffer(),
+                        _ => output::flush_buffer(),
                     }
                 }
 
-                Ok(())
+                result.map(|(exit_code, _)| exit_code)
+            }
+            Command::Daemon(run_args) => {
+                if run_args
+                    .iter()
+                    .any(|arg| matches!(arg, RunArg::Plan | RunArg::Output(_) | RunArg::Cron))
+                {
+                    return Err(Box::new(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        "The daemon does not support the run arguments '--plan', '--output' and '--cron'",
+                    )));
+                }
+
+                platform::install_shutdown_handler();
+                let config_path = config::find_config_path(global_args.config_path.as_deref());
+                let profile: Option<String> = Self::selected_profile(run_args);
+
+                daemon::run_daemon(&config_path, profile, run_args.clone())?;
+                Ok(ExitCode::SUCCESS)
+            }
+            Command::Restore(restore_args) => {
+                let dry_run: bool = restore_args
+                    .iter()
+                    .any(|arg| matches!(arg, RestoreArg::DryRun));
+                let profile: Option<String> = restore_args
+                    .iter()
+                    .rev()
+                    .find_map(|arg| match arg {
+                        RestoreArg::Profile(name) => Some(name.clone()),
+                        _ => None,
+                    })
+                    .or_else(config_env::profile);
+                let file_paths: Vec<PathBuf> = restore_args
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        RestoreArg::File(path) => Some(path.clone()),
+                        _ => None,
+                    })
+                    .collect();
+
+                //The config provides the state file, the lock file and the audit log
+                let config_path = config::find_config_path(global_args.config_path.as_deref());
+                let raw_config = config::load_config(&config_path, profile.as_deref())?;
+                let config = config::adjust_runner_config(raw_config, &[])?;
+                output::apply_config(&config)?;
+
+                let _run_scope = run_id::start();
+                let report = cleaner::run_restore(&config, &file_paths, dry_run)?;
+                report::print_task_stats(&report);
+                Ok(ExitCode::from(report.exit_code()))
+            }
+            Command::History(stats_args) => {
+                let (_, records) = Self::load_run_records(stats_args, global_args)?;
+                history::print_history(&records);
+                Ok(ExitCode::SUCCESS)
+            }
+            Command::Stats(stats_args) => {
+                let (config, records) = Self::load_run_records(stats_args, global_args)?;
+                history::print_file_stats(&records, &config);
+                Ok(ExitCode::SUCCESS)
             }
         }
     }
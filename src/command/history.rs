@@ -0,0 +1,499 @@
+//! Module for the presentation of the recorded run statistics
+//!
+//! The history lists the recorded runs with their totals, the stats
+//! summarize the recorded outcomes of every file over the same runs.
+//!
+//! The growth rate of a file is calculated from its recorded sizes. The
+//! growth between two runs is the size before the later run minus the
+//! size that remained after the earlier run. A file whose growth rate of
+//! the most recent day is more than twice its rate before is highlighted,
+//! as it may run out of space long before its usual rotation.
+//!
+//! With the growth rate and the current size of a file, the time until it
+//! reaches the size threshold of its config is forecast. The next run is
+//! expected after the usual time between the recorded runs. Files that
+//! reach their threshold before the next run are highlighted, so the
+//! schedule or the threshold can be adjusted in time.
+//!
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::cleaner::audit;
+use crate::config::{CleanUpMode, Config, RotationPolicy};
+use crate::output::table::{self, Align};
+use crate::output::{self, Color};
+use crate::resolver;
+use crate::state::{self, FileAction};
+use crate::stats::{FileRecord, RunRecord};
+
+/// Seconds of a day, the unit of the growth rates
+const DAY_S: u64 = 24 * 60 * 60;
+
+/// Time before the most recent run of a file that its recent growth rate is calculated for
+const RECENT_GROWTH_S: u64 = DAY_S;
+
+/// Factor of the recent growth rate over the earlier rate that marks an accelerated growth
+const ACCELERATION_FACTOR: u64 = 2;
+
+/// Represents the growth of a file between two of its recorded runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GrowthInterval {
+    /// Unix time in seconds of the later run
+    ended_at: u64,
+
+    elapsed_s: u64,
+    growth_bytes: u64,
+}
+
+/// Represents the growth rate of a file over all recorded runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GrowthRate {
+    bytes_per_day: u64,
+
+    /// True when the growth of the most recent day was faster than before
+    accelerated: bool,
+}
+
+/// Represents the forecast when a file reaches its size threshold
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Forecast {
+    threshold_bytes: u64,
+
+    /// Seconds until the file reaches the threshold, 0 when it is already reached
+    seconds_left: u64,
+
+    /// True when the threshold is reached before the next expected run
+    before_next_run: bool,
+}
+
+/// Represents the summary of the recorded outcomes of a single file
+#[derive(Debug, Default)]
+struct FileSummary {
+    runs: usize,
+
+    /// Number of runs that rotated, removed or trimmed the file
+    cleanups: usize,
+
+    failures: usize,
+    bytes_freed: u64,
+
+    /// Size of the file before the most recent run, None for a missing file
+    last_size: Option<u64>,
+
+    /// Unix time in seconds of the most recent run of the file
+    last_run: u64,
+
+    /// Size of the file after the most recent run, None for a missing file
+    remaining_size: Option<u64>,
+
+    /// Growth of the file between its consecutive recorded runs
+    intervals: Vec<GrowthInterval>,
+}
+
+impl FileSummary {
+    /// Get the growth rate over all intervals, None without any time between the runs
+    fn growth_rate(&self) -> Option<GrowthRate> {
+        let bytes_per_day = |intervals: &[GrowthInterval]| -> Option<u64> {
+            let elapsed_s: u64 = intervals.iter().map(|i| i.elapsed_s).sum();
+            let growth_bytes: u64 = intervals.iter().map(|i| i.growth_bytes).sum();
+
+            match elapsed_s {
+                0 => None,
+                _ => Some((growth_bytes as u128 * DAY_S as u128 / elapsed_s as u128) as u64),
+            }
+        };
+
+        //The intervals are ordered by time, the recent intervals ended within the last day
+        let recent_start: u64 = self.last_run.saturating_sub(RECENT_GROWTH_S);
+        let split: usize = self
+            .intervals
+            .partition_point(|interval| interval.ended_at <= recent_start);
+        let (earlier, recent) = self.intervals.split_at(split);
+
+        let accelerated: bool = match (bytes_per_day(earlier), bytes_per_day(recent)) {
+            (Some(earlier_rate), Some(recent_rate)) => {
+                recent_rate > earlier_rate.saturating_mul(ACCELERATION_FACTOR)
+            }
+            _ => false,
+        };
+
+        Some(GrowthRate {
+            bytes_per_day: bytes_per_day(&self.intervals)?,
+            accelerated,
+        })
+    }
+}
+
+/// Print the recorded runs as aligned table, one row per run
+pub fn print_history(records: &[RunRecord]) {
+    let rows: Vec<Vec<String>> = records
+        .iter()
+        .map(|record| {
+            vec![
+                audit::format_utc_time(record.started_at),
+                table::format_duration(Duration::from_millis(record.duration_ms)),
+                record.tasks_executed.to_string(),
+                record.tasks_failure.to_string(),
+                table::format_bytes(record.bytes_freed),
+                record.exit_code.to_string(),
+            ]
+        })
+        .collect();
+
+    for line in table::render(
+        &[
+            ("Started", Align::Left),
+            ("Duration", Align::Right),
+            ("Tasks", Align::Right),
+            ("Failed", Align::Right),
+            ("Freed", Align::Right),
+            ("Exit", Align::Right),
+        ],
+        &rows,
+    ) {
+        output::print_status(&line);
+    }
+}
+
+/// Print the summary of every recorded file as aligned table, one row per file.
+/// Files that reach their size threshold before the next run or with an accelerated growth are highlighted.
+pub fn print_file_stats(records: &[RunRecord], config: &Config) {
+    let summaries: BTreeMap<PathBuf, FileSummary> = summarize_files(records);
+    let growth_rates: Vec<Option<GrowthRate>> =
+        summaries.values().map(FileSummary::growth_rate).collect();
+
+    //A periodic schedule is assumed, the next run follows after the usual time between the runs
+    let now: u64 = state::unix_time_now();
+    let seconds_to_next_run: Option<u64> = match (run_interval_s(records), records.last()) {
+        (Some(interval_s), Some(last_record)) => {
+            Some(interval_s - now.saturating_sub(last_record.started_at) % interval_s)
+        }
+        _ => None,
+    };
+
+    let thresholds: BTreeMap<PathBuf, u64> = size_thresholds(config);
+    let forecasts: Vec<Option<Forecast>> = summaries
+        .keys()
+        .zip(growth_rates.iter())
+        .map(|(path, growth_rate)| {
+            forecast(
+                *thresholds.get(path)?,
+                fs::metadata(path).ok()?.len(),
+                growth_rate.as_ref()?.bytes_per_day,
+                seconds_to_next_run,
+            )
+        })
+        .collect();
+
+    let rows: Vec<Vec<String>> = summaries
+        .iter()
+        .zip(growth_rates.iter().zip(forecasts.iter()))
+        .map(|((path, summary), (growth_rate, forecast))| {
+            vec![
+                path.display().to_string(),
+                summary.runs.to_string(),
+                summary.cleanups.to_string(),
+                summary.failures.to_string(),
+                table::format_bytes(summary.bytes_freed),
+                summary
+                    .last_size
+                    .map_or_else(|| "-".to_string(), table::format_bytes),
+                growth_rate.map_or_else(
+                    || "-".to_string(),
+                    |rate| format!("{}/day", table::format_bytes(rate.bytes_per_day)),
+                ),
+                match growth_rate {
+                    Some(rate) if rate.accelerated => "accelerated".to_string(),
+                    Some(_) => "steady".to_string(),
+                    None => "-".to_string(),
+                },
+                match forecast {
+                    Some(forecast) if forecast.seconds_left == 0 => "reached".to_string(),
+                    Some(forecast) => format!("in {}", format_time_left(forecast.seconds_left)),
+                    None => "-".to_string(),
+                },
+                audit::format_utc_time(summary.last_run),
+            ]
+        })
+        .collect();
+
+    let lines: Vec<String> = table::render(
+        &[
+            ("File", Align::Left),
+            ("Runs", Align::Right),
+            ("Cleanups", Align::Right),
+            ("Failures", Align::Right),
+            ("Freed", Align::Right),
+            ("Last size", Align::Right),
+            ("Growth", Align::Right),
+            ("Trend", Align::Left),
+            ("Threshold", Align::Right),
+            ("Last run", Align::Left),
+        ],
+        &rows,
+    );
+
+    //The header and the separator are plain, the rows are colored by their forecast and trend
+    for (idx, line) in lines.iter().enumerate() {
+        let row: Option<usize> = idx.checked_sub(2);
+
+        if row
+            .and_then(|row| forecasts[row])
+            .is_some_and(|f| f.before_next_run)
+        {
+            output::print_colored(line, Color::Red);
+        } else if row
+            .and_then(|row| growth_rates[row])
+            .is_some_and(|r| r.accelerated)
+        {
+            output::print_colored(line, Color::Yellow);
+        } else {
+            output::print_status(line);
+        }
+    }
+
+    for (path, forecast) in summaries.keys().zip(forecasts.iter()) {
+        if let Some(forecast) = forecast
+            && forecast.before_next_run
+        {
+            output::warn(&format!(
+                "Warning: '{}' reaches its size threshold of {} before the next run",
+                path.display(),
+                table::format_bytes(forecast.threshold_bytes)
+            ));
+        }
+    }
+}
+
+/// Get the size thresholds of the configured files, files without a size condition have no threshold
+fn size_thresholds(config: &Config) -> BTreeMap<PathBuf, u64> {
+    resolver::resolve_tasks(config)
+        .into_iter()
+        .filter_map(|task| {
+            let policy: RotationPolicy = task.overrides.resolve(config);
+            let size_condition: bool = matches!(
+                policy.mode,
+                CleanUpMode::FileSize | CleanUpMode::AnyOf | CleanUpMode::AllOf
+            );
+
+            //A condition expression replaces the checks of the mode
+            match size_condition && policy.condition.is_none() {
+                true => Some((task.path, policy.retention.file_size_mib * 1024 * 1024)),
+                false => None,
+            }
+        })
+        .collect()
+}
+
+/// Get the usual time between two recorded runs, the median is not affected by single manual runs
+fn run_interval_s(records: &[RunRecord]) -> Option<u64> {
+    let mut intervals: Vec<u64> = records
+        .windows(2)
+        .map(|pair| pair[1].started_at.saturating_sub(pair[0].started_at))
+        .filter(|interval_s| *interval_s > 0)
+        .collect();
+
+    intervals.sort_unstable();
+    intervals.get(intervals.len() / 2).copied()
+}
+
+/// Forecast when a file reaches the threshold with its growth rate, None for a file that does not grow
+fn forecast(
+    threshold_bytes: u64,
+    size: u64,
+    bytes_per_day: u64,
+    seconds_to_next_run: Option<u64>,
+) -> Option<Forecast> {
+    let seconds_left: u64 = match (size >= threshold_bytes, bytes_per_day) {
+        (true, _) => 0,
+        (false, 0) => return None,
+        (false, _) => {
+            ((threshold_bytes - size) as u128 * DAY_S as u128 / bytes_per_day as u128) as u64
+        }
+    };
+
+    Some(Forecast {
+        threshold_bytes,
+        seconds_left,
+        before_next_run: seconds_to_next_run.is_some_and(|next_run_s| seconds_left <= next_run_s),
+    })
+}
+
+/// Format the time until a threshold is reached like '45 min', '5.0 h' or '3.2 d'
+fn format_time_left(seconds: u64) -> String {
+    match seconds {
+        s if s < 60 * 60 => format!("{} min", s / 60),
+        s if s < DAY_S => format!("{:.1} h", s as f64 / 3600.0),
+        s => format!("{:.1} d", s as f64 / DAY_S as f64),
+    }
+}
+
+/// Summarize the recorded outcomes of the files, the files are ordered by their path
+fn summarize_files(records: &[RunRecord]) -> BTreeMap<PathBuf, FileSummary> {
+    let mut summaries: BTreeMap<PathBuf, FileSummary> = BTreeMap::new();
+
+    for record in records.iter() {
+        for file in record.files.iter() {
+            let summary: &mut FileSummary = summaries.entry(file.path.clone()).or_default();
+            add_file_record(summary, file, record.started_at);
+        }
+    }
+
+    summaries
+}
+
+/// Add the outcome of a single run to the summary of the file
+fn add_file_record(summary: &mut FileSummary, file: &FileRecord, started_at: u64) {
+    //A file that shrank without a cleanup was changed by another program, the interval is skipped
+    if let (Some(remaining_size), Some(size)) = (summary.remaining_size, file.size)
+        && summary.runs > 0
+        && started_at > summary.last_run
+        && size >= remaining_size
+    {
+        summary.intervals.push(GrowthInterval {
+            ended_at: started_at,
+            elapsed_s: started_at - summary.last_run,
+            growth_bytes: size - remaining_size,
+        });
+    }
+
+    summary.runs += 1;
+    summary.bytes_freed += file.bytes_freed;
+    summary.last_size = file.size;
+    summary.last_run = started_at;
+    summary.remaining_size = file.size.map(|size| size.saturating_sub(file.bytes_freed));
+
+    match file.action {
+        FileAction::Rotated | FileAction::Removed | FileAction::Trimmed => summary.cleanups += 1,
+        FileAction::Failed => summary.failures += 1,
+        FileAction::Skipped | FileAction::Missing => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_files() {
+        let file = |action: FileAction, size: u64| FileRecord {
+            path: PathBuf::from("/var/log/app.log"),
+            action,
+            size: Some(size),
+            bytes_freed: match action {
+                FileAction::Rotated => size,
+                _ => 0,
+            },
+            duration_ms: 5,
+        };
+        let run = |started_at: u64, files: Vec<FileRecord>| RunRecord {
+            started_at,
+            duration_ms: 10,
+            exit_code: 0,
+            tasks_executed: files.len(),
+            tasks_failure: 0,
+            bytes_freed: 0,
+            files,
+        };
+
+        let summaries = summarize_files(&[
+            run(100, vec![file(FileAction::Rotated, 2048)]),
+            run(200, vec![file(FileAction::Skipped, 512)]),
+            run(300, vec![file(FileAction::Failed, 1024)]),
+        ]);
+        let summary: &FileSummary = &summaries[&PathBuf::from("/var/log/app.log")];
+
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summary.runs, 3);
+        assert_eq!(summary.cleanups, 1);
+        assert_eq!(summary.failures, 1);
+        assert_eq!(summary.bytes_freed, 2048);
+        assert_eq!(summary.last_size, Some(1024));
+        assert_eq!(summary.last_run, 300);
+    }
+
+    #[test]
+    fn test_forecast() {
+        const MIB: u64 = 1024 * 1024;
+
+        //10 MiB are left with a growth of 20 MiB per day
+        let half_day: Option<Forecast> = forecast(100 * MIB, 90 * MIB, 20 * MIB, Some(DAY_S));
+        assert_eq!(
+            half_day,
+            Some(Forecast {
+                threshold_bytes: 100 * MIB,
+                seconds_left: DAY_S / 2,
+                before_next_run: true
+            })
+        );
+        assert!(
+            !forecast(100 * MIB, 90 * MIB, 20 * MIB, Some(3600))
+                .unwrap()
+                .before_next_run
+        );
+        assert_eq!(forecast(100 * MIB, 90 * MIB, 0, Some(DAY_S)), None);
+        assert_eq!(
+            forecast(100 * MIB, 120 * MIB, 0, None)
+                .unwrap()
+                .seconds_left,
+            0
+        );
+        assert_eq!(format_time_left(DAY_S / 2), "12.0 h");
+
+        let run = |started_at: u64| RunRecord {
+            started_at,
+            duration_ms: 0,
+            exit_code: 0,
+            tasks_executed: 0,
+            tasks_failure: 0,
+            bytes_freed: 0,
+            files: Vec::new(),
+        };
+        //The manual run at 7300 does not change the hourly interval
+        let records: Vec<RunRecord> = [0, 3600, 7200, 7300, 10800].map(run).to_vec();
+        assert_eq!(run_interval_s(&records), Some(3600));
+    }
+
+    #[test]
+    fn test_growth_rate() {
+        const DAY: u64 = 24 * 60 * 60;
+        const MIB: u64 = 1024 * 1024;
+
+        let mut summary = FileSummary::default();
+        let add_run = |summary: &mut FileSummary, started_at: u64, size: u64, bytes_freed: u64| {
+            let file = FileRecord {
+                path: PathBuf::from("/var/log/app.log"),
+                action: FileAction::Rotated,
+                size: Some(size),
+                bytes_freed,
+                duration_ms: 0,
+            };
+            add_file_record(summary, &file, started_at);
+        };
+
+        //The file grows 10 MiB per day and is rotated every second day
+        add_run(&mut summary, 0, 0, 0);
+        add_run(&mut summary, DAY, 10 * MIB, 0);
+        add_run(&mut summary, 2 * DAY, 20 * MIB, 20 * MIB);
+        add_run(&mut summary, 3 * DAY, 10 * MIB, 0);
+        assert_eq!(
+            summary.growth_rate(),
+            Some(GrowthRate {
+                bytes_per_day: 10 * MIB,
+                accelerated: false
+            })
+        );
+
+        //The growth of the last day is five times the growth before
+        add_run(&mut summary, 4 * DAY, 60 * MIB, 0);
+        assert_eq!(
+            summary.growth_rate(),
+            Some(GrowthRate {
+                bytes_per_day: 20 * MIB,
+                accelerated: true
+            })
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Module for the presentation of the run reports
+//!
+//! The cleaner returns a report of every run, the report is presented here:
+//! the tables of the tasks and their operations, the success rates and the
+//! run_finished event. Runs of the daemon are presented the same way.
+//!
+use crate::cleaner::report::{RunReport, TaskReport};
+use crate::output::table::{self, Align};
+use crate::output::{self, Color, events};
+use crate::stats::ChangeKind;
+
+/// Present the report of a cleanup run with its final status
+pub fn print_run_report(report: &RunReport) {
+    print_task_stats(report);
+    events::emit("run_finished", report.total_fields());
+
+    //Log that all tasks have finished
+    match (report.interrupted_by, report.aborted) {
+        (Some(_), _) => output::print_status("Run interrupted"),
+        (None, true) => output::print_status("Run aborted"),
+        (None, false) => output::print_status("All tasks done"),
+    }
+}
+
+/// Print the success and failure rates of the executed tasks
+pub fn print_task_stats(report: &RunReport) {
+    if !report.tasks.is_empty() {
+        print_task_table(&report.tasks);
+    }
+
+    if report.tasks.iter().any(|task| !task.operations.is_empty()) {
+        print_operation_table(&report.tasks);
+    }
+
+    //Calculate percentage rates, avoid a division by zero without tasks
+    let success_rate: usize = report.tasks_success * 100 / report.tasks_executed.max(1);
+    let failure_rate: usize = report.tasks_failure * 100 / report.tasks_executed.max(1);
+
+    //Lines with failures are red, so they stand out between the successful lines
+    let failure_color = |failures: usize| match failures {
+        0 => Color::Green,
+        _ => Color::Red,
+    };
+
+    output::print_colored(
+        &format!(
+            "Successful tasks: {}/{} [{}%]",
+            report.tasks_success, report.tasks_executed, success_rate
+        ),
+        failure_color(report.tasks_failure),
+    );
+
+    let failure_line: String = format!(
+        "Failure tasks:    {}/{} [{}%]",
+        report.tasks_failure, report.tasks_executed, failure_rate
+    );
+    match report.tasks_failure {
+        0 => output::print_status(&failure_line),
+        _ => output::print_colored(&failure_line, Color::Red),
+    }
+
+    output::print_status(&format!(
+        "Run duration:     {} [{} freed]",
+        table::format_duration(report.duration),
+        table::format_bytes(report.bytes_freed())
+    ));
+
+    if report.tasks_deferred > 0 {
+        output::print_colored(
+            &format!(
+                "Deferred tasks:   {} [byte limit of the run reached]",
+                report.tasks_deferred
+            ),
+            Color::Yellow,
+        );
+    }
+
+    if let Some(excess) = &report.budget_exceeded {
+        output::print_colored(
+            &format!(
+                "Budget exceeded:  {} of {} [{} archives removed with {}]",
+                table::format_bytes(excess.used_bytes),
+                table::format_bytes(excess.budget_bytes),
+                excess.removed_count,
+                table::format_bytes(excess.removed_bytes)
+            ),
+            Color::Yellow,
+        );
+    }
+
+    if report.hook_failures > 0 {
+        output::print_colored(
+            &format!(
+                "Failed hooks:     {} [postrotate hooks of the groups]",
+                report.hook_failures
+            ),
+            Color::Red,
+        );
+    }
+
+    if report.tasks_warning() > 0 {
+        output::print_colored(
+            &format!(
+                "Warning tasks:    {} [missing or skipped files]",
+                report.tasks_warning()
+            ),
+            Color::Yellow,
+        );
+    }
+
+    if report.tasks_stale() > 0 {
+        output::print_colored(
+            &format!(
+                "Stale files:      {} [no write within stale_after]",
+                report.tasks_stale()
+            ),
+            Color::Yellow,
+        );
+    }
+
+    if !report.unmanaged_files.is_empty() {
+        output::print_colored(
+            &format!(
+                "Unmanaged files:  {} [matched by no entry]",
+                report.unmanaged_files.len()
+            ),
+            Color::Yellow,
+        );
+    }
+
+    //Changes to the previous run stand out of the repeated summary lines
+    if !report.changes.is_empty() {
+        output::print_colored(
+            &format!(
+                "Changed files:    {} [compared to their previous run]",
+                report.changes.len()
+            ),
+            Color::Yellow,
+        );
+
+        for change in report.changes.iter() {
+            let line: String = format!(
+                "  {:<20}{}",
+                format!("{}:", change.kind),
+                change.path.display()
+            );
+
+            match change.kind {
+                ChangeKind::NewlyFailing => output::print_colored(&line, Color::Red),
+                ChangeKind::Recovered => output::print_colored(&line, Color::Green),
+                ChangeKind::StartedTriggering | ChangeKind::StoppedTriggering => {
+                    output::print_status(&line)
+                }
+            }
+        }
+    }
+
+    for group in report.groups.iter() {
+        output::print_colored(
+            &format!(
+                "Group '{}': {}/{} successful",
+                group.name,
+                group.tasks_success,
+                group.tasks_success + group.tasks_failure
+            ),
+            failure_color(group.tasks_failure),
+        );
+    }
+}
+
+/// Print the results of the tasks as aligned table, one row per file
+fn print_task_table(tasks: &[TaskReport]) {
+    let lines: Vec<String> = task_table(tasks);
+
+    //The header and the separator are plain, the rows are colored by their status
+    for (idx, line) in lines.iter().enumerate() {
+        match idx.checked_sub(2).map(|row| &tasks[row]) {
+            Some(task) if task.error.is_some() => output::print_colored(line, Color::Red),
+            _ => output::print_status(line),
+        }
+    }
+}
+
+/// Render the results of the tasks as aligned table with a header line and a separator line
+pub fn task_table(tasks: &[TaskReport]) -> Vec<String> {
+    let rows: Vec<Vec<String>> = tasks
+        .iter()
+        .map(|task| {
+            vec![
+                task.path.display().to_string(),
+                task.trigger.clone().unwrap_or_else(|| "-".to_string()),
+                task.action.name().to_string(),
+                table::format_bytes(task.bytes_freed),
+                table::format_duration(task.duration),
+                match task.stale_since {
+                    Some(_) => format!("{} (stale)", task.status()),
+                    None => task.status().to_string(),
+                },
+            ]
+        })
+        .collect();
+
+    table::render(
+        &[
+            ("File", Align::Left),
+            ("Trigger", Align::Left),
+            ("Action", Align::Left),
+            ("Freed", Align::Right),
+            ("Duration", Align::Right),
+            ("Status", Align::Left),
+        ],
+        &rows,
+    )
+}
+
+/// Print the timed operations of the tasks as aligned table, so slow storage stands out
+fn print_operation_table(tasks: &[TaskReport]) {
+    let rows: Vec<Vec<String>> = tasks
+        .iter()
+        .flat_map(|task| task.operations.iter())
+        .map(|metric| {
+            vec![
+                metric.path.display().to_string(),
+                metric.operation.to_string(),
+                table::format_bytes(metric.bytes),
+                table::format_duration(metric.duration),
+                metric
+                    .bytes_per_second()
+                    .map_or_else(|| "-".to_string(), table::format_throughput),
+            ]
+        })
+        .collect();
+
+    output::print_status("");
+    for line in table::render(
+        &[
+            ("File", Align::Left),
+            ("Operation", Align::Left),
+            ("Size", Align::Right),
+            ("Duration", Align::Right),
+            ("Throughput", Align::Right),
+        ],
+        &rows,
+    ) {
+        output::print_status(&line);
+    }
+}
@@ -5,15 +5,37 @@
 //!
 
 pub mod config_commands;
+pub mod config_condition;
+pub mod config_deprecation;
+pub mod config_diff;
+pub mod config_env;
+pub mod config_location;
+pub mod config_merge;
+pub mod config_migrate;
 pub mod config_parser;
+pub mod config_regex;
+pub mod config_schema;
+pub mod config_timestamp;
+pub mod config_units;
+pub mod config_validator;
 pub mod toml_lexer;
 pub mod toml_parser;
+pub mod toml_writer;
+#[cfg(feature = "yaml")]
+pub mod yaml_parser;
 
 pub use config_commands::*;
 
 use std::fmt;
+use std::net::SocketAddr;
 use std::str::FromStr;
 
+use crate::config::config_condition::ConditionExpr;
+use crate::config::config_regex::Regex;
+use crate::config::config_timestamp::TimestampFormat;
+use crate::output::LogLevel;
+use crate::output::syslog::{Facility, Severity};
+
 /// Represents the config for an execution of the yalc cleanup
 #[derive(Debug)]
 pub struct Config {
@@ -24,10 +46,31 @@ pub struct Config {
     /// a file should be cleaned up or not
     pub mode: CleanUpMode,
 
+    /// What is done with a file when the mode decides that it is cleaned up
+    pub action: CleanUpAction,
+
     /// Number of files that are kept when a file rotation takes place.
     /// If this number is exceeded, the oldest file is deleted
     pub keep_rotate: u64,
 
+    /// Index of the most recent rotated file, 1 matches the naming of logrotate
+    pub rotate_start: u64,
+
+    /// When set to true gaps in the indices of the rotated files are closed before the shift
+    pub compact_rotations: bool,
+
+    /// When set to true rotated files with an index outside of the kept rotations are removed
+    pub remove_stray_archives: bool,
+
+    /// When set to true an archive with the content of the previous rotation is stored as hard link
+    pub dedup_archives: bool,
+
+    /// When set to true the allocated blocks are used as file size instead of the length
+    pub allocated_size: bool,
+
+    /// When set to true directories that are emptied by the removal of files are removed too
+    pub remove_empty_dirs: bool,
+
     /// When set to true then no error will be printed when
     /// a file in the file list does not exist in the file system.
     pub missing_files_ok: bool,
@@ -36,16 +79,467 @@ pub struct Config {
     /// without disturbing the process that is still writing
     pub copy_truncate: bool,
 
+    /// When set to true the file is locked with flock while it is copied and truncated
+    pub copy_truncate_lock: bool,
+
+    /// When set to true removed archives are overwritten before they are unlinked
+    pub shred: bool,
+
+    /// When set to true a manifest with the checksums of the archives is kept next to them
+    pub manifest: bool,
+
+    /// Expressions whose matches are masked in the rotated files
+    pub redact: Vec<Regex>,
+
+    /// When set to true a config without any files is valid
+    pub allow_empty_file_list: bool,
+
+    /// When set to true files without content are also rotated
+    pub rotate_empty: bool,
+
+    /// When set to true files are not rotated again while the content is unchanged
+    pub skip_unchanged: bool,
+
+    /// When set to true a new empty log file is created after a rename-based rotation
+    pub create: bool,
+
+    /// Permissions of the created log file, the values of the rotated file when not set
+    pub create_permissions: FilePermissions,
+
+    /// Permissions that are applied to the rotated files and archives
+    pub archive_permissions: FilePermissions,
+
+    /// Algorithm that is used to compress rotated files
+    pub compression: Compression,
+
+    /// Compression level of the algorithm, the default level when not set
+    pub compression_level: Option<u32>,
+
+    /// When set to true the most recent rotated file is compressed on the next rotation
+    pub delay_compress: bool,
+
+    /// When set to true a file that grows during the rotation keeps its fresh archive until the next run
+    pub defer_growing: bool,
+
+    /// Seconds after the last write before a rotated archive is removed, archives are kept when not set
+    pub max_archive_age_s: Option<u64>,
+
+    /// Maximum total size of the rotated archives of a file, the oldest archives are removed first
+    pub max_archive_size_bytes: Option<u64>,
+
+    /// Maximum total size of all managed log files and archives, the oldest archives are removed first
+    pub total_budget_bytes: Option<u64>,
+
+    /// Maximum bytes of the files that are rotated or trimmed in a run, the other files are deferred
+    pub max_bytes_per_run: Option<u64>,
+
+    /// Size of the buffer for streamed copies and compression input in bytes
+    pub io_buffer_bytes: u64,
+
+    /// Maximum bytes per second of copies and compression input, not limited when not set
+    pub io_limit_bytes_per_s: Option<u64>,
+
+    /// Minimum time between two rotations of a file
+    pub frequency: Option<Frequency>,
+
+    /// Seconds without a write after which a managed file is reported as stale, not checked when not set
+    pub stale_after_s: Option<u64>,
+
+    /// Minimum size of the files in watched directories that are reported when no entry matches them,
+    /// not checked when not set
+    pub unmanaged_min_size_bytes: Option<u64>,
+
+    /// Path of the file with the state of the managed files, the default location when not set
+    pub state_file: Option<String>,
+
+    /// Maximum size of the statistics file next to the state file, no statistics are recorded with 0
+    pub stats_max_size_bytes: u64,
+
+    /// Behavior of the run after a file task has failed
+    pub on_error: ErrorPolicy,
+
+    /// Results of the run that make the process exit with a non-zero status
+    pub fail_on: FailOn,
+
+    /// Most verbose level of the printed messages, '--verbose' has precedence
+    pub log_level: LogLevel,
+
+    /// Path of the file that the printed messages are appended to
+    pub log_file: Option<String>,
+
+    /// Size of the log file that starts a new log file, the previous file is kept as '.1'
+    pub log_file_max_size_bytes: u64,
+
+    /// Handling of log files that are symbolic links
+    pub symlinks: SymlinkPolicy,
+
+    /// Handling of log files that a process holds open for writing before a rename rotation
+    pub open_files: OpenFilePolicy,
+
+    /// Path of the lock file that prevents concurrent runs, the default location when not set
+    pub lock_file: Option<String>,
+
+    /// Path of the append-only audit log of the executed operations, no audit log when not set
+    pub audit_log: Option<String>,
+
+    /// Seconds to wait for the lock of another run, the run fails right away when not set
+    pub lock_wait_s: Option<u64>,
+
+    /// Directories that contain all files yalc may change, no restriction when empty
+    pub allowed_roots: Vec<String>,
+
     /// List with all file paths where log files should be processed
     pub file_list: Vec<String>,
 
+    /// File entries defined via '[[files]]' array of tables
+    pub files: Vec<FileEntry>,
+
+    /// Groups defined via '[group.NAME]' tables, sorted by the name
+    pub groups: Vec<GroupConfig>,
+
+    /// Names of the groups selected with '--group', the files of all groups are processed when empty
+    pub selected_groups: Vec<String>,
+
+    /// Glob patterns selected with '--match', all resolved files are processed when empty
+    pub selected_patterns: Vec<String>,
+
     /// Configuration of the conditions that are checked
     /// for each file before a rotation is started
     pub retention: RetentionConfig,
+
+    /// Amount of content that the trim action keeps in a file
+    pub trim: TrimConfig,
+
+    /// Retry policy for the file operations of a rotation
+    pub retry: RetryConfig,
+
+    /// Scheduling priorities of yalc and the started programs
+    pub priority: PriorityConfig,
+
+    /// Messages that are sent to the local syslog daemon
+    pub syslog: SyslogConfig,
+
+    /// Configuration of the daemon mode
+    pub daemon: DaemonConfig,
+
+    /// Upload of the archives to remote storage, the archives are only kept locally when not set
+    pub upload: Option<UploadConfig>,
+
+    /// Notifications about the results of the runs
+    pub notify: NotifyConfig,
 }
 
-/// Enum representing different ways to check if a file has to be cleaned up
+/// Represents a single '[[files]]' entry of the config
+#[derive(Debug)]
+pub struct FileEntry {
+    /// Source of the log files that are managed by this entry
+    pub source: FileSource,
+
+    /// Directory for the rotated files, next to the log file when not set
+    pub olddir: Option<String>,
+
+    /// Name of the group the files of this entry belong to
+    pub group: Option<String>,
+
+    /// Process that is signaled after a file of this entry was rotated
+    pub signal: Option<SignalTarget>,
+
+    /// Processing order of the files, files with a higher priority are processed first
+    pub priority: Option<i64>,
+
+    /// Rotation settings of this entry that replace the global values
+    pub overrides: FileOverrides,
+}
+
+/// Represents the optional rotation settings of a file entry, unset values use the global config
+#[derive(Debug, Clone, Default)]
+pub struct FileOverrides {
+    /// A file entry can only enable the dry run, a global dry run is never disabled
+    pub dry_run: Option<bool>,
+    pub mode: Option<CleanUpMode>,
+    pub keep_rotate: Option<u64>,
+    pub rotate_start: Option<u64>,
+    pub copy_truncate: Option<bool>,
+    pub file_size_mib: Option<u64>,
+    pub last_write_h: Option<u64>,
+    pub max_archive_age_s: Option<u64>,
+    pub max_archive_size_bytes: Option<u64>,
+
+    /// Condition expression that replaces the checks of the cleanup mode
+    pub condition: Option<ConditionExpr>,
+
+    pub frequency: Option<Frequency>,
+    pub symlinks: Option<SymlinkPolicy>,
+    pub shred: Option<bool>,
+    pub manifest: Option<bool>,
+    pub action: Option<CleanUpAction>,
+    pub trim_keep_lines: Option<u64>,
+    pub trim_keep_bytes: Option<u64>,
+    pub trim_max_age_s: Option<u64>,
+    pub trim_timestamp_format: Option<TimestampFormat>,
+    pub redact: Option<Vec<Regex>>,
+}
+
+impl FileOverrides {
+    /// Returns true when no global value is replaced
+    pub fn is_empty(&self) -> bool {
+        self.dry_run.is_none()
+            && self.mode.is_none()
+            && self.keep_rotate.is_none()
+            && self.rotate_start.is_none()
+            && self.copy_truncate.is_none()
+            && self.file_size_mib.is_none()
+            && self.last_write_h.is_none()
+            && self.max_archive_age_s.is_none()
+            && self.max_archive_size_bytes.is_none()
+            && self.condition.is_none()
+            && self.frequency.is_none()
+            && self.symlinks.is_none()
+            && self.shred.is_none()
+            && self.manifest.is_none()
+            && self.action.is_none()
+            && self.trim_keep_lines.is_none()
+            && self.trim_keep_bytes.is_none()
+            && self.trim_max_age_s.is_none()
+            && self.trim_timestamp_format.is_none()
+            && self.redact.is_none()
+    }
+
+    /// Combine the overrides with the fallback overrides, the values of self have precedence
+    pub fn with_fallback(&self, fallback: &FileOverrides) -> FileOverrides {
+        FileOverrides {
+            dry_run: self.dry_run.or(fallback.dry_run),
+            mode: self.mode.or(fallback.mode),
+            keep_rotate: self.keep_rotate.or(fallback.keep_rotate),
+            rotate_start: self.rotate_start.or(fallback.rotate_start),
+            copy_truncate: self.copy_truncate.or(fallback.copy_truncate),
+            file_size_mib: self.file_size_mib.or(fallback.file_size_mib),
+            last_write_h: self.last_write_h.or(fallback.last_write_h),
+            max_archive_age_s: self.max_archive_age_s.or(fallback.max_archive_age_s),
+            max_archive_size_bytes: self
+                .max_archive_size_bytes
+                .or(fallback.max_archive_size_bytes),
+            condition: self
+                .condition
+                .clone()
+                .or_else(|| fallback.condition.clone()),
+            frequency: self.frequency.or(fallback.frequency),
+            symlinks: self.symlinks.or(fallback.symlinks),
+            shred: self.shred.or(fallback.shred),
+            manifest: self.manifest.or(fallback.manifest),
+            action: self.action.or(fallback.action),
+            trim_keep_lines: self.trim_keep_lines.or(fallback.trim_keep_lines),
+            trim_keep_bytes: self.trim_keep_bytes.or(fallback.trim_keep_bytes),
+            trim_max_age_s: self.trim_max_age_s.or(fallback.trim_max_age_s),
+            trim_timestamp_format: self
+                .trim_timestamp_format
+                .clone()
+                .or_else(|| fallback.trim_timestamp_format.clone()),
+            redact: self.redact.clone().or_else(|| fallback.redact.clone()),
+        }
+    }
+
+    /// Combine the overrides with the global values of the config
+    pub fn resolve(&self, config: &Config) -> RotationPolicy {
+        RotationPolicy {
+            dry_run: config.dry_run || self.dry_run.unwrap_or(false),
+            mode: self.mode.unwrap_or(config.mode),
+            keep_rotate: self.keep_rotate.unwrap_or(config.keep_rotate),
+            rotate_start: self.rotate_start.unwrap_or(config.rotate_start),
+            copy_truncate: self.copy_truncate.unwrap_or(config.copy_truncate),
+            allocated_size: config.allocated_size,
+            retention: RetentionConfig {
+                file_size_mib: self.file_size_mib.unwrap_or(config.retention.file_size_mib),
+                last_write_h: self.last_write_h.unwrap_or(config.retention.last_write_h),
+                min_free_percent: config.retention.min_free_percent,
+                min_size_bytes: config.retention.min_size_bytes,
+                min_age_s: config.retention.min_age_s,
+                max_lines: config.retention.max_lines,
+                file_age_h: config.retention.file_age_h,
+            },
+            max_archive_age_s: self.max_archive_age_s.or(config.max_archive_age_s),
+            max_archive_size_bytes: self
+                .max_archive_size_bytes
+                .or(config.max_archive_size_bytes),
+            condition: self.condition.clone(),
+            frequency: self.frequency.or(config.frequency),
+            symlinks: self.symlinks.unwrap_or(config.symlinks),
+            shred: self.shred.unwrap_or(config.shred),
+            manifest: self.manifest.unwrap_or(config.manifest),
+            redact: self.redact.clone().unwrap_or_else(|| config.redact.clone()),
+            action: self.action.unwrap_or(config.action),
+            trim: TrimConfig {
+                keep_lines: self.trim_keep_lines.or(config.trim.keep_lines),
+                keep_bytes: self.trim_keep_bytes.or(config.trim.keep_bytes),
+                max_age_s: self.trim_max_age_s.or(config.trim.max_age_s),
+                timestamp_format: self
+                    .trim_timestamp_format
+                    .clone()
+                    .or_else(|| config.trim.timestamp_format.clone()),
+            },
+        }
+    }
+}
+
+/// Represents the effective rotation settings of a single file
+#[derive(Debug)]
+pub struct RotationPolicy {
+    /// When set to true the actions of the file are only logged
+    pub dry_run: bool,
+    pub mode: CleanUpMode,
+    pub keep_rotate: u64,
+    pub rotate_start: u64,
+    pub copy_truncate: bool,
+
+    /// When set to true the size conditions use the allocated blocks of the file
+    pub allocated_size: bool,
+
+    pub retention: RetentionConfig,
+    pub max_archive_age_s: Option<u64>,
+    pub max_archive_size_bytes: Option<u64>,
+
+    /// Condition expression that replaces the checks of the cleanup mode
+    pub condition: Option<ConditionExpr>,
+
+    pub frequency: Option<Frequency>,
+    pub symlinks: SymlinkPolicy,
+    pub shred: bool,
+    pub manifest: bool,
+    pub redact: Vec<Regex>,
+    pub action: CleanUpAction,
+    pub trim: TrimConfig,
+}
+
+/// Represents the process that is signaled after a rotation
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalTarget {
+    /// Name of the signal like 'HUP'
+    pub signal: String,
+
+    /// Path of the file that contains the process id
+    pub pidfile: String,
+}
+
+/// Represents a named group of file entries with shared settings and hooks
+#[derive(Debug)]
+pub struct GroupConfig {
+    /// Name of the group from the '[group.NAME]' table
+    pub name: String,
+
+    /// File paths of the group, can contain glob patterns like '*.log'
+    pub paths: Vec<String>,
+
+    /// Rotation settings of all files in the group, the file entries can replace them again
+    pub overrides: FileOverrides,
+
+    /// Processing order of the group, groups with a higher priority are processed first
+    pub priority: Option<i64>,
+
+    /// Combined size of the files of the group and their archives, the oldest are cleaned up first
+    pub max_total_size_bytes: Option<u64>,
+
+    /// Shell command that runs once before the first file of the group is rotated
+    pub prerotate: Option<String>,
+
+    /// Shell command that runs once after all files of the group were processed,
+    /// only executed when at least one file was rotated
+    pub postrotate: Option<String>,
+}
+
+/// Enum representing the different ways to select log files of an entry
+#[derive(Debug)]
+pub enum FileSource {
+    /// Path of the log file, can contain glob patterns like '*.log'
+    Path(String),
+
+    /// Directory that is scanned for log files
+    Dir(DirSource),
+}
+
+/// Represents a directory entry that is expanded into concrete files
+#[derive(Debug)]
+pub struct DirSource {
+    /// Path of the directory that contains the log files
+    pub dir: String,
+
+    /// When set to true the sub directories are scanned too
+    pub recursive: bool,
+
+    /// Only files with these extensions are selected, all files when empty
+    pub extensions: Vec<String>,
+
+    /// Maximum directory depth for recursive scans, files directly
+    /// in the directory have the depth 1. No limit when not set.
+    pub max_depth: Option<u64>,
+}
+
+impl fmt::Display for FileSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileSource::Path(path) => write!(f, "{}", path),
+            FileSource::Dir(source) => {
+                write!(f, "{} (dir", source.dir)?;
+
+                if source.recursive {
+                    write!(f, ", recursive")?;
+                }
+
+                if let Some(max_depth) = source.max_depth {
+                    write!(f, ", max depth {}", max_depth)?;
+                }
+
+                if !source.extensions.is_empty() {
+                    write!(f, ", extensions: {}", source.extensions.join(", "))?;
+                }
+
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Enum representing what is done with a file that is cleaned up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CleanUpAction {
+    /// Move the content into a rotated file
+    Rotate,
+
+    /// Rewrite the file in place, only the end of the content is kept
+    Trim,
+}
+
+/// Custom error type for parsing CleanUpAction
 #[derive(Debug)]
+pub struct ParseCleanUpActionError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseCleanUpActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse CleanUpAction: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseCleanUpActionError {}
+
+impl FromStr for CleanUpAction {
+    type Err = ParseCleanUpActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rotate" => Ok(CleanUpAction::Rotate),
+            "trim" => Ok(CleanUpAction::Trim),
+            _ => Err(ParseCleanUpActionError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Enum representing different ways to check if a file has to be cleaned up
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CleanUpMode {
     /// A file is cleaned up as soon as the file size
     /// from 'retention.file_size_mb' has been exceeded
@@ -55,9 +549,28 @@ pub enum CleanUpMode {
     /// operation is older than (now-'retention.last_write_h')
     LastWrite,
 
-    /// All cleanup modes are evaluated. A file is cleaned up
-    /// if at least one condition is met (OR combination)
-    All,
+    /// A file is cleaned up as soon as its creation is older
+    /// than (now-'retention.file_age_h')
+    FileAge,
+
+    /// A file is cleaned up as soon as the number of lines
+    /// exceeds 'retention.max_lines'
+    LineCount,
+
+    /// A file is cleaned up as soon as the free space of its
+    /// filesystem is below 'retention.min_free_percent'
+    DiskFree,
+
+    /// All conditions with a configured threshold are evaluated. A file
+    /// is cleaned up if at least one condition is met (OR combination).
+    /// The former name 'All' is still accepted.
+    AnyOf,
+
+    /// All conditions with a configured threshold are evaluated. A file
+    /// is only cleaned up if every condition is met (AND combination).
+    /// Besides the file size and the last write, this includes the file
+    /// age, the line count and the free disk space when they are set.
+    AllOf,
 }
 
 /// Custom error type for parsing CleanUpMode
@@ -83,7 +596,11 @@ impl FromStr for CleanUpMode {
         match s.to_uppercase().as_str() {
             "FILESIZE" => Ok(CleanUpMode::FileSize),
             "LASTWRITE" => Ok(CleanUpMode::LastWrite),
-            "ALL" => Ok(CleanUpMode::All),
+            "FILEAGE" => Ok(CleanUpMode::FileAge),
+            "LINECOUNT" => Ok(CleanUpMode::LineCount),
+            "DISKFREE" => Ok(CleanUpMode::DiskFree),
+            "ANYOF" | "ALL" => Ok(CleanUpMode::AnyOf),
+            "ALLOF" => Ok(CleanUpMode::AllOf),
             _ => Err(ParseCleanUpModeError {
                 invalid_value: s.to_string(),
             }),
@@ -91,37 +608,1244 @@ impl FromStr for CleanUpMode {
     }
 }
 
-/// Represents the config values before a file cleanup should be started
+/// Represents the permissions and ownership that are applied to a file
+#[derive(Debug, Default)]
+pub struct FilePermissions {
+    /// Unix file mode like 0o640
+    pub mode: Option<u32>,
+
+    /// Name or id of the owning user
+    pub owner: Option<String>,
+
+    /// Name or id of the owning group
+    pub group: Option<String>,
+}
+
+impl fmt::Display for FilePermissions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mode {
+            Some(mode) => write!(f, "mode {:04o}", mode)?,
+            None => write!(f, "mode unchanged")?,
+        }
+
+        write!(
+            f,
+            ", owner {}, group {}",
+            self.owner.as_deref().unwrap_or("unchanged"),
+            self.group.as_deref().unwrap_or("unchanged")
+        )
+    }
+}
+
+/// Enum representing the algorithms for compressing rotated files
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Compression {
+    /// Rotated files are not compressed
+    None,
+
+    /// Compression with 'gzip', creates '.gz' files
+    Gzip,
+
+    /// Compression with 'zstd', creates '.zst' files
+    Zstd,
+
+    /// Compression with 'xz', creates '.xz' files
+    Xz,
+
+    /// Compression with 'bzip2', creates '.bz2' files
+    Bzip2,
+}
+
+impl Compression {
+    /// Get the range of the supported compression levels
+    pub fn level_range(&self) -> Option<(u32, u32)> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip | Compression::Bzip2 => Some((1, 9)),
+            Compression::Zstd => Some((1, 19)),
+            Compression::Xz => Some((0, 9)),
+        }
+    }
+}
+
+/// Custom error type for parsing Compression
 #[derive(Debug)]
-pub struct RetentionConfig {
-    /// Size in MiB=(1024*1024 Bytes) that a file must exceed in order to be cleaned up
-    pub file_size_mib: u64,
+pub struct ParseCompressionError {
+    invalid_value: String,
+}
 
-    /// Hours since the last write operation before a file is cleaned up
-    pub last_write_h: u64,
+//Implement the Display trait
+impl fmt::Display for ParseCompressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse Compression: {}", self.invalid_value)
+    }
 }
 
-impl Config {
-    /// Display all config values in a very readable way
-    pub fn print_config_values(&self) {
-        println!("Config:");
-        println!("  Dry Run: {}", self.dry_run);
-        println!("  Mode: {:?}", self.mode);
-        println!("  Keep Rotate: {}", self.keep_rotate);
-        println!("  Missing Files OK: {}", self.missing_files_ok);
-        println!("  Copy Truncate: {}", self.copy_truncate);
+//Implement the std Error trait
+impl std::error::Error for ParseCompressionError {}
 
-        println!("  File List:");
-        if self.file_list.is_empty() {
-            println!("    (empty)");
-        } else {
-            for (i, file) in self.file_list.iter().enumerate() {
-                println!("    {}: {}", i + 1, file);
-            }
+impl FromStr for Compression {
+    type Err = ParseCompressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Compression::None),
+            "gzip" => Ok(Compression::Gzip),
+            "zstd" => Ok(Compression::Zstd),
+            "xz" => Ok(Compression::Xz),
+            "bzip2" => Ok(Compression::Bzip2),
+            _ => Err(ParseCompressionError {
+                invalid_value: s.to_string(),
+            }),
         }
+    }
+}
 
-        println!("  Retention Config:");
-        println!("    File Size (MiB): {}", self.retention.file_size_mib);
-        println!("    Last Write (hours): {}", self.retention.last_write_h);
+/// Enum representing the minimum time between two rotations of a file
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Frequency {
+    /// Get the length of the rotation window in seconds, a month has 30 days
+    pub fn window_s(&self) -> u64 {
+        match self {
+            Frequency::Daily => 86400,
+            Frequency::Weekly => 7 * 86400,
+            Frequency::Monthly => 30 * 86400,
+        }
+    }
+}
+
+/// Enum representing the behavior of a run after a failed file task
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorPolicy {
+    /// Process the remaining files, the failure is reported in the summary
+    Continue,
+
+    /// Stop the run at the first failed file task
+    Abort,
+}
+
+/// Custom error type for parsing ErrorPolicy
+#[derive(Debug)]
+pub struct ParseErrorPolicyError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseErrorPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse ErrorPolicy: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseErrorPolicyError {}
+
+impl FromStr for ErrorPolicy {
+    type Err = ParseErrorPolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "continue" => Ok(ErrorPolicy::Continue),
+            "abort" => Ok(ErrorPolicy::Abort),
+            _ => Err(ParseErrorPolicyError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Enum representing the results of a run that make the process exit with a non-zero status
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailOn {
+    /// Warnings like missing or skipped files and failed tasks
+    Warn,
+
+    /// Only failed tasks and aborted runs
+    Error,
+
+    /// The results of the tasks never change the exit status
+    Never,
+}
+
+/// Custom error type for parsing FailOn
+#[derive(Debug)]
+pub struct ParseFailOnError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseFailOnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse FailOn: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseFailOnError {}
+
+impl FromStr for FailOn {
+    type Err = ParseFailOnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "warn" => Ok(FailOn::Warn),
+            "error" => Ok(FailOn::Error),
+            "never" => Ok(FailOn::Never),
+            _ => Err(ParseFailOnError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Enum representing the handling of log files that are symbolic links
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymlinkPolicy {
+    /// Resolve the link and rotate the final target
+    Follow,
+
+    /// Leave the link and its target untouched
+    Skip,
+
+    /// Fail the file task
+    Error,
+}
+
+/// Custom error type for parsing SymlinkPolicy
+#[derive(Debug)]
+pub struct ParseSymlinkPolicyError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseSymlinkPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse SymlinkPolicy: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseSymlinkPolicyError {}
+
+impl FromStr for SymlinkPolicy {
+    type Err = ParseSymlinkPolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "follow" => Ok(SymlinkPolicy::Follow),
+            "skip" => Ok(SymlinkPolicy::Skip),
+            "error" => Ok(SymlinkPolicy::Error),
+            _ => Err(ParseSymlinkPolicyError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Enum representing the handling of log files that are open for writing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpenFilePolicy {
+    /// Do not check for processes that write the file
+    Ignore,
+
+    /// Rotate the file and report the writing processes
+    Warn,
+
+    /// Copy and truncate the file instead of renaming it
+    CopyTruncate,
+
+    /// Leave the file untouched until it is closed
+    Skip,
+}
+
+/// Custom error type for parsing OpenFilePolicy
+#[derive(Debug)]
+pub struct ParseOpenFilePolicyError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseOpenFilePolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse OpenFilePolicy: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseOpenFilePolicyError {}
+
+impl FromStr for OpenFilePolicy {
+    type Err = ParseOpenFilePolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ignore" => Ok(OpenFilePolicy::Ignore),
+            "warn" => Ok(OpenFilePolicy::Warn),
+            "copy_truncate" | "copytruncate" => Ok(OpenFilePolicy::CopyTruncate),
+            "skip" => Ok(OpenFilePolicy::Skip),
+            _ => Err(ParseOpenFilePolicyError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Custom error type for parsing Frequency
+#[derive(Debug)]
+pub struct ParseFrequencyError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseFrequencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse Frequency: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseFrequencyError {}
+
+impl FromStr for Frequency {
+    type Err = ParseFrequencyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "daily" => Ok(Frequency::Daily),
+            "weekly" => Ok(Frequency::Weekly),
+            "monthly" => Ok(Frequency::Monthly),
+            _ => Err(ParseFrequencyError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Represents the config values before a file cleanup should be started
+#[derive(Debug)]
+pub struct RetentionConfig {
+    /// Size in MiB=(1024*1024 Bytes) that a file must exceed in order to be cleaned up
+    pub file_size_mib: u64,
+
+    /// Hours since the last write operation before a file is cleaned up
+    pub last_write_h: u64,
+
+    /// Minimum percentage of free space on the filesystem of a file, not checked when not set
+    pub min_free_percent: Option<u64>,
+
+    /// Minimum size in bytes before a file is cleaned up, even when other conditions are met
+    pub min_size_bytes: Option<u64>,
+
+    /// Minimum seconds since the creation of a file before it is cleaned up, even when other conditions are met
+    pub min_age_s: Option<u64>,
+
+    /// Number of lines that a file must exceed in order to be cleaned up, not checked when not set
+    pub max_lines: Option<u64>,
+
+    /// Hours since the creation of a file before it is cleaned up, not checked when not set
+    pub file_age_h: Option<u64>,
+}
+
+/// Represents the content that is kept by the trim action
+#[derive(Debug, Clone, Default)]
+pub struct TrimConfig {
+    /// Number of lines at the end of the file that are kept
+    pub keep_lines: Option<u64>,
+
+    /// Number of bytes at the end of the file that are kept, cut at the next line start
+    pub keep_bytes: Option<u64>,
+
+    /// Seconds since the timestamp of a line before it is removed
+    pub max_age_s: Option<u64>,
+
+    /// Format of the timestamps at the start of the lines, the default format when not set
+    pub timestamp_format: Option<TimestampFormat>,
+}
+
+impl TrimConfig {
+    /// Returns true when at least one limit of the kept content is set
+    pub fn has_limit(&self) -> bool {
+        self.keep_lines.is_some() || self.keep_bytes.is_some() || self.max_age_s.is_some()
+    }
+}
+
+/// Represents the retry policy for transient errors of file operations
+#[derive(Debug, Clone, Copy)]
+pub struct RetryConfig {
+    /// Number of attempts of an operation, 1 disables the retries
+    pub attempts: u64,
+
+    /// Milliseconds before the first retry, doubled for every further retry
+    pub backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            attempts: 1,
+            backoff_ms: 100,
+        }
+    }
+}
+
+/// Enum representing the I/O scheduling classes that lower the priority
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IoClass {
+    /// Default class of all processes with the levels 0 (high) to 7 (low)
+    BestEffort,
+
+    /// I/O is only performed when no other process uses the disk
+    Idle,
+}
+
+/// Custom error type for parsing IoClass
+#[derive(Debug)]
+pub struct ParseIoClassError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseIoClassError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse IoClass: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseIoClassError {}
+
+impl FromStr for IoClass {
+    type Err = ParseIoClassError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "best-effort" | "besteffort" => Ok(IoClass::BestEffort),
+            "idle" => Ok(IoClass::Idle),
+            _ => Err(ParseIoClassError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Represents the scheduling priorities of a run, unset values are not changed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PriorityConfig {
+    /// Nice value of the CPU scheduling from 0 to 19
+    pub nice: Option<u8>,
+
+    /// I/O scheduling class
+    pub io_class: Option<IoClass>,
+
+    /// Level of the best-effort class from 0 to 7
+    pub io_level: Option<u8>,
+}
+
+/// Represents the messages that are sent to the local syslog daemon
+#[derive(Debug, Clone, Copy)]
+pub struct SyslogConfig {
+    /// When set to true every printed message is also sent to syslog
+    pub enabled: bool,
+
+    /// Facility of the messages, used by the syslog daemon to route them
+    pub facility: Facility,
+
+    /// Severity of the status messages, errors and warnings keep their severity
+    pub priority: Severity,
+}
+
+impl Default for SyslogConfig {
+    fn default() -> Self {
+        SyslogConfig {
+            enabled: false,
+            facility: Facility::User,
+            priority: Severity::Info,
+        }
+    }
+}
+
+/// Represents the config values of the daemon mode
+#[derive(Debug)]
+pub struct DaemonConfig {
+    /// Seconds between two cleanup runs of the daemon
+    pub interval_s: u64,
+
+    /// When set to true the config is reloaded after it was changed
+    pub reload: bool,
+
+    /// Address of the http endpoint with the health and status of the daemon, None when disabled
+    pub status_listen: Option<SocketAddr>,
+}
+
+/// Represents the channels that are notified about the results of the runs
+#[derive(Debug, Clone, Default)]
+pub struct NotifyConfig {
+    /// Summary of the run sent as email, no email is sent when not set
+    pub email: Option<EmailNotify>,
+
+    /// Messages posted to a Slack incoming webhook
+    pub slack: Option<SlackNotify>,
+
+    /// Messages sent to a Matrix room
+    pub matrix: Option<MatrixNotify>,
+}
+
+/// Enum representing the events that a channel can be notified about
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotifyEvent {
+    /// Summary of a finished run
+    RunFinished,
+
+    /// A single file task has failed
+    TaskFailed,
+
+    /// The total budget was exceeded and archives were removed
+    BudgetExceeded,
+}
+
+/// Custom error type for parsing NotifyEvent
+#[derive(Debug)]
+pub struct ParseNotifyEventError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseNotifyEventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse NotifyEvent: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseNotifyEventError {}
+
+impl FromStr for NotifyEvent {
+    type Err = ParseNotifyEventError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "run_finished" => Ok(NotifyEvent::RunFinished),
+            "task_failed" => Ok(NotifyEvent::TaskFailed),
+            "budget_exceeded" => Ok(NotifyEvent::BudgetExceeded),
+            _ => Err(ParseNotifyEventError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Represents the events that are sent to a single channel
+#[derive(Debug, Clone)]
+pub struct NotifyFilter {
+    /// Runs whose summary is sent, the other events are always failures
+    pub on: NotifyOn,
+
+    pub events: Vec<NotifyEvent>,
+}
+
+impl Default for NotifyFilter {
+    fn default() -> Self {
+        NotifyFilter {
+            on: NotifyOn::Failure,
+            events: vec![NotifyEvent::RunFinished],
+        }
+    }
+}
+
+/// Enum representing the runs that send a notification
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotifyOn {
+    /// Only runs with a failed task, an abort or an interruption
+    Failure,
+
+    /// Every run
+    Always,
+}
+
+/// Custom error type for parsing NotifyOn
+#[derive(Debug)]
+pub struct ParseNotifyOnError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseNotifyOnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse NotifyOn: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseNotifyOnError {}
+
+impl FromStr for NotifyOn {
+    type Err = ParseNotifyOnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "failure" => Ok(NotifyOn::Failure),
+            "always" => Ok(NotifyOn::Always),
+            _ => Err(ParseNotifyOnError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Represents the SMTP server and the recipients of the email notification
+#[derive(Debug, Clone)]
+pub struct EmailNotify {
+    /// Url of the SMTP server like 'smtp://mail.example.com:25'
+    pub server: String,
+
+    /// Sender address of the emails
+    pub from: String,
+
+    /// Recipient addresses of the emails
+    pub to: Vec<String>,
+
+    /// Netrc file with the login of the server, the emails are sent without login when not set
+    pub netrc_file: Option<String>,
+
+    pub filter: NotifyFilter,
+}
+
+/// Represents the incoming webhook of a Slack channel
+#[derive(Debug, Clone)]
+pub struct SlackNotify {
+    /// Url of the webhook like 'https://hooks.slack.com/services/...', it contains the secret
+    pub webhook_url: String,
+
+    pub filter: NotifyFilter,
+}
+
+/// Represents the room of a Matrix homeserver
+#[derive(Debug, Clone)]
+pub struct MatrixNotify {
+    /// Url of the homeserver like 'https://matrix.example.com'
+    pub homeserver: String,
+
+    /// Internal id of the room like '!abc123:example.com'
+    pub room_id: String,
+
+    /// File with the access token of the sending user
+    pub access_token_file: String,
+
+    pub filter: NotifyFilter,
+}
+
+/// Represents the upload of the finished archives to remote storage
+#[derive(Debug, Clone)]
+pub struct UploadConfig {
+    /// Remote storage that receives the archives
+    pub backend: UploadBackend,
+
+    /// When set to true the local archive is removed after a successful upload
+    pub delete_local: bool,
+
+    /// Template of the remote names with the tokens {name}, {time}, {hostname} and {app}
+    pub name_template: String,
+
+    /// Retry policy of failed uploads, all upload failures are retried
+    pub retry: RetryConfig,
+}
+
+/// Enum representing the supported remote storages of the upload
+#[derive(Debug, Clone)]
+pub enum UploadBackend {
+    /// Bucket of an S3 compatible object storage
+    S3(S3Target),
+
+    /// Directory of a remote host that is reachable with ssh
+    Ssh(SshTarget),
+
+    /// Bucket of Google Cloud Storage
+    #[cfg(feature = "gcs")]
+    Gcs(GcsTarget),
+
+    /// Container of Azure Blob Storage
+    #[cfg(feature = "azure")]
+    Azure(AzureTarget),
+
+    /// Collection of a WebDAV server
+    #[cfg(feature = "webdav")]
+    WebDav(WebDavTarget),
+}
+
+impl UploadBackend {
+    /// Get the remote location of the archives like 's3://logs/web1/' or 'backup@host:/srv/logs/'
+    pub fn location(&self) -> String {
+        match self {
+            UploadBackend::S3(target) => format!("s3://{}/{}", target.bucket, target.prefix),
+            UploadBackend::Ssh(target) => format!("{}:{}", target.destination(), target.path),
+            #[cfg(feature = "gcs")]
+            UploadBackend::Gcs(target) => format!("gs://{}/{}", target.bucket, target.prefix),
+            #[cfg(feature = "azure")]
+            UploadBackend::Azure(target) => format!(
+                "https://{}.blob.core.windows.net/{}/{}",
+                target.account, target.container, target.prefix
+            ),
+            #[cfg(feature = "webdav")]
+            UploadBackend::WebDav(target) => target.url.clone(),
+        }
+    }
+}
+
+/// Represents the bucket and the credentials of an S3 upload
+#[derive(Debug, Clone)]
+pub struct S3Target {
+    pub bucket: String,
+
+    /// Prefix of the object keys like 'web1/', the archives are stored at the top level when empty
+    pub prefix: String,
+
+    /// Region of the bucket, the region of the aws cli config when not set
+    pub region: Option<String>,
+
+    pub credentials: S3Credentials,
+}
+
+/// Represents the remote host and the directory of an ssh upload
+#[derive(Debug, Clone)]
+pub struct SshTarget {
+    pub host: String,
+
+    /// Login user on the remote host, the user of the ssh config when not set
+    pub user: Option<String>,
+
+    /// Port of the ssh server, the port of the ssh config when not set
+    pub port: Option<u16>,
+
+    /// Remote directory of the archives like '/srv/logs/', always ends with '/'
+    pub path: String,
+
+    /// Private key of the login, the keys of the ssh config when not set
+    pub identity_file: Option<String>,
+
+    pub transfer: SshTransfer,
+}
+
+impl SshTarget {
+    /// Get the destination of the ssh login like 'backup@host'
+    pub fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{}@{}", user, self.host),
+            None => self.host.clone(),
+        }
+    }
+}
+
+/// Represents the bucket of a Google Cloud Storage upload
+#[cfg(feature = "gcs")]
+#[derive(Debug, Clone)]
+pub struct GcsTarget {
+    pub bucket: String,
+
+    /// Prefix of the object names like 'web1/', the archives are stored at the top level when empty
+    pub prefix: String,
+}
+
+/// Represents the storage account and the container of an Azure Blob Storage upload
+#[cfg(feature = "azure")]
+#[derive(Debug, Clone)]
+pub struct AzureTarget {
+    pub account: String,
+    pub container: String,
+
+    /// Prefix of the blob names like 'web1/', the archives are stored at the top level when empty
+    pub prefix: String,
+}
+
+/// Represents the collection of a WebDAV upload
+#[cfg(feature = "webdav")]
+#[derive(Debug, Clone)]
+pub struct WebDavTarget {
+    /// Url of the collection like 'https://dav.example.com/logs/', always ends with '/'
+    pub url: String,
+
+    /// Netrc file with the credentials of the server, no credentials are sent when not set
+    pub netrc_file: Option<String>,
+}
+
+/// Enum representing the programs that copy the archives of an ssh upload
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SshTransfer {
+    Scp,
+    Sftp,
+}
+
+/// Custom error type for parsing SshTransfer
+#[derive(Debug)]
+pub struct ParseSshTransferError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseSshTransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse SshTransfer: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseSshTransferError {}
+
+impl FromStr for SshTransfer {
+    type Err = ParseSshTransferError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "scp" => Ok(SshTransfer::Scp),
+            "sftp" => Ok(SshTransfer::Sftp),
+            _ => Err(ParseSshTransferError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Enum representing the sources of the credentials of an S3 upload
+#[derive(Debug, Clone, PartialEq)]
+pub enum S3Credentials {
+    /// Default credential chain of the aws cli, like the instance profile
+    Default,
+
+    /// Access keys of the variables AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY
+    Env,
+
+    /// Named profile of the aws cli config
+    Profile(String),
+}
+
+/// Custom error type for parsing S3Credentials
+#[derive(Debug)]
+pub struct ParseS3CredentialsError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseS3CredentialsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse S3Credentials: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseS3CredentialsError {}
+
+impl FromStr for S3Credentials {
+    type Err = ParseS3CredentialsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        //The profile name keeps its case, only the source is case insensitive
+        if let Some((source, name)) = s.split_once(':')
+            && source.eq_ignore_ascii_case("profile")
+            && !name.is_empty()
+        {
+            return Ok(S3Credentials::Profile(name.to_string()));
+        }
+
+        match s.to_lowercase().as_str() {
+            "default" => Ok(S3Credentials::Default),
+            "env" => Ok(S3Credentials::Env),
+            _ => Err(ParseS3CredentialsError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl Config {
+    /// Display all config values in a very readable way
+    pub fn print_config_values(&self) {
+        println!("Config:");
+        println!("  Dry Run: {}", self.dry_run);
+        println!("  Mode: {:?}", self.mode);
+        println!("  Action: {:?}", self.action);
+        println!("  Keep Rotate: {}", self.keep_rotate);
+        println!("  Rotate Start: {}", self.rotate_start);
+        println!("  Compact Rotations: {}", self.compact_rotations);
+        println!("  Remove Stray Archives: {}", self.remove_stray_archives);
+        println!("  Dedup Archives: {}", self.dedup_archives);
+        println!("  Allocated Size: {}", self.allocated_size);
+        println!("  Remove Empty Dirs: {}", self.remove_empty_dirs);
+        println!("  Missing Files OK: {}", self.missing_files_ok);
+        println!("  Copy Truncate: {}", self.copy_truncate);
+        println!("  Copy Truncate Lock: {}", self.copy_truncate_lock);
+        println!("  Shred: {}", self.shred);
+        println!("  Manifest: {}", self.manifest);
+
+        for pattern in self.redact.iter() {
+            println!("  Redact: {}", pattern);
+        }
+        println!("  Allow Empty File List: {}", self.allow_empty_file_list);
+        println!("  Rotate Empty: {}", self.rotate_empty);
+        println!("  Skip Unchanged: {}", self.skip_unchanged);
+        println!("  Create: {}", self.create);
+
+        if self.create {
+            println!("  Create Permissions: {}", self.create_permissions);
+        }
+
+        println!("  Archive Permissions: {}", self.archive_permissions);
+        println!("  Compression: {:?}", self.compression);
+
+        if let Some(level) = self.compression_level {
+            println!("  Compression Level: {}", level);
+        }
+
+        println!("  Delay Compress: {}", self.delay_compress);
+        println!("  Defer Growing: {}", self.defer_growing);
+
+        if let Some(max_age_s) = self.max_archive_age_s {
+            println!("  Max Archive Age (seconds): {}", max_age_s);
+        }
+
+        if let Some(max_size_bytes) = self.max_archive_size_bytes {
+            println!("  Max Archive Size (bytes): {}", max_size_bytes);
+        }
+
+        if let Some(budget_bytes) = self.total_budget_bytes {
+            println!("  Total Budget (bytes): {}", budget_bytes);
+        }
+
+        if let Some(max_bytes) = self.max_bytes_per_run {
+            println!("  Max Bytes per Run: {}", max_bytes);
+        }
+
+        println!("  IO Buffer (bytes): {}", self.io_buffer_bytes);
+
+        if let Some(limit) = self.io_limit_bytes_per_s {
+            println!("  IO Limit (bytes per second): {}", limit);
+        }
+
+        if let Some(frequency) = self.frequency {
+            println!("  Frequency: {:?}", frequency);
+        }
+
+        if let Some(stale_after_s) = self.stale_after_s {
+            println!("  Stale After (seconds): {}", stale_after_s);
+        }
+
+        if let Some(unmanaged_min_size_bytes) = self.unmanaged_min_size_bytes {
+            println!("  Unmanaged Min Size (bytes): {}", unmanaged_min_size_bytes);
+        }
+
+        if let Some(state_file) = &self.state_file {
+            println!("  State File: {}", state_file);
+        }
+
+        println!("  Stats Max Size (bytes): {}", self.stats_max_size_bytes);
+
+        println!("  On Error: {:?}", self.on_error);
+        println!("  Fail On: {:?}", self.fail_on);
+        println!("  Log Level: {:?}", self.log_level);
+
+        if let Some(log_file) = &self.log_file {
+            println!(
+                "  Log File: {} (max {} bytes)",
+                log_file, self.log_file_max_size_bytes
+            );
+        }
+        println!("  Symlinks: {:?}", self.symlinks);
+        println!("  Open Files: {:?}", self.open_files);
+
+        if let Some(lock_file) = &self.lock_file {
+            println!("  Lock File: {}", lock_file);
+        }
+
+        if let Some(audit_log) = &self.audit_log {
+            println!("  Audit Log: {}", audit_log);
+        }
+
+        if let Some(lock_wait_s) = self.lock_wait_s {
+            println!("  Lock Wait (seconds): {}", lock_wait_s);
+        }
+
+        if !self.allowed_roots.is_empty() {
+            println!("  Allowed Roots: {}", self.allowed_roots.join(", "));
+        }
+
+        println!("  File List:");
+        if self.file_list.is_empty() {
+            println!("    (empty)");
+        } else {
+            for (i, file) in self.file_list.iter().enumerate() {
+                println!("    {}: {}", i + 1, file);
+            }
+        }
+
+        println!("  Files:");
+        if self.files.is_empty() {
+            println!("    (empty)");
+        } else {
+            for (i, entry) in self.files.iter().enumerate() {
+                println!("    {}: {}", i + 1, entry.source);
+
+                if let Some(olddir) = &entry.olddir {
+                    println!("       Olddir: {}", olddir);
+                }
+
+                if let Some(priority) = entry.priority {
+                    println!("       Priority: {}", priority);
+                }
+                if let Some(group) = &entry.group {
+                    println!("       Group: {}", group);
+                }
+
+                if let Some(target) = &entry.signal {
+                    println!(
+                        "       Signal: {} to pidfile {}",
+                        target.signal, target.pidfile
+                    );
+                }
+
+                if let Some(condition) = &entry.overrides.condition {
+                    println!("       Condition: {}", condition);
+                }
+
+                if !entry.overrides.is_empty() {
+                    let policy: RotationPolicy = entry.overrides.resolve(self);
+                    println!(
+                        "       Overrides: mode {:?}, keep rotate {}, copy truncate {}, file size {} MiB, last write {} h",
+                        policy.mode,
+                        policy.keep_rotate,
+                        policy.copy_truncate,
+                        policy.retention.file_size_mib,
+                        policy.retention.last_write_h
+                    );
+                }
+            }
+        }
+
+        println!("  Groups:");
+        if self.groups.is_empty() {
+            println!("    (empty)");
+        } else {
+            for group in self.groups.iter() {
+                println!("    {}:", group.name);
+                for path in group.paths.iter() {
+                    println!("      Path: {}", path);
+                }
+                if let Some(priority) = group.priority {
+                    println!("      Priority: {}", priority);
+                }
+                if let Some(max_total_size_bytes) = group.max_total_size_bytes {
+                    println!("      Max Total Size: {} bytes", max_total_size_bytes);
+                }
+                if !group.overrides.is_empty() {
+                    let policy: RotationPolicy = group.overrides.resolve(self);
+                    println!(
+                        "      Overrides: mode {:?}, keep rotate {}, copy truncate {}, file size {} MiB, last write {} h",
+                        policy.mode,
+                        policy.keep_rotate,
+                        policy.copy_truncate,
+                        policy.retention.file_size_mib,
+                        policy.retention.last_write_h
+                    );
+                }
+                println!(
+                    "      Prerotate: {}",
+                    group.prerotate.as_deref().unwrap_or("(none)")
+                );
+                println!(
+                    "      Postrotate: {}",
+                    group.postrotate.as_deref().unwrap_or("(none)")
+                );
+            }
+        }
+
+        println!("  Retention Config:");
+        println!("    File Size (MiB): {}", self.retention.file_size_mib);
+        println!("    Last Write (hours): {}", self.retention.last_write_h);
+
+        if let Some(min_free_percent) = self.retention.min_free_percent {
+            println!("    Min Free (percent): {}", min_free_percent);
+        }
+
+        if let Some(min_size_bytes) = self.retention.min_size_bytes {
+            println!("    Min Size (bytes): {}", min_size_bytes);
+        }
+
+        if let Some(min_age_s) = self.retention.min_age_s {
+            println!("    Min Age (seconds): {}", min_age_s);
+        }
+
+        if let Some(max_lines) = self.retention.max_lines {
+            println!("    Max Lines: {}", max_lines);
+        }
+
+        if let Some(file_age_h) = self.retention.file_age_h {
+            println!("    File Age (hours): {}", file_age_h);
+        }
+
+        println!("  Trim Config:");
+
+        if let Some(keep_lines) = self.trim.keep_lines {
+            println!("    Keep Lines: {}", keep_lines);
+        }
+
+        if let Some(keep_bytes) = self.trim.keep_bytes {
+            println!("    Keep (bytes): {}", keep_bytes);
+        }
+
+        if let Some(max_age_s) = self.trim.max_age_s {
+            println!("    Max Age (seconds): {}", max_age_s);
+        }
+
+        if let Some(timestamp_format) = &self.trim.timestamp_format {
+            println!("    Timestamp Format: {}", timestamp_format);
+        }
+
+        println!("  Retry Config:");
+        println!("    Attempts: {}", self.retry.attempts);
+        println!("    Backoff (milliseconds): {}", self.retry.backoff_ms);
+        println!("  Priority Config:");
+
+        if let Some(nice) = self.priority.nice {
+            println!("    Nice: {}", nice);
+        }
+
+        if let Some(io_class) = self.priority.io_class {
+            println!("    IO Class: {:?}", io_class);
+        }
+
+        if let Some(io_level) = self.priority.io_level {
+            println!("    IO Level: {}", io_level);
+        }
+
+        if self.syslog.enabled {
+            println!("  Syslog Config:");
+            println!("    Facility: {:?}", self.syslog.facility);
+            println!("    Priority: {:?}", self.syslog.priority);
+        }
+
+        println!("  Daemon Config:");
+        println!("    Interval (seconds): {}", self.daemon.interval_s);
+        println!("    Reload: {}", self.daemon.reload);
+
+        if let Some(status_listen) = self.daemon.status_listen {
+            println!("    Status endpoint: http://{}/status", status_listen);
+        }
+
+        if let Some(upload) = &self.upload {
+            println!("  Upload Config:");
+            println!("    Location: {}", upload.backend.location());
+            println!("    Delete Local: {}", upload.delete_local);
+            println!("    Name: {}", upload.name_template);
+            println!("    Attempts: {}", upload.retry.attempts);
+        }
+
+        if let Some(email) = &self.notify.email {
+            println!("  Email Notification:");
+            println!("    Server: {}", email.server);
+            println!("    From: {}", email.from);
+            println!("    To: {}", email.to.join(", "));
+            println!("    On: {:?}", email.filter.on);
+            println!("    Events: {:?}", email.filter.events);
+        }
+
+        if let Some(slack) = &self.notify.slack {
+            println!("  Slack Notification:");
+            println!("    On: {:?}", slack.filter.on);
+            println!("    Events: {:?}", slack.filter.events);
+        }
+
+        if let Some(matrix) = &self.notify.matrix {
+            println!("  Matrix Notification:");
+            println!("    Homeserver: {}", matrix.homeserver);
+            println!("    Room: {}", matrix.room_id);
+            println!("    On: {:?}", matrix.filter.on);
+            println!("    Events: {:?}", matrix.filter.events);
+        }
+    }
+}
+
+#[cfg(test)]
+impl Config {
+    /// Create a valid config with the default values for the unit tests, the file list
+    /// contains a single log file. Tests change the fields they depend on.
+    pub fn test_config() -> Config {
+        Config {
+            dry_run: false,
+            mode: CleanUpMode::FileSize,
+            action: CleanUpAction::Rotate,
+            keep_rotate: 3,
+            rotate_start: 0,
+            compact_rotations: false,
+            remove_stray_archives: false,
+            dedup_archives: false,
+            allocated_size: false,
+            remove_empty_dirs: false,
+            missing_files_ok: false,
+            copy_truncate: false,
+            copy_truncate_lock: false,
+            shred: false,
+            manifest: false,
+            redact: Vec::new(),
+            allow_empty_file_list: false,
+            rotate_empty: false,
+            skip_unchanged: false,
+            create: false,
+            create_permissions: FilePermissions::default(),
+            archive_permissions: FilePermissions::default(),
+            compression: Compression::None,
+            compression_level: None,
+            delay_compress: false,
+            defer_growing: false,
+            max_archive_age_s: None,
+            max_archive_size_bytes: None,
+            total_budget_bytes: None,
+            max_bytes_per_run: None,
+            io_buffer_bytes: 65536,
+            io_limit_bytes_per_s: None,
+            frequency: None,
+            stale_after_s: None,
+            unmanaged_min_size_bytes: None,
+            state_file: None,
+            stats_max_size_bytes: 1024 * 1024,
+            on_error: ErrorPolicy::Continue,
+            fail_on: FailOn::Error,
+            log_level: LogLevel::Info,
+            log_file: None,
+            log_file_max_size_bytes: 10 * 1024 * 1024,
+            symlinks: SymlinkPolicy::Follow,
+            open_files: OpenFilePolicy::Ignore,
+            lock_file: None,
+            audit_log: None,
+            lock_wait_s: None,
+            allowed_roots: vec![],
+            file_list: vec!["/var/log/a.log".to_string()],
+            files: vec![],
+            groups: vec![],
+            selected_groups: vec![],
+            selected_patterns: vec![],
+            retention: RetentionConfig {
+                file_size_mib: 50,
+                last_write_h: 168,
+                min_free_percent: None,
+                min_size_bytes: None,
+                min_age_s: None,
+                max_lines: None,
+                file_age_h: None,
+            },
+            trim: TrimConfig::default(),
+            retry: RetryConfig::default(),
+            priority: PriorityConfig::default(),
+            syslog: SyslogConfig::default(),
+            daemon: DaemonConfig {
+                interval_s: 3600,
+                reload: true,
+                status_listen: None,
+            },
+            upload: None,
+            notify: NotifyConfig::default(),
+        }
     }
 }
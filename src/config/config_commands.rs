@@ -4,22 +4,25 @@
 //! Note that the config module is also used by other non-config commands.
 //! These function should help the user to configure yalc in an easy way.
 //!
-use std::fs::{File, metadata};
+use std::fs::{self, File, metadata};
 use std::io::{self, Error, ErrorKind, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::command::RunArg;
-use crate::config::{Config, toml_parser};
-use crate::constants::{DEFAULT_CONFIG_CONTENT, DEFAULT_CONFIG_PATH};
+use crate::config::config_location::{self, ConfigLocation};
+use crate::config::config_validator::ValidationError;
+use crate::config::{Config, config_env, config_migrate, config_schema, toml_parser, toml_writer};
+use crate::output;
 
 /// This command is called via "yalc config init".
 /// This will create a new default config file.
 /// Will result in an error if a config file already exists.
-pub fn execute_init_config_command() -> Result<(), io::Error> {
-    let path = Path::new(DEFAULT_CONFIG_PATH);
+/// Without explicit path the file is created in the home directory for non-root users.
+pub fn execute_init_config_command(cli_path: Option<&Path>) -> Result<(), io::Error> {
+    let path = config_location::init_config_location(cli_path).path;
 
     //First check if the file already exists
-    if metadata(path).is_ok() {
+    if metadata(&path).is_ok() {
         return Err(Error::new(
             ErrorKind::AlreadyExists,
             "Config file already exists",
@@ -27,16 +30,21 @@ pub fn execute_init_config_command() -> Result<(), io::Error> {
     }
 
     //Create new config file
-    create_default_config_file(path)
+    create_default_config_file(&path)
 }
 
 fn create_default_config_file(path: &Path) -> Result<(), io::Error> {
     println!("Creating new template config file at: {}", path.display());
 
+    //User config directories may not exist yet
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
     //Create new file handle
     let mut file = File::create(&path)?;
 
-    let content = DEFAULT_CONFIG_CONTENT;
+    let content: String = config_schema::example_config();
     file.write_all(content.as_bytes())?;
 
     //Log the successful write operation
@@ -44,91 +52,202 @@ fn create_default_config_file(path: &Path) -> Result<(), io::Error> {
     Ok(())
 }
 
+/// This command is called via "yalc config example".
+/// Prints the commented example config with all supported keys.
+pub fn execute_example_config_command() {
+    print!("{}", config_schema::example_config());
+}
+
 /// This command is called via "yalc config check".
-pub fn execute_check_config_command() -> Result<(), io::Error> {
-    let path = Path::new(DEFAULT_CONFIG_PATH);
+/// Returns true when the config passed the check, with strict set warnings also fail the check.
+pub fn execute_check_config_command(
+    cli_path: Option<&Path>,
+    strict: bool,
+) -> Result<bool, io::Error> {
+    let path = find_config_path(cli_path);
 
     //The config is validated by the load function
-    match toml_parser::load_config(&path) {
-        Ok(config) => {
-            println!("Yalc config check: [VALID]");
+    let profile: Option<String> = config_env::profile();
+
+    match toml_parser::load_config_with_warnings(&path, profile.as_deref()) {
+        Ok((config, warnings)) => {
+            let passed: bool = !strict || warnings.is_empty();
+
+            match (passed, warnings.is_empty()) {
+                (false, _) => println!("Yalc config check: [ERROR]"),
+                (true, true) => println!("Yalc config check: [VALID]"),
+                (true, false) => println!("Yalc config check: [WARN]"),
+            }
+
+            if !warnings.is_empty() {
+                eprintln!("Config has {} warnings:", warnings.len());
+            }
+
+            //Strict checks report the warnings as errors
+            let label: &str = if strict { "ERROR" } else { "WARN" };
+
+            for warning in warnings.iter() {
+                eprintln!("  [{}] {}", label, warning);
+            }
+
             config.print_config_values();
+            Ok(passed)
         }
         Err(e) => {
             println!("Yalc config check: [ERROR]");
-            eprintln!("Config error: {}", e);
+
+            //Render every violation of the validation on a separate line
+            match e
+                .get_ref()
+                .and_then(|inner| inner.downcast_ref::<ValidationError>())
+            {
+                Some(validation_error) => {
+                    eprintln!(
+                        "Config has {} invalid values:",
+                        validation_error.diagnostics.len()
+                    );
+
+                    for diagnostic in validation_error.diagnostics.iter() {
+                        eprintln!("  [ERROR] {}", diagnostic);
+                    }
+                }
+                None => eprintln!("Config error: {}", e),
+            }
+
+            Ok(false)
         }
     }
+}
+
+/// This command is called via "yalc config migrate".
+/// Upgrades the config file to the current schema version. The original
+/// file is kept as backup with the '.bak' extension. Note that comments
+/// of the config file are not preserved by the rewrite.
+pub fn execute_migrate_config_command(cli_path: Option<&Path>) -> Result<(), io::Error> {
+    let path = find_config_path(cli_path);
+
+    //Only the main config file is migrated, includes and drop-ins are kept as they are
+    let mut table = toml_parser::load_toml_table(&path)?;
+    let changes: Vec<String> = config_migrate::migrate_table(&mut table)?;
+
+    if changes.is_empty() {
+        println!(
+            "Config is already at the current schema version: {}",
+            config_migrate::CURRENT_CONFIG_VERSION
+        );
+        return Ok(());
+    }
+
+    //Keep the original config file as backup
+    let mut backup_path = path.clone().into_os_string();
+    backup_path.push(".bak");
+    fs::copy(&path, &backup_path)?;
+    println!(
+        "Created config backup at: {}",
+        Path::new(&backup_path).display()
+    );
+
+    fs::write(&path, toml_writer::to_toml_string(&table))?;
+    println!("Migrated config file at: {}", path.display());
+
+    for change in changes.iter() {
+        println!("  {}", change);
+    }
 
     Ok(())
 }
 
-/// Load the config from a specific path
-pub fn load_config(path: &Path) -> Result<Config, io::Error> {
-    match toml_parser::load_config(&path) {
-        Ok(config) => Ok(config),
-        Err(e) => Err(e),
-    }
+/// Search the config file and report which file is used
+pub fn find_config_path(cli_path: Option<&Path>) -> PathBuf {
+    let location: ConfigLocation = config_location::find_config(cli_path);
+    output::print_status(&format!(
+        "Using config file: {} ({})",
+        location.path.display(),
+        location.source
+    ));
+    location.path
 }
 
-/// Create a new config where the cli args overwrite the config values
-pub fn adjust_runner_config(config: Config, run_args: &Vec<RunArg>) -> Config {
-    //Do not change the config on empty args
-    if run_args.is_empty() {
-        return config;
-    }
+/// Load the config from a specific path with an optional profile
+pub fn load_config(path: &Path, profile: Option<&str>) -> Result<Config, io::Error> {
+    toml_parser::load_config(path, profile)
+}
 
+/// Create a new config where the env variables and cli args overwrite the config values.
+/// The cli args have precedence over the env variables.
+pub fn adjust_runner_config(config: Config, run_args: &[RunArg]) -> Result<Config, io::Error> {
     //Config attributes that can be overwritten
-    let mut dry_run: bool = config.dry_run;
-    let mut missing_files_ok: bool = config.missing_files_ok;
-    let mut copy_truncate: bool = config.copy_truncate;
+    let mut adjusted_config: Config = config_env::apply_env_overrides(config)?;
 
     for arg in run_args.iter() {
         match arg {
-            RunArg::DryRun => dry_run = true,
-            RunArg::MissingFilesOk => missing_files_ok = true,
-            RunArg::Truncate => copy_truncate = true,
+            RunArg::DryRun => adjusted_config.dry_run = true,
+            RunArg::MissingFilesOk => adjusted_config.missing_files_ok = true,
+            RunArg::Truncate => {
+                //The argument applies to all files, also to entries that disable copy_truncate
+                adjusted_config.copy_truncate = true;
+
+                let entry_overrides = adjusted_config.files.iter_mut().map(|f| &mut f.overrides);
+                let group_overrides = adjusted_config.groups.iter_mut().map(|g| &mut g.overrides);
+                for overrides in entry_overrides.chain(group_overrides) {
+                    overrides.copy_truncate = None;
+                }
+            }
+            RunArg::OnError(policy) => adjusted_config.on_error = *policy,
+            RunArg::FailOn(fail_on) => adjusted_config.fail_on = *fail_on,
+            RunArg::Group(name) => {
+                if !adjusted_config.groups.iter().any(|g| &g.name == name) {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Unknown group for run argument '--group': '{}'", name),
+                    ));
+                }
+
+                adjusted_config.selected_groups.push(name.clone());
+            }
+            RunArg::Match(pattern) => adjusted_config.selected_patterns.push(pattern.clone()),
+            RunArg::Profile(_) => {} //Profiles are applied while loading the config
+            RunArg::Plan | RunArg::Output(_) | RunArg::Cron => {} //Only change the execution of the command
         }
     }
 
-    let adjusted_config: Config = Config {
-        dry_run,
-        mode: config.mode,
-        keep_rotate: config.keep_rotate,
-        missing_files_ok,
-        copy_truncate,
-        file_list: config.file_list,
-        retention: config.retention,
-    };
-
-    adjusted_config
+    Ok(adjusted_config)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{CleanUpMode, RetentionConfig};
+    use crate::config::{ErrorPolicy, FailOn, FileEntry, FileOverrides, FileSource};
 
     #[test]
     fn test_adjust_runner_config() {
-        let raw_config: Config = Config {
-            dry_run: false,
-            mode: CleanUpMode::FileSize,
-            keep_rotate: 3,
-            missing_files_ok: false,
-            copy_truncate: false,
-            file_list: vec!["/var/log/my_app.log".to_string()],
-            retention: RetentionConfig {
-                file_size_mib: 50,
-                last_write_h: 168,
+        let mut raw_config: Config = Config::test_config();
+        raw_config.file_list = vec!["/var/log/my_app.log".to_string()];
+        raw_config.files = vec![FileEntry {
+            source: FileSource::Path("/var/log/other.log".to_string()),
+            olddir: None,
+            group: None,
+            signal: None,
+            priority: None,
+            overrides: FileOverrides {
+                copy_truncate: Some(false),
+                ..FileOverrides::default()
             },
-        };
+        }];
 
-        let args: Vec<RunArg> = vec![RunArg::DryRun, RunArg::Truncate];
-        let adjusted_config = adjust_runner_config(raw_config, &args);
+        let args: Vec<RunArg> = vec![
+            RunArg::DryRun,
+            RunArg::Truncate,
+            RunArg::OnError(ErrorPolicy::Abort),
+            RunArg::FailOn(FailOn::Warn),
+        ];
+        let adjusted_config = adjust_runner_config(raw_config, &args).unwrap();
 
         assert_eq!(adjusted_config.dry_run, true);
         assert_eq!(adjusted_config.missing_files_ok, false);
         assert_eq!(adjusted_config.copy_truncate, true);
+        assert_eq!(adjusted_config.files[0].overrides.copy_truncate, None);
+        assert_eq!(adjusted_config.on_error, ErrorPolicy::Abort);
+        assert_eq!(adjusted_config.fail_on, FailOn::Warn);
     }
 }
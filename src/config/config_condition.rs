@@ -0,0 +1,407 @@
+//! Module for the condition expressions of file entries
+//!
+//! A condition like "size > 100MB && age > 2d" replaces the cleanup mode
+//! of a file entry. The expression is parsed into a tree when the config
+//! is loaded, so syntax errors are reported before any file is touched.
+//! The values of the fields are requested while the tree is evaluated,
+//! expensive fields like the line count are only read when they are used.
+//!
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+use crate::config::config_units;
+
+/// Enum representing the file properties that can be compared
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConditionField {
+    /// File size in bytes
+    Size,
+
+    /// Seconds since the last write
+    Age,
+
+    /// Seconds since the creation of the file
+    FileAge,
+
+    /// Number of lines
+    Lines,
+
+    /// Percentage of free space on the filesystem of the file
+    FreePercent,
+}
+
+/// Enum representing the comparison operators
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+}
+
+/// Node of a parsed condition expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Compare {
+        field: ConditionField,
+        op: CompareOp,
+
+        /// Value in the base unit of the field
+        value: u64,
+    },
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+/// Represents a parsed condition with its source text for logging
+#[derive(Debug, Clone)]
+pub struct ConditionExpr {
+    pub source: String,
+    pub root: Condition,
+}
+
+//Implement the Display trait
+impl fmt::Display for ConditionExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl ConditionField {
+    fn from_name(name: &str) -> Option<ConditionField> {
+        match name {
+            "size" => Some(ConditionField::Size),
+            "age" => Some(ConditionField::Age),
+            "file_age" => Some(ConditionField::FileAge),
+            "lines" => Some(ConditionField::Lines),
+            "free" => Some(ConditionField::FreePercent),
+            _ => None,
+        }
+    }
+
+    /// Convert a raw value like "100MB" into the base unit of the field.
+    /// Plain numbers are always read in the base unit.
+    fn parse_value(&self, raw: &str) -> Option<u64> {
+        if let Ok(number) = raw.parse::<u64>() {
+            return Some(number);
+        }
+
+        match self {
+            ConditionField::Size => config_units::parse_size_bytes(raw),
+            ConditionField::Age | ConditionField::FileAge => config_units::parse_duration_s(raw),
+            ConditionField::FreePercent => raw.strip_suffix('%')?.parse().ok(),
+            ConditionField::Lines => None,
+        }
+    }
+}
+
+impl CompareOp {
+    fn compare(&self, actual: u64, expected: u64) -> bool {
+        match self {
+            CompareOp::Greater => actual > expected,
+            CompareOp::GreaterEqual => actual >= expected,
+            CompareOp::Less => actual < expected,
+            CompareOp::LessEqual => actual <= expected,
+            CompareOp::Equal => actual == expected,
+            CompareOp::NotEqual => actual != expected,
+        }
+    }
+}
+
+impl Condition {
+    /// Evaluate the condition, the facts function returns the value of a field.
+    /// The compared value is passed to the facts function, so counting can stop early.
+    /// The operands are evaluated from left to right and skipped when the result is known.
+    pub fn evaluate(
+        &self,
+        facts: &mut dyn FnMut(ConditionField, u64) -> Result<u64, io::Error>,
+    ) -> Result<bool, io::Error> {
+        match self {
+            Condition::Compare { field, op, value } => {
+                let actual: u64 = facts(*field, *value)?;
+                Ok(op.compare(actual, *value))
+            }
+            Condition::And(left, right) => Ok(left.evaluate(facts)? && right.evaluate(facts)?),
+            Condition::Or(left, right) => Ok(left.evaluate(facts)? || right.evaluate(facts)?),
+            Condition::Not(inner) => Ok(!inner.evaluate(facts)?),
+        }
+    }
+}
+
+/// Token of a condition expression
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+/// Split the source of a condition into tokens
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut pos: usize = 0;
+
+    while pos < chars.len() {
+        let current: char = chars[pos];
+        let next: Option<char> = chars.get(pos + 1).copied();
+
+        let (token, length): (Token, usize) = match (current, next) {
+            (c, _) if c.is_whitespace() => {
+                pos += 1;
+                continue;
+            }
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('>', Some('=')) => (Token::Op(CompareOp::GreaterEqual), 2),
+            ('<', Some('=')) => (Token::Op(CompareOp::LessEqual), 2),
+            ('=', Some('=')) => (Token::Op(CompareOp::Equal), 2),
+            ('!', Some('=')) => (Token::Op(CompareOp::NotEqual), 2),
+            ('>', _) => (Token::Op(CompareOp::Greater), 1),
+            ('<', _) => (Token::Op(CompareOp::Less), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            (c, _) if c.is_ascii_alphanumeric() || c == '_' || c == '%' => {
+                let length: usize = chars[pos..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || **c == '_' || **c == '%')
+                    .count();
+
+                let word: String = chars[pos..pos + length].iter().collect();
+                (Token::Word(word), length)
+            }
+            (c, _) => return Err(format!("Unexpected character '{}'", c)),
+        };
+
+        tokens.push(token);
+        pos += length;
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser for the tokens of a condition.
+/// Grammar: or = and ('||' and)*, and = unary ('&&' unary)*,
+/// unary = '!' unary | '(' or ')' | field op value
+struct ConditionParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl ConditionParser {
+    fn next(&mut self) -> Option<Token> {
+        let token: Option<Token> = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn parse_or(&mut self) -> Result<Condition, String> {
+        let mut condition: Condition = self.parse_and()?;
+
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            condition = Condition::Or(Box::new(condition), Box::new(self.parse_and()?));
+        }
+
+        Ok(condition)
+    }
+
+    fn parse_and(&mut self) -> Result<Condition, String> {
+        let mut condition: Condition = self.parse_unary()?;
+
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            condition = Condition::And(Box::new(condition), Box::new(self.parse_unary()?));
+        }
+
+        Ok(condition)
+    }
+
+    fn parse_unary(&mut self) -> Result<Condition, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Condition::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let condition: Condition = self.parse_or()?;
+
+                match self.next() {
+                    Some(Token::RParen) => Ok(condition),
+                    _ => Err("Missing closing parenthesis".to_string()),
+                }
+            }
+            Some(Token::Word(name)) => {
+                let field: ConditionField = ConditionField::from_name(&name).ok_or(format!(
+                    "Unknown field '{}', expected size, age, file_age, lines or free",
+                    name
+                ))?;
+
+                let Some(Token::Op(op)) = self.next() else {
+                    return Err(format!("Expected comparison operator after '{}'", name));
+                };
+
+                let Some(Token::Word(raw_value)) = self.next() else {
+                    return Err(format!("Expected value after the operator of '{}'", name));
+                };
+
+                let value: u64 = field.parse_value(&raw_value).ok_or(format!(
+                    "Invalid value '{}' for field '{}'",
+                    raw_value, name
+                ))?;
+
+                Ok(Condition::Compare { field, op, value })
+            }
+            Some(token) => Err(format!("Unexpected token {:?}", token)),
+            None => Err("Unexpected end of the condition".to_string()),
+        }
+    }
+}
+
+/// Parse a condition expression like "size > 100MB && age > 2d"
+pub fn parse_condition(source: &str) -> Result<ConditionExpr, io::Error> {
+    let to_error = |message: String| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid condition '{}': {}", source, message),
+        )
+    };
+
+    let mut parser = ConditionParser {
+        tokens: tokenize(source).map_err(to_error)?,
+        pos: 0,
+    };
+
+    let root: Condition = parser.parse_or().map_err(to_error)?;
+
+    if let Some(token) = parser.peek() {
+        return Err(to_error(format!("Unexpected token {:?}", token)));
+    }
+
+    Ok(ConditionExpr {
+        source: source.to_string(),
+        root,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(source: &str, size: u64, age: u64) -> bool {
+        parse_condition(source)
+            .unwrap()
+            .root
+            .evaluate(&mut |field, _| match field {
+                ConditionField::Size => Ok(size),
+                ConditionField::Age => Ok(age),
+                _ => Ok(0),
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_condition() {
+        let condition = parse_condition("size > 100MB && age >= 2d").unwrap().root;
+
+        assert_eq!(
+            condition,
+            Condition::And(
+                Box::new(Condition::Compare {
+                    field: ConditionField::Size,
+                    op: CompareOp::Greater,
+                    value: 100_000_000,
+                }),
+                Box::new(Condition::Compare {
+                    field: ConditionField::Age,
+                    op: CompareOp::GreaterEqual,
+                    value: 172800,
+                }),
+            )
+        );
+
+        assert!(parse_condition("size >").is_err());
+        assert!(parse_condition("color > 5").is_err());
+        assert!(parse_condition("(size > 5").is_err());
+        assert!(parse_condition("size > 5 age > 1").is_err());
+        assert!(parse_condition("lines > 10MB").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_condition() {
+        assert!(evaluate("size > 1KB && age > 1h", 2000, 7200));
+        assert!(!evaluate("size > 1KB && age > 1h", 2000, 60));
+        assert!(evaluate("size > 1KB || age > 1h", 0, 7200));
+
+        //And binds stronger than or
+        assert!(evaluate("size > 10 || size > 1 && age > 1h", 20, 0));
+        assert!(!evaluate("(size > 10 || size > 1) && age > 1h", 20, 0));
+        assert!(evaluate("!(size < 5)", 20, 0));
+    }
+
+    #[test]
+    fn test_nested_condition() {
+        let source: &str = "!(size > 1KB && (age > 1h || !(size < 10))) || age == 5";
+
+        assert!(evaluate(source, 500, 0));
+        assert!(!evaluate(source, 2000, 7200));
+        assert!(evaluate(source, 2000, 5));
+        assert!(!evaluate("((((size > 1))))", 0, 0));
+
+        //Empty groups and operators without operands are rejected
+        assert!(parse_condition("").is_err());
+        assert!(parse_condition("()").is_err());
+        assert!(parse_condition("size > 1 && ()").is_err());
+        assert!(parse_condition("size > 1 ||").is_err());
+        assert!(parse_condition("!").is_err());
+    }
+
+    #[test]
+    fn test_short_circuit() {
+        let count_fields = |source: &str, size: u64| -> (bool, Vec<ConditionField>) {
+            let mut requested: Vec<ConditionField> = Vec::new();
+            let result: bool = parse_condition(source)
+                .unwrap()
+                .root
+                .evaluate(&mut |field, _| {
+                    requested.push(field);
+                    Ok(size)
+                })
+                .unwrap();
+            (result, requested)
+        };
+
+        //The right operand is skipped once the result is known
+        assert_eq!(
+            count_fields("size > 10 || lines > 5", 20),
+            (true, vec![ConditionField::Size])
+        );
+        assert_eq!(
+            count_fields("size > 10 && lines > 5", 0),
+            (false, vec![ConditionField::Size])
+        );
+        assert_eq!(
+            count_fields("size > 10 && lines > 5", 20),
+            (true, vec![ConditionField::Size, ConditionField::Lines])
+        );
+
+        //Errors of skipped operands are never raised
+        let result = parse_condition("size > 10 || lines > 5")
+            .unwrap()
+            .root
+            .evaluate(&mut |field, _| match field {
+                ConditionField::Size => Ok(20),
+                _ => Err(io::Error::other("Not readable")),
+            });
+        assert!(result.unwrap());
+    }
+}
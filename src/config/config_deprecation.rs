@@ -0,0 +1,241 @@
+//! Module for the registry of deprecated config keys and cli flags
+//!
+//! Renamed options are not removed at once. Every deprecated key or flag is
+//! registered with its replacement and the version that removes it. While
+//! the option is still supported, a warning is emitted and the value is
+//! moved to its replacement, so existing configs survive upgrades.
+//!
+use std::fmt;
+
+use crate::config::config_migrate;
+use crate::config::toml_parser::{Table, TopLevelTable, Value};
+
+/// Enum representing where a deprecated option is used
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OptionKind {
+    /// Config key, sub tables are separated by '.'
+    ConfigKey,
+
+    /// Argument of the cli
+    CliFlag,
+}
+
+/// Represents a registered deprecated option
+pub struct Deprecation {
+    pub kind: OptionKind,
+
+    /// Name of the deprecated key or flag
+    pub name: &'static str,
+
+    /// Name of the option that replaces the deprecated option
+    pub replacement: Option<&'static str>,
+
+    /// Yalc version that removes the support of the deprecated option
+    pub removal_version: &'static str,
+}
+
+/// All deprecated options that are still supported.
+/// Keys that are renamed by a schema migration are not registered here.
+pub const DEPRECATIONS: &[Deprecation] = &[];
+
+/// Represents the usage of a deprecated option
+#[derive(Debug, PartialEq)]
+pub struct DeprecationWarning {
+    pub kind: OptionKind,
+    pub name: String,
+    pub replacement: Option<String>,
+    pub removal_version: String,
+
+    /// When set to true the value was ignored, because the replacement is also set
+    pub ignored: bool,
+}
+
+//Implement the Display trait
+impl fmt::Display for DeprecationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind: &str = match self.kind {
+            OptionKind::ConfigKey => "Config key",
+            OptionKind::CliFlag => "Flag",
+        };
+
+        write!(f, "{} '{}' {}", kind, self.name, self.details())
+    }
+}
+
+impl DeprecationWarning {
+    fn new(deprecation: &Deprecation, ignored: bool) -> DeprecationWarning {
+        DeprecationWarning {
+            kind: deprecation.kind,
+            name: deprecation.name.to_string(),
+            replacement: deprecation.replacement.map(str::to_string),
+            removal_version: deprecation.removal_version.to_string(),
+            ignored,
+        }
+    }
+
+    /// Get the details of the deprecation without the name of the option
+    pub fn details(&self) -> String {
+        let details: String = format!(
+            "is deprecated and will be removed in version {}",
+            self.removal_version
+        );
+
+        match (&self.replacement, self.ignored) {
+            (Some(replacement), true) => format!(
+                "{}, the value is ignored because '{}' is set",
+                details, replacement
+            ),
+            (Some(replacement), false) => format!("{}, use '{}' instead", details, replacement),
+            (None, _) => details,
+        }
+    }
+}
+
+/// Move the values of deprecated keys to their replacements.
+/// Returns a warning for every deprecated key that is used in the table.
+pub fn apply_deprecated_keys(root: &mut TopLevelTable) -> Vec<DeprecationWarning> {
+    apply_keys_of(DEPRECATIONS, root)
+}
+
+/// Move the values of the deprecated keys of the registry to their replacements
+fn apply_keys_of(
+    deprecations: &[Deprecation],
+    root: &mut TopLevelTable,
+) -> Vec<DeprecationWarning> {
+    let mut warnings: Vec<DeprecationWarning> = Vec::new();
+
+    for deprecation in deprecations.iter() {
+        if deprecation.kind != OptionKind::ConfigKey || !contains_key(root, deprecation.name) {
+            continue;
+        }
+
+        let ignored: bool = match deprecation.replacement {
+            Some(replacement) if contains_key(root, replacement) => {
+                config_migrate::remove_value(root, deprecation.name);
+                true
+            }
+            Some(replacement) => {
+                //Keys that can not be moved are kept, the parser reports the missing replacement
+                config_migrate::rename_key(root, deprecation.name, replacement).is_err()
+            }
+            None => false,
+        };
+
+        warnings.push(DeprecationWarning::new(deprecation, ignored));
+    }
+
+    warnings
+}
+
+/// Replace a deprecated cli flag with its replacement.
+/// Returns the flag that is parsed and the warning when the flag is deprecated.
+pub fn replace_deprecated_flag(flag: &str) -> (String, Option<DeprecationWarning>) {
+    replace_flag_of(DEPRECATIONS, flag)
+}
+
+/// Replace a cli flag that is deprecated by the registry
+fn replace_flag_of(
+    deprecations: &[Deprecation],
+    flag: &str,
+) -> (String, Option<DeprecationWarning>) {
+    match deprecations
+        .iter()
+        .find(|d| d.kind == OptionKind::CliFlag && d.name == flag)
+    {
+        Some(deprecation) => (
+            deprecation.replacement.unwrap_or(flag).to_string(),
+            Some(DeprecationWarning::new(deprecation, false)),
+        ),
+        None => (flag.to_string(), None),
+    }
+}
+
+/// Returns true when the key exists, sub tables are separated by '.'
+fn contains_key(root: &Table, key: &str) -> bool {
+    let mut current_table: &Table = root;
+    let key_parts: Vec<&str> = key.split('.').collect();
+
+    for (idx, part) in key_parts.iter().enumerate() {
+        match current_table.get(*part) {
+            Some(_) if idx == key_parts.len() - 1 => return true,
+            Some(Value::Table(table)) => current_table = table,
+            _ => return false,
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Registry of the tests, the real registry can be empty
+    const TEST_DEPRECATIONS: &[Deprecation] = &[
+        Deprecation {
+            kind: OptionKind::ConfigKey,
+            name: "retention.file_size_kb",
+            replacement: Some("retention.file_size_mib"),
+            removal_version: "0.9.0",
+        },
+        Deprecation {
+            kind: OptionKind::CliFlag,
+            name: "--dryrun",
+            replacement: Some("--dry-run"),
+            removal_version: "0.9.0",
+        },
+    ];
+
+    fn create_retention(keys: &[(&str, i64)]) -> TopLevelTable {
+        let mut retention: Table = HashMap::new();
+
+        for (key, value) in keys.iter() {
+            retention.insert(key.to_string(), Value::Integer(*value));
+        }
+
+        let mut root: TopLevelTable = HashMap::new();
+        root.insert("retention".to_string(), Value::Table(retention));
+        root
+    }
+
+    #[test]
+    fn test_deprecated_key_is_moved() {
+        let mut root = create_retention(&[("file_size_kb", 10)]);
+        let warnings = apply_keys_of(TEST_DEPRECATIONS, &mut root);
+
+        assert_eq!(warnings.len(), 1);
+        assert!(!warnings[0].ignored);
+        assert!(contains_key(&root, "retention.file_size_mib"));
+        assert!(!contains_key(&root, "retention.file_size_kb"));
+    }
+
+    #[test]
+    fn test_deprecated_key_with_replacement() {
+        let mut root = create_retention(&[("file_size_kb", 10), ("file_size_mib", 20)]);
+        let warnings = apply_keys_of(TEST_DEPRECATIONS, &mut root);
+
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].ignored);
+        assert!(!contains_key(&root, "retention.file_size_kb"));
+
+        //Configs without deprecated keys have no warnings
+        assert!(apply_keys_of(TEST_DEPRECATIONS, &mut root).is_empty());
+    }
+
+    #[test]
+    fn test_deprecated_flag() {
+        let (flag, warning) = replace_flag_of(TEST_DEPRECATIONS, "--dryrun");
+        assert_eq!(flag, "--dry-run");
+        assert_eq!(
+            warning.unwrap().to_string(),
+            "Flag '--dryrun' is deprecated and will be removed in version 0.9.0, use '--dry-run' instead"
+        );
+
+        //Flags that are not deprecated are kept without a warning
+        assert_eq!(
+            replace_flag_of(TEST_DEPRECATIONS, "--dry-run"),
+            ("--dry-run".to_string(), None)
+        );
+    }
+}
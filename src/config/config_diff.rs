@@ -0,0 +1,591 @@
+//! Module for comparing two yalc configs
+//!
+//! When the config is reloaded, the changed values are logged. Every config
+//! value is flattened into a key with a printable value, so two configs can
+//! be compared without comparing the nested structs.
+//!
+use std::fmt;
+
+use crate::config::{Config, FileOverrides};
+
+/// Represents a config value that differs between two configs
+#[derive(Debug, PartialEq)]
+pub struct ConfigChange {
+    /// Config key of the changed value, sub tables are separated by '.'
+    pub key: String,
+
+    /// Printable value of the old config, None when the key was added
+    pub old_value: Option<String>,
+
+    /// Printable value of the new config, None when the key was removed
+    pub new_value: Option<String>,
+}
+
+//Implement the Display trait
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.old_value, &self.new_value) {
+            (Some(old), Some(new)) => write!(f, "{}: {} -> {}", self.key, old, new),
+            (None, Some(new)) => write!(f, "{}: added {}", self.key, new),
+            (Some(old), None) => write!(f, "{}: removed {}", self.key, old),
+            (None, None) => write!(f, "{}: unchanged", self.key),
+        }
+    }
+}
+
+/// Compare two configs and return all changed values in key order
+pub fn diff_configs(old: &Config, new: &Config) -> Vec<ConfigChange> {
+    let old_values: Vec<(String, String)> = flatten_config(old);
+    let new_values: Vec<(String, String)> = flatten_config(new);
+    let mut changes: Vec<ConfigChange> = Vec::new();
+
+    for (key, old_value) in old_values.iter() {
+        let new_value: Option<&String> = find_value(&new_values, key);
+
+        if new_value != Some(old_value) {
+            changes.push(ConfigChange {
+                key: key.clone(),
+                old_value: Some(old_value.clone()),
+                new_value: new_value.cloned(),
+            });
+        }
+    }
+
+    //Keys that only exist in the new config, like additional list entries
+    for (key, new_value) in new_values.iter() {
+        if find_value(&old_values, key).is_none() {
+            changes.push(ConfigChange {
+                key: key.clone(),
+                old_value: None,
+                new_value: Some(new_value.clone()),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Find the value of a key in the flattened config values
+fn find_value<'a>(values: &'a [(String, String)], key: &str) -> Option<&'a String> {
+    values.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+/// Format an optional value, missing values are shown as 'none'
+fn format_optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or(String::from("none"), |v| v.to_string())
+}
+
+/// Convert all values of the config to keys with printable values
+fn flatten_config(config: &Config) -> Vec<(String, String)> {
+    let mut values: Vec<(String, String)> = vec![
+        ("dry_run".to_string(), config.dry_run.to_string()),
+        ("mode".to_string(), format!("{:?}", config.mode)),
+        ("action".to_string(), format!("{:?}", config.action)),
+        ("keep_rotate".to_string(), config.keep_rotate.to_string()),
+        ("rotate_start".to_string(), config.rotate_start.to_string()),
+        (
+            "compact_rotations".to_string(),
+            config.compact_rotations.to_string(),
+        ),
+        (
+            "remove_stray_archives".to_string(),
+            config.remove_stray_archives.to_string(),
+        ),
+        (
+            "dedup_archives".to_string(),
+            config.dedup_archives.to_string(),
+        ),
+        (
+            "allocated_size".to_string(),
+            config.allocated_size.to_string(),
+        ),
+        (
+            "remove_empty_dirs".to_string(),
+            config.remove_empty_dirs.to_string(),
+        ),
+        (
+            "missing_files_ok".to_string(),
+            config.missing_files_ok.to_string(),
+        ),
+        (
+            "copy_truncate".to_string(),
+            config.copy_truncate.to_string(),
+        ),
+        (
+            "copy_truncate_lock".to_string(),
+            config.copy_truncate_lock.to_string(),
+        ),
+        ("shred".to_string(), config.shred.to_string()),
+        ("manifest".to_string(), config.manifest.to_string()),
+        (
+            "allow_empty_file_list".to_string(),
+            config.allow_empty_file_list.to_string(),
+        ),
+        ("rotate_empty".to_string(), config.rotate_empty.to_string()),
+        (
+            "skip_unchanged".to_string(),
+            config.skip_unchanged.to_string(),
+        ),
+        (
+            "compression".to_string(),
+            format!("{:?}", config.compression),
+        ),
+        (
+            "compression_level".to_string(),
+            format_optional(config.compression_level),
+        ),
+        (
+            "delay_compress".to_string(),
+            config.delay_compress.to_string(),
+        ),
+        (
+            "defer_growing".to_string(),
+            config.defer_growing.to_string(),
+        ),
+        (
+            "max_archive_age_s".to_string(),
+            format_optional(config.max_archive_age_s),
+        ),
+        (
+            "max_archive_size_bytes".to_string(),
+            format_optional(config.max_archive_size_bytes),
+        ),
+        (
+            "total_budget_bytes".to_string(),
+            format_optional(config.total_budget_bytes),
+        ),
+        (
+            "max_bytes_per_run".to_string(),
+            format_optional(config.max_bytes_per_run),
+        ),
+        ("io_buffer".to_string(), config.io_buffer_bytes.to_string()),
+        (
+            "io_limit".to_string(),
+            format_optional(config.io_limit_bytes_per_s),
+        ),
+        (
+            "frequency".to_string(),
+            format_optional(config.frequency.map(|f| format!("{:?}", f))),
+        ),
+        (
+            "stale_after_s".to_string(),
+            format_optional(config.stale_after_s),
+        ),
+        (
+            "unmanaged_min_size_bytes".to_string(),
+            format_optional(config.unmanaged_min_size_bytes),
+        ),
+        (
+            "state_file".to_string(),
+            format_optional(config.state_file.as_ref()),
+        ),
+        (
+            "stats_max_size".to_string(),
+            config.stats_max_size_bytes.to_string(),
+        ),
+        ("on_error".to_string(), format!("{:?}", config.on_error)),
+        ("fail_on".to_string(), format!("{:?}", config.fail_on)),
+        ("log_level".to_string(), format!("{:?}", config.log_level)),
+        (
+            "log_file".to_string(),
+            format_optional(config.log_file.as_ref()),
+        ),
+        (
+            "log_file_max_size".to_string(),
+            config.log_file_max_size_bytes.to_string(),
+        ),
+        ("symlinks".to_string(), format!("{:?}", config.symlinks)),
+        ("open_files".to_string(), format!("{:?}", config.open_files)),
+        (
+            "lock_file".to_string(),
+            format_optional(config.lock_file.as_ref()),
+        ),
+        (
+            "audit_log".to_string(),
+            format_optional(config.audit_log.as_ref()),
+        ),
+        ("lock_wait".to_string(), format_optional(config.lock_wait_s)),
+    ];
+
+    //List entries are compared by their position in the list
+    for (idx, pattern) in config.redact.iter().enumerate() {
+        values.push((format!("redact[{}]", idx + 1), pattern.to_string()));
+    }
+
+    for (idx, root) in config.allowed_roots.iter().enumerate() {
+        values.push((format!("allowed_roots[{}]", idx + 1), root.clone()));
+    }
+
+    for (idx, path) in config.file_list.iter().enumerate() {
+        values.push((format!("file_list[{}]", idx + 1), path.clone()));
+    }
+
+    for (idx, entry) in config.files.iter().enumerate() {
+        values.push((format!("files[{}]", idx + 1), entry.source.to_string()));
+        values.push((
+            format!("files[{}].olddir", idx + 1),
+            format_optional(entry.olddir.as_ref()),
+        ));
+        values.push((
+            format!("files[{}].group", idx + 1),
+            format_optional(entry.group.as_ref()),
+        ));
+        values.push((
+            format!("files[{}].priority", idx + 1),
+            format_optional(entry.priority),
+        ));
+        values.push((
+            format!("files[{}].signal", idx + 1),
+            format_optional(
+                entry
+                    .signal
+                    .as_ref()
+                    .map(|t| format!("{} {}", t.signal, t.pidfile)),
+            ),
+        ));
+
+        flatten_overrides(
+            &mut values,
+            &format!("files[{}]", idx + 1),
+            &entry.overrides,
+        );
+    }
+
+    for group in config.groups.iter() {
+        for (idx, path) in group.paths.iter().enumerate() {
+            values.push((
+                format!("group.{}.paths[{}]", group.name, idx + 1),
+                path.clone(),
+            ));
+        }
+        flatten_overrides(
+            &mut values,
+            &format!("group.{}", group.name),
+            &group.overrides,
+        );
+        values.push((
+            format!("group.{}.priority", group.name),
+            format_optional(group.priority),
+        ));
+        values.push((
+            format!("group.{}.max_total_size", group.name),
+            format_optional(group.max_total_size_bytes),
+        ));
+        values.push((
+            format!("group.{}.prerotate", group.name),
+            format_optional(group.prerotate.as_ref()),
+        ));
+        values.push((
+            format!("group.{}.postrotate", group.name),
+            format_optional(group.postrotate.as_ref()),
+        ));
+    }
+
+    values.push((
+        "retention.file_size_mib".to_string(),
+        config.retention.file_size_mib.to_string(),
+    ));
+    values.push((
+        "retention.last_write_h".to_string(),
+        config.retention.last_write_h.to_string(),
+    ));
+    values.push((
+        "retention.min_free_percent".to_string(),
+        format_optional(config.retention.min_free_percent),
+    ));
+    values.push((
+        "retention.min_size_bytes".to_string(),
+        format_optional(config.retention.min_size_bytes),
+    ));
+    values.push((
+        "retention.min_age_s".to_string(),
+        format_optional(config.retention.min_age_s),
+    ));
+    values.push((
+        "retention.max_lines".to_string(),
+        format_optional(config.retention.max_lines),
+    ));
+    values.push((
+        "retention.file_age_h".to_string(),
+        format_optional(config.retention.file_age_h),
+    ));
+    values.push((
+        "trim.keep_lines".to_string(),
+        format_optional(config.trim.keep_lines),
+    ));
+    values.push((
+        "trim.keep_bytes".to_string(),
+        format_optional(config.trim.keep_bytes),
+    ));
+    values.push((
+        "trim.max_age_s".to_string(),
+        format_optional(config.trim.max_age_s),
+    ));
+    values.push((
+        "trim.timestamp_format".to_string(),
+        format_optional(config.trim.timestamp_format.as_ref()),
+    ));
+    values.push((
+        "retry.attempts".to_string(),
+        config.retry.attempts.to_string(),
+    ));
+    values.push((
+        "retry.backoff_ms".to_string(),
+        config.retry.backoff_ms.to_string(),
+    ));
+    values.push((
+        "priority.nice".to_string(),
+        format_optional(config.priority.nice),
+    ));
+    values.push((
+        "priority.io_class".to_string(),
+        format_optional(config.priority.io_class.map(|c| format!("{:?}", c))),
+    ));
+    values.push((
+        "priority.io_level".to_string(),
+        format_optional(config.priority.io_level),
+    ));
+    values.push((
+        "syslog.enabled".to_string(),
+        config.syslog.enabled.to_string(),
+    ));
+    values.push((
+        "syslog.facility".to_string(),
+        format!("{:?}", config.syslog.facility),
+    ));
+    values.push((
+        "syslog.priority".to_string(),
+        format!("{:?}", config.syslog.priority),
+    ));
+    values.push((
+        "daemon.interval_s".to_string(),
+        config.daemon.interval_s.to_string(),
+    ));
+    values.push((
+        "daemon.reload".to_string(),
+        config.daemon.reload.to_string(),
+    ));
+    values.push((
+        "daemon.status_listen".to_string(),
+        format_optional(config.daemon.status_listen),
+    ));
+    values.push((
+        "upload.location".to_string(),
+        format_optional(config.upload.as_ref().map(|u| u.backend.location())),
+    ));
+    values.push((
+        "upload.delete_local".to_string(),
+        format_optional(config.upload.as_ref().map(|u| u.delete_local)),
+    ));
+    values.push((
+        "upload.name".to_string(),
+        format_optional(config.upload.as_ref().map(|u| u.name_template.clone())),
+    ));
+    values.push((
+        "notify.email.server".to_string(),
+        format_optional(config.notify.email.as_ref().map(|e| e.server.clone())),
+    ));
+    values.push((
+        "notify.email.from".to_string(),
+        format_optional(config.notify.email.as_ref().map(|e| e.from.clone())),
+    ));
+    values.push((
+        "notify.email.to".to_string(),
+        format_optional(config.notify.email.as_ref().map(|e| e.to.join(", "))),
+    ));
+    values.push((
+        "notify.email.on".to_string(),
+        format_optional(
+            config
+                .notify
+                .email
+                .as_ref()
+                .map(|e| format!("{:?}", e.filter.on)),
+        ),
+    ));
+    values.push((
+        "notify.email.events".to_string(),
+        format_optional(
+            config
+                .notify
+                .email
+                .as_ref()
+                .map(|e| format!("{:?}", e.filter.events)),
+        ),
+    ));
+    //The webhook url contains the secret of the webhook, so only its presence is compared
+    values.push((
+        "notify.slack".to_string(),
+        config.notify.slack.is_some().to_string(),
+    ));
+    values.push((
+        "notify.slack.on".to_string(),
+        format_optional(
+            config
+                .notify
+                .slack
+                .as_ref()
+                .map(|s| format!("{:?}", s.filter.on)),
+        ),
+    ));
+    values.push((
+        "notify.slack.events".to_string(),
+        format_optional(
+            config
+                .notify
+                .slack
+                .as_ref()
+                .map(|s| format!("{:?}", s.filter.events)),
+        ),
+    ));
+    values.push((
+        "notify.matrix.homeserver".to_string(),
+        format_optional(config.notify.matrix.as_ref().map(|m| m.homeserver.clone())),
+    ));
+    values.push((
+        "notify.matrix.room_id".to_string(),
+        format_optional(config.notify.matrix.as_ref().map(|m| m.room_id.clone())),
+    ));
+    values.push((
+        "notify.matrix.on".to_string(),
+        format_optional(
+            config
+                .notify
+                .matrix
+                .as_ref()
+                .map(|m| format!("{:?}", m.filter.on)),
+        ),
+    ));
+    values.push((
+        "notify.matrix.events".to_string(),
+        format_optional(
+            config
+                .notify
+                .matrix
+                .as_ref()
+                .map(|m| format!("{:?}", m.filter.events)),
+        ),
+    ));
+
+    values
+}
+
+/// Add the rotation settings of a file entry or a group, the prefix is the table of the keys
+fn flatten_overrides(values: &mut Vec<(String, String)>, prefix: &str, overrides: &FileOverrides) {
+    values.push((
+        format!("{}.dry_run", prefix),
+        format_optional(overrides.dry_run),
+    ));
+    values.push((
+        format!("{}.mode", prefix),
+        format_optional(overrides.mode.map(|m| format!("{:?}", m))),
+    ));
+    values.push((
+        format!("{}.keep_rotate", prefix),
+        format_optional(overrides.keep_rotate),
+    ));
+    values.push((
+        format!("{}.rotate_start", prefix),
+        format_optional(overrides.rotate_start),
+    ));
+    values.push((
+        format!("{}.copy_truncate", prefix),
+        format_optional(overrides.copy_truncate),
+    ));
+    values.push((
+        format!("{}.file_size_mib", prefix),
+        format_optional(overrides.file_size_mib),
+    ));
+    values.push((
+        format!("{}.last_write_h", prefix),
+        format_optional(overrides.last_write_h),
+    ));
+    values.push((
+        format!("{}.max_archive_age_s", prefix),
+        format_optional(overrides.max_archive_age_s),
+    ));
+    values.push((
+        format!("{}.max_archive_size_bytes", prefix),
+        format_optional(overrides.max_archive_size_bytes),
+    ));
+    values.push((
+        format!("{}.condition", prefix),
+        format_optional(overrides.condition.as_ref()),
+    ));
+    values.push((
+        format!("{}.frequency", prefix),
+        format_optional(overrides.frequency.map(|f| format!("{:?}", f))),
+    ));
+    values.push((
+        format!("{}.symlinks", prefix),
+        format_optional(overrides.symlinks.map(|s| format!("{:?}", s))),
+    ));
+    values.push((
+        format!("{}.shred", prefix),
+        format_optional(overrides.shred),
+    ));
+    values.push((
+        format!("{}.manifest", prefix),
+        format_optional(overrides.manifest),
+    ));
+    for (pattern_idx, pattern) in overrides.redact.iter().flatten().enumerate() {
+        values.push((
+            format!("{}.redact[{}]", prefix, pattern_idx + 1),
+            pattern.to_string(),
+        ));
+    }
+    values.push((
+        format!("{}.action", prefix),
+        format_optional(overrides.action.map(|a| format!("{:?}", a))),
+    ));
+    values.push((
+        format!("{}.trim_keep_lines", prefix),
+        format_optional(overrides.trim_keep_lines),
+    ));
+    values.push((
+        format!("{}.trim_keep_bytes", prefix),
+        format_optional(overrides.trim_keep_bytes),
+    ));
+    values.push((
+        format!("{}.trim_max_age_s", prefix),
+        format_optional(overrides.trim_max_age_s),
+    ));
+    values.push((
+        format!("{}.trim_timestamp_format", prefix),
+        format_optional(overrides.trim_timestamp_format.as_ref()),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CleanUpMode;
+
+    #[test]
+    fn test_diff_equal_configs() {
+        assert!(diff_configs(&Config::test_config(), &Config::test_config()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_changed_values() {
+        let old = Config::test_config();
+        let mut new = Config::test_config();
+        new.keep_rotate = 5;
+        new.mode = CleanUpMode::AnyOf;
+        new.file_list.push("/var/log/b.log".to_string());
+
+        let changes: Vec<String> = diff_configs(&old, &new)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+
+        assert_eq!(
+            changes,
+            vec![
+                "mode: FileSize -> AnyOf",
+                "keep_rotate: 3 -> 5",
+                "file_list[2]: added /var/log/b.log"
+            ]
+        );
+    }
+}
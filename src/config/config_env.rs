@@ -0,0 +1,149 @@
+//! Module for the yalc environment variable overrides
+//!
+//! Config values can be overwritten by environment variables with the
+//! 'YALC_' prefix. This is useful for container deployments where editing
+//! the config file is awkward. The env values are applied after parsing
+//! the config file and before the cli args are applied.
+//!
+use std::env;
+use std::io::{self, ErrorKind};
+
+use crate::config::{CleanUpMode, Config};
+
+/// Env variable for the path of the config file
+pub const ENV_CONFIG: &str = "YALC_CONFIG";
+
+/// Env variable for the name of the selected config profile
+pub const ENV_PROFILE: &str = "YALC_PROFILE";
+
+/// Env variable to overwrite the config value 'dry_run'
+pub const ENV_DRY_RUN: &str = "YALC_DRY_RUN";
+
+/// Env variable to overwrite the config value 'mode'
+pub const ENV_MODE: &str = "YALC_MODE";
+
+/// Env variable to overwrite the config value 'keep_rotate'
+pub const ENV_KEEP_ROTATE: &str = "YALC_KEEP_ROTATE";
+
+/// Env variable to overwrite the config value 'missing_files_ok'
+pub const ENV_MISSING_FILES_OK: &str = "YALC_MISSING_FILES_OK";
+
+/// Env variable to overwrite the config value 'copy_truncate'
+pub const ENV_COPY_TRUNCATE: &str = "YALC_COPY_TRUNCATE";
+
+/// Env variable to overwrite the config value 'retention.file_size_mib'
+pub const ENV_FILE_SIZE_MIB: &str = "YALC_FILE_SIZE_MIB";
+
+/// Env variable to overwrite the config value 'retention.last_write_h'
+pub const ENV_LAST_WRITE_H: &str = "YALC_LAST_WRITE_H";
+
+/// Get the name of the config profile selected by the env variable
+pub fn profile() -> Option<String> {
+    env::var(ENV_PROFILE).ok().filter(|name| !name.is_empty())
+}
+
+/// Apply the overrides of the process environment variables to the config
+pub fn apply_env_overrides(config: Config) -> Result<Config, io::Error> {
+    apply_overrides(config, |name| env::var(name).ok())
+}
+
+/// Apply the overrides to the config, the lookup function returns the value of a variable
+fn apply_overrides<F>(mut config: Config, lookup: F) -> Result<Config, io::Error>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if let Some(value) = lookup(ENV_DRY_RUN) {
+        config.dry_run = parse_env_bool(ENV_DRY_RUN, &value)?;
+    }
+
+    if let Some(value) = lookup(ENV_MODE) {
+        config.mode = value
+            .parse::<CleanUpMode>()
+            .map_err(|e| env_error(ENV_MODE, &e.to_string()))?;
+    }
+
+    if let Some(value) = lookup(ENV_KEEP_ROTATE) {
+        config.keep_rotate = parse_env_uint(ENV_KEEP_ROTATE, &value)?;
+    }
+
+    if let Some(value) = lookup(ENV_MISSING_FILES_OK) {
+        config.missing_files_ok = parse_env_bool(ENV_MISSING_FILES_OK, &value)?;
+    }
+
+    if let Some(value) = lookup(ENV_COPY_TRUNCATE) {
+        config.copy_truncate = parse_env_bool(ENV_COPY_TRUNCATE, &value)?;
+    }
+
+    if let Some(value) = lookup(ENV_FILE_SIZE_MIB) {
+        config.retention.file_size_mib = parse_env_uint(ENV_FILE_SIZE_MIB, &value)?;
+    }
+
+    if let Some(value) = lookup(ENV_LAST_WRITE_H) {
+        config.retention.last_write_h = parse_env_uint(ENV_LAST_WRITE_H, &value)?;
+    }
+
+    Ok(config)
+}
+
+/// Parse the boolean value of an env variable
+fn parse_env_bool(name: &str, value: &str) -> Result<bool, io::Error> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Ok(true),
+        "false" | "0" | "no" | "off" => Ok(false),
+        _ => Err(env_error(name, "Expected boolean value")),
+    }
+}
+
+/// Parse the unsigned integer value of an env variable
+fn parse_env_uint(name: &str, value: &str) -> Result<u64, io::Error> {
+    value
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| env_error(name, "Expected unsigned integer value"))
+}
+
+/// Create the error for an invalid env variable value
+fn env_error(name: &str, message: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidInput,
+        format!("Invalid value for env variable '{}': {}", name, message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_apply_overrides() {
+        let mut vars: HashMap<&str, &str> = HashMap::new();
+        vars.insert(ENV_DRY_RUN, "yes");
+        vars.insert(ENV_MODE, "lastwrite");
+        vars.insert(ENV_KEEP_ROTATE, "7");
+        vars.insert(ENV_FILE_SIZE_MIB, " 20 ");
+
+        let config = apply_overrides(Config::test_config(), |name| {
+            vars.get(name).map(|v| v.to_string())
+        })
+        .unwrap();
+
+        assert!(config.dry_run);
+        assert!(matches!(config.mode, CleanUpMode::LastWrite));
+        assert_eq!(config.keep_rotate, 7);
+        assert_eq!(config.retention.file_size_mib, 20);
+
+        //Values without env variable are not changed
+        assert!(!config.copy_truncate);
+        assert_eq!(config.retention.last_write_h, 168);
+    }
+
+    #[test]
+    fn test_invalid_override() {
+        let res = apply_overrides(Config::test_config(), |name| {
+            (name == ENV_KEEP_ROTATE).then(|| "-1".to_string())
+        });
+
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! Module for finding the location of the config file
+//!
+//! When no config path is provided via the cli, multiple locations are
+//! searched. This allows non-root users to use yalc with a config file
+//! in their home directory. The first existing config file is used.
+//!
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::config::config_env::ENV_CONFIG;
+use crate::constants::DEFAULT_CONFIG_PATH;
+use crate::platform;
+
+/// Path of the config file relative to a user config directory
+const USER_CONFIG_FILE: &str = "yalc/yalc.toml";
+
+/// Enum representing where the path of the config file comes from
+#[derive(Debug, PartialEq)]
+pub enum ConfigSource {
+    /// Path provided via the '--config' cli option
+    CliArg,
+
+    /// Path provided via the 'YALC_CONFIG' env variable
+    EnvVar,
+
+    /// Config file in the directory of '$XDG_CONFIG_HOME'
+    XdgConfigHome,
+
+    /// Config file in the directory '~/.config'
+    HomeConfig,
+
+    /// System wide default config file
+    System,
+}
+
+//Implement the Display trait
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::CliArg => write!(f, "--config option"),
+            ConfigSource::EnvVar => write!(f, "${}", ENV_CONFIG),
+            ConfigSource::XdgConfigHome => write!(f, "$XDG_CONFIG_HOME"),
+            ConfigSource::HomeConfig => write!(f, "home directory"),
+            ConfigSource::System => write!(f, "system default"),
+        }
+    }
+}
+
+/// Represents the path of the config file and where it was found
+#[derive(Debug)]
+pub struct ConfigLocation {
+    pub path: PathBuf,
+    pub source: ConfigSource,
+}
+
+/// Find the config file that should be loaded.
+///
+/// The locations are searched in this order:
+/// 1. The path of the '--config' cli option
+/// 2. The path of the 'YALC_CONFIG' env variable
+/// 3. '$XDG_CONFIG_HOME/yalc/yalc.toml' if the file exists
+/// 4. '~/.config/yalc/yalc.toml' if the file exists
+/// 5. '/etc/yalc.toml'
+pub fn find_config(cli_path: Option<&Path>) -> ConfigLocation {
+    find_config_with(cli_path, |name| env::var(name).ok(), |path| path.is_file())
+}
+
+/// Get the location where 'config init' should create a new config file.
+/// Without explicit path the system config is used for root and the
+/// config of the home directory for all other users.
+pub fn init_config_location(cli_path: Option<&Path>) -> ConfigLocation {
+    let lookup = |name: &str| env::var(name).ok();
+
+    if let Some(location) = explicit_location(cli_path, lookup) {
+        return location;
+    }
+
+    if !platform::is_root_user() {
+        let user_locations = user_locations(lookup);
+
+        if let Some(location) = user_locations.into_iter().next() {
+            return location;
+        }
+    }
+
+    system_location()
+}
+
+/// Find the config file with a custom env lookup and file check
+fn find_config_with<L, E>(cli_path: Option<&Path>, lookup: L, exists: E) -> ConfigLocation
+where
+    L: Fn(&str) -> Option<String>,
+    E: Fn(&Path) -> bool,
+{
+    if let Some(location) = explicit_location(cli_path, &lookup) {
+        return location;
+    }
+
+    user_locations(&lookup)
+        .into_iter()
+        .find(|location| exists(&location.path))
+        .unwrap_or_else(system_location)
+}
+
+/// Get the explicitly configured location of the cli or env variable
+fn explicit_location<L>(cli_path: Option<&Path>, lookup: L) -> Option<ConfigLocation>
+where
+    L: Fn(&str) -> Option<String>,
+{
+    if let Some(path) = cli_path {
+        return Some(ConfigLocation {
+            path: path.to_path_buf(),
+            source: ConfigSource::CliArg,
+        });
+    }
+
+    match lookup(ENV_CONFIG) {
+        Some(path) if !path.is_empty() => Some(ConfigLocation {
+            path: PathBuf::from(path),
+            source: ConfigSource::EnvVar,
+        }),
+        _ => None,
+    }
+}
+
+/// Get the config locations of the user directories in the order they are searched
+fn user_locations<L>(lookup: L) -> Vec<ConfigLocation>
+where
+    L: Fn(&str) -> Option<String>,
+{
+    let mut locations: Vec<ConfigLocation> = Vec::new();
+
+    if let Some(xdg_dir) = lookup("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        locations.push(ConfigLocation {
+            path: Path::new(&xdg_dir).join(USER_CONFIG_FILE),
+            source: ConfigSource::XdgConfigHome,
+        });
+    }
+
+    if let Some(home_dir) = lookup("HOME").filter(|dir| !dir.is_empty()) {
+        locations.push(ConfigLocation {
+            path: Path::new(&home_dir).join(".config").join(USER_CONFIG_FILE),
+            source: ConfigSource::HomeConfig,
+        });
+    }
+
+    locations
+}
+
+/// Get the location of the system wide config file
+fn system_location() -> ConfigLocation {
+    ConfigLocation {
+        path: PathBuf::from(DEFAULT_CONFIG_PATH),
+        source: ConfigSource::System,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_find_config_order() {
+        let mut vars: HashMap<&str, &str> = HashMap::new();
+        vars.insert("XDG_CONFIG_HOME", "/home/user/.xdg");
+        vars.insert("HOME", "/home/user");
+        let lookup = |name: &str| vars.get(name).map(|v| v.to_string());
+
+        //Nothing exists, the system config is used
+        let location = find_config_with(None, lookup, |_| false);
+        assert_eq!(location.source, ConfigSource::System);
+        assert_eq!(location.path, PathBuf::from(DEFAULT_CONFIG_PATH));
+
+        //The home config exists
+        let location = find_config_with(None, lookup, |path| {
+            path == Path::new("/home/user/.config/yalc/yalc.toml")
+        });
+        assert_eq!(location.source, ConfigSource::HomeConfig);
+
+        //Both user configs exist, the xdg config has precedence
+        let location = find_config_with(None, lookup, |_| true);
+        assert_eq!(location.source, ConfigSource::XdgConfigHome);
+        assert_eq!(
+            location.path,
+            PathBuf::from("/home/user/.xdg/yalc/yalc.toml")
+        );
+
+        //The cli path has precedence over all other locations
+        let location = find_config_with(Some(Path::new("/tmp/yalc.toml")), lookup, |_| true);
+        assert_eq!(location.source, ConfigSource::CliArg);
+    }
+
+    #[test]
+    fn test_find_config_env_var() {
+        let lookup = |name: &str| (name == ENV_CONFIG).then(|| "/opt/yalc.toml".to_string());
+        let location = find_config_with(None, lookup, |_| true);
+
+        assert_eq!(location.source, ConfigSource::EnvVar);
+        assert_eq!(location.path, PathBuf::from("/opt/yalc.toml"));
+    }
+}
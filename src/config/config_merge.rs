@@ -0,0 +1,510 @@
+//! Module for merging multiple toml config files
+//!
+//! Provides logic for resolving config includes and merging the parsed
+//! tables of several files into a single top level table. This allows
+//! config fragments to be managed independently of the main config.
+//! Files with the extension '.yaml' or '.yml' are parsed as yaml files.
+//!
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::config::toml_parser::{self, Table, TopLevelTable, Value};
+#[cfg(feature = "yaml")]
+use crate::config::yaml_parser;
+use crate::constants::DROP_IN_DIR_NAME;
+use crate::output;
+
+/// Name of the top level key that contains the list of included files
+pub const INCLUDE_KEY: &str = "include";
+
+/// Name of the top level table that contains the named profiles
+pub const PROFILE_KEY: &str = "profile";
+
+/// Load a toml file and merge all files referenced by its include key.
+///
+/// Precedence rules for merging the included files:
+/// - Values of the including file always win over included values
+/// - Values of earlier includes win over values of later includes
+/// - Arrays are concatenated, entries of the including file come first
+/// - Sub tables are merged recursively with the same rules
+///
+/// Included files can include other files. Relative include paths are
+/// resolved relative to the directory of the including file.
+pub fn load_table_with_includes(path: &Path) -> Result<TopLevelTable, io::Error> {
+    let mut include_stack: Vec<PathBuf> = Vec::new();
+    load_table_recursive(path, &mut include_stack)
+}
+
+/// Load the main config file and merge all files of the drop-in directory.
+///
+/// The drop-in directory is located next to the main config file, for the
+/// config '/etc/yalc.toml' the directory '/etc/yalc.d' is used. All files
+/// with the '.toml' extension are merged in alphabetical order after the
+/// main config. The same precedence rules as for includes are applied.
+pub fn load_table_with_drop_ins(path: &Path) -> Result<TopLevelTable, io::Error> {
+    let mut table: TopLevelTable = load_table_with_includes(path)?;
+
+    for drop_in_path in list_drop_in_files(&drop_in_dir(path))? {
+        output::print_status(&format!(
+            "Merging drop-in config file: {}",
+            drop_in_path.display()
+        ));
+        let drop_in_table = load_table_with_includes(&drop_in_path)?;
+        merge_tables(&mut table, drop_in_table);
+    }
+
+    Ok(table)
+}
+
+/// Get the path of the drop-in directory for a config file path
+pub fn drop_in_dir(config_path: &Path) -> PathBuf {
+    let base_dir: &Path = config_path.parent().unwrap_or(Path::new("/"));
+    base_dir.join(DROP_IN_DIR_NAME)
+}
+
+/// List all toml files of the drop-in directory sorted by their file name.
+/// A missing drop-in directory is not an error and results in an empty list.
+pub fn list_drop_in_files(dir: &Path) -> Result<Vec<PathBuf>, io::Error> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path: PathBuf = entry?.path();
+        let is_config: bool = is_yaml_file(&path) && cfg!(feature = "yaml")
+            || path.extension().is_some_and(|ext| ext == "toml");
+
+        if is_config && path.is_file() {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// List the main config file, its drop-in files and all files they include.
+/// Files that can not be loaded are listed, but their includes are unknown.
+/// Errors are reported when the config is loaded, this function never fails.
+pub fn list_config_files(path: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut visited: HashSet<PathBuf> = HashSet::new();
+    collect_included_files(path, &mut files, &mut visited);
+
+    for drop_in_path in list_drop_in_files(&drop_in_dir(path)).unwrap_or_default() {
+        collect_included_files(&drop_in_path, &mut files, &mut visited);
+    }
+
+    files
+}
+
+/// Add the file and the files of its includes to the list.
+/// Every file is added once, so include cycles are no endless recursion.
+fn collect_included_files(path: &Path, files: &mut Vec<PathBuf>, visited: &mut HashSet<PathBuf>) {
+    let canonical_path: PathBuf = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    if !visited.insert(canonical_path.clone()) {
+        return;
+    }
+
+    files.push(path.to_path_buf());
+
+    let include_paths: Vec<PathBuf> = match load_file_table(&canonical_path) {
+        Ok(mut table) => take_include_paths(&mut table, &canonical_path).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+
+    for include_path in include_paths.iter() {
+        collect_included_files(include_path, files, visited);
+    }
+}
+
+/// Load a toml file and resolve the includes while tracking the include chain
+fn load_table_recursive(
+    path: &Path,
+    include_stack: &mut Vec<PathBuf>,
+) -> Result<TopLevelTable, io::Error> {
+    //Canonical paths are required to detect cycles via different path notations
+    let canonical_path: PathBuf = fs::canonicalize(path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Failed to load config file '{}': {}", path.display(), e),
+        )
+    })?;
+
+    if include_stack.contains(&canonical_path) {
+        let mut chain: Vec<String> = include_stack
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        chain.push(canonical_path.display().to_string());
+
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Config include cycle detected: {}", chain.join(" -> ")),
+        ));
+    }
+
+    let mut table: TopLevelTable = load_file_table(&canonical_path)?;
+    let include_paths: Vec<PathBuf> = take_include_paths(&mut table, &canonical_path)?;
+
+    //Process all included files with the current file on the include stack
+    include_stack.push(canonical_path);
+
+    for include_path in include_paths.iter() {
+        output::print_status(&format!(
+            "Including config file: {}",
+            include_path.display()
+        ));
+        let included_table = load_table_recursive(include_path, include_stack)?;
+        merge_tables(&mut table, included_table);
+    }
+
+    include_stack.pop();
+    Ok(table)
+}
+
+/// Load a single config file, the file format is selected by the file extension
+fn load_file_table(path: &Path) -> Result<TopLevelTable, io::Error> {
+    if is_yaml_file(path) {
+        load_yaml_file_table(path)
+    } else {
+        toml_parser::load_toml_table(path)
+    }
+}
+
+/// Returns true when the file extension is a yaml file extension
+fn is_yaml_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "yaml" || ext == "yml")
+}
+
+#[cfg(feature = "yaml")]
+fn load_yaml_file_table(path: &Path) -> Result<TopLevelTable, io::Error> {
+    yaml_parser::load_yaml_table(path)
+}
+
+#[cfg(not(feature = "yaml"))]
+fn load_yaml_file_table(path: &Path) -> Result<TopLevelTable, io::Error> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        format!(
+            "Yaml config support is not enabled, rebuild yalc with '--features yaml' to load: {}",
+            path.display()
+        ),
+    ))
+}
+
+/// Remove the include key from the table and return the referenced paths
+fn take_include_paths(table: &mut Table, file_path: &Path) -> Result<Vec<PathBuf>, io::Error> {
+    let include_value: Value = match table.remove(INCLUDE_KEY) {
+        None => return Ok(Vec::new()),
+        Some(value) => value,
+    };
+
+    let raw_paths: Vec<Value> = match include_value {
+        Value::Array(values) => values,
+        _ => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Expected array for config key: '{}'", INCLUDE_KEY),
+            ));
+        }
+    };
+
+    //Relative include paths are relative to the including file
+    let base_dir: &Path = file_path.parent().unwrap_or(Path::new("/"));
+    let mut paths: Vec<PathBuf> = Vec::with_capacity(raw_paths.len());
+
+    for raw_path in raw_paths {
+        match raw_path {
+            Value::String(s) => paths.push(base_dir.join(s)),
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Expected string items in list for config key: '{}'",
+                        INCLUDE_KEY
+                    ),
+                ));
+            }
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Merge the values of a lower precedence table into the base table.
+///
+/// Existing values of the base table are kept, missing values are added.
+/// Arrays of both tables are concatenated and sub tables are merged.
+pub fn merge_tables(base: &mut Table, lower: Table) {
+    for (key, lower_value) in lower {
+        match base.get_mut(&key) {
+            None => {
+                base.insert(key, lower_value);
+            }
+            Some(Value::Array(base_array)) => {
+                if let Value::Array(lower_array) = lower_value {
+                    base_array.extend(lower_array);
+                }
+            }
+            Some(Value::Table(base_table)) => {
+                if let Value::Table(lower_table) = lower_value {
+                    merge_tables(base_table, lower_table);
+                }
+            }
+            Some(_) => {
+                //Scalar values of the base table have precedence
+            }
+        }
+    }
+}
+
+/// Apply the values of a named profile to the root table.
+///
+/// The profiles are removed from the root table in any case. When a profile
+/// is selected, the profile table like '[profile.prod]' is merged into the
+/// root table. Values of the profile replace the base values and sub tables
+/// are merged recursively. Selecting an unknown profile is an error.
+pub fn apply_profile(root: &mut TopLevelTable, profile: Option<&str>) -> Result<(), io::Error> {
+    let profiles: Option<Value> = root.remove(PROFILE_KEY);
+
+    let name: &str = match profile {
+        None => return Ok(()),
+        Some(name) => name,
+    };
+
+    let profile_table: Table = match profiles {
+        Some(Value::Table(mut profiles)) => match profiles.remove(name) {
+            Some(Value::Table(profile_table)) => profile_table,
+            Some(_) => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Config profile '{}' is not a table", name),
+                ));
+            }
+            None => {
+                return Err(io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("Config profile not found: '{}'", name),
+                ));
+            }
+        },
+        _ => {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("Config profile not found: '{}'", name),
+            ));
+        }
+    };
+
+    output::print_status(&format!("Applying config profile: {}", name));
+    override_tables(root, profile_table);
+    Ok(())
+}
+
+/// Merge the values of a higher precedence table into the base table.
+///
+/// Values of the base table are replaced, this also applies to arrays.
+/// Sub tables of both tables are merged recursively.
+pub fn override_tables(base: &mut Table, higher: Table) {
+    for (key, higher_value) in higher {
+        match (base.get_mut(&key), higher_value) {
+            (Some(Value::Table(base_table)), Value::Table(higher_table)) => {
+                override_tables(base_table, higher_table);
+            }
+            (_, higher_value) => {
+                base.insert(key, higher_value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::env;
+
+    /// Create an empty temporary directory for a test
+    fn create_test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("yalc_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_merge_tables() {
+        let mut base: Table = HashMap::new();
+        base.insert("dry_run".to_string(), Value::Bool(true));
+        base.insert(
+            "file_list".to_string(),
+            Value::Array(vec![Value::String("a.log".to_string())]),
+        );
+
+        let mut lower: Table = HashMap::new();
+        lower.insert("dry_run".to_string(), Value::Bool(false));
+        lower.insert("keep_rotate".to_string(), Value::Integer(4));
+        lower.insert(
+            "file_list".to_string(),
+            Value::Array(vec![Value::String("b.log".to_string())]),
+        );
+
+        merge_tables(&mut base, lower);
+
+        assert_eq!(base.get("dry_run"), Some(&Value::Bool(true)));
+        assert_eq!(base.get("keep_rotate"), Some(&Value::Integer(4)));
+        assert_eq!(
+            base.get("file_list"),
+            Some(&Value::Array(vec![
+                Value::String("a.log".to_string()),
+                Value::String("b.log".to_string()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_merge_sub_tables() {
+        let mut base_retention: Table = HashMap::new();
+        base_retention.insert("file_size_mib".to_string(), Value::Integer(10));
+
+        let mut lower_retention: Table = HashMap::new();
+        lower_retention.insert("file_size_mib".to_string(), Value::Integer(20));
+        lower_retention.insert("last_write_h".to_string(), Value::Integer(5));
+
+        let mut base: Table = HashMap::new();
+        base.insert("retention".to_string(), Value::Table(base_retention));
+
+        let mut lower: Table = HashMap::new();
+        lower.insert("retention".to_string(), Value::Table(lower_retention));
+
+        merge_tables(&mut base, lower);
+
+        let Some(Value::Table(retention)) = base.get("retention") else {
+            panic!("Expected retention table");
+        };
+
+        assert_eq!(retention.get("file_size_mib"), Some(&Value::Integer(10)));
+        assert_eq!(retention.get("last_write_h"), Some(&Value::Integer(5)));
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let mut prod_retention: Table = HashMap::new();
+        prod_retention.insert("file_size_mib".to_string(), Value::Integer(100));
+
+        let mut prod: Table = HashMap::new();
+        prod.insert("dry_run".to_string(), Value::Bool(false));
+        prod.insert("retention".to_string(), Value::Table(prod_retention));
+
+        let mut profiles: Table = HashMap::new();
+        profiles.insert("prod".to_string(), Value::Table(prod));
+
+        let mut retention: Table = HashMap::new();
+        retention.insert("file_size_mib".to_string(), Value::Integer(10));
+        retention.insert("last_write_h".to_string(), Value::Integer(5));
+
+        let mut root: Table = HashMap::new();
+        root.insert("dry_run".to_string(), Value::Bool(true));
+        root.insert("retention".to_string(), Value::Table(retention));
+        root.insert(PROFILE_KEY.to_string(), Value::Table(profiles));
+
+        //Unknown profiles are an error
+        let mut unknown_root = root.clone();
+        assert!(apply_profile(&mut unknown_root, Some("dev")).is_err());
+
+        apply_profile(&mut root, Some("prod")).unwrap();
+
+        assert!(!root.contains_key(PROFILE_KEY));
+        assert_eq!(root.get("dry_run"), Some(&Value::Bool(false)));
+
+        let Some(Value::Table(retention)) = root.get("retention") else {
+            panic!("Expected retention table");
+        };
+
+        assert_eq!(retention.get("file_size_mib"), Some(&Value::Integer(100)));
+        assert_eq!(retention.get("last_write_h"), Some(&Value::Integer(5)));
+    }
+
+    #[test]
+    fn test_load_includes() {
+        let dir = create_test_dir("includes");
+        fs::write(
+            dir.join("main.toml"),
+            "include = [\"web.toml\"]\ndry_run = true\nfile_list = [\"/var/log/a.log\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("web.toml"),
+            "dry_run = false\nfile_list = [\"/var/log/web.log\"]\n",
+        )
+        .unwrap();
+
+        let table = load_table_with_includes(&dir.join("main.toml")).unwrap();
+
+        assert!(!table.contains_key(INCLUDE_KEY));
+        assert_eq!(table.get("dry_run"), Some(&Value::Bool(true)));
+        assert_eq!(
+            table.get("file_list"),
+            Some(&Value::Array(vec![
+                Value::String("/var/log/a.log".to_string()),
+                Value::String("/var/log/web.log".to_string()),
+            ]))
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_drop_ins() {
+        let dir = create_test_dir("drop_ins");
+        let drop_in_dir = dir.join(DROP_IN_DIR_NAME);
+        fs::create_dir_all(&drop_in_dir).unwrap();
+
+        fs::write(dir.join("yalc.toml"), "file_list = [\"/var/log/a.log\"]\n").unwrap();
+        fs::write(
+            drop_in_dir.join("20-db.toml"),
+            "file_list = [\"/var/log/db.log\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            drop_in_dir.join("10-web.toml"),
+            "file_list = [\"/var/log/web.log\"]\n",
+        )
+        .unwrap();
+        fs::write(drop_in_dir.join("ignored.txt"), "file_list = [1]\n").unwrap();
+
+        let table = load_table_with_drop_ins(&dir.join("yalc.toml")).unwrap();
+
+        assert_eq!(
+            table.get("file_list"),
+            Some(&Value::Array(vec![
+                Value::String("/var/log/a.log".to_string()),
+                Value::String("/var/log/web.log".to_string()),
+                Value::String("/var/log/db.log".to_string()),
+            ]))
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let dir = create_test_dir("include_cycle");
+        fs::write(dir.join("a.toml"), "include = [\"b.toml\"]\n").unwrap();
+        fs::write(dir.join("b.toml"), "include = [\"a.toml\"]\n").unwrap();
+
+        let res = load_table_with_includes(&dir.join("a.toml"));
+        assert!(res.is_err());
+        assert!(res.unwrap_err().to_string().contains("cycle"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+//! Module for the yalc config schema migrations
+//!
+//! Every config has a schema version defined by the 'config_version' key.
+//! Configs without this key have the version 1. Older configs are upgraded
+//! step by step to the current schema version by the migrations below.
+//!
+use std::io;
+use std::io::ErrorKind;
+
+use crate::config::toml_parser::{Table, TopLevelTable, Value};
+
+/// Name of the top level key that contains the config schema version
+pub const CONFIG_VERSION_KEY: &str = "config_version";
+
+/// Schema version of configs that do not define the config version key
+const INITIAL_CONFIG_VERSION: u64 = 1;
+
+/// Schema version of the config that is expected by the config parser
+pub const CURRENT_CONFIG_VERSION: u64 = 2;
+
+/// Represents the upgrade from one schema version to the next version
+struct Migration {
+    /// Schema version that is upgraded by this migration
+    from_version: u64,
+
+    /// Keys that are moved to a new key, sub tables are separated by '.'
+    renamed_keys: &'static [(&'static str, &'static str)],
+}
+
+/// All migrations ordered by the schema version
+const MIGRATIONS: &[Migration] = &[Migration {
+    from_version: 1,
+    renamed_keys: &[("retention.file_size_mb", "retention.file_size_mib")],
+}];
+
+/// Read the schema version of the root table
+pub fn config_version(root: &TopLevelTable) -> Result<u64, io::Error> {
+    match root.get(CONFIG_VERSION_KEY) {
+        None => Ok(INITIAL_CONFIG_VERSION),
+        Some(Value::Integer(version)) if *version > 0 => Ok(*version as u64),
+        Some(_) => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Expected positive integer for config key: '{}'",
+                CONFIG_VERSION_KEY
+            ),
+        )),
+    }
+}
+
+/// Upgrade the root table to the current schema version.
+/// Returns a description of every change, the list is empty for current configs.
+pub fn migrate_table(root: &mut TopLevelTable) -> Result<Vec<String>, io::Error> {
+    let version: u64 = config_version(root)?;
+
+    if version > CURRENT_CONFIG_VERSION {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Config version {} is newer than the supported version {}",
+                version, CURRENT_CONFIG_VERSION
+            ),
+        ));
+    }
+
+    let mut changes: Vec<String> = Vec::new();
+
+    for migration in MIGRATIONS.iter().filter(|m| m.from_version >= version) {
+        for (old_key, new_key) in migration.renamed_keys.iter() {
+            if rename_key(root, old_key, new_key)? {
+                changes.push(format!("Renamed key '{}' to '{}'", old_key, new_key));
+            }
+        }
+    }
+
+    if version < CURRENT_CONFIG_VERSION {
+        root.insert(
+            CONFIG_VERSION_KEY.to_string(),
+            Value::Integer(CURRENT_CONFIG_VERSION as i64),
+        );
+
+        changes.push(format!(
+            "Updated '{}' from {} to {}",
+            CONFIG_VERSION_KEY, version, CURRENT_CONFIG_VERSION
+        ));
+    }
+
+    Ok(changes)
+}
+
+/// Move the value of the old key to the new key.
+/// Returns false when the old key does not exist in the table.
+pub fn rename_key(root: &mut Table, old_key: &str, new_key: &str) -> Result<bool, io::Error> {
+    let value: Value = match remove_value(root, old_key) {
+        None => return Ok(false),
+        Some(value) => value,
+    };
+
+    let key_parts: Vec<&str> = new_key.split('.').collect();
+    let (last_key, parent_keys) = key_parts.split_last().unwrap_or((&new_key, &[]));
+    let mut current_table: &mut Table = root;
+
+    for key in parent_keys {
+        let entry = current_table
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Table(Table::new()));
+
+        current_table = match entry {
+            Value::Table(table) => table,
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Cannot migrate key '{}', '{}' is not a table", old_key, key),
+                ));
+            }
+        };
+    }
+
+    if current_table.contains_key(*last_key) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Cannot migrate key '{}', the key '{}' already exists",
+                old_key, new_key
+            ),
+        ));
+    }
+
+    current_table.insert(last_key.to_string(), value);
+    Ok(true)
+}
+
+/// Remove a value from the table, sub tables are separated by '.'
+pub fn remove_value(root: &mut Table, key: &str) -> Option<Value> {
+    let key_parts: Vec<&str> = key.split('.').collect();
+    let (last_key, parent_keys) = key_parts.split_last()?;
+    let mut current_table: &mut Table = root;
+
+    for key in parent_keys {
+        current_table = match current_table.get_mut(*key) {
+            Some(Value::Table(table)) => table,
+            _ => return None,
+        };
+    }
+
+    current_table.remove(*last_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config_deprecation::DEPRECATIONS;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_renamed_keys_are_not_deprecated() {
+        //Renamed keys are moved by the migration, a deprecation would warn a second time
+        for migration in MIGRATIONS.iter() {
+            for (old_key, _) in migration.renamed_keys.iter() {
+                assert!(DEPRECATIONS.iter().all(|d| d.name != *old_key));
+            }
+        }
+    }
+
+    #[test]
+    fn test_migrate_initial_version() {
+        let mut retention: Table = HashMap::new();
+        retention.insert("file_size_mb".to_string(), Value::Integer(10));
+        retention.insert("last_write_h".to_string(), Value::Integer(5));
+
+        let mut root: TopLevelTable = HashMap::new();
+        root.insert("retention".to_string(), Value::Table(retention));
+
+        let changes = migrate_table(&mut root).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(config_version(&root).unwrap(), CURRENT_CONFIG_VERSION);
+
+        let Some(Value::Table(retention)) = root.get("retention") else {
+            panic!("Expected retention table");
+        };
+
+        assert_eq!(retention.get("file_size_mib"), Some(&Value::Integer(10)));
+        assert!(!retention.contains_key("file_size_mb"));
+    }
+
+    #[test]
+    fn test_migrate_current_version() {
+        let mut root: TopLevelTable = HashMap::new();
+        root.insert(
+            CONFIG_VERSION_KEY.to_string(),
+            Value::Integer(CURRENT_CONFIG_VERSION as i64),
+        );
+
+        assert!(migrate_table(&mut root).unwrap().is_empty());
+
+        //Configs of newer yalc versions are rejected
+        root.insert(
+            CONFIG_VERSION_KEY.to_string(),
+            Value::Integer(CURRENT_CONFIG_VERSION as i64 + 1),
+        );
+
+        assert!(migrate_table(&mut root).is_err());
+    }
+}
@@ -5,11 +5,69 @@
 //!
 use std::io;
 use std::io::ErrorKind;
+use std::net::SocketAddr;
 
+#[cfg(feature = "azure")]
+use crate::config::AzureTarget;
+#[cfg(feature = "gcs")]
+use crate::config::GcsTarget;
+#[cfg(feature = "webdav")]
+use crate::config::WebDavTarget;
 use crate::config::{
-    CleanUpMode, Config, RetentionConfig,
+    CleanUpAction, CleanUpMode, Compression, Config, DaemonConfig, DirSource, EmailNotify,
+    ErrorPolicy, FailOn, FileEntry, FileOverrides, FilePermissions, FileSource, Frequency,
+    GroupConfig, IoClass, MatrixNotify, NotifyConfig, NotifyEvent, NotifyFilter, NotifyOn,
+    OpenFilePolicy, PriorityConfig, RetentionConfig, RetryConfig, S3Credentials, S3Target,
+    SignalTarget, SlackNotify, SshTarget, SshTransfer, SymlinkPolicy, SyslogConfig, TrimConfig,
+    UploadBackend, UploadConfig, config_condition,
+    config_regex::{self, Regex},
+    config_timestamp::{self, TimestampFormat},
+    config_units,
     toml_parser::{Table, TopLevelTable, Value},
 };
+use crate::output::LogLevel;
+use crate::output::syslog::{Facility, Severity};
+use crate::platform;
+
+/// Name of the top level table that contains the named groups
+const GROUP_KEY: &str = "group";
+
+/// Default size of the buffer for streamed copies
+const DEFAULT_IO_BUFFER_BYTES: u64 = 64 * 1024;
+
+/// Size of the log file that starts a new log file when the config does not set it
+const DEFAULT_LOG_FILE_MAX_SIZE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Default maximum size of the statistics file
+const DEFAULT_STATS_MAX_SIZE_BYTES: u64 = 1024 * 1024;
+
+/// Default seconds between two cleanup runs of the daemon
+const DEFAULT_INTERVAL_S: u64 = 3600;
+
+/// Default number of attempts of an upload, remote storage fails more often than local disks
+const DEFAULT_UPLOAD_ATTEMPTS: u64 = 3;
+
+/// Default milliseconds before the first retry of an upload
+const DEFAULT_UPLOAD_BACKOFF_MS: u64 = 1000;
+
+/// Default template of the remote names of uploaded archives
+const DEFAULT_UPLOAD_NAME: &str = "{name}-{time}";
+
+#[cfg(test)]
+thread_local! {
+    /// Keys that were requested by the parser, the tests compare them with the schema
+    static REQUESTED_KEYS: std::cell::RefCell<std::collections::BTreeSet<String>> =
+        const { std::cell::RefCell::new(std::collections::BTreeSet::new()) };
+}
+
+/// Backend tables of the upload, a backend must be enabled for the build when its table is used
+const UPLOAD_BACKEND_TABLES: [&str; 5] = [
+    "upload.s3",
+    "upload.ssh",
+    "upload.gcs",
+    "upload.azure",
+    "upload.webdav",
+];
 
 /// Parse the config instance from a parsed toml top level table
 pub fn parse_config(root: &TopLevelTable) -> Result<Config, io::Error> {
@@ -22,31 +80,225 @@ pub fn parse_config(root: &TopLevelTable) -> Result<Config, io::Error> {
         .parse::<CleanUpMode>()
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
 
+    let action: CleanUpAction =
+        get_optional(root, "action", get_action)?.unwrap_or(CleanUpAction::Rotate);
     let keep_rotate: u64 = get_uint(&root, "keep_rotate")?;
+    let rotate_start: u64 = get_optional(root, "rotate_start", get_uint::<u64>)?.unwrap_or(0);
+    let compact_rotations: bool =
+        get_optional(root, "compact_rotations", get_bool)?.unwrap_or(false);
+    let remove_stray_archives: bool =
+        get_optional(root, "remove_stray_archives", get_bool)?.unwrap_or(false);
+    let dedup_archives: bool = get_optional(root, "dedup_archives", get_bool)?.unwrap_or(false);
+    let allocated_size: bool = get_optional(root, "allocated_size", get_bool)?.unwrap_or(false);
+    let remove_empty_dirs: bool =
+        get_optional(root, "remove_empty_dirs", get_bool)?.unwrap_or(false);
     let missing_files_ok: bool = get_bool(&root, "missing_files_ok")?;
     let copy_truncate: bool = get_bool(&root, "copy_truncate")?;
+    let copy_truncate_lock: bool =
+        get_optional(root, "copy_truncate_lock", get_bool)?.unwrap_or(false);
+    let shred: bool = get_optional(root, "shred", get_bool)?.unwrap_or(false);
+    let manifest: bool = get_optional(root, "manifest", get_bool)?.unwrap_or(false);
+    let redact: Vec<Regex> = get_optional(root, "redact", get_regex_list)?.unwrap_or_default();
+    let rotate_empty: bool = get_optional(root, "rotate_empty", get_bool)?.unwrap_or(false);
+    let skip_unchanged: bool = get_optional(root, "skip_unchanged", get_bool)?.unwrap_or(false);
+
+    //Creation of a new log file after the rotation
+    let create: bool = get_optional(root, "create", get_bool)?.unwrap_or(false);
+    let create_permissions: FilePermissions = parse_permissions(root, "create")?;
+    let archive_permissions: FilePermissions = parse_permissions(root, "archive")?;
+
+    //Compression of rotated files
+    let compression: Compression = match get_optional(root, "compression", get_string)? {
+        Some(raw) => raw
+            .parse::<Compression>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?,
+        None => Compression::None,
+    };
+    let compression_level: Option<u32> = get_optional(root, "compression_level", get_uint::<u32>)?;
+    let delay_compress: bool = get_optional(root, "delay_compress", get_bool)?.unwrap_or(false);
+    let defer_growing: bool = get_optional(root, "defer_growing", get_bool)?.unwrap_or(false);
+
+    //Expiration of rotated archives
+    let max_archive_age_s: Option<u64> = get_optional(root, "max_archive_age", get_duration)?;
+    let max_archive_size_bytes: Option<u64> = get_optional(root, "max_archive_size", get_size)?;
+    let total_budget_bytes: Option<u64> = get_optional(root, "total_budget", get_size)?;
+    let max_bytes_per_run: Option<u64> = get_optional(root, "max_bytes_per_run", get_size)?;
+    let io_buffer_bytes: u64 =
+        get_optional(root, "io_buffer", get_size)?.unwrap_or(DEFAULT_IO_BUFFER_BYTES);
+    let io_limit_bytes_per_s: Option<u64> = get_optional(root, "io_limit", get_rate)?;
+
+    //Limits that depend on the state of previous runs
+    let frequency: Option<Frequency> = get_optional(root, "frequency", get_frequency)?;
+    let stale_after_s: Option<u64> = get_optional(root, "stale_after", get_duration)?;
+    let unmanaged_min_size_bytes: Option<u64> = get_optional(root, "unmanaged_min_size", get_size)?;
+    let state_file: Option<String> = get_optional(root, "state_file", get_string)?;
+    let stats_max_size_bytes: u64 =
+        get_optional(root, "stats_max_size", get_size)?.unwrap_or(DEFAULT_STATS_MAX_SIZE_BYTES);
+    let on_error: ErrorPolicy =
+        get_optional(root, "on_error", get_error_policy)?.unwrap_or(ErrorPolicy::Continue);
+    let fail_on: FailOn = get_optional(root, "fail_on", get_fail_on)?.unwrap_or(FailOn::Error);
+    let log_level: LogLevel =
+        get_optional(root, "log_level", get_log_level)?.unwrap_or(LogLevel::Info);
+    let log_file: Option<String> = get_optional(root, "log_file", get_string)?;
+    let log_file_max_size_bytes: u64 = get_optional(root, "log_file_max_size", get_size)?
+        .unwrap_or(DEFAULT_LOG_FILE_MAX_SIZE_BYTES);
+    let symlinks: SymlinkPolicy =
+        get_optional(root, "symlinks", get_symlink_policy)?.unwrap_or(SymlinkPolicy::Follow);
+    let open_files: OpenFilePolicy =
+        get_optional(root, "open_files", get_open_file_policy)?.unwrap_or(OpenFilePolicy::Ignore);
+    let lock_file: Option<String> = get_optional(root, "lock_file", get_string)?;
+    let audit_log: Option<String> = get_optional(root, "audit_log", get_string)?;
+    let lock_wait_s: Option<u64> = get_optional(root, "lock_wait", get_duration)?;
+    let allowed_roots: Vec<String> =
+        get_optional(root, "allowed_roots", parse_string_vec)?.unwrap_or_default();
 
     //File list config
+    let allow_empty_file_list: bool =
+        get_optional(root, "allow_empty_file_list", get_bool)?.unwrap_or(false);
     let file_list: Vec<String> = parse_string_vec(&root, "file_list")?;
+    let files: Vec<FileEntry> = parse_file_entries(root)?;
+    let groups: Vec<GroupConfig> = parse_groups(root)?;
 
     //Retention config
     let file_size_mib: u64 = get_uint(&root, "retention.file_size_mib")?;
     let last_write_h: u64 = get_uint(&root, "retention.last_write_h")?;
+    let min_free_percent: Option<u64> =
+        get_optional(root, "retention.min_free_percent", get_uint::<u64>)?;
+    let min_size_bytes: Option<u64> = get_optional(root, "retention.min_size", get_size)?;
+    let min_age_s: Option<u64> = get_optional(root, "retention.min_age", get_duration)?;
+    let max_lines: Option<u64> = get_optional(root, "retention.max_lines", get_uint::<u64>)?;
+    let file_age_h: Option<u64> = get_optional(root, "retention.file_age_h", get_uint::<u64>)?;
 
     let retention = RetentionConfig {
         file_size_mib,
         last_write_h,
+        min_free_percent,
+        min_size_bytes,
+        min_age_s,
+        max_lines,
+        file_age_h,
+    };
+
+    //Trim config, the whole table is optional
+    let trim = TrimConfig {
+        keep_lines: get_optional(root, "trim.keep_lines", get_uint::<u64>)?,
+        keep_bytes: get_optional(root, "trim.keep_size", get_size)?,
+        max_age_s: get_optional(root, "trim.max_age", get_duration)?,
+        timestamp_format: get_optional(root, "trim.timestamp_format", get_timestamp_format)?,
+    };
+
+    //Retry config, the whole table is optional
+    let default_retry = RetryConfig::default();
+    let retry = RetryConfig {
+        attempts: get_optional(root, "retry.attempts", get_uint::<u64>)?
+            .unwrap_or(default_retry.attempts),
+        backoff_ms: get_optional(root, "retry.backoff_ms", get_uint::<u64>)?
+            .unwrap_or(default_retry.backoff_ms),
+    };
+
+    //Priority config, the whole table is optional
+    let priority = PriorityConfig {
+        nice: get_optional(root, "priority.nice", get_uint::<u8>)?,
+        io_class: get_optional(root, "priority.io_class", get_io_class)?,
+        io_level: get_optional(root, "priority.io_level", get_uint::<u8>)?,
+    };
+
+    //Syslog config, the whole table is optional
+    let default_syslog = SyslogConfig::default();
+    let syslog = SyslogConfig {
+        enabled: get_optional(root, "syslog.enabled", get_bool)?.unwrap_or(default_syslog.enabled),
+        facility: get_optional(root, "syslog.facility", get_syslog_facility)?
+            .unwrap_or(default_syslog.facility),
+        priority: get_optional(root, "syslog.priority", get_syslog_severity)?
+            .unwrap_or(default_syslog.priority),
+    };
+
+    //Daemon config, the whole table is optional
+    let interval_s: u64 =
+        get_optional(root, "daemon.interval_s", get_uint::<u64>)?.unwrap_or(DEFAULT_INTERVAL_S);
+    let reload: bool = get_optional(root, "daemon.reload", get_bool)?.unwrap_or(true);
+    let status_listen: Option<SocketAddr> =
+        get_optional(root, "daemon.status_listen", get_socket_address)?;
+
+    let daemon = DaemonConfig {
+        interval_s,
+        reload,
+        status_listen,
+    };
+
+    //Upload config, the whole table is optional
+    let upload: Option<UploadConfig> = parse_upload(root)?;
+
+    //Notify config, every channel is optional
+    let notify = NotifyConfig {
+        email: parse_email_notify(root)?,
+        slack: parse_slack_notify(root)?,
+        matrix: parse_matrix_notify(root)?,
     };
 
     //Create the final config instance
     let config = Config {
         dry_run,
         mode,
+        action,
         keep_rotate,
+        rotate_start,
+        compact_rotations,
+        remove_stray_archives,
+        dedup_archives,
+        allocated_size,
+        remove_empty_dirs,
         missing_files_ok,
         copy_truncate,
+        copy_truncate_lock,
+        shred,
+        manifest,
+        redact,
+        allow_empty_file_list,
+        rotate_empty,
+        skip_unchanged,
+        create,
+        create_permissions,
+        archive_permissions,
+        compression,
+        compression_level,
+        delay_compress,
+        defer_growing,
+        max_archive_age_s,
+        max_archive_size_bytes,
+        total_budget_bytes,
+        max_bytes_per_run,
+        io_buffer_bytes,
+        io_limit_bytes_per_s,
+        frequency,
+        stale_after_s,
+        unmanaged_min_size_bytes,
+        state_file,
+        stats_max_size_bytes,
+        on_error,
+        fail_on,
+        log_level,
+        log_file,
+        log_file_max_size_bytes,
+        symlinks,
+        open_files,
+        lock_file,
+        audit_log,
+        lock_wait_s,
+        allowed_roots,
         file_list,
+        files,
+        groups,
+        selected_groups: Vec::new(),
+        selected_patterns: Vec::new(),
         retention,
+        trim,
+        retry,
+        priority,
+        syslog,
+        daemon,
+        upload,
+        notify,
     };
 
     Ok(config)
@@ -54,6 +306,9 @@ pub fn parse_config(root: &TopLevelTable) -> Result<Config, io::Error> {
 
 /// Get a value from the top level table. Use '.' to separate between sub tables
 fn get_value<'a>(root: &'a TopLevelTable, key: &str) -> Result<&'a Value, io::Error> {
+    #[cfg(test)]
+    REQUESTED_KEYS.with(|keys| keys.borrow_mut().insert(key.to_string()));
+
     //Split the key by dot to access sub tables
     let keys: Vec<&str> = key.split('.').collect();
     let mut current_table: &Table = root;
@@ -68,8 +323,26 @@ fn get_value<'a>(root: &'a TopLevelTable, key: &str) -> Result<&'a Value, io::Er
                 //We are at the last key part
                 return Ok(value);
             }
+            None => {
+                //Key lookup failed, suggest a similar key of the same table
+                let message: String = match find_similar_key(current_table, current_key) {
+                    Some(similar_key) => {
+                        let mut suggested_parts: Vec<&str> = keys[..i].to_vec();
+                        suggested_parts.push(similar_key);
+
+                        format!(
+                            "Missing config key: '{}' - did you mean '{}'?",
+                            key,
+                            suggested_parts.join(".")
+                        )
+                    }
+                    None => format!("Missing or invalid config key: '{}'", key),
+                };
+
+                return Err(io::Error::new(ErrorKind::NotFound, message));
+            }
             _ => {
-                //Key lookup failed or value is not a table
+                //Value is not a table
                 return Err(io::Error::new(
                     ErrorKind::NotFound,
                     format!("Missing or invalid config key: '{}'", key),
@@ -84,6 +357,248 @@ fn get_value<'a>(root: &'a TopLevelTable, key: &str) -> Result<&'a Value, io::Er
     ))
 }
 
+/// Maximum edit distance of a key to be suggested for a missing key
+pub(crate) const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// Find the most similar key of a table for a missing key
+fn find_similar_key<'a>(table: &'a Table, missing_key: &str) -> Option<&'a str> {
+    table
+        .keys()
+        .map(|key| (edit_distance(key, missing_key), key))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min()
+        .map(|(_, key)| key.as_str())
+}
+
+/// Calculate the Levenshtein distance between two strings.
+/// This is the minimum number of inserted, removed or replaced chars.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+
+    //Distances of the previous row, starting with the distances to the empty string
+    let mut prev_row: Vec<usize> = (0..=b_chars.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row: Vec<usize> = Vec::with_capacity(b_chars.len() + 1);
+        row.push(i + 1);
+
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let replace_cost: usize = prev_row[j] + usize::from(a_char != *b_char);
+            let insert_cost: usize = row[j] + 1;
+            let remove_cost: usize = prev_row[j + 1] + 1;
+
+            row.push(replace_cost.min(insert_cost).min(remove_cost));
+        }
+
+        prev_row = row;
+    }
+
+    prev_row[b_chars.len()]
+}
+
+/// Returns true when the key exists in the top level table
+fn has_key(root: &TopLevelTable, key: &str) -> bool {
+    get_value(root, key).is_ok()
+}
+
+/// Get a duration like "30d" as seconds
+fn get_duration(root: &TopLevelTable, key: &str) -> Result<u64, io::Error> {
+    let raw: String = get_string(root, key)?;
+
+    config_units::parse_duration_s(&raw).ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Expected duration like \"30d\" with unit s, m, h, d or w for config key: '{}'",
+                key
+            ),
+        )
+    })
+}
+
+/// Get a size like "500MB" as bytes
+fn get_size(root: &TopLevelTable, key: &str) -> Result<u64, io::Error> {
+    let raw: String = get_string(root, key)?;
+
+    config_units::parse_size_bytes(&raw).ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Expected size like \"500MB\" with unit B, KB, MB, GB, TB, KiB, MiB, GiB or TiB for config key: '{}'",
+                key
+            ),
+        )
+    })
+}
+
+/// Get a rate like "20MB/s" in bytes per second
+fn get_rate(root: &TopLevelTable, key: &str) -> Result<u64, io::Error> {
+    let raw: String = get_string(root, key)?;
+
+    config_units::parse_rate_bytes_per_s(&raw).ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Expected rate like \"20MB/s\" with a size unit per second for config key: '{}'",
+                key
+            ),
+        )
+    })
+}
+
+/// Get a socket address like "127.0.0.1:9810"
+fn get_socket_address(root: &TopLevelTable, key: &str) -> Result<SocketAddr, io::Error> {
+    get_string(root, key)?.parse::<SocketAddr>().map_err(|_| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Expected address like \"127.0.0.1:9810\" with ip and port for config key: '{}'",
+                key
+            ),
+        )
+    })
+}
+
+/// Get a log level like "debug"
+fn get_log_level(root: &TopLevelTable, key: &str) -> Result<LogLevel, io::Error> {
+    get_string(root, key)?
+        .parse::<LogLevel>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Get the runs that send a notification like "failure"
+fn get_notify_on(root: &TopLevelTable, key: &str) -> Result<NotifyOn, io::Error> {
+    get_string(root, key)?
+        .parse::<NotifyOn>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Get a list of notification events like ["run_finished", "task_failed"]
+fn get_notify_events(root: &TopLevelTable, key: &str) -> Result<Vec<NotifyEvent>, io::Error> {
+    parse_string_vec(root, key)?
+        .iter()
+        .map(|event| {
+            event
+                .parse::<NotifyEvent>()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+        })
+        .collect()
+}
+
+/// Get a syslog facility like "local0"
+fn get_syslog_facility(root: &TopLevelTable, key: &str) -> Result<Facility, io::Error> {
+    get_string(root, key)?
+        .parse::<Facility>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Get a syslog severity like "notice"
+fn get_syslog_severity(root: &TopLevelTable, key: &str) -> Result<Severity, io::Error> {
+    get_string(root, key)?
+        .parse::<Severity>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Get an I/O scheduling class like "idle"
+fn get_io_class(root: &TopLevelTable, key: &str) -> Result<IoClass, io::Error> {
+    get_string(root, key)?
+        .parse::<IoClass>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Get the credentials source of an S3 upload like "profile:backup"
+fn get_s3_credentials(root: &TopLevelTable, key: &str) -> Result<S3Credentials, io::Error> {
+    get_string(root, key)?
+        .parse::<S3Credentials>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Get the transfer program of an ssh upload like "sftp"
+fn get_ssh_transfer(root: &TopLevelTable, key: &str) -> Result<SshTransfer, io::Error> {
+    get_string(root, key)?
+        .parse::<SshTransfer>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Get a signed integer value like -10
+fn get_int(root: &TopLevelTable, key: &str) -> Result<i64, io::Error> {
+    match get_value(root, key)? {
+        Value::Integer(i) => Ok(*i),
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Expected integer for config key: '{}'", key),
+        )),
+    }
+}
+
+/// Get a rotation frequency like "daily"
+fn get_frequency(root: &TopLevelTable, key: &str) -> Result<Frequency, io::Error> {
+    get_string(root, key)?
+        .parse::<Frequency>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Get an error policy like "abort"
+fn get_error_policy(root: &TopLevelTable, key: &str) -> Result<ErrorPolicy, io::Error> {
+    get_string(root, key)?
+        .parse::<ErrorPolicy>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Get the results that fail the run like "warn"
+fn get_fail_on(root: &TopLevelTable, key: &str) -> Result<FailOn, io::Error> {
+    get_string(root, key)?
+        .parse::<FailOn>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Get a timestamp format like "%Y-%m-%d %H:%M:%S"
+fn get_timestamp_format(root: &TopLevelTable, key: &str) -> Result<TimestampFormat, io::Error> {
+    config_timestamp::parse_timestamp_format(&get_string(root, key)?)
+}
+
+/// Get a list of regular expressions like ["\\d{16}"]
+fn get_regex_list(root: &TopLevelTable, key: &str) -> Result<Vec<Regex>, io::Error> {
+    parse_string_vec(root, key)?
+        .iter()
+        .map(|raw| config_regex::parse_regex(raw))
+        .collect()
+}
+
+/// Get a cleanup action like "trim"
+fn get_action(root: &TopLevelTable, key: &str) -> Result<CleanUpAction, io::Error> {
+    get_string(root, key)?
+        .parse::<CleanUpAction>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Get a symlink policy like "skip"
+fn get_symlink_policy(root: &TopLevelTable, key: &str) -> Result<SymlinkPolicy, io::Error> {
+    get_string(root, key)?
+        .parse::<SymlinkPolicy>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Get a handling of open log files like "copy_truncate"
+fn get_open_file_policy(root: &TopLevelTable, key: &str) -> Result<OpenFilePolicy, io::Error> {
+    get_string(root, key)?
+        .parse::<OpenFilePolicy>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Helper function to extract an optional value, None is returned for missing keys
+fn get_optional<T>(
+    root: &TopLevelTable,
+    key: &str,
+    getter: fn(&TopLevelTable, &str) -> Result<T, io::Error>,
+) -> Result<Option<T>, io::Error> {
+    if has_key(root, key) {
+        getter(root, key).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
 /// Helper function to extract a boolean value
 fn get_bool(root: &TopLevelTable, key: &str) -> Result<bool, io::Error> {
     match get_value(&root, &key)? {
@@ -171,10 +686,528 @@ fn parse_string_vec(root: &TopLevelTable, key: &str) -> Result<Vec<String>, io::
     Ok(list)
 }
 
+/// Parse the optional permission keys with the prefix like 'create_mode'.
+/// The mode is an octal string like "0640", so it is not read as decimal number.
+fn parse_permissions(table: &Table, prefix: &str) -> Result<FilePermissions, io::Error> {
+    let mode_key: String = format!("{}_mode", prefix);
+
+    let mode: Option<u32> = match get_optional(table, &mode_key, get_string)? {
+        Some(raw_mode) => match u32::from_str_radix(&raw_mode, 8) {
+            Ok(mode) if mode <= 0o7777 => Some(mode),
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Expected octal file mode like \"0640\" for config key: '{}'",
+                        mode_key
+                    ),
+                ));
+            }
+        },
+        None => None,
+    };
+
+    Ok(FilePermissions {
+        mode,
+        owner: get_optional(table, &format!("{}_owner", prefix), get_string)?,
+        group: get_optional(table, &format!("{}_group", prefix), get_string)?,
+    })
+}
+
+/// Parse all '[[files]]' entries, a config without file entries is valid
+fn parse_file_entries(root: &TopLevelTable) -> Result<Vec<FileEntry>, io::Error> {
+    let mut entries: Vec<FileEntry> = Vec::new();
+
+    if !has_key(root, "files") {
+        return Ok(entries);
+    }
+
+    for (idx_entry, raw_entry) in get_array(root, "files")?.iter().enumerate() {
+        match raw_entry {
+            Value::Table(table) => {
+                //Add the entry number to errors, the keys are the same for all entries
+                let entry = parse_file_entry(table).map_err(|e| {
+                    io::Error::new(e.kind(), format!("files[{}]: {}", idx_entry + 1, e))
+                })?;
+
+                entries.push(entry);
+            }
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "Expected table items in list for config key: 'files'",
+                ));
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Parse a single file entry table, an entry has either a path or a dir key
+fn parse_file_entry(table: &Table) -> Result<FileEntry, io::Error> {
+    let source: FileSource = match (has_key(table, "path"), has_key(table, "dir")) {
+        (true, false) => FileSource::Path(get_string(table, "path")?),
+        (false, true) => FileSource::Dir(parse_dir_source(table)?),
+        _ => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "File entry requires exactly one of the keys: 'path', 'dir'",
+            ));
+        }
+    };
+
+    let olddir: Option<String> = get_optional(table, "olddir", get_string)?;
+    let group: Option<String> = get_optional(table, "group", get_string)?;
+    let signal: Option<SignalTarget> = parse_signal_target(table)?;
+    let priority: Option<i64> = get_optional(table, "priority", get_int)?;
+    let overrides: FileOverrides = parse_file_overrides(table)?;
+
+    Ok(FileEntry {
+        source,
+        olddir,
+        group,
+        signal,
+        priority,
+        overrides,
+    })
+}
+
+/// Parse the rotation settings of a file entry that replace the global values
+fn parse_file_overrides(table: &Table) -> Result<FileOverrides, io::Error> {
+    let mode: Option<CleanUpMode> = match get_optional(table, "mode", get_string)? {
+        Some(raw) => Some(
+            raw.parse::<CleanUpMode>()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?,
+        ),
+        None => None,
+    };
+
+    Ok(FileOverrides {
+        dry_run: get_optional(table, "dry_run", get_bool)?,
+        mode,
+        keep_rotate: get_optional(table, "keep_rotate", get_uint::<u64>)?,
+        rotate_start: get_optional(table, "rotate_start", get_uint::<u64>)?,
+        copy_truncate: get_optional(table, "copy_truncate", get_bool)?,
+        file_size_mib: get_optional(table, "file_size_mib", get_uint::<u64>)?,
+        last_write_h: get_optional(table, "last_write_h", get_uint::<u64>)?,
+        max_archive_age_s: get_optional(table, "max_archive_age", get_duration)?,
+        max_archive_size_bytes: get_optional(table, "max_archive_size", get_size)?,
+        condition: get_optional(table, "condition", get_string)?
+            .map(|raw| config_condition::parse_condition(&raw))
+            .transpose()?,
+        frequency: get_optional(table, "frequency", get_frequency)?,
+        symlinks: get_optional(table, "symlinks", get_symlink_policy)?,
+        shred: get_optional(table, "shred", get_bool)?,
+        manifest: get_optional(table, "manifest", get_bool)?,
+        redact: get_optional(table, "redact", get_regex_list)?,
+        action: get_optional(table, "action", get_action)?,
+        trim_keep_lines: get_optional(table, "trim_keep_lines", get_uint::<u64>)?,
+        trim_keep_bytes: get_optional(table, "trim_keep_size", get_size)?,
+        trim_max_age_s: get_optional(table, "trim_max_age", get_duration)?,
+        trim_timestamp_format: get_optional(table, "trim_timestamp_format", get_timestamp_format)?,
+    })
+}
+
+/// Parse the signal and pidfile keys of a file entry, both keys must be set together
+fn parse_signal_target(table: &Table) -> Result<Option<SignalTarget>, io::Error> {
+    let signal: Option<String> = get_optional(table, "signal", get_string)?;
+    let pidfile: Option<String> = get_optional(table, "pidfile", get_string)?;
+
+    match (signal, pidfile) {
+        (None, None) => Ok(None),
+        (Some(signal), Some(pidfile)) => {
+            if platform::signal_number(&signal).is_none() {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Unknown signal for config key 'signal': '{}'", signal),
+                ));
+            }
+
+            Ok(Some(SignalTarget { signal, pidfile }))
+        }
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "The keys 'signal' and 'pidfile' must be set together",
+        )),
+    }
+}
+
+/// Parse all '[group.NAME]' tables, a config without groups is valid
+fn parse_groups(root: &TopLevelTable) -> Result<Vec<GroupConfig>, io::Error> {
+    let group_tables: &Table = match root.get(GROUP_KEY) {
+        None => return Ok(Vec::new()),
+        Some(Value::Table(table)) => table,
+        Some(_) => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Expected table for config key: '{}'", GROUP_KEY),
+            ));
+        }
+    };
+
+    let mut groups: Vec<GroupConfig> = Vec::new();
+
+    for (name, value) in group_tables.iter() {
+        let Value::Table(table) = value else {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Expected table for config key: '{}.{}'", GROUP_KEY, name),
+            ));
+        };
+
+        //Add the group name to errors, the keys are the same for all groups
+        let with_group =
+            |e: io::Error| io::Error::new(e.kind(), format!("{}.{}: {}", GROUP_KEY, name, e));
+
+        groups.push(GroupConfig {
+            name: name.clone(),
+            paths: get_optional(table, "paths", parse_string_vec)
+                .map_err(with_group)?
+                .unwrap_or_default(),
+            overrides: parse_file_overrides(table).map_err(with_group)?,
+            priority: get_optional(table, "priority", get_int).map_err(with_group)?,
+            max_total_size_bytes: get_optional(table, "max_total_size", get_size)
+                .map_err(with_group)?,
+            prerotate: get_optional(table, "prerotate", get_string).map_err(with_group)?,
+            postrotate: get_optional(table, "postrotate", get_string).map_err(with_group)?,
+        });
+    }
+
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(groups)
+}
+
+/// Parse the '[upload]' table with the table of its backend, a config without upload is valid
+fn parse_upload(root: &TopLevelTable) -> Result<Option<UploadConfig>, io::Error> {
+    if !has_key(root, "upload") {
+        return Ok(None);
+    }
+
+    let tables: Vec<&str> = UPLOAD_BACKEND_TABLES
+        .iter()
+        .copied()
+        .filter(|table| has_key(root, table))
+        .collect();
+
+    let backend: UploadBackend = match tables.as_slice() {
+        [table] => parse_upload_backend(root, table)?,
+        [] => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "The upload table requires a backend table like '[upload.s3]' or '[upload.ssh]'",
+            ));
+        }
+        _ => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "The upload table allows only one backend table, found '[{}]'",
+                    tables.join("]', '[")
+                ),
+            ));
+        }
+    };
+
+    let retry = RetryConfig {
+        attempts: get_optional(root, "upload.attempts", get_uint::<u64>)?
+            .unwrap_or(DEFAULT_UPLOAD_ATTEMPTS),
+        backoff_ms: get_optional(root, "upload.backoff_ms", get_uint::<u64>)?
+            .unwrap_or(DEFAULT_UPLOAD_BACKOFF_MS),
+    };
+
+    Ok(Some(UploadConfig {
+        backend,
+        delete_local: get_optional(root, "upload.delete_local", get_bool)?.unwrap_or(false),
+        name_template: get_optional(root, "upload.name", get_string)?
+            .unwrap_or_else(|| DEFAULT_UPLOAD_NAME.to_string()),
+        retry,
+    }))
+}
+
+/// Parse the email notification, None when the table is not defined
+fn parse_email_notify(root: &TopLevelTable) -> Result<Option<EmailNotify>, io::Error> {
+    if !has_key(root, "notify.email") {
+        return Ok(None);
+    }
+
+    //A single recipient can be written as string
+    let to: Vec<String> = match get_value(root, "notify.email.to")? {
+        Value::String(address) => vec![address.clone()],
+        _ => parse_string_vec(root, "notify.email.to")?,
+    };
+
+    Ok(Some(EmailNotify {
+        server: get_string(root, "notify.email.server")
+            .map(|server| normalize_smtp_url(&server))?,
+        from: get_string(root, "notify.email.from")?,
+        to,
+        netrc_file: get_optional(root, "notify.email.netrc_file", get_string)?,
+        filter: parse_notify_filter(root, "notify.email")?,
+    }))
+}
+
+/// Parse the Slack notification, None when the table is not defined
+fn parse_slack_notify(root: &TopLevelTable) -> Result<Option<SlackNotify>, io::Error> {
+    if !has_key(root, "notify.slack") {
+        return Ok(None);
+    }
+
+    Ok(Some(SlackNotify {
+        webhook_url: get_string(root, "notify.slack.webhook_url")?,
+        filter: parse_notify_filter(root, "notify.slack")?,
+    }))
+}
+
+/// Parse the Matrix notification, None when the table is not defined
+fn parse_matrix_notify(root: &TopLevelTable) -> Result<Option<MatrixNotify>, io::Error> {
+    if !has_key(root, "notify.matrix") {
+        return Ok(None);
+    }
+
+    Ok(Some(MatrixNotify {
+        homeserver: get_string(root, "notify.matrix.homeserver")?
+            .trim_end_matches('/')
+            .to_string(),
+        room_id: get_string(root, "notify.matrix.room_id")?,
+        access_token_file: get_string(root, "notify.matrix.access_token_file")?,
+        filter: parse_notify_filter(root, "notify.matrix")?,
+    }))
+}
+
+/// Parse the events of a notification table like 'notify.slack'
+fn parse_notify_filter(root: &TopLevelTable, table: &str) -> Result<NotifyFilter, io::Error> {
+    let default_filter = NotifyFilter::default();
+
+    Ok(NotifyFilter {
+        on: get_optional(root, &format!("{}.on", table), get_notify_on)?
+            .unwrap_or(default_filter.on),
+        events: get_optional(root, &format!("{}.events", table), get_notify_events)?
+            .unwrap_or(default_filter.events),
+    })
+}
+
+/// Normalize a server like 'mail.example.com' to 'smtp://mail.example.com', urls are kept
+fn normalize_smtp_url(server: &str) -> String {
+    match server.contains("://") {
+        true => server.to_string(),
+        false => format!("smtp://{}", server),
+    }
+}
+
+/// Parse the backend table of the upload like 'upload.s3'
+fn parse_upload_backend(root: &TopLevelTable, table: &str) -> Result<UploadBackend, io::Error> {
+    match table {
+        "upload.s3" => Ok(UploadBackend::S3(S3Target {
+            bucket: get_string(root, "upload.s3.bucket")?,
+            prefix: get_optional(root, "upload.s3.prefix", get_string)?
+                .map(|prefix| normalize_prefix(&prefix))
+                .unwrap_or_default(),
+            region: get_optional(root, "upload.s3.region", get_string)?,
+            credentials: get_optional(root, "upload.s3.credentials", get_s3_credentials)?
+                .unwrap_or(S3Credentials::Default),
+        })),
+        "upload.ssh" => Ok(UploadBackend::Ssh(SshTarget {
+            host: get_string(root, "upload.ssh.host")?,
+            user: get_optional(root, "upload.ssh.user", get_string)?,
+            port: get_optional(root, "upload.ssh.port", get_uint::<u16>)?,
+            path: get_string(root, "upload.ssh.path").map(|path| normalize_remote_dir(&path))?,
+            identity_file: get_optional(root, "upload.ssh.identity_file", get_string)?,
+            transfer: get_optional(root, "upload.ssh.transfer", get_ssh_transfer)?
+                .unwrap_or(SshTransfer::Scp),
+        })),
+        #[cfg(feature = "gcs")]
+        "upload.gcs" => Ok(UploadBackend::Gcs(GcsTarget {
+            bucket: get_string(root, "upload.gcs.bucket")?,
+            prefix: get_optional(root, "upload.gcs.prefix", get_string)?
+                .map(|prefix| normalize_prefix(&prefix))
+                .unwrap_or_default(),
+        })),
+        #[cfg(feature = "azure")]
+        "upload.azure" => Ok(UploadBackend::Azure(AzureTarget {
+            account: get_string(root, "upload.azure.account")?,
+            container: get_string(root, "upload.azure.container")?,
+            prefix: get_optional(root, "upload.azure.prefix", get_string)?
+                .map(|prefix| normalize_prefix(&prefix))
+                .unwrap_or_default(),
+        })),
+        #[cfg(feature = "webdav")]
+        "upload.webdav" => Ok(UploadBackend::WebDav(WebDavTarget {
+            url: get_string(root, "upload.webdav.url").map(|url| normalize_remote_dir(&url))?,
+            netrc_file: get_optional(root, "upload.webdav.netrc_file", get_string)?,
+        })),
+        _ => {
+            //The backend table is known, but the backend was not enabled for the build
+            let feature: &str = table.trim_start_matches("upload.");
+            Err(io::Error::new(
+                ErrorKind::Unsupported,
+                format!(
+                    "Upload backend '[{}]' is not enabled, rebuild yalc with '--features {}'",
+                    table, feature
+                ),
+            ))
+        }
+    }
+}
+
+/// Normalize a key prefix like '/web1' to 'web1/', an empty prefix stays empty
+fn normalize_prefix(prefix: &str) -> String {
+    match prefix.trim_matches('/') {
+        "" => String::new(),
+        trimmed => format!("{}/", trimmed),
+    }
+}
+
+/// Normalize a remote directory like '/srv/logs' to '/srv/logs/', an empty path stays empty.
+/// Urls of a WebDAV collection are normalized the same way.
+fn normalize_remote_dir(path: &str) -> String {
+    match path.ends_with('/') || path.is_empty() {
+        true => path.to_string(),
+        false => format!("{}/", path),
+    }
+}
+
+/// Parse the directory source values of a file entry
+fn parse_dir_source(table: &Table) -> Result<DirSource, io::Error> {
+    let dir: String = get_string(table, "dir")?;
+    let recursive: bool = get_optional(table, "recursive", get_bool)?.unwrap_or(false);
+    let max_depth: Option<u64> = get_optional(table, "max_depth", get_uint::<u64>)?;
+
+    //Extensions are stored without the leading dot
+    let extensions: Vec<String> = get_optional(table, "extensions", parse_string_vec)?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|ext| ext.trim_start_matches('.').to_string())
+        .collect();
+
+    Ok(DirSource {
+        dir,
+        recursive,
+        extensions,
+        max_depth,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use crate::config::config_schema::{CONFIG_SCHEMA, SchemaSection};
+    use crate::config::toml_parser;
+    use std::collections::{BTreeSet, HashMap};
+
+    /// Get the keys that are requested by the parse function
+    fn requested_keys<T>(parse: impl FnOnce() -> Result<T, io::Error>) -> BTreeSet<String> {
+        REQUESTED_KEYS.with(|keys| keys.borrow_mut().clear());
+        assert!(parse().is_ok());
+        REQUESTED_KEYS.with(|keys| keys.take())
+    }
+
+    /// Create a table with the example values of all keys of the schema section
+    fn schema_table(section: &SchemaSection) -> Table {
+        let mut table: Table = HashMap::new();
+
+        for key in section.keys.iter() {
+            let parsed = toml_parser::parse_toml(&format!("{} = {}", key.name, key.value)).unwrap();
+            table.extend(parsed);
+        }
+
+        table
+    }
+
+    /// Insert the table at the section path like 'upload.s3'
+    fn insert_section(root: &mut Table, path: &str, table: Table) {
+        match path.split_once('.') {
+            _ if path.is_empty() => root.extend(table),
+            Some((name, rest)) => {
+                let entry = root
+                    .entry(name.to_string())
+                    .or_insert_with(|| Value::Table(HashMap::new()));
+
+                if let Value::Table(sub_table) = entry {
+                    insert_section(sub_table, rest, table);
+                }
+            }
+            None => {
+                root.insert(path.to_string(), Value::Table(table));
+            }
+        }
+    }
+
+    #[test]
+    fn test_schema_matches_parser() {
+        let find_section = |name: &str| CONFIG_SCHEMA.iter().find(|s| s.name == name).unwrap();
+        let section_keys = |section: &SchemaSection| -> BTreeSet<String> {
+            section
+                .keys
+                .iter()
+                .map(|key| match section.name {
+                    "" | "files" | "group.nginx" => key.name.to_string(),
+                    name => format!("{}.{}", name, key.name),
+                })
+                .collect()
+        };
+
+        //Every upload backend is parsed on its own, only one backend table is allowed
+        let mut root: TopLevelTable = HashMap::new();
+        let mut expected_root: BTreeSet<String> = BTreeSet::new();
+
+        for section in CONFIG_SCHEMA.iter() {
+            if !["files", "group.nginx"].contains(&section.name)
+                && !UPLOAD_BACKEND_TABLES.contains(&section.name)
+            {
+                insert_section(&mut root, section.name, schema_table(section));
+                expected_root.extend(section_keys(section));
+            }
+        }
+
+        let mut requested_root: BTreeSet<String> = BTreeSet::new();
+        for backend in UPLOAD_BACKEND_TABLES.iter() {
+            let mut backend_root: TopLevelTable = root.clone();
+            insert_section(
+                &mut backend_root,
+                backend,
+                schema_table(find_section(backend)),
+            );
+
+            //Backends that are not enabled for the build read no keys
+            if parse_config(&backend_root).is_ok() {
+                requested_root.extend(requested_keys(|| parse_config(&backend_root)));
+                expected_root.extend(section_keys(find_section(backend)));
+            }
+        }
+
+        //Tables are requested to check if they exist
+        for section in CONFIG_SCHEMA.iter() {
+            requested_root.remove(section.name);
+        }
+        requested_root.remove("files");
+        assert_eq!(requested_root, expected_root);
+
+        //The example entry has a path and a dir, both sources are parsed on their own
+        let files_section: &SchemaSection = find_section("files");
+        let mut requested_entry: BTreeSet<String> = BTreeSet::new();
+        for source in ["path", "dir"] {
+            let mut entry: Table = schema_table(files_section);
+            entry.remove(if source == "path" { "dir" } else { "path" });
+            requested_entry.extend(requested_keys(|| parse_file_entry(&entry)));
+        }
+        assert_eq!(requested_entry, section_keys(files_section));
+
+        //Groups are parsed from their own table below the group table
+        let group_section: &SchemaSection = find_section("group.nginx");
+        let mut group_root: TopLevelTable = HashMap::new();
+        insert_section(
+            &mut group_root,
+            group_section.name,
+            schema_table(group_section),
+        );
+        let requested_group: BTreeSet<String> = requested_keys(|| parse_groups(&group_root));
+
+        //Groups support the overrides of the file entries as well
+        let mut expected_group: BTreeSet<String> = section_keys(group_section);
+        expected_group.extend(requested_keys(|| parse_file_overrides(&HashMap::new())));
+        assert_eq!(requested_group, expected_group);
+    }
 
     #[test]
     fn test_get_bool() {
@@ -231,6 +1264,44 @@ mod tests {
         assert!(only_table.is_err());
     }
 
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("dry_run", "dry_run"), 0);
+        assert_eq!(edit_distance("dry_run", "dryrun"), 1);
+        assert_eq!(edit_distance("keep_rotate", "keep_rotaet"), 2);
+        assert_eq!(edit_distance("keep_rotate", "keep_rotation"), 3);
+        assert_eq!(edit_distance("", "mode"), 4);
+    }
+
+    #[test]
+    fn test_missing_key_suggestion() {
+        let mut retention: Table = HashMap::new();
+        retention.insert("file_size_mb".to_string(), Value::Integer(10));
+
+        let mut root: TopLevelTable = HashMap::new();
+        root.insert("dryrun".to_string(), Value::Bool(true));
+        root.insert("retention".to_string(), Value::Table(retention));
+
+        let error = get_bool(&root, "dry_run").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Missing config key: 'dry_run' - did you mean 'dryrun'?"
+        );
+
+        let error = get_uint::<u64>(&root, "retention.file_size_mib").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Missing config key: 'retention.file_size_mib' - did you mean 'retention.file_size_mb'?"
+        );
+
+        //Keys that are not similar are not suggested
+        let error = get_bool(&root, "copy_truncate").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Missing or invalid config key: 'copy_truncate'"
+        );
+    }
+
     #[test]
     fn test_get_string() {
         let mut root: TopLevelTable = HashMap::new();
@@ -241,6 +1312,151 @@ mod tests {
         assert_eq!(get_string(&root, "other_key").unwrap(), "other".to_string());
     }
 
+    #[test]
+    fn test_parse_file_entries() {
+        let mut entry_a: Table = HashMap::new();
+        entry_a.insert(
+            "path".to_string(),
+            Value::String("/var/log/app/*.log".to_string()),
+        );
+
+        let mut entry_b: Table = HashMap::new();
+        entry_b.insert(
+            "path".to_string(),
+            Value::String("/var/log/b.log".to_string()),
+        );
+
+        let mut root: TopLevelTable = HashMap::new();
+        root.insert(
+            "files".to_string(),
+            Value::Array(vec![Value::Table(entry_a), Value::Table(entry_b)]),
+        );
+
+        let entries = parse_file_entries(&root).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].source.to_string(), "/var/log/app/*.log");
+        assert_eq!(entries[1].source.to_string(), "/var/log/b.log");
+
+        //The files key is optional
+        let empty_root: TopLevelTable = HashMap::new();
+        assert!(parse_file_entries(&empty_root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_dir_entry() {
+        let mut entry: Table = HashMap::new();
+        entry.insert(
+            "dir".to_string(),
+            Value::String("/var/log/containers".to_string()),
+        );
+        entry.insert("recursive".to_string(), Value::Bool(true));
+        entry.insert("max_depth".to_string(), Value::Integer(3));
+        entry.insert(
+            "extensions".to_string(),
+            Value::Array(vec![
+                Value::String("log".to_string()),
+                Value::String(".txt".to_string()),
+            ]),
+        );
+
+        let FileSource::Dir(source) = parse_file_entry(&entry).unwrap().source else {
+            panic!("Expected dir source");
+        };
+
+        assert_eq!(source.dir, "/var/log/containers");
+        assert!(source.recursive);
+        assert_eq!(source.max_depth, Some(3));
+        assert_eq!(source.extensions, vec!["log", "txt"]);
+
+        //Path and dir keys are mutually exclusive
+        entry.insert(
+            "path".to_string(),
+            Value::String("/var/log/a.log".to_string()),
+        );
+        assert!(parse_file_entry(&entry).is_err());
+    }
+
+    #[test]
+    fn test_parse_file_overrides() {
+        let mut entry: Table = HashMap::new();
+        entry.insert(
+            "path".to_string(),
+            Value::String("/var/log/a.log".to_string()),
+        );
+
+        //Entries without overrides use the global values
+        assert!(parse_file_entry(&entry).unwrap().overrides.is_empty());
+
+        entry.insert("mode".to_string(), Value::String("lastwrite".to_string()));
+        entry.insert("keep_rotate".to_string(), Value::Integer(7));
+        entry.insert("last_write_h".to_string(), Value::Integer(24));
+        entry.insert("rotate_start".to_string(), Value::Integer(1));
+        entry.insert("dry_run".to_string(), Value::Bool(true));
+
+        let overrides = parse_file_entry(&entry).unwrap().overrides;
+        assert!(matches!(overrides.mode, Some(CleanUpMode::LastWrite)));
+        assert_eq!(overrides.keep_rotate, Some(7));
+        assert_eq!(overrides.rotate_start, Some(1));
+        assert_eq!(overrides.dry_run, Some(true));
+        assert_eq!(overrides.last_write_h, Some(24));
+        assert_eq!(overrides.copy_truncate, None);
+
+        entry.insert("mode".to_string(), Value::String("Never".to_string()));
+        assert!(parse_file_entry(&entry).is_err());
+    }
+
+    #[test]
+    fn test_parse_groups() {
+        let mut nginx: Table = HashMap::new();
+        nginx.insert(
+            "paths".to_string(),
+            Value::Array(vec![Value::String("/var/log/nginx/*.log".to_string())]),
+        );
+        nginx.insert("keep_rotate".to_string(), Value::Integer(14));
+        nginx.insert("copy_truncate".to_string(), Value::Bool(true));
+
+        let mut app: Table = HashMap::new();
+        app.insert(
+            "postrotate".to_string(),
+            Value::String("systemctl reload app".to_string()),
+        );
+
+        let mut group_tables: Table = HashMap::new();
+        group_tables.insert("nginx".to_string(), Value::Table(nginx));
+        group_tables.insert("app".to_string(), Value::Table(app));
+
+        let mut root: TopLevelTable = HashMap::new();
+        root.insert(GROUP_KEY.to_string(), Value::Table(group_tables));
+
+        //The groups are sorted by the name
+        let groups = parse_groups(&root).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name, "app");
+        assert!(groups[0].paths.is_empty());
+        assert!(groups[0].overrides.is_empty());
+        assert_eq!(groups[1].paths, vec!["/var/log/nginx/*.log"]);
+        assert_eq!(groups[1].overrides.keep_rotate, Some(14));
+
+        //The values of an entry have precedence over the values of its group
+        let entry_overrides = FileOverrides {
+            keep_rotate: Some(3),
+            ..FileOverrides::default()
+        };
+        let combined = entry_overrides.with_fallback(&groups[1].overrides);
+        assert_eq!(combined.keep_rotate, Some(3));
+        assert_eq!(combined.copy_truncate, Some(true));
+    }
+
+    #[test]
+    fn test_parse_combined_modes() {
+        assert_eq!("AnyOf".parse::<CleanUpMode>().unwrap(), CleanUpMode::AnyOf);
+        assert_eq!("All".parse::<CleanUpMode>().unwrap(), CleanUpMode::AnyOf);
+        assert_eq!("AllOf".parse::<CleanUpMode>().unwrap(), CleanUpMode::AllOf);
+        assert_eq!("allof".parse::<CleanUpMode>().unwrap(), CleanUpMode::AllOf);
+        assert!("AnyAll".parse::<CleanUpMode>().is_err());
+        assert!("".parse::<CleanUpMode>().is_err());
+    }
+
     #[test]
     fn test_get_array() {
         let mut root: TopLevelTable = HashMap::new();
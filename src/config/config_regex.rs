@@ -0,0 +1,583 @@
+//! Module for the regular expressions of the redaction filters
+//!
+//! Supports literals, '.', character classes like '[a-z]' or '[^0-9]', the
+//! classes '\d', '\w' and '\s' with their negations, groups with '|', the
+//! anchors '^' and '$' and the greedy quantifiers '*', '+', '?' and '{n,m}'.
+//! The expression is parsed when the config is loaded and matched against
+//! the bytes of a line, so no UTF-8 input is required.
+//!
+//! The expression is compiled into a small program that is executed by a
+//! Pike VM. All possible matches advance together byte by byte, so the
+//! time grows linear with the length of the line and no input can stall a
+//! rotation, like a long line or nested quantifiers such as '(a|a)*b'.
+//!
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+/// Maximum nesting depth of groups, protects the recursive parser
+const MAX_GROUP_DEPTH: usize = 32;
+
+/// Maximum number of instructions of a compiled expression
+const MAX_PROGRAM_SIZE: usize = 10_000;
+
+/// Set of the matched bytes of a character class
+type ByteSet = Box<[bool; 256]>;
+
+/// Enum representing a single element of an expression without its quantifier
+#[derive(Debug, Clone)]
+enum Atom {
+    /// Byte that must match exactly
+    Byte(u8),
+
+    /// Any byte except the line end
+    Any,
+
+    /// One byte of the set
+    Class(ByteSet),
+
+    /// Start of the text
+    Start,
+
+    /// End of the text
+    End,
+
+    /// Alternatives of a group
+    Group(Vec<Vec<Item>>),
+}
+
+/// Represents an atom with the number of repetitions of its quantifier
+#[derive(Debug, Clone)]
+struct Item {
+    atom: Atom,
+    min: usize,
+    max: usize,
+}
+
+/// Represents a parsed regular expression with its source text for logging
+#[derive(Debug, Clone)]
+pub struct Regex {
+    pub source: String,
+    program: Vec<Inst>,
+}
+
+//Implement the Display trait
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Parse a regular expression like "\d{16}"
+pub fn parse_regex(raw: &str) -> Result<Regex, io::Error> {
+    let chars: Vec<char> = raw.chars().collect();
+    let mut pos: usize = 0;
+
+    let alternatives: Vec<Vec<Item>> =
+        parse_alternatives(&chars, &mut pos, 0).map_err(|message| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid regular expression '{}': {}", raw, message),
+            )
+        })?;
+
+    if pos < chars.len() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid regular expression '{}': Unmatched ')'", raw),
+        ));
+    }
+
+    let program: Vec<Inst> = compile(&alternatives).map_err(|message| {
+        io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid regular expression '{}': {}", raw, message),
+        )
+    })?;
+
+    Ok(Regex {
+        source: raw.to_string(),
+        program,
+    })
+}
+
+/// Parse alternatives separated by '|' until the end of the group
+fn parse_alternatives(
+    chars: &[char],
+    pos: &mut usize,
+    depth: usize,
+) -> Result<Vec<Vec<Item>>, String> {
+    let mut alternatives: Vec<Vec<Item>> = vec![parse_sequence(chars, pos, depth)?];
+
+    while chars.get(*pos) == Some(&'|') {
+        *pos += 1;
+        alternatives.push(parse_sequence(chars, pos, depth)?);
+    }
+
+    Ok(alternatives)
+}
+
+/// Parse a sequence of quantified atoms until a '|', a ')' or the end
+fn parse_sequence(chars: &[char], pos: &mut usize, depth: usize) -> Result<Vec<Item>, String> {
+    let mut items: Vec<Item> = Vec::new();
+
+    while let Some(&c) = chars.get(*pos) {
+        *pos += 1;
+
+        let atom: Atom = match c {
+            '|' | ')' => {
+                *pos -= 1;
+                break;
+            }
+            '(' => {
+                if depth >= MAX_GROUP_DEPTH {
+                    return Err("Groups are nested too deep".to_string());
+                }
+
+                //Groups never capture, so the non-capturing syntax is the same group
+                if chars.get(*pos) == Some(&'?') && chars.get(*pos + 1) == Some(&':') {
+                    *pos += 2;
+                }
+
+                let alternatives = parse_alternatives(chars, pos, depth + 1)?;
+                if chars.get(*pos) != Some(&')') {
+                    return Err("Unclosed group".to_string());
+                }
+                *pos += 1;
+                Atom::Group(alternatives)
+            }
+            '[' => Atom::Class(parse_class(chars, pos)?),
+            '.' => Atom::Any,
+            '^' => Atom::Start,
+            '$' => Atom::End,
+            '\\' => parse_escape(chars, pos)?,
+            '*' | '+' | '?' | '{' => return Err(format!("Nothing to repeat before '{}'", c)),
+            c if c.is_ascii() => Atom::Byte(c as u8),
+            c => {
+                //A multibyte char is repeated as a whole by a quantifier
+                let mut buffer = [0; 4];
+                let bytes: Vec<Item> = c
+                    .encode_utf8(&mut buffer)
+                    .bytes()
+                    .map(|b| Item {
+                        atom: Atom::Byte(b),
+                        min: 1,
+                        max: 1,
+                    })
+                    .collect();
+                Atom::Group(vec![bytes])
+            }
+        };
+
+        let (min, max) = parse_quantifier(chars, pos)?;
+        items.push(Item { atom, min, max });
+    }
+
+    Ok(items)
+}
+
+/// Parse the optional quantifier after an atom, returns the minimum and maximum repetitions
+fn parse_quantifier(chars: &[char], pos: &mut usize) -> Result<(usize, usize), String> {
+    let quantifier: (usize, usize) = match chars.get(*pos) {
+        Some('*') => (0, usize::MAX),
+        Some('+') => (1, usize::MAX),
+        Some('?') => (0, 1),
+        Some('{') => {
+            let end: usize = chars[*pos..]
+                .iter()
+                .position(|c| *c == '}')
+                .map(|offset| *pos + offset)
+                .ok_or("Unclosed repetition")?;
+            let content: String = chars[*pos + 1..end].iter().collect();
+            let parse_count = |raw: &str| {
+                raw.parse::<usize>()
+                    .map_err(|_| "Invalid repetition".to_string())
+            };
+
+            let counts: (usize, usize) = match content.split_once(',') {
+                None => (parse_count(&content)?, parse_count(&content)?),
+                Some((min, "")) => (parse_count(min)?, usize::MAX),
+                Some((min, max)) => (parse_count(min)?, parse_count(max)?),
+            };
+
+            if counts.0 > counts.1 {
+                return Err(format!("Invalid repetition {{{}}}", content));
+            }
+
+            *pos = end;
+            counts
+        }
+        _ => return Ok((1, 1)),
+    };
+    *pos += 1;
+
+    if chars.get(*pos) == Some(&'?') {
+        return Err("Lazy quantifiers are not supported".to_string());
+    }
+
+    Ok(quantifier)
+}
+
+/// Parse an escape sequence after a backslash
+fn parse_escape(chars: &[char], pos: &mut usize) -> Result<Atom, String> {
+    let c: char = *chars.get(*pos).ok_or("Trailing backslash")?;
+    *pos += 1;
+
+    match escape_class(c) {
+        Some(set) => Ok(Atom::Class(set)),
+        None => Ok(Atom::Byte(escape_byte(c)?)),
+    }
+}
+
+/// Get the byte of an escaped char like '\n' or '\.'
+fn escape_byte(c: char) -> Result<u8, String> {
+    match c {
+        'n' => Ok(b'\n'),
+        't' => Ok(b'\t'),
+        'r' => Ok(b'\r'),
+        c if c.is_ascii_punctuation() => Ok(c as u8),
+        c => Err(format!("Unsupported escape '\\{}'", c)),
+    }
+}
+
+/// Get the byte set of a class escape like '\d', None for other escapes
+fn escape_class(c: char) -> Option<ByteSet> {
+    let (matcher, negated): (fn(&u8) -> bool, bool) = match c {
+        'd' => (u8::is_ascii_digit, false),
+        'D' => (u8::is_ascii_digit, true),
+        'w' => (|b: &u8| b.is_ascii_alphanumeric() || *b == b'_', false),
+        'W' => (|b: &u8| b.is_ascii_alphanumeric() || *b == b'_', true),
+        's' => (u8::is_ascii_whitespace, false),
+        'S' => (u8::is_ascii_whitespace, true),
+        _ => return None,
+    };
+
+    let mut set: ByteSet = Box::new([false; 256]);
+    for b in 0..=255u8 {
+        set[b as usize] = matcher(&b) != negated;
+    }
+    Some(set)
+}
+
+/// Parse a character class after the '[', a ']' right at the start is a literal
+fn parse_class(chars: &[char], pos: &mut usize) -> Result<ByteSet, String> {
+    let mut set: ByteSet = Box::new([false; 256]);
+    let negated: bool = chars.get(*pos) == Some(&'^');
+    if negated {
+        *pos += 1;
+    }
+
+    let class_start: usize = *pos;
+
+    loop {
+        let c: char = *chars.get(*pos).ok_or("Unclosed character class")?;
+        *pos += 1;
+
+        if c == ']' && *pos - 1 > class_start {
+            break;
+        }
+
+        let first: u8 = match c {
+            '\\' => {
+                let escaped: char = *chars.get(*pos).ok_or("Trailing backslash")?;
+                *pos += 1;
+
+                if let Some(escape_set) = escape_class(escaped) {
+                    for (matched, escape_matched) in set.iter_mut().zip(escape_set.iter()) {
+                        *matched |= *escape_matched;
+                    }
+                    continue;
+                }
+
+                escape_byte(escaped)?
+            }
+            c if c.is_ascii() => c as u8,
+            c => return Err(format!("Character classes only support ASCII, got '{}'", c)),
+        };
+
+        //A '-' before the closing bracket is a literal
+        let last: u8 = match (chars.get(*pos), chars.get(*pos + 1)) {
+            (Some('-'), Some(&end)) if end != ']' && end.is_ascii() => {
+                *pos += 2;
+                end as u8
+            }
+            _ => first,
+        };
+
+        if last < first {
+            return Err(format!("Invalid range {}-{}", first as char, last as char));
+        }
+
+        for b in first..=last {
+            set[b as usize] = true;
+        }
+    }
+
+    if negated {
+        for matched in set.iter_mut() {
+            *matched = !*matched;
+        }
+    }
+
+    Ok(set)
+}
+
+/// Enum representing a single instruction of a compiled expression
+#[derive(Debug, Clone)]
+enum Inst {
+    /// Consume a byte that must match exactly
+    Byte(u8),
+
+    /// Consume any byte except the line end
+    Any,
+
+    /// Consume one byte of the set
+    Class(ByteSet),
+
+    /// Continue only at the start of the text
+    Start,
+
+    /// Continue only at the end of the text
+    End,
+
+    /// Continue at both instructions, the first one has the higher priority
+    Split(usize, usize),
+
+    /// Continue at the instruction
+    Jump(usize),
+
+    /// The expression has matched
+    Match,
+}
+
+/// Compile the alternatives of the expression into the instructions of the matcher
+fn compile(alternatives: &[Vec<Item>]) -> Result<Vec<Inst>, String> {
+    let mut program: Vec<Inst> = Vec::new();
+    compile_alternatives(alternatives, &mut program)?;
+    program.push(Inst::Match);
+    Ok(program)
+}
+
+/// Add an instruction, counted repetitions of groups can create very large programs
+fn push_inst(program: &mut Vec<Inst>, inst: Inst) -> Result<usize, String> {
+    if program.len() >= MAX_PROGRAM_SIZE {
+        return Err("Expression is too large, reduce the counted repetitions".to_string());
+    }
+
+    program.push(inst);
+    Ok(program.len() - 1)
+}
+
+/// Compile alternatives, earlier alternatives have the higher priority
+fn compile_alternatives(alternatives: &[Vec<Item>], program: &mut Vec<Inst>) -> Result<(), String> {
+    let mut jumps: Vec<usize> = Vec::new();
+
+    for (idx, items) in alternatives.iter().enumerate() {
+        if idx + 1 == alternatives.len() {
+            compile_items(items, program)?;
+            break;
+        }
+
+        let split: usize = push_inst(program, Inst::Split(0, 0))?;
+        compile_items(items, program)?;
+        jumps.push(push_inst(program, Inst::Jump(0))?);
+        program[split] = Inst::Split(split + 1, program.len());
+    }
+
+    for jump in jumps {
+        program[jump] = Inst::Jump(program.len());
+    }
+
+    Ok(())
+}
+
+/// Compile a sequence of quantified atoms, repetitions are greedy
+fn compile_items(items: &[Item], program: &mut Vec<Inst>) -> Result<(), String> {
+    for item in items.iter() {
+        for _ in 0..item.min {
+            compile_atom(&item.atom, program)?;
+        }
+
+        if item.max == usize::MAX {
+            let split: usize = push_inst(program, Inst::Split(0, 0))?;
+            compile_atom(&item.atom, program)?;
+            push_inst(program, Inst::Jump(split))?;
+            program[split] = Inst::Split(split + 1, program.len());
+        } else {
+            for _ in item.min..item.max {
+                let split: usize = push_inst(program, Inst::Split(0, 0))?;
+                compile_atom(&item.atom, program)?;
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Compile a single atom without its quantifier
+fn compile_atom(atom: &Atom, program: &mut Vec<Inst>) -> Result<(), String> {
+    let inst: Inst = match atom {
+        Atom::Byte(b) => Inst::Byte(*b),
+        Atom::Any => Inst::Any,
+        Atom::Class(set) => Inst::Class(set.clone()),
+        Atom::Start => Inst::Start,
+        Atom::End => Inst::End,
+        Atom::Group(alternatives) => return compile_alternatives(alternatives, program),
+    };
+
+    push_inst(program, inst)?;
+    Ok(())
+}
+
+/// Represents the threads of the matcher at a text position, ordered by their priority
+struct ThreadList {
+    /// Instruction and match start of every thread
+    threads: Vec<(usize, usize)>,
+
+    /// True for every instruction that was already reached at this position
+    visited: Vec<bool>,
+}
+
+impl ThreadList {
+    fn new(program_size: usize) -> ThreadList {
+        ThreadList {
+            threads: Vec::new(),
+            visited: vec![false; program_size],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.threads.clear();
+        self.visited.fill(false);
+    }
+}
+
+impl Regex {
+    /// Find the first match at or after the start, returns the start and the end of the match.
+    /// All positions are matched in a single pass, so the time grows linear with the text.
+    pub fn find_at(&self, text: &[u8], start: usize) -> Option<(usize, usize)> {
+        let mut current = ThreadList::new(self.program.len());
+        let mut next = ThreadList::new(self.program.len());
+        let mut matched: Option<(usize, usize)> = None;
+
+        for pos in start..=text.len() {
+            //Later starts have a lower priority, no new match is started after the first match
+            if matched.is_none() {
+                self.add_thread(&mut current, 0, pos, text, pos);
+            }
+
+            if current.threads.is_empty() && matched.is_some() {
+                break;
+            }
+
+            for idx in 0..current.threads.len() {
+                let (pc, match_start) = current.threads[idx];
+
+                let consumed: bool = match &self.program[pc] {
+                    Inst::Match => {
+                        //Threads with a lower priority are dropped
+                        matched = Some((match_start, pos));
+                        break;
+                    }
+                    Inst::Byte(expected) => text.get(pos) == Some(expected),
+                    Inst::Any => text.get(pos).is_some_and(|b| *b != b'\n'),
+                    Inst::Class(set) => text.get(pos).is_some_and(|b| set[*b as usize]),
+                    _ => false,
+                };
+
+                if consumed {
+                    self.add_thread(&mut next, pc + 1, pos + 1, text, match_start);
+                }
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+
+        matched
+    }
+
+    /// Add the thread and follow its jumps and anchors without recursion
+    fn add_thread(
+        &self,
+        list: &mut ThreadList,
+        pc: usize,
+        pos: usize,
+        text: &[u8],
+        match_start: usize,
+    ) {
+        let mut stack: Vec<usize> = vec![pc];
+
+        while let Some(pc) = stack.pop() {
+            if list.visited[pc] {
+                continue;
+            }
+            list.visited[pc] = true;
+
+            match &self.program[pc] {
+                Inst::Jump(target) => stack.push(*target),
+                Inst::Split(first, second) => {
+                    //The first branch is processed first, so it keeps the higher priority
+                    stack.push(*second);
+                    stack.push(*first);
+                }
+                Inst::Start if pos == 0 => stack.push(pc + 1),
+                Inst::End if pos == text.len() => stack.push(pc + 1),
+                Inst::Start | Inst::End => {}
+                _ => list.threads.push((pc, match_start)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(pattern: &str, text: &str) -> Option<(usize, usize)> {
+        parse_regex(pattern).unwrap().find_at(text.as_bytes(), 0)
+    }
+
+    #[test]
+    fn test_regex_matching() {
+        assert_eq!(find(r"\d{16}", "card 4111111111111111 ok"), Some((5, 21)));
+        assert_eq!(find(r"\d{16}", "card 4111 1111"), None);
+        assert_eq!(
+            find(r"[a-z.]+@[a-z]+\.(com|org)", "to: jo.e@mail.org"),
+            Some((4, 17))
+        );
+        assert_eq!(find(r"token=\S+", "token=abc def"), Some((0, 9)));
+        assert_eq!(find(r"^a|b$", "cab"), Some((2, 3)));
+        assert_eq!(find(r"(ab)*c", "xababc"), Some((1, 6)));
+        assert_eq!(find(r"[^0-9 ]+", "12 ab3"), Some((3, 5)));
+        assert_eq!(find(r"x?", "abc"), Some((0, 0)));
+        assert_eq!(find(r"pä+", "päää"), Some((0, 7)));
+
+        //Long lines do not exhaust the stack
+        let long_line: String = "a".repeat(200_000) + "b";
+        assert_eq!(find(".*b", &long_line), Some((0, 200_001)));
+
+        let repeated_group: String = "ab".repeat(200_000);
+        assert_eq!(find("(ab)+", &repeated_group), Some((0, 400_000)));
+
+        //Nested alternatives do not backtrack exponentially
+        assert_eq!(find("(a|a)*b", &"a".repeat(30)), None);
+        assert_eq!(find("(a*)*c", "aaaac"), Some((0, 5)));
+        assert_eq!(find("a|ab", "ab"), Some((0, 1)));
+        assert_eq!(find("$", "ab"), Some((2, 2)));
+        assert_eq!(find("b", "ab"), Some((1, 2)));
+
+        for invalid in [
+            "(ab",
+            "ab)",
+            "*a",
+            "a{3,1}",
+            "[a-",
+            r"\b",
+            "a+?",
+            "(a{1000}){1000}",
+        ] {
+            assert!(parse_regex(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,1302 @@
+//! Module for the yalc config schema
+//!
+//! Describes every key that is supported by the config parser with its
+//! default value and documentation. The schema is used to generate the
+//! commented example config, so the template always matches the parser.
+//!
+use crate::config::config_deprecation::{DEPRECATIONS, OptionKind};
+use crate::config::config_migrate::{CONFIG_VERSION_KEY, CURRENT_CONFIG_VERSION};
+use crate::config::config_parser::{self, MAX_SUGGESTION_DISTANCE};
+use crate::config::config_validator::Diagnostic;
+use crate::config::toml_parser::{Table, Value};
+
+/// Name of the array of tables with the file entries
+const FILES_SECTION: &str = "files";
+
+/// Name of the table with the named groups, the groups of the schema are examples
+const GROUP_SECTION: &str = "group";
+
+/// Enum representing how the keys of a schema section are written
+#[derive(Debug, PartialEq)]
+pub enum SectionKind {
+    /// Keys at the root level of the config
+    Root,
+
+    /// Keys of a '[name]' table section
+    Table,
+
+    /// Optional keys of a '[[name]]' array of tables, written as comments
+    ArrayOfTables,
+
+    /// Optional keys of a '[name]' table, written as comments
+    OptionalTable,
+}
+
+/// Represents a group of config keys that are defined in the same table
+pub struct SchemaSection {
+    /// Name of the section, empty for the root section
+    pub name: &'static str,
+
+    /// Documentation of the whole section
+    pub doc: &'static str,
+
+    pub kind: SectionKind,
+    pub keys: &'static [SchemaKey],
+}
+
+/// Represents a single supported config key
+pub struct SchemaKey {
+    /// Name of the key within the section
+    pub name: &'static str,
+
+    /// Default or example value formatted as toml value
+    pub value: &'static str,
+
+    /// Documentation of the key, lines are separated by '\n'
+    pub doc: &'static str,
+
+    /// Optional keys without default value are written as comment
+    pub commented: bool,
+}
+
+/// All supported config keys, ordered as they appear in the example config
+pub const CONFIG_SCHEMA: &[SchemaSection] = &[
+    SchemaSection {
+        name: "",
+        doc: "",
+        kind: SectionKind::Root,
+        keys: &[
+            SchemaKey {
+                name: "dry_run",
+                value: "false",
+                doc: "Only log the cleanup actions without modifying any files",
+                commented: false,
+            },
+            SchemaKey {
+                name: "mode",
+                value: "\"FileSize\"",
+                doc: "Condition for the cleanup of a file\nFileSize: The file is larger than retention.file_size_mib\nLastWrite: The last write is older than retention.last_write_h\nFileAge: The creation of the file is older than retention.file_age_h\nLineCount: The file has more lines than retention.max_lines\nDiskFree: The free space of the filesystem is below retention.min_free_percent\nAnyOf: At least one of the conditions is fulfilled\nAllOf: All conditions are fulfilled, like FileSize and LastWrite together\nAnyOf and AllOf also check FileAge, LineCount and DiskFree when their value is set",
+                commented: false,
+            },
+            SchemaKey {
+                name: "action",
+                value: "\"rotate\"",
+                doc: "Action for the files that are cleaned up: rotate or trim\nTrim rewrites the file in place and only keeps the end of the content, see the trim table",
+                commented: true,
+            },
+            SchemaKey {
+                name: "keep_rotate",
+                value: "3",
+                doc: "Number of rotated files to keep, 0 removes the file instead",
+                commented: false,
+            },
+            SchemaKey {
+                name: "rotate_start",
+                value: "1",
+                doc: "Index of the most recent rotated file like app.log.1, the default is 0\nStarting at 1 keeps the history of files that were rotated by logrotate",
+                commented: true,
+            },
+            SchemaKey {
+                name: "compact_rotations",
+                value: "false",
+                doc: "Renumber the rotated files into a dense sequence before the shift\nGaps like app.log.0, app.log.2 and app.log.5 after manual deletions become 0, 1 and 2",
+                commented: true,
+            },
+            SchemaKey {
+                name: "remove_stray_archives",
+                value: "false",
+                doc: "Remove rotated files with an index outside of keep_rotate in every run\nThese are left by earlier configs with a higher keep_rotate and are never shifted out",
+                commented: true,
+            },
+            SchemaKey {
+                name: "dedup_archives",
+                value: "false",
+                doc: "Store a new archive as hard link when it has the same checksum as the previous rotation\nUseful for logs that rarely change but are rotated on a schedule",
+                commented: true,
+            },
+            SchemaKey {
+                name: "allocated_size",
+                value: "false",
+                doc: "Use the allocated disk blocks as file size for the size conditions and the total budget\nSparse files with holes are otherwise counted with their full length",
+                commented: true,
+            },
+            SchemaKey {
+                name: "remove_empty_dirs",
+                value: "false",
+                doc: "Remove the archive directory when its last expired archive was removed\nWith keep_rotate = 0 the directory of a removed log file is removed when it is empty",
+                commented: true,
+            },
+            SchemaKey {
+                name: "missing_files_ok",
+                value: "true",
+                doc: "Do not report an error when a configured file is missing",
+                commented: false,
+            },
+            SchemaKey {
+                name: "copy_truncate",
+                value: "true",
+                doc: "Copy the file and truncate the original instead of moving it\nWrites during the copy are copied as tail diff right before the truncate",
+                commented: false,
+            },
+            SchemaKey {
+                name: "copy_truncate_lock",
+                value: "false",
+                doc: "Lock the file with flock while it is copied and truncated\nOnly writers that also use flock wait for the rotation",
+                commented: true,
+            },
+            SchemaKey {
+                name: "redact",
+                value: "[\"\\\\d{16}\"]",
+                doc: "Regular expressions whose matches are replaced with [REDACTED] in the rotated files\nThe live log file is not changed, the masking happens before the compression",
+                commented: true,
+            },
+            SchemaKey {
+                name: "shred",
+                value: "false",
+                doc: "Overwrite archives before they are removed by keep_rotate, the age or size cap or the budget\nThe old content can remain on copy-on-write filesystems like btrfs and on SSDs",
+                commented: true,
+            },
+            SchemaKey {
+                name: "manifest",
+                value: "false",
+                doc: "Keep a manifest like app.log.manifest.toml next to the archives\nEach archive is listed with its original path, rotation time, size and SHA-256 checksum",
+                commented: true,
+            },
+            SchemaKey {
+                name: "allow_empty_file_list",
+                value: "false",
+                doc: "Allow a config without any configured files",
+                commented: false,
+            },
+            SchemaKey {
+                name: "rotate_empty",
+                value: "false",
+                doc: "Also rotate files without content, empty files are skipped by default",
+                commented: false,
+            },
+            SchemaKey {
+                name: "skip_unchanged",
+                value: "false",
+                doc: "Skip the rotation when the content is unchanged since the last rotation\nUses the fingerprints of the state file",
+                commented: true,
+            },
+            SchemaKey {
+                name: "create",
+                value: "false",
+                doc: "Create a new empty log file after the log file was renamed\nNot used with copy_truncate, the original file is kept in that case",
+                commented: false,
+            },
+            SchemaKey {
+                name: "create_mode",
+                value: "\"0640\"",
+                doc: "Octal file mode of the created file, the mode of the rotated file when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "create_owner",
+                value: "\"app\"",
+                doc: "Owner of the created file, the owner of the rotated file when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "create_group",
+                value: "\"app\"",
+                doc: "Group of the created file, the group of the rotated file when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "archive_mode",
+                value: "\"0600\"",
+                doc: "Octal file mode of the rotated files, the mode of the log file when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "archive_owner",
+                value: "\"root\"",
+                doc: "Owner of the rotated files, the owner of the log file when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "archive_group",
+                value: "\"adm\"",
+                doc: "Group of the rotated files, the group of the log file when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "compression",
+                value: "\"none\"",
+                doc: "Compression of rotated files: none, gzip, zstd, xz or bzip2\nThe matching program must be installed",
+                commented: false,
+            },
+            SchemaKey {
+                name: "compression_level",
+                value: "6",
+                doc: "Level of the compression algorithm, the default level when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "delay_compress",
+                value: "false",
+                doc: "Keep the most recent rotated file uncompressed until the next rotation\nUseful when a process keeps writing to the rotated file for a short time",
+                commented: false,
+            },
+            SchemaKey {
+                name: "defer_growing",
+                value: "false",
+                doc: "Defer the compression and removal of the fresh archive to the next run\nwhen the file has grown since its conditions were checked",
+                commented: true,
+            },
+            SchemaKey {
+                name: "max_archive_age",
+                value: "\"30d\"",
+                doc: "Remove rotated archives after this time since their last write, independent of keep_rotate\nUnits: s, m, h, d and w",
+                commented: true,
+            },
+            SchemaKey {
+                name: "max_archive_size",
+                value: "\"500MB\"",
+                doc: "Maximum total size of the rotated archives of a file, the oldest archives are removed first\nUnits: B, KB, MB, GB, TB, KiB, MiB, GiB and TiB",
+                commented: true,
+            },
+            SchemaKey {
+                name: "total_budget",
+                value: "\"5GiB\"",
+                doc: "Maximum disk usage of all managed log files and their archives\nThe oldest archives of all files are removed first until the usage is within the budget",
+                commented: true,
+            },
+            SchemaKey {
+                name: "max_bytes_per_run",
+                value: "\"2GiB\"",
+                doc: "Maximum size of the files that are copied, compressed or trimmed in a single run\nFiles that would exceed the limit are deferred to the next run, their archives are still cleaned up",
+                commented: true,
+            },
+            SchemaKey {
+                name: "io_buffer",
+                value: "\"64KiB\"",
+                doc: "Buffer size of copies that can not be done by the kernel and of the compression input\nLarger buffers increase the throughput on remote filesystems at the cost of memory, at most 64MiB",
+                commented: true,
+            },
+            SchemaKey {
+                name: "io_limit",
+                value: "\"20MB/s\"",
+                doc: "Maximum throughput of the copies and the compression of a rotation\nProtects the I/O of services on the same disk, copies are no longer done by the kernel",
+                commented: true,
+            },
+            SchemaKey {
+                name: "frequency",
+                value: "\"daily\"",
+                doc: "Minimum time between two rotations of a file: daily, weekly or monthly\nRotations within this time are skipped, even when a condition is met",
+                commented: true,
+            },
+            SchemaKey {
+                name: "stale_after",
+                value: "\"14d\"",
+                doc: "Warn about managed files without a write for this time, like a service that logs to a new path\nUnits: s, m, h, d and w",
+                commented: true,
+            },
+            SchemaKey {
+                name: "unmanaged_min_size",
+                value: "\"10MiB\"",
+                doc: "Report files of at least this size in the directories of the glob and directory entries that no entry matches\nRotated files and archives are not reported",
+                commented: true,
+            },
+            SchemaKey {
+                name: "state_file",
+                value: "\"/var/lib/yalc/state.toml\"",
+                doc: "File with the state of previous runs like the time of the last rotation\nThe default is '/var/lib/yalc/state.toml' for root and '~/.local/state/yalc/state.toml' for other users",
+                commented: true,
+            },
+            SchemaKey {
+                name: "stats_max_size",
+                value: "\"1MiB\"",
+                doc: "Maximum size of the run statistics next to the state file like '/var/lib/yalc/state.stats'\nThe oldest runs are removed first, no statistics are recorded with \"0B\"",
+                commented: true,
+            },
+            SchemaKey {
+                name: "on_error",
+                value: "\"continue\"",
+                doc: "Behavior after a failed file: continue with the remaining files or abort the run",
+                commented: true,
+            },
+            SchemaKey {
+                name: "fail_on",
+                value: "\"error\"",
+                doc: "Results that make yalc exit with a non-zero status: warn, error or never\nWarnings are missing, skipped, stale, deferred and unmanaged files",
+                commented: true,
+            },
+            SchemaKey {
+                name: "log_level",
+                value: "\"info\"",
+                doc: "Most verbose level of the printed messages: error, warn, info, debug or trace\nThe option '--verbose' has precedence and selects debug, twice trace",
+                commented: true,
+            },
+            SchemaKey {
+                name: "log_file",
+                value: "\"/var/log/yalc.log\"",
+                doc: "File that the printed messages are appended to with their time and level\nKeeps a record of unattended runs when the console output is discarded",
+                commented: true,
+            },
+            SchemaKey {
+                name: "log_file_max_size",
+                value: "\"10MiB\"",
+                doc: "Size of the log file that starts a new log file\nThe previous log file is kept with the suffix '.1'",
+                commented: true,
+            },
+            SchemaKey {
+                name: "symlinks",
+                value: "\"follow\"",
+                doc: "Handling of log files that are symbolic links: follow, skip or error\nFollowed links are resolved, so the final target is rotated and the link is kept",
+                commented: true,
+            },
+            SchemaKey {
+                name: "open_files",
+                value: "\"ignore\"",
+                doc: "Handling of files that a process holds open for writing: ignore, warn, copy_truncate or skip\nRenamed files would still be written by the process, copy_truncate keeps the file in place\nThe writers are detected with /proc, so the check requires Linux",
+                commented: true,
+            },
+            SchemaKey {
+                name: "lock_file",
+                value: "\"/run/yalc.lock\"",
+                doc: "Lock file that prevents overlapping runs, dry runs do not use the lock\nThe default is '/run/yalc.lock' for root and '$XDG_RUNTIME_DIR/yalc.lock' for other users",
+                commented: true,
+            },
+            SchemaKey {
+                name: "audit_log",
+                value: "\"/var/log/yalc-audit.log\"",
+                doc: "Append-only file that records every executed operation with its time and result\nRenames, copies, truncations, deletions and hooks are recorded, dry runs are not",
+                commented: true,
+            },
+            SchemaKey {
+                name: "lock_wait",
+                value: "\"5m\"",
+                doc: "Maximum time to wait for the lock of another run, the run fails right away when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "allowed_roots",
+                value: "[\"/var/log\", \"/opt/app/logs\"]",
+                doc: "Directories that contain all files yalc may change, no restriction when not set\nResolved files, rotation targets and olddirs outside of these directories are refused",
+                commented: true,
+            },
+            SchemaKey {
+                name: "file_list",
+                value: "[\n    \"/var/log/test.log\",\n    \"/opt/app/logs/server.log\"\n]",
+                doc: "Absolute paths of the files to clean up, glob patterns are supported",
+                commented: false,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "retention",
+        doc: "Thresholds that are checked by the cleanup mode",
+        kind: SectionKind::Table,
+        keys: &[
+            SchemaKey {
+                name: "file_size_mib",
+                value: "10",
+                doc: "Minimum file size in MiB for the FileSize mode",
+                commented: false,
+            },
+            SchemaKey {
+                name: "last_write_h",
+                value: "5",
+                doc: "Minimum hours since the last write for the LastWrite mode",
+                commented: false,
+            },
+            SchemaKey {
+                name: "min_free_percent",
+                value: "10",
+                doc: "Minimum percentage of free space on the filesystem for the DiskFree mode",
+                commented: true,
+            },
+            SchemaKey {
+                name: "max_lines",
+                value: "100000",
+                doc: "Maximum number of lines for the LineCount mode",
+                commented: true,
+            },
+            SchemaKey {
+                name: "file_age_h",
+                value: "168",
+                doc: "Minimum hours since the creation of the file for the FileAge mode\nThe last write is used on filesystems without creation time",
+                commented: true,
+            },
+            SchemaKey {
+                name: "min_size",
+                value: "\"1MB\"",
+                doc: "Files below this size are never cleaned up, even when a condition of the mode is met",
+                commented: true,
+            },
+            SchemaKey {
+                name: "min_age",
+                value: "\"1h\"",
+                doc: "Files created within this time are never cleaned up, even when a condition of the mode is met\nUnits: s, m, h, d and w",
+                commented: true,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "trim",
+        doc: "Content that is kept by the trim action, the smallest result of all limits is kept",
+        kind: SectionKind::Table,
+        keys: &[
+            SchemaKey {
+                name: "keep_lines",
+                value: "10000",
+                doc: "Number of lines at the end of the file that are kept",
+                commented: true,
+            },
+            SchemaKey {
+                name: "keep_size",
+                value: "\"50MiB\"",
+                doc: "Maximum size of the kept content, the content starts at the next full line",
+                commented: true,
+            },
+            SchemaKey {
+                name: "max_age",
+                value: "\"7d\"",
+                doc: "Maximum age of the kept lines, read from the timestamp at the start of each line\nThe content starts at the first line with a newer timestamp, lines without timestamp belong to the previous line",
+                commented: true,
+            },
+            SchemaKey {
+                name: "timestamp_format",
+                value: "\"%Y-%m-%d %H:%M:%S\"",
+                doc: "Format of the line timestamps: %Y %m %b %d %H %M %S %f (fraction) %z (zone offset)\nTimestamps without %z are UTC, formats without %Y like syslog use the last year that is not in the future",
+                commented: true,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "retry",
+        doc: "Retries of file operations that failed with transient errors like EBUSY",
+        kind: SectionKind::Table,
+        keys: &[
+            SchemaKey {
+                name: "attempts",
+                value: "3",
+                doc: "Number of attempts of a rename, copy, truncate or remove, 1 disables the retries",
+                commented: true,
+            },
+            SchemaKey {
+                name: "backoff_ms",
+                value: "100",
+                doc: "Milliseconds before the first retry, the wait is doubled for every further retry",
+                commented: true,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "priority",
+        doc: "Lower scheduling priorities of yalc, hooks and compressors inherit them",
+        kind: SectionKind::Table,
+        keys: &[
+            SchemaKey {
+                name: "nice",
+                value: "10",
+                doc: "Nice value of the CPU scheduling from 0 to 19, higher values lower the priority",
+                commented: true,
+            },
+            SchemaKey {
+                name: "io_class",
+                value: "\"best-effort\"",
+                doc: "I/O scheduling class: best-effort or idle",
+                commented: true,
+            },
+            SchemaKey {
+                name: "io_level",
+                value: "7",
+                doc: "Level of the best-effort class from 0 to 7, higher values lower the priority",
+                commented: true,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "syslog",
+        doc: "Messages of yalc that are also sent to the local syslog daemon",
+        kind: SectionKind::Table,
+        keys: &[
+            SchemaKey {
+                name: "enabled",
+                value: "true",
+                doc: "Send every printed message to the socket of the syslog daemon like /dev/log",
+                commented: true,
+            },
+            SchemaKey {
+                name: "facility",
+                value: "\"daemon\"",
+                doc: "Facility of the messages: user, mail, daemon, auth, syslog, cron, authpriv or local0 to local7\nThe default is user",
+                commented: true,
+            },
+            SchemaKey {
+                name: "priority",
+                value: "\"notice\"",
+                doc: "Severity of the status messages from emerg to debug, the default is info\nErrors and warnings are always sent with the severities err and warning",
+                commented: true,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "daemon",
+        doc: "Settings of the daemon mode started by 'yalc daemon'",
+        kind: SectionKind::Table,
+        keys: &[
+            SchemaKey {
+                name: "interval_s",
+                value: "3600",
+                doc: "Seconds between two cleanup runs",
+                commented: false,
+            },
+            SchemaKey {
+                name: "reload",
+                value: "true",
+                doc: "Reload the config when the config file or drop-in directory changes",
+                commented: false,
+            },
+            SchemaKey {
+                name: "status_listen",
+                value: "\"127.0.0.1:9810\"",
+                doc: "Address of the http endpoint for monitoring, disabled when not set\n/healthz: Returns 200 while the daemon is running\n/status: Last run, next scheduled run and the errors of the last run as json\n/metrics: Run counters and the sizes of the managed files for Prometheus\nA change is only applied after a restart of the daemon",
+                commented: true,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "upload",
+        doc: "Upload of every finished archive to remote storage, requires a backend table",
+        kind: SectionKind::OptionalTable,
+        keys: &[
+            SchemaKey {
+                name: "delete_local",
+                value: "false",
+                doc: "Remove the local archive after a successful upload, failed uploads keep it",
+                commented: false,
+            },
+            SchemaKey {
+                name: "name",
+                value: "\"{hostname}/{app}/{name}-{time}\"",
+                doc: "Template of the remote archive names, the extension of the compression is appended\nTokens: {name} log file name, {time} upload time, {hostname} and {app} group or log name\nThe default is \"{name}-{time}\", the template must contain {time}",
+                commented: true,
+            },
+            SchemaKey {
+                name: "attempts",
+                value: "3",
+                doc: "Number of attempts of an upload, 1 disables the retries",
+                commented: false,
+            },
+            SchemaKey {
+                name: "backoff_ms",
+                value: "1000",
+                doc: "Milliseconds before the first retry, doubled for every further retry",
+                commented: false,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "upload.s3",
+        doc: "S3 bucket of the upload, the archives are copied with the installed aws cli",
+        kind: SectionKind::OptionalTable,
+        keys: &[
+            SchemaKey {
+                name: "bucket",
+                value: "\"company-logs\"",
+                doc: "Name of the bucket",
+                commented: false,
+            },
+            SchemaKey {
+                name: "prefix",
+                value: "\"web1/\"",
+                doc: "Prefix of the object keys, the keys are like 'web1/app.log-20240301T123000Z.gz'",
+                commented: false,
+            },
+            SchemaKey {
+                name: "region",
+                value: "\"eu-central-1\"",
+                doc: "Region of the bucket, the region of the aws cli config when not set",
+                commented: false,
+            },
+            SchemaKey {
+                name: "credentials",
+                value: "\"default\"",
+                doc: "Source of the credentials: default, env or profile:NAME\ndefault: Credential chain of the aws cli, like the instance profile\nenv: The variables AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY\nprofile:NAME: Named profile of the aws cli config",
+                commented: false,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "upload.ssh",
+        doc: "Remote directory of the upload, the archives are copied with scp or sftp in batch mode",
+        kind: SectionKind::OptionalTable,
+        keys: &[
+            SchemaKey {
+                name: "host",
+                value: "\"backup.example.com\"",
+                doc: "Host name of the ssh server",
+                commented: false,
+            },
+            SchemaKey {
+                name: "user",
+                value: "\"logs\"",
+                doc: "Login user, the user of the ssh config when not set",
+                commented: false,
+            },
+            SchemaKey {
+                name: "port",
+                value: "22",
+                doc: "Port of the ssh server, the port of the ssh config when not set",
+                commented: false,
+            },
+            SchemaKey {
+                name: "path",
+                value: "\"/srv/logs/web1/\"",
+                doc: "Existing remote directory of the archives",
+                commented: false,
+            },
+            SchemaKey {
+                name: "identity_file",
+                value: "\"/etc/yalc/id_ed25519\"",
+                doc: "Absolute path of the private key, the keys of the ssh config when not set",
+                commented: false,
+            },
+            SchemaKey {
+                name: "transfer",
+                value: "\"scp\"",
+                doc: "Program of the transfer: scp or sftp",
+                commented: false,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "upload.gcs",
+        doc: "Google Cloud Storage bucket of the upload, requires the build feature 'gcs' and the gcloud cli",
+        kind: SectionKind::OptionalTable,
+        keys: &[
+            SchemaKey {
+                name: "bucket",
+                value: "\"company-logs\"",
+                doc: "Name of the bucket",
+                commented: false,
+            },
+            SchemaKey {
+                name: "prefix",
+                value: "\"web1/\"",
+                doc: "Prefix of the object names",
+                commented: false,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "upload.azure",
+        doc: "Azure Blob Storage container of the upload, requires the build feature 'azure' and the az cli",
+        kind: SectionKind::OptionalTable,
+        keys: &[
+            SchemaKey {
+                name: "account",
+                value: "\"companylogs\"",
+                doc: "Name of the storage account",
+                commented: false,
+            },
+            SchemaKey {
+                name: "container",
+                value: "\"archives\"",
+                doc: "Name of the container",
+                commented: false,
+            },
+            SchemaKey {
+                name: "prefix",
+                value: "\"web1/\"",
+                doc: "Prefix of the blob names",
+                commented: false,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "upload.webdav",
+        doc: "WebDAV collection of the upload, requires the build feature 'webdav' and curl",
+        kind: SectionKind::OptionalTable,
+        keys: &[
+            SchemaKey {
+                name: "url",
+                value: "\"https://dav.example.com/logs/web1/\"",
+                doc: "Url of the existing collection",
+                commented: false,
+            },
+            SchemaKey {
+                name: "netrc_file",
+                value: "\"/etc/yalc/netrc\"",
+                doc: "Absolute path of a netrc file with the credentials of the server",
+                commented: false,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "notify.email",
+        doc: "Summary of the run sent as email with the installed curl",
+        kind: SectionKind::OptionalTable,
+        keys: &[
+            SchemaKey {
+                name: "server",
+                value: "\"smtp://mail.example.com:25\"",
+                doc: "Host name or url of the SMTP server, smtps:// urls use TLS",
+                commented: false,
+            },
+            SchemaKey {
+                name: "from",
+                value: "\"yalc@example.com\"",
+                doc: "Sender address of the emails",
+                commented: false,
+            },
+            SchemaKey {
+                name: "to",
+                value: "[\"ops@example.com\"]",
+                doc: "Recipient addresses of the emails",
+                commented: false,
+            },
+            SchemaKey {
+                name: "on",
+                value: "\"failure\"",
+                doc: "Runs that send their summary: failure or always",
+                commented: false,
+            },
+            SchemaKey {
+                name: "events",
+                value: "[\"run_finished\"]",
+                doc: "Events that send an email: run_finished, task_failed and budget_exceeded",
+                commented: true,
+            },
+            SchemaKey {
+                name: "netrc_file",
+                value: "\"/etc/yalc/netrc\"",
+                doc: "Absolute path of a netrc file with the login of the server",
+                commented: true,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "notify.slack",
+        doc: "Messages posted to a Slack incoming webhook with the installed curl",
+        kind: SectionKind::OptionalTable,
+        keys: &[
+            SchemaKey {
+                name: "webhook_url",
+                value: "\"https://hooks.slack.com/services/T000/B000/XXXX\"",
+                doc: "Url of the incoming webhook, keep the config file private as the url is the secret",
+                commented: false,
+            },
+            SchemaKey {
+                name: "on",
+                value: "\"failure\"",
+                doc: "Runs that send their summary: failure or always",
+                commented: false,
+            },
+            SchemaKey {
+                name: "events",
+                value: "[\"run_finished\", \"budget_exceeded\"]",
+                doc: "Events that post a message: run_finished, task_failed and budget_exceeded\nThe default is [\"run_finished\"]",
+                commented: true,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "notify.matrix",
+        doc: "Messages sent to a Matrix room with the installed curl",
+        kind: SectionKind::OptionalTable,
+        keys: &[
+            SchemaKey {
+                name: "homeserver",
+                value: "\"https://matrix.example.com\"",
+                doc: "Url of the homeserver",
+                commented: false,
+            },
+            SchemaKey {
+                name: "room_id",
+                value: "\"!abc123:example.com\"",
+                doc: "Internal id of the room, the sending user must have joined it",
+                commented: false,
+            },
+            SchemaKey {
+                name: "access_token_file",
+                value: "\"/etc/yalc/matrix-token\"",
+                doc: "Absolute path of a file with the access token of the sending user",
+                commented: false,
+            },
+            SchemaKey {
+                name: "on",
+                value: "\"failure\"",
+                doc: "Runs that send their summary: failure or always",
+                commented: false,
+            },
+            SchemaKey {
+                name: "events",
+                value: "[\"run_finished\", \"task_failed\"]",
+                doc: "Events that send a message: run_finished, task_failed and budget_exceeded\nThe default is [\"run_finished\"]",
+                commented: true,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "files",
+        doc: "Additional file sources, each entry requires either 'path' or 'dir'",
+        kind: SectionKind::ArrayOfTables,
+        keys: &[
+            SchemaKey {
+                name: "path",
+                value: "\"/var/log/app/*.log\"",
+                doc: "Absolute path or glob pattern of the files",
+                commented: false,
+            },
+            SchemaKey {
+                name: "dir",
+                value: "\"/var/log/containers\"",
+                doc: "Absolute path of a directory, used instead of 'path'",
+                commented: false,
+            },
+            SchemaKey {
+                name: "recursive",
+                value: "false",
+                doc: "Also include the files of sub directories",
+                commented: false,
+            },
+            SchemaKey {
+                name: "extensions",
+                value: "[\"log\"]",
+                doc: "Only include files with these extensions, all files when empty",
+                commented: false,
+            },
+            SchemaKey {
+                name: "max_depth",
+                value: "2",
+                doc: "Maximum directory depth for recursive scans",
+                commented: false,
+            },
+            SchemaKey {
+                name: "group",
+                value: "\"nginx\"",
+                doc: "Name of the group with the shared settings and hooks of the files",
+                commented: false,
+            },
+            SchemaKey {
+                name: "priority",
+                value: "10",
+                doc: "Processing order of the files, higher priorities first, default 0\nInside of a group the files are ordered by their priority",
+                commented: false,
+            },
+            SchemaKey {
+                name: "condition",
+                value: "\"size > 100MB && age > 2d\"",
+                doc: "Condition expression that replaces the mode for these files\nFields: size, age (last write), file_age (creation), lines and free (percent)\nOperators: > >= < <= == != && || ! and parentheses",
+                commented: true,
+            },
+            SchemaKey {
+                name: "dry_run",
+                value: "true",
+                doc: "Only log the actions of these files, useful to observe a new entry\nA global dry run can not be disabled for single files",
+                commented: true,
+            },
+            SchemaKey {
+                name: "mode",
+                value: "\"LastWrite\"",
+                doc: "Cleanup mode of these files, the global mode when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "action",
+                value: "\"trim\"",
+                doc: "Cleanup action of these files, the global action when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "trim_keep_lines",
+                value: "1000",
+                doc: "Replaces trim.keep_lines for these files",
+                commented: true,
+            },
+            SchemaKey {
+                name: "trim_keep_size",
+                value: "\"10MiB\"",
+                doc: "Replaces trim.keep_size for these files",
+                commented: true,
+            },
+            SchemaKey {
+                name: "trim_max_age",
+                value: "\"2d\"",
+                doc: "Replaces trim.max_age for these files",
+                commented: true,
+            },
+            SchemaKey {
+                name: "trim_timestamp_format",
+                value: "\"%b %d %H:%M:%S\"",
+                doc: "Replaces trim.timestamp_format for these files",
+                commented: true,
+            },
+            SchemaKey {
+                name: "keep_rotate",
+                value: "7",
+                doc: "Number of rotated files to keep, the global value when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "rotate_start",
+                value: "1",
+                doc: "Index of the most recent rotated file of these files, the global value when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "copy_truncate",
+                value: "false",
+                doc: "Copy and truncate these files, the global value when not set\nThe run argument --trunc copies and truncates all files",
+                commented: true,
+            },
+            SchemaKey {
+                name: "file_size_mib",
+                value: "100",
+                doc: "Replaces retention.file_size_mib for these files",
+                commented: true,
+            },
+            SchemaKey {
+                name: "last_write_h",
+                value: "24",
+                doc: "Replaces retention.last_write_h for these files",
+                commented: true,
+            },
+            SchemaKey {
+                name: "max_archive_age",
+                value: "\"7d\"",
+                doc: "Maximum age of the rotated archives of these files",
+                commented: true,
+            },
+            SchemaKey {
+                name: "frequency",
+                value: "\"weekly\"",
+                doc: "Minimum time between two rotations of these files",
+                commented: true,
+            },
+            SchemaKey {
+                name: "symlinks",
+                value: "\"skip\"",
+                doc: "Handling of symbolic links for these files, the global value when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "redact",
+                value: "[\"password=\\\\S+\"]",
+                doc: "Redaction expressions of these files, the global list when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "shred",
+                value: "true",
+                doc: "Overwrite the removed archives of these files, the global value when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "manifest",
+                value: "true",
+                doc: "Keep a manifest of the archives of these files, the global value when not set",
+                commented: true,
+            },
+            SchemaKey {
+                name: "max_archive_size",
+                value: "\"1GiB\"",
+                doc: "Maximum total size of the rotated archives of these files",
+                commented: true,
+            },
+            SchemaKey {
+                name: "signal",
+                value: "\"HUP\"",
+                doc: "Signal that is sent to the process of the pidfile after a rotation",
+                commented: false,
+            },
+            SchemaKey {
+                name: "pidfile",
+                value: "\"/run/app.pid\"",
+                doc: "Absolute path of the file with the process id, required for 'signal'",
+                commented: false,
+            },
+            SchemaKey {
+                name: "olddir",
+                value: "\"/var/log/archive\"",
+                doc: "Directory for the rotated files, created when missing\nMust be on the same filesystem unless copy_truncate is used",
+                commented: false,
+            },
+        ],
+    },
+    SchemaSection {
+        name: "group.nginx",
+        doc: "Named group of files with shared settings and hooks that run once per run for the group",
+        kind: SectionKind::OptionalTable,
+        keys: &[
+            SchemaKey {
+                name: "paths",
+                value: "[\"/var/log/nginx/*.log\"]",
+                doc: "File paths of the group, glob patterns are expanded\nFile entries join the group with their group key",
+                commented: false,
+            },
+            SchemaKey {
+                name: "priority",
+                value: "100",
+                doc: "Processing order of the group, higher priorities first, default 0\nThe files of a group are always processed together",
+                commented: false,
+            },
+            SchemaKey {
+                name: "max_total_size",
+                value: "\"500MiB\"",
+                doc: "Combined size of all files of the group and their archives that triggers a cleanup\nThe oldest archives are removed first, then the oldest files are cleaned up",
+                commented: true,
+            },
+            SchemaKey {
+                name: "keep_rotate",
+                value: "14",
+                doc: "Shared rotation settings of the files, like the keys mode, keep_rotate or action of a file entry\nThe keys of a file entry have precedence over the keys of its group",
+                commented: false,
+            },
+            SchemaKey {
+                name: "prerotate",
+                value: "\"systemctl stop nginx-exporter\"",
+                doc: "Shell command before the first file of the group is rotated",
+                commented: false,
+            },
+            SchemaKey {
+                name: "postrotate",
+                value: "\"systemctl reload nginx\"",
+                doc: "Shell command after all files of the group were processed\nOnly executed when at least one file was rotated",
+                commented: false,
+            },
+        ],
+    },
+];
+
+/// Generate the commented example config from the schema
+pub fn example_config() -> String {
+    let mut output = String::from("# Yalc log rotation config\n");
+    output.push_str(&format!(
+        "\n# Schema version of this config file\n{} = {}\n",
+        CONFIG_VERSION_KEY, CURRENT_CONFIG_VERSION
+    ));
+
+    for section in CONFIG_SCHEMA.iter() {
+        //Optional sections are commented out completely
+        let prefix: &str = match section.kind {
+            SectionKind::ArrayOfTables | SectionKind::OptionalTable => "# ",
+            _ => "",
+        };
+
+        if !section.doc.is_empty() {
+            output.push_str(&format!("\n# {}\n", section.doc));
+        }
+
+        match section.kind {
+            SectionKind::Root => {}
+            SectionKind::Table => output.push_str(&format!("[{}]\n", section.name)),
+            SectionKind::OptionalTable => {
+                output.push_str(&format!("{}[{}]\n", prefix, section.name))
+            }
+            SectionKind::ArrayOfTables => {
+                output.push_str(&format!("{}[[{}]]\n", prefix, section.name))
+            }
+        }
+
+        for (idx_key, key) in section.keys.iter().enumerate() {
+            //Section keys directly follow the section header
+            if section.kind == SectionKind::Root || idx_key > 0 {
+                output.push('\n');
+            }
+
+            for doc_line in key.doc.lines() {
+                output.push_str(&format!("# {}\n", doc_line));
+            }
+
+            let prefix: &str = if key.commented { "# " } else { prefix };
+
+            for (idx, value_line) in key.value.lines().enumerate() {
+                match idx {
+                    0 => output.push_str(&format!("{}{} = {}\n", prefix, key.name, value_line)),
+                    _ => output.push_str(&format!("{}{}\n", prefix, value_line)),
+                }
+            }
+        }
+    }
+
+    output
+}
+
+/// Find all keys of the table that are not part of the schema.
+/// Unknown keys are ignored by the parser, so they are usually typos.
+pub fn find_unknown_keys(root: &Table) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    check_known_keys(&mut diagnostics, root, "");
+
+    //The order of the table keys is random
+    diagnostics.sort_by(|a, b| a.key.cmp(&b.key));
+    diagnostics
+}
+
+/// Add a diagnostic for every unknown key of the table at the path
+fn check_known_keys(diagnostics: &mut Vec<Diagnostic>, table: &Table, path: &str) {
+    let known_keys: Vec<&str> = known_keys(path);
+
+    for (key, value) in table.iter() {
+        let key_path: String = match path {
+            "" => key.to_string(),
+            _ => format!("{}.{}", path, key),
+        };
+
+        //Every table in the group table is a named group
+        if path != GROUP_SECTION && !known_keys.contains(&key.as_str()) {
+            //Deprecated keys are reported by their own warning
+            let is_deprecated: bool = DEPRECATIONS.iter().any(|deprecation| {
+                deprecation.kind == OptionKind::ConfigKey && deprecation.name == key_path
+            });
+
+            if !is_deprecated {
+                diagnostics.push(unknown_key(&key_path, key, &known_keys));
+            }
+            continue;
+        }
+
+        match value {
+            Value::Table(sub_table) => check_known_keys(diagnostics, sub_table, &key_path),
+            Value::Array(entries) if key_path == FILES_SECTION => {
+                for (idx, entry) in entries.iter().enumerate() {
+                    if let Value::Table(entry) = entry {
+                        check_entry_keys(diagnostics, entry, idx);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Add a diagnostic for every unknown key of a file entry
+fn check_entry_keys(diagnostics: &mut Vec<Diagnostic>, entry: &Table, idx: usize) {
+    let known_keys: Vec<&str> = known_keys(FILES_SECTION);
+
+    for key in entry.keys() {
+        if !known_keys.contains(&key.as_str()) {
+            let key_path: String = format!("{}[{}].{}", FILES_SECTION, idx + 1, key);
+            diagnostics.push(unknown_key(&key_path, key, &known_keys));
+        }
+    }
+}
+
+/// Get the keys and sub tables of the schema that are supported in the table at the path
+fn known_keys(path: &str) -> Vec<&'static str> {
+    let mut known_keys: Vec<&'static str> = Vec::new();
+
+    //Groups support the keys of the example group and the overrides of the file entries
+    let section_names: Vec<&str> = match path.split_once('.') {
+        Some((GROUP_SECTION, _)) => vec!["group.nginx", FILES_SECTION],
+        _ => vec![path],
+    };
+
+    for section in CONFIG_SCHEMA.iter() {
+        if section_names.contains(&section.name) {
+            known_keys.extend(section.keys.iter().map(|key| key.name));
+        }
+
+        //Sections below the path are known sub tables, like 'upload.s3' for 'upload'
+        let sub_table: Option<&'static str> = match path {
+            "" => section.name.split('.').next(),
+            _ => section
+                .name
+                .strip_prefix(path)
+                .and_then(|name| name.strip_prefix('.'))
+                .and_then(|name| name.split('.').next()),
+        };
+
+        if let Some(sub_table) = sub_table.filter(|name| !name.is_empty()) {
+            known_keys.push(sub_table);
+        }
+    }
+
+    if path.is_empty() {
+        known_keys.push(CONFIG_VERSION_KEY);
+    }
+
+    known_keys
+}
+
+/// Create the diagnostic of an unknown key with the most similar known key
+fn unknown_key(key_path: &str, key: &str, known_keys: &[&str]) -> Diagnostic {
+    let suggestion: Option<&str> = known_keys
+        .iter()
+        .map(|known_key| (config_parser::edit_distance(known_key, key), *known_key))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min()
+        .map(|(_, known_key)| known_key);
+
+    let message: String = match suggestion {
+        Some(known_key) => format!("Unknown key is ignored, did you mean '{}'?", known_key),
+        None => "Unknown key is ignored".to_string(),
+    };
+
+    Diagnostic {
+        key: key_path.to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::toml_parser::parse_toml;
+    use crate::config::{config_parser, config_validator};
+
+    #[test]
+    fn test_example_config_is_valid() {
+        let root = parse_toml(&example_config()).unwrap();
+        let config = config_parser::parse_config(&root).unwrap();
+
+        assert!(config_validator::validate_config(&config).is_ok());
+        assert_eq!(
+            root.get(CONFIG_VERSION_KEY),
+            Some(&Value::Integer(CURRENT_CONFIG_VERSION as i64))
+        );
+    }
+
+    #[test]
+    fn test_example_contains_schema_keys() {
+        let root = parse_toml(&example_config()).unwrap();
+
+        for section in CONFIG_SCHEMA.iter() {
+            for key in section.keys.iter().filter(|k| !k.commented) {
+                let value = match section.kind {
+                    SectionKind::Root => root.get(key.name),
+                    SectionKind::Table => match root.get(section.name) {
+                        Some(Value::Table(table)) => table.get(key.name),
+                        _ => None,
+                    },
+                    SectionKind::ArrayOfTables | SectionKind::OptionalTable => continue,
+                };
+
+                assert!(value.is_some(), "Missing key in example: {}", key.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_unknown_keys() {
+        let root = parse_toml(
+            "keep_rotat = 3\nconfig_version = 2\n\n[retention]\nfile_size_mib = 10\nfile_size_mb = 10\n\n[upload.s3]\nbuckett = \"logs\"\n\n[group.web]\npaths = [\"/var/log/web/*.log\"]\ncopy_truncate = true\n\n[[files]]\npath = \"/var/log/app.log\"\ncolor = \"red\"\n",
+        )
+        .unwrap();
+
+        let keys: Vec<String> = find_unknown_keys(&root)
+            .into_iter()
+            .map(|d| d.to_string())
+            .collect();
+
+        assert_eq!(
+            keys,
+            vec![
+                "files[1].color: Unknown key is ignored",
+                "keep_rotat: Unknown key is ignored, did you mean 'keep_rotate'?",
+                "retention.file_size_mb: Unknown key is ignored, did you mean 'file_size_mib'?",
+                "upload.s3.buckett: Unknown key is ignored, did you mean 'bucket'?"
+            ]
+        );
+
+        //The example config only contains known keys
+        assert!(find_unknown_keys(&parse_toml(&example_config()).unwrap()).is_empty());
+    }
+}
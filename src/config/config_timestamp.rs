@@ -0,0 +1,305 @@
+//! Module for the timestamp formats of log lines
+//!
+//! The trim action can drop all lines that are older than a maximum age.
+//! The age of a line is read from the timestamp at the start of the line.
+//! The format uses strftime like specifiers and is parsed when the config
+//! is loaded, so unknown specifiers are reported before any file is read.
+//! Timestamps without a zone offset are interpreted as UTC.
+//!
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+/// Format of the line timestamps when no format is configured
+pub const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Short names of the months for the '%b' specifier
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Enum representing a single element of a timestamp format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormatItem {
+    /// Character that must match exactly
+    Literal(char),
+
+    /// Year with four digits '%Y'
+    Year,
+
+    /// Month number '%m'
+    Month,
+
+    /// Short month name like 'Jan' '%b'
+    MonthName,
+
+    /// Day of the month, a leading space is allowed '%d'
+    Day,
+
+    /// Hour of the day '%H'
+    Hour,
+
+    /// Minute '%M'
+    Minute,
+
+    /// Second '%S'
+    Second,
+
+    /// Fractional seconds with any number of digits '%f'
+    Fraction,
+
+    /// Zone offset like 'Z', '+0200' or '+02:00' '%z'
+    Zone,
+}
+
+/// Represents a parsed timestamp format with its source text for logging
+#[derive(Debug, Clone)]
+pub struct TimestampFormat {
+    pub source: String,
+    pub items: Vec<FormatItem>,
+}
+
+//Implement the Display trait
+impl fmt::Display for TimestampFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Parse a timestamp format like "%Y-%m-%d %H:%M:%S"
+pub fn parse_timestamp_format(raw: &str) -> Result<TimestampFormat, io::Error> {
+    let mut items: Vec<FormatItem> = Vec::new();
+    let mut chars = raw.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            items.push(FormatItem::Literal(c));
+            continue;
+        }
+
+        let item: FormatItem = match chars.next() {
+            Some('Y') => FormatItem::Year,
+            Some('m') => FormatItem::Month,
+            Some('b') => FormatItem::MonthName,
+            Some('d') | Some('e') => FormatItem::Day,
+            Some('H') => FormatItem::Hour,
+            Some('M') => FormatItem::Minute,
+            Some('S') => FormatItem::Second,
+            Some('f') => FormatItem::Fraction,
+            Some('z') => FormatItem::Zone,
+            Some('%') => FormatItem::Literal('%'),
+            other => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid timestamp format '{}': Unknown specifier '%{}'",
+                        raw,
+                        other.map_or(String::new(), String::from)
+                    ),
+                ));
+            }
+        };
+
+        items.push(item);
+    }
+
+    let has_time: bool = items.contains(&FormatItem::Hour);
+    let has_date: bool = items.contains(&FormatItem::Day)
+        && (items.contains(&FormatItem::Month) || items.contains(&FormatItem::MonthName));
+
+    if !has_time || !has_date {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Invalid timestamp format '{}': The format requires a day, a month and an hour",
+                raw
+            ),
+        ));
+    }
+
+    Ok(TimestampFormat {
+        source: raw.to_string(),
+        items,
+    })
+}
+
+impl TimestampFormat {
+    /// Get the unix time of the timestamp at the start of the line, None without a timestamp.
+    /// Formats without year use the most recent year that is not in the future.
+    pub fn parse_leading(&self, line: &str, now_s: u64) -> Option<u64> {
+        let mut rest: &str = line;
+        let mut year: Option<i64> = None;
+        let (mut month, mut day, mut hour, mut minute, mut second) = (1, 1, 0, 0, 0);
+        let mut offset_s: i64 = 0;
+
+        for item in self.items.iter() {
+            match item {
+                FormatItem::Literal(c) => rest = rest.strip_prefix(*c)?,
+                FormatItem::Year => year = Some(take_number(&mut rest, 4, 4)?),
+                FormatItem::Month => month = take_number(&mut rest, 1, 2)?,
+                FormatItem::MonthName => {
+                    let idx: usize = MONTH_NAMES.iter().position(|name| rest.starts_with(name))?;
+                    month = idx as i64 + 1;
+                    rest = &rest[3..];
+                }
+                FormatItem::Day => {
+                    rest = rest.strip_prefix(' ').unwrap_or(rest);
+                    day = take_number(&mut rest, 1, 2)?;
+                }
+                FormatItem::Hour => hour = take_number(&mut rest, 1, 2)?,
+                FormatItem::Minute => minute = take_number(&mut rest, 1, 2)?,
+                FormatItem::Second => second = take_number(&mut rest, 1, 2)?,
+                FormatItem::Fraction => {
+                    take_number(&mut rest, 1, usize::MAX)?;
+                }
+                FormatItem::Zone => offset_s = take_zone(&mut rest)?,
+            }
+        }
+
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+            return None;
+        }
+
+        let time_of_day: i64 = hour * 3600 + minute * 60 + second - offset_s;
+        let unix_time = |year: i64| days_from_civil(year, month, day) * 86400 + time_of_day;
+
+        let timestamp_s: i64 = match year {
+            Some(year) => unix_time(year),
+            None => {
+                //The timestamp is placed in the current year, a future time is from the last year
+                let now_year: i64 = civil_year(now_s);
+                match unix_time(now_year) > now_s as i64 + 86400 {
+                    true => unix_time(now_year - 1),
+                    false => unix_time(now_year),
+                }
+            }
+        };
+
+        u64::try_from(timestamp_s).ok()
+    }
+}
+
+/// Take a decimal number with a digit count within the limits from the start of the text
+fn take_number(rest: &mut &str, min_digits: usize, max_digits: usize) -> Option<i64> {
+    let digits: usize = rest
+        .bytes()
+        .take(max_digits)
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+
+    if digits < min_digits {
+        return None;
+    }
+
+    //Long fractions do not fit into the number, they are only skipped
+    let value: i64 = rest[..digits].parse().unwrap_or(0);
+    *rest = &rest[digits..];
+    Some(value)
+}
+
+/// Take a zone offset from the start of the text, returns the offset in seconds east of UTC
+fn take_zone(rest: &mut &str) -> Option<i64> {
+    if let Some(after) = rest.strip_prefix('Z') {
+        *rest = after;
+        return Some(0);
+    }
+
+    let sign: i64 = match rest.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    *rest = &rest[1..];
+
+    let hours: i64 = take_number(rest, 2, 2)?;
+    *rest = rest.strip_prefix(':').unwrap_or(rest);
+    let minutes: i64 = take_number(rest, 2, 2)?;
+
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Get the number of days since 1970-01-01 of a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year: i64 = if month <= 2 { year - 1 } else { year };
+    let era: i64 = year.div_euclid(400);
+    let year_of_era: i64 = year - era * 400;
+    let day_of_year: i64 = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era: i64 = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+/// Get the year, month and day of the days since 1970-01-01 in the proleptic Gregorian calendar
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let shifted_days: i64 = days + 719468;
+    let era: i64 = shifted_days.div_euclid(146097);
+    let day_of_era: i64 = shifted_days - era * 146097;
+    let year_of_era: i64 =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index: i64 = (5 * day_of_year + 2) / 153;
+    let day: i64 = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month: i64 = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+
+    //The months of the calculation start in march, so january and february are in the next year
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Get the year of a unix time
+fn civil_year(unix_s: u64) -> i64 {
+    civil_from_days((unix_s / 86400) as i64).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19783), (2024, 3, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+
+        for days in [-719468, -1, 0, 59, 11016, 19782, 2932896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn test_parse_leading_timestamp() {
+        let iso = parse_timestamp_format("%Y-%m-%dT%H:%M:%S%z").unwrap();
+        assert_eq!(
+            iso.parse_leading("2024-03-01T12:30:00+02:00 service started", 0),
+            Some(1709289000)
+        );
+        assert_eq!(iso.parse_leading("2024-03-01T10:30:00.123Z", 0), None);
+        assert_eq!(iso.parse_leading("  at Main.java:12", 0), None);
+
+        let with_fraction = parse_timestamp_format("[%Y-%m-%d %H:%M:%S.%f]").unwrap();
+        assert_eq!(
+            with_fraction.parse_leading("[1970-01-02 00:00:01.500] x", 0),
+            Some(86401)
+        );
+
+        //Syslog timestamps have no year, the time is placed in the year of now
+        let syslog = parse_timestamp_format("%b %d %H:%M:%S").unwrap();
+        let now_s: u64 = 1709289000;
+        assert_eq!(
+            syslog.parse_leading("Mar  1 10:30:00 host", now_s),
+            Some(now_s)
+        );
+        assert_eq!(
+            syslog.parse_leading("Dec 31 00:00:00 host", now_s),
+            Some(1703980800)
+        );
+
+        assert!(parse_timestamp_format("%Y-%m-%d %Q").is_err());
+        assert!(parse_timestamp_format("%H:%M:%S").is_err());
+        assert_eq!(civil_year(1709289000), 2024);
+        assert_eq!(civil_year(1704067199), 2023);
+    }
+}
@@ -0,0 +1,91 @@
+//! Module for config values with units
+//!
+//! Durations like "30d", sizes like "500MB" and rates like "20MB/s" are more
+//! readable than plain numbers. The values are converted into their base unit
+//! when the config is parsed.
+//!
+/// Units of durations with their length in seconds
+const DURATION_UNITS: &[(&str, u64)] = &[
+    ("s", 1),
+    ("m", 60),
+    ("h", 3600),
+    ("d", 86400),
+    ("w", 604800),
+];
+
+/// Units of sizes with their length in bytes, decimal and binary units are supported
+const SIZE_UNITS: &[(&str, u64)] = &[
+    ("B", 1),
+    ("KB", 1000),
+    ("MB", 1000 * 1000),
+    ("GB", 1000 * 1000 * 1000),
+    ("TB", 1000 * 1000 * 1000 * 1000),
+    ("KiB", 1024),
+    ("MiB", 1024 * 1024),
+    ("GiB", 1024 * 1024 * 1024),
+    ("TiB", 1024 * 1024 * 1024 * 1024),
+];
+
+/// Split a value like "30d" into the number and the unit
+fn split_unit(raw: &str) -> Option<(u64, &str)> {
+    let trimmed: &str = raw.trim();
+    let unit_start: usize = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+
+    let number: u64 = trimmed[..unit_start].parse().ok()?;
+    Some((number, trimmed[unit_start..].trim()))
+}
+
+/// Parse a duration like "12h" or "30d" into seconds, None for invalid durations
+pub fn parse_duration_s(raw: &str) -> Option<u64> {
+    let (number, unit) = split_unit(raw)?;
+
+    DURATION_UNITS
+        .iter()
+        .find(|(name, _)| *name == unit)
+        .and_then(|(_, factor)| number.checked_mul(*factor))
+}
+
+/// Parse a size like "500MB" or "5GiB" into bytes, None for invalid sizes
+pub fn parse_size_bytes(raw: &str) -> Option<u64> {
+    let (number, unit) = split_unit(raw)?;
+
+    SIZE_UNITS
+        .iter()
+        .find(|(name, _)| *name == unit)
+        .and_then(|(_, factor)| number.checked_mul(*factor))
+}
+
+/// Parse a rate like "20MB/s" into bytes per second, None for invalid rates
+pub fn parse_rate_bytes_per_s(raw: &str) -> Option<u64> {
+    parse_size_bytes(raw.trim().strip_suffix("/s")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration_s("45s"), Some(45));
+        assert_eq!(parse_duration_s("12h"), Some(43200));
+        assert_eq!(parse_duration_s("30d"), Some(2592000));
+        assert_eq!(parse_duration_s(" 2 w "), Some(1209600));
+        assert_eq!(parse_duration_s("30"), None);
+        assert_eq!(parse_duration_s("d"), None);
+        assert_eq!(parse_duration_s("3y"), None);
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size_bytes("512B"), Some(512));
+        assert_eq!(parse_size_bytes("500MB"), Some(500_000_000));
+        assert_eq!(parse_size_bytes("5GiB"), Some(5 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size_bytes("10"), None);
+        assert_eq!(parse_size_bytes("10mb"), None);
+        assert_eq!(parse_size_bytes("99999999TiB"), None);
+        assert_eq!(parse_rate_bytes_per_s("20MB/s"), Some(20_000_000));
+        assert_eq!(parse_rate_bytes_per_s("20MB"), None);
+    }
+}
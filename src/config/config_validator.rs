@@ -0,0 +1,951 @@
+//! Module for the yalc config semantic validation
+//!
+//! The config parser only checks that all keys exist with the correct type.
+//! This module checks the semantic constraints of the parsed values. All
+//! violations are collected, so every problem of a config is reported at once.
+//!
+use std::fmt;
+use std::path::Path;
+
+use crate::config::{
+    CleanUpAction, CleanUpMode, Compression, Config, EmailNotify, FileOverrides, FilePermissions,
+    FileSource, IoClass, MatrixNotify, NotifyFilter, SlackNotify, UploadBackend, UploadConfig,
+};
+use crate::platform;
+
+/// Maximum value for the config key 'keep_rotate'
+pub const MAX_KEEP_ROTATE: u64 = 1000;
+
+/// Maximum value for the config key 'io_buffer', larger buffers only cost memory
+pub const MAX_IO_BUFFER_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Maximum value for the config key 'priority.nice'
+const MAX_NICE: u8 = 19;
+
+/// Maximum value for the config key 'priority.io_level'
+const MAX_IO_LEVEL: u8 = 7;
+
+/// Tokens of the config key 'upload.name'
+const UPLOAD_NAME_TOKENS: [&str; 4] = ["{name}", "{time}", "{hostname}", "{app}"];
+
+/// Represents a single violation of a config constraint
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// Config key of the invalid value
+    pub key: String,
+
+    /// Description of the violated constraint
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+/// Custom error type for configs that violate constraints
+#[derive(Debug)]
+pub struct ValidationError {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+//Implement the Display trait
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Config validation failed with {} errors",
+            self.diagnostics.len()
+        )?;
+
+        for diagnostic in self.diagnostics.iter() {
+            write!(f, "\n  {}", diagnostic)?;
+        }
+
+        Ok(())
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ValidationError {}
+
+/// Validate the config, returns an error with all violations
+pub fn validate_config(config: &Config) -> Result<(), ValidationError> {
+    let diagnostics: Vec<Diagnostic> = collect_diagnostics(config);
+
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError { diagnostics })
+    }
+}
+
+/// Collect the suspicious values of a valid config, like settings that discard the content.
+/// Warnings do not prevent a run, 'config check --strict' reports them as failures.
+pub fn collect_warnings(config: &Config) -> Vec<Diagnostic> {
+    let mut warnings: Vec<Diagnostic> = Vec::new();
+
+    if config.dry_run {
+        warnings.push(Diagnostic {
+            key: "dry_run".to_string(),
+            message: "Value true only logs the cleanup actions, no file is changed".to_string(),
+        });
+    }
+
+    if config.keep_rotate == 0 && config.copy_truncate {
+        warnings.push(keep_rotate_warning("keep_rotate"));
+    }
+
+    if let Some(status_listen) = config.daemon.status_listen
+        && !status_listen.ip().is_loopback()
+    {
+        warnings.push(Diagnostic {
+            key: "daemon.status_listen".to_string(),
+            message: format!(
+                "Value {} exposes the status of the daemon to other hosts",
+                status_listen
+            ),
+        });
+    }
+
+    if config.delay_compress && config.compression == Compression::None {
+        warnings.push(Diagnostic {
+            key: "delay_compress".to_string(),
+            message: "Value has no effect without compression".to_string(),
+        });
+    }
+
+    //Entries and groups are only reported when they set one of the values themselves
+    let mut overrides: Vec<(String, &FileOverrides, FileOverrides)> = config
+        .groups
+        .iter()
+        .map(|group| {
+            let prefix: String = format!("group.{}", group.name);
+            (prefix, &group.overrides, group.overrides.clone())
+        })
+        .collect();
+
+    for (idx, entry) in config.files.iter().enumerate() {
+        let effective: FileOverrides = entry
+            .group
+            .as_ref()
+            .and_then(|name| config.groups.iter().find(|g| &g.name == name))
+            .map(|group| entry.overrides.with_fallback(&group.overrides))
+            .unwrap_or_else(|| entry.overrides.clone());
+
+        overrides.push((format!("files[{}]", idx + 1), &entry.overrides, effective));
+    }
+
+    for (prefix, own, effective) in overrides.iter() {
+        if own.keep_rotate.is_none() && own.copy_truncate.is_none() {
+            continue;
+        }
+
+        let keep_rotate: u64 = effective.keep_rotate.unwrap_or(config.keep_rotate);
+        let copy_truncate: bool = effective.copy_truncate.unwrap_or(config.copy_truncate);
+
+        if keep_rotate == 0 && copy_truncate {
+            warnings.push(keep_rotate_warning(&format!("{}.keep_rotate", prefix)));
+        }
+    }
+
+    warnings
+}
+
+/// Create the warning of a file that is truncated without keeping its content
+fn keep_rotate_warning(key: &str) -> Diagnostic {
+    Diagnostic {
+        key: key.to_string(),
+        message:
+            "Value 0 with copy_truncate discards the copied content, the file is only truncated"
+                .to_string(),
+    }
+}
+
+/// Returns true when the mode is used globally or by a file entry
+fn uses_mode(config: &Config, mode: CleanUpMode) -> bool {
+    config.mode == mode
+        || config
+            .files
+            .iter()
+            .any(|entry| entry.overrides.mode == Some(mode))
+}
+
+/// Check all constraints of the config and collect the violations
+fn collect_diagnostics(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    if config.keep_rotate > MAX_KEEP_ROTATE {
+        diagnostics.push(Diagnostic {
+            key: "keep_rotate".to_string(),
+            message: format!(
+                "Value {} exceeds the maximum of {}",
+                config.keep_rotate, MAX_KEEP_ROTATE
+            ),
+        });
+    }
+
+    if config.retention.file_size_mib == 0 {
+        diagnostics.push(Diagnostic {
+            key: "retention.file_size_mib".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    match config.retention.min_free_percent {
+        Some(percent) if percent == 0 || percent > 100 => diagnostics.push(Diagnostic {
+            key: "retention.min_free_percent".to_string(),
+            message: format!("Value {} is outside the range 1-100", percent),
+        }),
+        Some(_) => {}
+        None if uses_mode(config, CleanUpMode::DiskFree) => diagnostics.push(Diagnostic {
+            key: "retention.min_free_percent".to_string(),
+            message: "Value is required for the DiskFree mode".to_string(),
+        }),
+        None => {}
+    }
+
+    match config.retention.max_lines {
+        Some(0) => diagnostics.push(Diagnostic {
+            key: "retention.max_lines".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        }),
+        Some(_) => {}
+        None if uses_mode(config, CleanUpMode::LineCount) => diagnostics.push(Diagnostic {
+            key: "retention.max_lines".to_string(),
+            message: "Value is required for the LineCount mode".to_string(),
+        }),
+        None => {}
+    }
+
+    if config.retention.file_age_h.is_none() && uses_mode(config, CleanUpMode::FileAge) {
+        diagnostics.push(Diagnostic {
+            key: "retention.file_age_h".to_string(),
+            message: "Value is required for the FileAge mode".to_string(),
+        });
+    }
+
+    if let Some(level) = config.compression_level {
+        match config.compression.level_range() {
+            Some((min, max)) if level < min || level > max => diagnostics.push(Diagnostic {
+                key: "compression_level".to_string(),
+                message: format!(
+                    "Value {} is outside the range {}-{} of {:?}",
+                    level, min, max, config.compression
+                ),
+            }),
+            Some(_) => {}
+            None => diagnostics.push(Diagnostic {
+                key: "compression_level".to_string(),
+                message: "Value requires a compression algorithm".to_string(),
+            }),
+        }
+    }
+
+    if config.max_archive_age_s == Some(0) {
+        diagnostics.push(Diagnostic {
+            key: "max_archive_age".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    if config.max_archive_size_bytes == Some(0) {
+        diagnostics.push(Diagnostic {
+            key: "max_archive_size".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    if config.total_budget_bytes == Some(0) {
+        diagnostics.push(Diagnostic {
+            key: "total_budget".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    if config.stale_after_s == Some(0) {
+        diagnostics.push(Diagnostic {
+            key: "stale_after".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    if config.max_bytes_per_run == Some(0) {
+        diagnostics.push(Diagnostic {
+            key: "max_bytes_per_run".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    if config.io_buffer_bytes == 0 {
+        diagnostics.push(Diagnostic {
+            key: "io_buffer".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        });
+    } else if config.io_buffer_bytes > MAX_IO_BUFFER_BYTES {
+        diagnostics.push(Diagnostic {
+            key: "io_buffer".to_string(),
+            message: format!("Value must be at most {} MiB", MAX_IO_BUFFER_BYTES >> 20),
+        });
+    }
+
+    if config.io_limit_bytes_per_s == Some(0) {
+        diagnostics.push(Diagnostic {
+            key: "io_limit".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    if let Some(state_file) = &config.state_file {
+        check_absolute_path(&mut diagnostics, "state_file", state_file);
+    }
+
+    if let Some(lock_file) = &config.lock_file {
+        check_absolute_path(&mut diagnostics, "lock_file", lock_file);
+    }
+
+    if let Some(audit_log) = &config.audit_log {
+        check_absolute_path(&mut diagnostics, "audit_log", audit_log);
+    }
+
+    if let Some(log_file) = &config.log_file {
+        check_absolute_path(&mut diagnostics, "log_file", log_file);
+    }
+
+    if config.log_file_max_size_bytes == 0 {
+        diagnostics.push(Diagnostic {
+            key: "log_file_max_size".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    check_permissions(&mut diagnostics, "create", &config.create_permissions);
+    check_permissions(&mut diagnostics, "archive", &config.archive_permissions);
+
+    if config.action == CleanUpAction::Trim && !config.trim.has_limit() {
+        diagnostics.push(Diagnostic {
+            key: "trim".to_string(),
+            message: "Value keep_lines, keep_size or max_age is required for the trim action"
+                .to_string(),
+        });
+    }
+
+    for (key, value) in [
+        ("trim.keep_lines", config.trim.keep_lines),
+        ("trim.keep_size", config.trim.keep_bytes),
+        ("trim.max_age", config.trim.max_age_s),
+    ] {
+        if value == Some(0) {
+            diagnostics.push(Diagnostic {
+                key: key.to_string(),
+                message: "Value must be greater than 0".to_string(),
+            });
+        }
+    }
+
+    if config.retry.attempts == 0 {
+        diagnostics.push(Diagnostic {
+            key: "retry.attempts".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    if let Some(nice) = config.priority.nice
+        && nice > MAX_NICE
+    {
+        diagnostics.push(Diagnostic {
+            key: "priority.nice".to_string(),
+            message: format!("Value {} exceeds the maximum of {}", nice, MAX_NICE),
+        });
+    }
+
+    match (config.priority.io_class, config.priority.io_level) {
+        (_, Some(level)) if level > MAX_IO_LEVEL => diagnostics.push(Diagnostic {
+            key: "priority.io_level".to_string(),
+            message: format!("Value {} exceeds the maximum of {}", level, MAX_IO_LEVEL),
+        }),
+        (Some(IoClass::BestEffort), _) | (_, None) => {}
+        (None, Some(_)) => diagnostics.push(Diagnostic {
+            key: "priority.io_level".to_string(),
+            message: "Value requires the key 'priority.io_class'".to_string(),
+        }),
+        (Some(IoClass::Idle), Some(_)) => diagnostics.push(Diagnostic {
+            key: "priority.io_level".to_string(),
+            message: "Value requires the io_class best-effort".to_string(),
+        }),
+    }
+
+    for group in config.groups.iter() {
+        for (idx, path) in group.paths.iter().enumerate() {
+            check_absolute_path(
+                &mut diagnostics,
+                &format!("group.{}.paths[{}]", group.name, idx + 1),
+                path,
+            );
+        }
+
+        if group.max_total_size_bytes == Some(0) {
+            diagnostics.push(Diagnostic {
+                key: format!("group.{}.max_total_size", group.name),
+                message: "Value must be greater than 0".to_string(),
+            });
+        }
+
+        check_overrides(
+            &mut diagnostics,
+            &format!("group.{}", group.name),
+            &group.overrides,
+            &group.overrides,
+            config,
+        );
+    }
+
+    if let Some(email) = &config.notify.email {
+        check_email_notify(&mut diagnostics, email);
+    }
+
+    if let Some(slack) = &config.notify.slack {
+        check_slack_notify(&mut diagnostics, slack);
+    }
+
+    if let Some(matrix) = &config.notify.matrix {
+        check_matrix_notify(&mut diagnostics, matrix);
+    }
+
+    if let Some(upload) = &config.upload {
+        check_upload(&mut diagnostics, upload);
+    }
+
+    if config.daemon.interval_s == 0 {
+        diagnostics.push(Diagnostic {
+            key: "daemon.interval_s".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    if config.file_list.is_empty() && config.files.is_empty() && !config.allow_empty_file_list {
+        diagnostics.push(Diagnostic {
+            key: "file_list".to_string(),
+            message: "No files configured, set 'allow_empty_file_list = true' to allow this"
+                .to_string(),
+        });
+    }
+
+    //All configured paths must be absolute
+    for (idx, root) in config.allowed_roots.iter().enumerate() {
+        check_absolute_path(
+            &mut diagnostics,
+            &format!("allowed_roots[{}]", idx + 1),
+            root,
+        );
+    }
+
+    for (idx, path) in config.file_list.iter().enumerate() {
+        check_absolute_path(&mut diagnostics, &format!("file_list[{}]", idx + 1), path);
+    }
+
+    for (idx, entry) in config.files.iter().enumerate() {
+        match &entry.source {
+            FileSource::Path(path) => {
+                check_absolute_path(&mut diagnostics, &format!("files[{}].path", idx + 1), path)
+            }
+            FileSource::Dir(source) => check_absolute_path(
+                &mut diagnostics,
+                &format!("files[{}].dir", idx + 1),
+                &source.dir,
+            ),
+        }
+
+        if let Some(group) = &entry.group
+            && !config.groups.iter().any(|g| &g.name == group)
+        {
+            diagnostics.push(Diagnostic {
+                key: format!("files[{}].group", idx + 1),
+                message: format!("Unknown group: '{}'", group),
+            });
+        }
+
+        //The settings of the group are used for all values that the entry does not set
+        let group_overrides: FileOverrides = entry
+            .group
+            .as_ref()
+            .and_then(|name| config.groups.iter().find(|g| &g.name == name))
+            .map(|group| entry.overrides.with_fallback(&group.overrides))
+            .unwrap_or_else(|| entry.overrides.clone());
+
+        check_overrides(
+            &mut diagnostics,
+            &format!("files[{}]", idx + 1),
+            &entry.overrides,
+            &group_overrides,
+            config,
+        );
+
+        if let Some(target) = &entry.signal {
+            check_absolute_path(
+                &mut diagnostics,
+                &format!("files[{}].pidfile", idx + 1),
+                &target.pidfile,
+            );
+        }
+
+        if let Some(olddir) = &entry.olddir {
+            check_absolute_path(
+                &mut diagnostics,
+                &format!("files[{}].olddir", idx + 1),
+                olddir,
+            );
+        }
+    }
+
+    diagnostics
+}
+
+/// Check the rotation settings of a file entry or a group, the prefix is the table of the keys.
+/// The effective overrides also contain the values that are inherited from the group.
+fn check_overrides(
+    diagnostics: &mut Vec<Diagnostic>,
+    prefix: &str,
+    overrides: &FileOverrides,
+    effective: &FileOverrides,
+    config: &Config,
+) {
+    if let Some(keep_rotate) = overrides.keep_rotate
+        && keep_rotate > MAX_KEEP_ROTATE
+    {
+        diagnostics.push(Diagnostic {
+            key: format!("{}.keep_rotate", prefix),
+            message: format!(
+                "Value {} exceeds the maximum of {}",
+                keep_rotate, MAX_KEEP_ROTATE
+            ),
+        });
+    }
+
+    if overrides.file_size_mib == Some(0) {
+        diagnostics.push(Diagnostic {
+            key: format!("{}.file_size_mib", prefix),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    if overrides.max_archive_age_s == Some(0) {
+        diagnostics.push(Diagnostic {
+            key: format!("{}.max_archive_age", prefix),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    if overrides.max_archive_size_bytes == Some(0) {
+        diagnostics.push(Diagnostic {
+            key: format!("{}.max_archive_size", prefix),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    if overrides.action == Some(CleanUpAction::Trim) && !effective.resolve(config).trim.has_limit()
+    {
+        diagnostics.push(Diagnostic {
+            key: format!("{}.action", prefix),
+            message: "Value trim requires trim_keep_lines, trim_keep_size or trim_max_age"
+                .to_string(),
+        });
+    }
+
+    for (key, value) in [
+        ("trim_keep_lines", overrides.trim_keep_lines),
+        ("trim_keep_size", overrides.trim_keep_bytes),
+        ("trim_max_age", overrides.trim_max_age_s),
+    ] {
+        if value == Some(0) {
+            diagnostics.push(Diagnostic {
+                key: format!("{}.{}", prefix, key),
+                message: "Value must be greater than 0".to_string(),
+            });
+        }
+    }
+}
+
+/// Add a diagnostic for every owner or group of the permissions that does not exist
+fn check_permissions(
+    diagnostics: &mut Vec<Diagnostic>,
+    prefix: &str,
+    permissions: &FilePermissions,
+) {
+    if let Some(owner) = &permissions.owner
+        && platform::lookup_user_id(owner).is_none()
+    {
+        diagnostics.push(Diagnostic {
+            key: format!("{}_owner", prefix),
+            message: format!("Unknown user: '{}'", owner),
+        });
+    }
+
+    if let Some(group) = &permissions.group
+        && platform::lookup_group_id(group).is_none()
+    {
+        diagnostics.push(Diagnostic {
+            key: format!("{}_group", prefix),
+            message: format!("Unknown group: '{}'", group),
+        });
+    }
+}
+
+/// Add a diagnostic when the path is not an absolute path
+fn check_absolute_path(diagnostics: &mut Vec<Diagnostic>, key: &str, path: &str) {
+    if !Path::new(path).is_absolute() {
+        diagnostics.push(Diagnostic {
+            key: key.to_string(),
+            message: format!("Path must be absolute: '{}'", path),
+        });
+    }
+}
+
+/// Check that the name template of the upload only contains known tokens.
+/// Without the time every upload of a file would overwrite the previous one.
+fn check_name_template(diagnostics: &mut Vec<Diagnostic>, template: &str) {
+    let mut rest: &str = template;
+
+    while let Some(open) = rest.find('{') {
+        let token: &str = match rest[open..].find('}') {
+            Some(close) => &rest[open..open + close + 1],
+            None => &rest[open..],
+        };
+
+        if !UPLOAD_NAME_TOKENS.contains(&token) {
+            diagnostics.push(Diagnostic {
+                key: "upload.name".to_string(),
+                message: format!(
+                    "Unknown token '{}', valid tokens: {}",
+                    token,
+                    UPLOAD_NAME_TOKENS.join(", ")
+                ),
+            });
+        }
+
+        rest = &rest[open + token.len()..];
+    }
+
+    if !template.contains("{time}") {
+        diagnostics.push(Diagnostic {
+            key: "upload.name".to_string(),
+            message: "Value must contain the token {time}, the uploads would overwrite each other"
+                .to_string(),
+        });
+    }
+}
+
+/// Check the server and the addresses of the email notification
+fn check_email_notify(diagnostics: &mut Vec<Diagnostic>, email: &EmailNotify) {
+    if !email.server.starts_with("smtp://") && !email.server.starts_with("smtps://") {
+        diagnostics.push(Diagnostic {
+            key: "notify.email.server".to_string(),
+            message: "Value must be a host name or an smtp:// or smtps:// url".to_string(),
+        });
+    }
+
+    //Addresses with a leading '-' would be passed as option to curl
+    let is_address = |address: &String| address.contains('@') && !address.starts_with('-');
+
+    if !is_address(&email.from) {
+        diagnostics.push(Diagnostic {
+            key: "notify.email.from".to_string(),
+            message: "Value must be an email address".to_string(),
+        });
+    }
+
+    if email.to.is_empty() || !email.to.iter().all(is_address) {
+        diagnostics.push(Diagnostic {
+            key: "notify.email.to".to_string(),
+            message: "Value must contain at least one email address".to_string(),
+        });
+    }
+
+    if let Some(netrc_file) = &email.netrc_file {
+        check_absolute_path(diagnostics, "notify.email.netrc_file", netrc_file);
+    }
+
+    check_notify_filter(diagnostics, "notify.email", &email.filter);
+}
+
+/// Check the webhook of the Slack notification
+fn check_slack_notify(diagnostics: &mut Vec<Diagnostic>, slack: &SlackNotify) {
+    if !slack.webhook_url.starts_with("https://") {
+        diagnostics.push(Diagnostic {
+            key: "notify.slack.webhook_url".to_string(),
+            message: "Value must be an https:// url".to_string(),
+        });
+    }
+
+    check_notify_filter(diagnostics, "notify.slack", &slack.filter);
+}
+
+/// Check the homeserver and the room of the Matrix notification
+fn check_matrix_notify(diagnostics: &mut Vec<Diagnostic>, matrix: &MatrixNotify) {
+    if !matrix.homeserver.starts_with("https://") && !matrix.homeserver.starts_with("http://") {
+        diagnostics.push(Diagnostic {
+            key: "notify.matrix.homeserver".to_string(),
+            message: "Value must be an http:// or https:// url".to_string(),
+        });
+    }
+
+    if !matrix.room_id.starts_with('!') || !matrix.room_id.contains(':') {
+        diagnostics.push(Diagnostic {
+            key: "notify.matrix.room_id".to_string(),
+            message: "Value must be a room id like '!abc123:example.com'".to_string(),
+        });
+    }
+
+    check_absolute_path(
+        diagnostics,
+        "notify.matrix.access_token_file",
+        &matrix.access_token_file,
+    );
+    check_notify_filter(diagnostics, "notify.matrix", &matrix.filter);
+}
+
+/// Check that a notification channel receives at least one event
+fn check_notify_filter(diagnostics: &mut Vec<Diagnostic>, table: &str, filter: &NotifyFilter) {
+    if filter.events.is_empty() {
+        diagnostics.push(Diagnostic {
+            key: format!("{}.events", table),
+            message: "Value must contain at least one event".to_string(),
+        });
+    }
+}
+
+/// Check the values of the upload and its backend
+fn check_upload(diagnostics: &mut Vec<Diagnostic>, upload: &UploadConfig) {
+    if upload.retry.attempts == 0 {
+        diagnostics.push(Diagnostic {
+            key: "upload.attempts".to_string(),
+            message: "Value must be greater than 0".to_string(),
+        });
+    }
+
+    check_name_template(diagnostics, &upload.name_template);
+
+    match &upload.backend {
+        UploadBackend::S3(target) => {
+            if target.bucket.is_empty() || target.bucket.contains('/') {
+                diagnostics.push(Diagnostic {
+                    key: "upload.s3.bucket".to_string(),
+                    message: "Value must be a bucket name without '/'".to_string(),
+                });
+            }
+        }
+        UploadBackend::Ssh(target) => {
+            //Hosts with a leading '-' would be passed as option to the programs
+            if target.host.is_empty() || target.host.starts_with('-') || target.host.contains('@') {
+                diagnostics.push(Diagnostic {
+                    key: "upload.ssh.host".to_string(),
+                    message: "Value must be a host name, the login is set with upload.ssh.user"
+                        .to_string(),
+                });
+            }
+
+            if target.path.is_empty() {
+                diagnostics.push(Diagnostic {
+                    key: "upload.ssh.path".to_string(),
+                    message: "Value must not be empty".to_string(),
+                });
+            }
+
+            if target.port == Some(0) {
+                diagnostics.push(Diagnostic {
+                    key: "upload.ssh.port".to_string(),
+                    message: "Value must be greater than 0".to_string(),
+                });
+            }
+
+            if let Some(identity_file) = &target.identity_file {
+                check_absolute_path(diagnostics, "upload.ssh.identity_file", identity_file);
+            }
+        }
+        #[cfg(feature = "gcs")]
+        UploadBackend::Gcs(target) => {
+            if target.bucket.is_empty() || target.bucket.contains('/') {
+                diagnostics.push(Diagnostic {
+                    key: "upload.gcs.bucket".to_string(),
+                    message: "Value must be a bucket name without '/'".to_string(),
+                });
+            }
+        }
+        #[cfg(feature = "azure")]
+        UploadBackend::Azure(target) => {
+            //The account is a part of the host name of the storage
+            if target.account.is_empty()
+                || !target
+                    .account
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+            {
+                diagnostics.push(Diagnostic {
+                    key: "upload.azure.account".to_string(),
+                    message: "Value must be a storage account of lowercase letters and digits"
+                        .to_string(),
+                });
+            }
+
+            if target.container.is_empty() || target.container.contains('/') {
+                diagnostics.push(Diagnostic {
+                    key: "upload.azure.container".to_string(),
+                    message: "Value must be a container name without '/'".to_string(),
+                });
+            }
+        }
+        #[cfg(feature = "webdav")]
+        UploadBackend::WebDav(target) => {
+            if !target.url.starts_with("https://") && !target.url.starts_with("http://") {
+                diagnostics.push(Diagnostic {
+                    key: "upload.webdav.url".to_string(),
+                    message: format!("Value must be a http or https url: '{}'", target.url),
+                });
+            }
+
+            if let Some(netrc_file) = &target.netrc_file {
+                check_absolute_path(diagnostics, "upload.webdav.netrc_file", netrc_file);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{FileEntry, FileOverrides};
+
+    #[test]
+    fn test_valid_config() {
+        assert!(validate_config(&Config::test_config()).is_ok());
+    }
+
+    #[test]
+    fn test_aggregated_diagnostics() {
+        let mut config = Config::test_config();
+        config.keep_rotate = MAX_KEEP_ROTATE + 1;
+        config.retention.file_size_mib = 0;
+        config.file_list = vec!["relative.log".to_string()];
+        config.files = vec![FileEntry {
+            source: FileSource::Path("logs/*.log".to_string()),
+            olddir: None,
+            group: None,
+            signal: None,
+            priority: None,
+            overrides: FileOverrides {
+                keep_rotate: Some(MAX_KEEP_ROTATE + 1),
+                ..FileOverrides::default()
+            },
+        }];
+
+        let error = validate_config(&config).unwrap_err();
+        let keys: Vec<&str> = error.diagnostics.iter().map(|d| d.key.as_str()).collect();
+
+        assert_eq!(
+            keys,
+            vec![
+                "keep_rotate",
+                "retention.file_size_mib",
+                "file_list[1]",
+                "files[1].path",
+                "files[1].keep_rotate"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disk_free_threshold() {
+        let mut config = Config::test_config();
+        config.mode = CleanUpMode::DiskFree;
+        assert!(validate_config(&config).is_err());
+
+        config.retention.min_free_percent = Some(101);
+        assert!(validate_config(&config).is_err());
+
+        config.retention.min_free_percent = Some(10);
+        assert!(validate_config(&config).is_ok());
+    }
+
+    #[test]
+    fn test_io_buffer_limit() {
+        let mut config = Config::test_config();
+        config.io_buffer_bytes = MAX_IO_BUFFER_BYTES;
+        assert!(validate_config(&config).is_ok());
+
+        config.io_buffer_bytes = MAX_IO_BUFFER_BYTES + 1;
+        assert!(validate_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_io_priority() {
+        let mut config = Config::test_config();
+        config.priority.io_level = Some(2);
+        let error = validate_config(&config).unwrap_err();
+        assert_eq!(error.diagnostics[0].key, "priority.io_level");
+
+        config.priority.io_class = Some(IoClass::Idle);
+        assert!(validate_config(&config).is_err());
+
+        config.priority.io_class = Some(IoClass::BestEffort);
+        assert!(validate_config(&config).is_ok());
+    }
+
+    #[test]
+    fn test_check_name_template() {
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        check_name_template(&mut diagnostics, "{hostname}/{app}/{name}-{time}");
+        assert!(diagnostics.is_empty());
+
+        check_name_template(&mut diagnostics, "{host}-{time}");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("'{host}'"));
+
+        diagnostics.clear();
+        check_name_template(&mut diagnostics, "{name}");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].key, "upload.name");
+    }
+
+    #[test]
+    fn test_collect_warnings() {
+        let mut config = Config::test_config();
+        assert!(collect_warnings(&config).is_empty());
+
+        config.keep_rotate = 0;
+        config.copy_truncate = true;
+        config.files = vec![FileEntry {
+            source: FileSource::Path("/var/log/app.log".to_string()),
+            olddir: None,
+            group: None,
+            signal: None,
+            priority: None,
+            overrides: FileOverrides {
+                keep_rotate: Some(1),
+                ..FileOverrides::default()
+            },
+        }];
+
+        let keys: Vec<String> = collect_warnings(&config)
+            .into_iter()
+            .map(|d| d.key)
+            .collect();
+        assert_eq!(keys, vec!["keep_rotate"]);
+
+        //Warnings are no validation errors
+        assert!(validate_config(&config).is_ok());
+
+        config.files[0].overrides.keep_rotate = Some(0);
+        assert_eq!(collect_warnings(&config).len(), 2);
+    }
+
+    #[test]
+    fn test_empty_file_list() {
+        let mut config = Config::test_config();
+        config.file_list.clear();
+        assert!(validate_config(&config).is_err());
+
+        config.allow_empty_file_list = true;
+        assert!(validate_config(&config).is_ok());
+    }
+}
@@ -209,6 +209,10 @@ impl Lexer {
                         _ if c.is_alphanumeric() || c == '_' || c == '.' => {
                             self.parse_key_or_value(c)
                         }
+                        //Signed numbers like -5 are only valid as values
+                        '-' | '+' if self.equals_consumed && !self.bracket_consumed => {
+                            self.parse_value(c)
+                        }
                         _ => Token::Error(format!("Unknown token at: {}", c)), //Handle any unexpected characters
                     }
                 }
@@ -345,7 +349,21 @@ impl Lexer {
             //Consume the next char
             let next_char = self.next_char();
 
-            if let Some(c) = next_char {
+            //Escape sequences are resolved, unknown escapes are kept as written
+            if next_char == Some('\\') {
+                match self.next_char() {
+                    Some('\\') => string_value.push('\\'),
+                    Some('"') => string_value.push('"'),
+                    Some('n') => string_value.push('\n'),
+                    Some('t') => string_value.push('\t'),
+                    Some('r') => string_value.push('\r'),
+                    Some(c) => {
+                        string_value.push('\\');
+                        string_value.push(c);
+                    }
+                    None => string_value.push('\\'),
+                }
+            } else if let Some(c) = next_char {
                 string_value.push(c);
             }
         }
@@ -399,6 +417,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_string_escape_sequences() {
+        let input = r#"redact = "\\d{16} say \"hi\"\n C:\logs""#;
+        let mut lexer = Lexer::new(input);
+
+        let tokens = vec![
+            Token::Key("redact".to_string()),
+            Token::Whitespace,
+            Token::Equal,
+            Token::Whitespace,
+            Token::Value(Value::String("\\d{16} say \"hi\"\n C:\\logs".to_string())),
+            Token::EOF,
+        ];
+
+        for expected_token in tokens {
+            let token = lexer.next_token();
+            assert_eq!(token, expected_token);
+        }
+    }
+
     #[test]
     fn test_simple_key_value_integer() {
         let input = "key = 1";
@@ -419,6 +457,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_signed_integer_values() {
+        let mut lexer = Lexer::new("low = -5\nhigh = +10");
+
+        let tokens = vec![
+            Token::Key("low".to_string()),
+            Token::Whitespace,
+            Token::Equal,
+            Token::Whitespace,
+            Token::Value(Value::Integer(-5)),
+            Token::Newline,
+            Token::Key("high".to_string()),
+            Token::Whitespace,
+            Token::Equal,
+            Token::Whitespace,
+            Token::Value(Value::Integer(10)),
+            Token::EOF,
+        ];
+
+        for expected_token in tokens {
+            let token = lexer.next_token();
+            assert_eq!(token, expected_token);
+        }
+    }
+
     #[test]
     fn test_simple_key_value_boolean() {
         let input = "key = true";
@@ -13,22 +13,93 @@ use std::io::ErrorKind;
 use std::path::Path;
 
 use crate::config::Config;
+use crate::config::config_deprecation;
+use crate::config::config_merge;
+use crate::config::config_migrate;
 use crate::config::config_parser;
+use crate::config::config_schema;
+use crate::config::config_validator;
+use crate::config::config_validator::Diagnostic;
 use crate::config::toml_lexer::Lexer;
 use crate::config::toml_lexer::SectionName;
 use crate::config::toml_lexer::Token;
 
 use crate::config::toml_lexer::Value as LValue;
+use crate::output::{self, LogLevel};
 
 /// Load the config file from disk and parse the config.
 /// This function will also validate the config before parsing.
-/// The config file will be decoded with UTF-8.
-pub fn load_config(path: &Path) -> Result<Config, io::Error> {
-    println!("Loading config from: {}", &path.display());
-    let config_content: String = load_config_file_content(&path)?;
+/// The config file will be decoded with UTF-8. When a profile
+/// is selected, the values of the profile overwrite the base values.
+pub fn load_config(path: &Path, profile: Option<&str>) -> Result<Config, io::Error> {
+    let (config, warnings) = load_config_with_warnings(path, profile)?;
 
+    for warning in warnings.iter() {
+        output::warn(&format!("Warning: {}", warning));
+    }
+
+    Ok(config)
+}
+
+/// Load and validate the config like 'load_config' without printing the warnings.
+/// Warnings are unknown keys, deprecated keys and suspicious values of a valid config.
+pub fn load_config_with_warnings(
+    path: &Path,
+    profile: Option<&str>,
+) -> Result<(Config, Vec<Diagnostic>), io::Error> {
+    output::print_status(&format!("Loading config from: {}", &path.display()));
+
+    //Parse the toml table including all referenced config fragments
+    let mut table: TopLevelTable = config_merge::load_table_with_drop_ins(path)?;
+    config_merge::apply_profile(&mut table, profile)?;
+
+    //Older configs are upgraded in memory, the file is only changed by 'config migrate'
+    let migration_changes: Vec<String> = config_migrate::migrate_table(&mut table)?;
+
+    if !migration_changes.is_empty() {
+        output::print_status(
+            "Config uses an outdated schema, run 'yalc config migrate' to upgrade it:",
+        );
+
+        for change in migration_changes.iter() {
+            output::print_status(&format!("  {}", change));
+        }
+    }
+
+    //Deprecated keys are still supported until their removal version
+    let mut warnings: Vec<Diagnostic> = config_deprecation::apply_deprecated_keys(&mut table)
+        .into_iter()
+        .map(|warning| Diagnostic {
+            message: format!("Key {}", warning.details()),
+            key: warning.name,
+        })
+        .collect();
+
+    warnings.extend(config_schema::find_unknown_keys(&table));
+
+    //Parse the concrete config values from the toml table
+    output::debug(&format!("Parsed config table with {} keys", table.len()));
+    let config: Config = config_parser::parse_config(&table)?;
+
+    //Check the semantic constraints, all violations are part of the error
+    config_validator::validate_config(&config)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+
+    warnings.extend(config_validator::collect_warnings(&config));
+    Ok((config, warnings))
+}
+
+/// Load a single toml file from disk and parse it into a table.
+/// Include directives are not resolved by this function.
+pub fn load_toml_table(path: &Path) -> Result<TopLevelTable, io::Error> {
+    let config_content: String = load_config_file_content(path)?;
+    parse_toml(&config_content)
+}
+
+/// Parse the toml content of a string into a table
+pub fn parse_toml(content: &str) -> Result<TopLevelTable, io::Error> {
     //Collect all tokens and store in a vector
-    let mut lexer = Lexer::new(&config_content);
+    let mut lexer = Lexer::new(content);
     let mut tokens: Vec<Token> = Vec::new();
 
     loop {
@@ -40,13 +111,16 @@ pub fn load_config(path: &Path) -> Result<Config, io::Error> {
         }
     }
 
+    //The token table is only useful to debug the parser itself
+    if output::is_enabled(LogLevel::Trace) {
+        for token in tokens.iter() {
+            output::trace(&format!("Token: {:?}", token));
+        }
+    }
+
     //Perform the parsing of the token list
     let parser = Parser::new(tokens);
-    let table: TopLevelTable = parser.parse()?;
-
-    //Parse the concrete config values from the toml table
-    let config: Config = config_parser::parse_config(&table)?;
-    Ok(config)
+    parser.parse()
 }
 
 /// Load the config file content. Will return an error if the file does not exist.
@@ -65,7 +139,7 @@ pub type Table = HashMap<Key, Value>;
 /// Name or identifier of the key-value pair
 type Key = String;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     /// Represents text with a String
     String(String),
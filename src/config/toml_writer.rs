@@ -0,0 +1,201 @@
+//! Module for the yalc toml writer logic
+//!
+//! Provides logic for serializing toml tables back into the toml format.
+//! The output can be parsed again by the toml parser of this crate.
+//! Note that comments and the original key order are not preserved.
+//!
+use crate::config::toml_parser::{Table, TopLevelTable, Value};
+
+/// Maximum length of an inline array before it is written over multiple lines
+const MAX_INLINE_ARRAY_LEN: usize = 80;
+
+/// Serialize the top level table into a toml string.
+///
+/// Key-value pairs are written before the sections. The keys are sorted,
+/// only the key 'config_version' is always written as the first key.
+pub fn to_toml_string(root: &TopLevelTable) -> String {
+    let mut output = String::new();
+    write_table(&mut output, root, &[]);
+    output
+}
+
+/// Write all values of a table, the path contains the keys of the parent tables
+fn write_table(output: &mut String, table: &Table, path: &[String]) {
+    let keys: Vec<&String> = sorted_keys(table);
+
+    //Write the simple key-value pairs of the current table first
+    for key in keys.iter() {
+        let value: &Value = &table[*key];
+
+        if !is_table(value) && !is_array_of_tables(value) {
+            output.push_str(&format!("{} = {}\n", key, format_value(value)));
+        }
+    }
+
+    //Write all sub tables as sections
+    for key in keys.iter() {
+        let mut section_path: Vec<String> = path.to_vec();
+        section_path.push(key.to_string());
+
+        match &table[*key] {
+            Value::Table(sub_table) => {
+                //Headers of tables that only contain sections are implicitly defined
+                let only_sections: bool = !sub_table.is_empty()
+                    && sub_table
+                        .values()
+                        .all(|v| is_table(v) || is_array_of_tables(v));
+
+                if !only_sections {
+                    output.push_str(&format!("\n[{}]\n", section_path.join(".")));
+                }
+
+                write_table(output, sub_table, &section_path);
+            }
+            value @ Value::Array(items) if is_array_of_tables(value) => {
+                for item in items.iter() {
+                    if let Value::Table(item_table) = item {
+                        output.push_str(&format!("\n[[{}]]\n", section_path.join(".")));
+                        write_table(output, item_table, &section_path);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Get the keys of the table in the order they are written
+fn sorted_keys(table: &Table) -> Vec<&String> {
+    let mut keys: Vec<&String> = table.keys().collect();
+    keys.sort_by_key(|key| (key.as_str() != "config_version", key.as_str()));
+    keys
+}
+
+/// Returns true when the value is a table
+fn is_table(value: &Value) -> bool {
+    matches!(value, Value::Table(_))
+}
+
+/// Returns true when the value is a non-empty array that only contains tables
+fn is_array_of_tables(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => !items.is_empty() && items.iter().all(is_table),
+        _ => false,
+    }
+}
+
+/// Format a value that is written on the right side of a key-value pair
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => format_string(s),
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) => {
+            //Floats always need a decimal point to be parsed as float again
+            let formatted: String = f.to_string();
+
+            if formatted.contains('.') {
+                formatted
+            } else {
+                format!("{}.0", formatted)
+            }
+        }
+        Value::Bool(b) => b.to_string(),
+        Value::DateTime(d) => d.clone(),
+        Value::Array(items) => {
+            let formatted_items: Vec<String> = items.iter().map(format_value).collect();
+            let inline: String = format!("[{}]", formatted_items.join(", "));
+
+            if inline.len() <= MAX_INLINE_ARRAY_LEN {
+                inline
+            } else {
+                format!("[\n    {}\n]", formatted_items.join(",\n    "))
+            }
+        }
+        Value::Table(_) => String::from("[]"), //Tables are written as sections
+    }
+}
+
+/// Format a string value with surrounding quotes
+fn format_string(s: &str) -> String {
+    let mut formatted = String::with_capacity(s.len() + 2);
+    formatted.push('"');
+
+    for c in s.chars() {
+        match c {
+            '\\' => formatted.push_str("\\\\"),
+            '"' => formatted.push_str("\\\""),
+            '\n' => formatted.push_str("\\n"),
+            _ => formatted.push(c),
+        }
+    }
+
+    formatted.push('"');
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::toml_parser::parse_toml;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_write_root_values() {
+        let mut root: TopLevelTable = HashMap::new();
+        root.insert("mode".to_string(), Value::String("FileSize".to_string()));
+        root.insert("config_version".to_string(), Value::Integer(2));
+        root.insert("dry_run".to_string(), Value::Bool(false));
+        root.insert(
+            "file_list".to_string(),
+            Value::Array(vec![
+                Value::String("a.log".to_string()),
+                Value::String("b.log".to_string()),
+            ]),
+        );
+
+        let output = to_toml_string(&root);
+
+        assert_eq!(
+            output,
+            "config_version = 2\ndry_run = false\nfile_list = [\"a.log\", \"b.log\"]\nmode = \"FileSize\"\n"
+        );
+    }
+
+    #[test]
+    fn test_write_escaped_strings() {
+        assert_eq!(format_string(r"C:\logs"), r#""C:\\logs""#);
+        assert_eq!(format_string("say \"hi\"\n"), r#""say \"hi\"\n""#);
+
+        let mut root: TopLevelTable = HashMap::new();
+        let value = "\\d{16} \"quoted\"\nC:\\logs".to_string();
+        root.insert("pattern".to_string(), Value::String(value.clone()));
+
+        let written = to_toml_string(&root);
+        let parsed = parse_toml(&written).unwrap();
+        assert_eq!(parsed.get("pattern"), Some(&Value::String(value)));
+    }
+
+    #[test]
+    fn test_write_parse_round_trip() {
+        let input = r#"dry_run = true
+keep_rotate = 3
+file_list = ["/var/log/a.log"]
+
+[retention]
+file_size_mib = 10
+last_write_h = 5
+
+[[files]]
+path = "/var/log/app/*.log"
+
+[[files]]
+dir = "/var/log/containers"
+recursive = true
+"#;
+
+        let table = parse_toml(input).unwrap();
+        let output = to_toml_string(&table);
+
+        assert_eq!(parse_toml(&output).unwrap(), table);
+    }
+}
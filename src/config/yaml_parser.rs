@@ -0,0 +1,482 @@
+//! Module for the yalc yaml parser logic
+//!
+//! Provides logic for parsing yaml documents into the same table model
+//! that is used by the toml parser. So the config parser can be used for
+//! both formats. Note that this parser only covers a small yaml subset:
+//! block mappings, block sequences, flow sequences and scalar values.
+//!
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::config::toml_parser::{Table, TopLevelTable, Value};
+
+/// Load a yaml file from disk and parse it into a table
+pub fn load_yaml_table(path: &Path) -> Result<TopLevelTable, io::Error> {
+    let content: String = fs::read_to_string(path)?;
+    parse_yaml(&content)
+}
+
+/// Parse the yaml content of a string into a table
+pub fn parse_yaml(content: &str) -> Result<TopLevelTable, io::Error> {
+    let lines: Vec<Line> = collect_lines(content);
+
+    if lines.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut parser = YamlParser { lines, pos: 0 };
+    let indent: usize = parser.lines[0].indent;
+
+    match parser.parse_block(indent)? {
+        Value::Table(table) => {
+            if let Some(line) = parser.lines.get(parser.pos) {
+                return Err(syntax_error(line, "Unexpected indentation"));
+            }
+
+            Ok(table)
+        }
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Expected a mapping at the root level of the yaml document",
+        )),
+    }
+}
+
+/// Represents a significant line of the yaml document
+struct Line {
+    /// Number of the line in the document for error messages
+    number: usize,
+
+    /// Number of leading spaces
+    indent: usize,
+
+    /// Content of the line without indentation and comments
+    content: String,
+}
+
+/// Collect all lines with content, comments and document markers are removed
+fn collect_lines(content: &str) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+
+    for (idx, raw_line) in content.lines().enumerate() {
+        let without_comment: &str = strip_comment(raw_line);
+        let trimmed: &str = without_comment.trim();
+
+        if trimmed.is_empty() || trimmed == "---" || trimmed == "..." {
+            continue;
+        }
+
+        lines.push(Line {
+            number: idx + 1,
+            indent: without_comment.len() - without_comment.trim_start().len(),
+            content: trimmed.to_string(),
+        });
+    }
+
+    lines
+}
+
+/// Remove a trailing comment, the '#' char must not be part of a quoted string
+fn strip_comment(line: &str) -> &str {
+    let mut quote: Option<char> = None;
+    let mut prev_char: char = ' ';
+
+    for (idx, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && prev_char.is_whitespace() => return &line[..idx],
+            None => {}
+        }
+
+        prev_char = c;
+    }
+
+    line
+}
+
+struct YamlParser {
+    /// All significant lines of the document
+    lines: Vec<Line>,
+
+    /// Index of the next line that will be processed
+    pos: usize,
+}
+
+impl YamlParser {
+    /// Parse a block mapping or block sequence with the given indentation
+    fn parse_block(&mut self, indent: usize) -> Result<Value, io::Error> {
+        match self.lines.get(self.pos) {
+            Some(line) if is_sequence_item(&line.content) => self.parse_sequence(indent),
+            Some(_) => self.parse_mapping(indent),
+            None => Ok(Value::Table(HashMap::new())),
+        }
+    }
+
+    /// Parse all 'key: value' lines with the same indentation
+    fn parse_mapping(&mut self, indent: usize) -> Result<Value, io::Error> {
+        let mut table: Table = HashMap::new();
+
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent < indent || is_sequence_item(&line.content) {
+                break;
+            }
+
+            if line.indent > indent {
+                return Err(syntax_error(line, "Unexpected indentation"));
+            }
+
+            let line_number: usize = line.number;
+            let (key, rest) = split_key_value(line)?;
+            self.pos += 1;
+
+            let value: Option<Value> = if rest.is_empty() {
+                //The value is a nested block or a sequence with the same indentation
+                match self.lines.get(self.pos) {
+                    Some(next) if next.indent > indent => Some(self.parse_block(next.indent)?),
+                    Some(next) if next.indent == indent && is_sequence_item(&next.content) => {
+                        Some(self.parse_sequence(indent)?)
+                    }
+                    _ => None, //Null values are handled like missing keys
+                }
+            } else {
+                Some(parse_inline_value(&rest, line_number)?)
+            };
+
+            if let Some(value) = value
+                && table.insert(key.clone(), value).is_some()
+            {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Duplicate yaml key: {}", key),
+                ));
+            }
+        }
+
+        Ok(Value::Table(table))
+    }
+
+    /// Parse all '- item' lines with the same indentation
+    fn parse_sequence(&mut self, indent: usize) -> Result<Value, io::Error> {
+        let mut values: Vec<Value> = Vec::new();
+
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent != indent || !is_sequence_item(&line.content) {
+                break;
+            }
+
+            let rest: String = line.content[1..].trim_start().to_string();
+            let line_number: usize = line.number;
+
+            if rest.is_empty() {
+                //The item is a nested block on the following lines
+                self.pos += 1;
+
+                match self.lines.get(self.pos) {
+                    Some(next) if next.indent > indent => {
+                        values.push(self.parse_block(next.indent)?);
+                    }
+                    _ => {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Empty sequence item at yaml line {}", line_number),
+                        ));
+                    }
+                }
+            } else if find_key_separator(&rest).is_some() {
+                //A mapping starts directly after the dash, the keys are aligned after the dash
+                let item_indent: usize = indent + line.content.len() - rest.len();
+                self.lines[self.pos].indent = item_indent;
+                self.lines[self.pos].content = rest;
+                values.push(self.parse_mapping(item_indent)?);
+            } else {
+                self.pos += 1;
+                values.push(parse_inline_value(&rest, line_number)?);
+            }
+        }
+
+        Ok(Value::Array(values))
+    }
+}
+
+/// Returns true when the line content is an item of a block sequence
+fn is_sequence_item(content: &str) -> bool {
+    content == "-" || content.starts_with("- ")
+}
+
+/// Find the byte index of the ':' separator of a 'key: value' pair
+fn find_key_separator(content: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    let chars: Vec<(usize, char)> = content.char_indices().collect();
+
+    for (i, &(idx, c)) in chars.iter().enumerate() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '[' => return None, //Flow sequences contain no keys
+            None if c == ':' => {
+                let at_end: bool = i + 1 == chars.len();
+
+                if at_end || chars[i + 1].1 == ' ' {
+                    return Some(idx);
+                }
+            }
+            None => {}
+        }
+    }
+
+    None
+}
+
+/// Split a mapping line into the key and the remaining value content
+fn split_key_value(line: &Line) -> Result<(String, String), io::Error> {
+    let separator: usize = find_key_separator(&line.content)
+        .ok_or_else(|| syntax_error(line, "Expected 'key: value' pair"))?;
+
+    let raw_key: &str = line.content[..separator].trim();
+    let key: String = unquote(raw_key).unwrap_or_else(|| raw_key.to_string());
+    let rest: String = line.content[separator + 1..].trim().to_string();
+
+    Ok((key, rest))
+}
+
+/// Parse a value that is defined on a single line
+fn parse_inline_value(content: &str, line_number: usize) -> Result<Value, io::Error> {
+    if content.starts_with('[') {
+        if !content.ends_with(']') {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Unclosed flow sequence at yaml line {}", line_number),
+            ));
+        }
+
+        let inner: &str = content[1..content.len() - 1].trim();
+
+        if inner.is_empty() {
+            return Ok(Value::Array(Vec::new()));
+        }
+
+        let values: Vec<Value> = split_flow_items(inner)
+            .iter()
+            .map(|item| parse_scalar(item.trim()))
+            .collect();
+
+        return Ok(Value::Array(values));
+    }
+
+    if content.starts_with('{') {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Flow mappings are not supported at yaml line {}",
+                line_number
+            ),
+        ));
+    }
+
+    Ok(parse_scalar(content))
+}
+
+/// Split the items of a flow sequence at commas outside of quotes
+fn split_flow_items(content: &str) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+    let mut current: String = String::new();
+    let mut quote: Option<char> = None;
+
+    for c in content.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == ',' => {
+                items.push(current.clone());
+                current.clear();
+                continue;
+            }
+            None => {}
+        }
+
+        current.push(c);
+    }
+
+    items.push(current);
+    items
+}
+
+/// Parse a scalar value, plain scalars that are no bool or number are strings
+fn parse_scalar(content: &str) -> Value {
+    if let Some(s) = unquote(content) {
+        return Value::String(s);
+    }
+
+    match content {
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => {}
+    }
+
+    if let Ok(int_val) = content.parse::<i64>() {
+        return Value::Integer(int_val);
+    }
+
+    if let Ok(float_val) = content.parse::<f64>() {
+        return Value::Float(float_val);
+    }
+
+    Value::String(content.to_string())
+}
+
+/// Remove the quotes of a quoted string, None is returned for unquoted content
+fn unquote(content: &str) -> Option<String> {
+    let quoted = |quote: char| {
+        content
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+    };
+
+    if let Some(inner) = quoted('"') {
+        //Double quoted strings support escape sequences
+        let mut result = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                match chars.next() {
+                    Some('n') => result.push('\n'),
+                    Some('t') => result.push('\t'),
+                    Some(other) => result.push(other),
+                    None => result.push('\\'),
+                }
+            } else {
+                result.push(c);
+            }
+        }
+
+        Some(result)
+    } else {
+        quoted('\'').map(|inner| inner.replace("''", "'"))
+    }
+}
+
+/// Create a syntax error for a specific line
+fn syntax_error(line: &Line, message: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("{} at yaml line {}: {}", message, line.number, line.content),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_values() {
+        let input = r#"---
+# Yalc log rotation config
+dry_run: false
+mode: "FileSize"
+keep_rotate: 3
+name: plain text # Trailing comment
+"#;
+
+        let table = parse_yaml(input).unwrap();
+
+        let mut exp_table: TopLevelTable = HashMap::new();
+        exp_table.insert("dry_run".to_string(), Value::Bool(false));
+        exp_table.insert("mode".to_string(), Value::String("FileSize".to_string()));
+        exp_table.insert("keep_rotate".to_string(), Value::Integer(3));
+        exp_table.insert("name".to_string(), Value::String("plain text".to_string()));
+
+        assert_eq!(table, exp_table);
+    }
+
+    #[test]
+    fn test_multibyte_values() {
+        let input = "path: /var/log/café\nquoted: \"café\"\nsingle: 'é'\nshort: é\n";
+
+        let table = parse_yaml(input).unwrap();
+
+        let mut exp_table: TopLevelTable = HashMap::new();
+        exp_table.insert(
+            "path".to_string(),
+            Value::String("/var/log/café".to_string()),
+        );
+        exp_table.insert("quoted".to_string(), Value::String("café".to_string()));
+        exp_table.insert("single".to_string(), Value::String("é".to_string()));
+        exp_table.insert("short".to_string(), Value::String("é".to_string()));
+
+        assert_eq!(table, exp_table);
+    }
+
+    #[test]
+    fn test_sequences_and_tables() {
+        let input = r#"file_list:
+  - /var/log/a.log
+  - "/var/log/b.log"
+extensions: [log, "txt"]
+retention:
+  file_size_mib: 10
+  last_write_h: 5
+files:
+- path: /var/log/app/*.log
+- dir: /var/log/containers
+  recursive: true
+"#;
+
+        let table = parse_yaml(input).unwrap();
+
+        assert_eq!(
+            table.get("file_list"),
+            Some(&Value::Array(vec![
+                Value::String("/var/log/a.log".to_string()),
+                Value::String("/var/log/b.log".to_string()),
+            ]))
+        );
+
+        assert_eq!(
+            table.get("extensions"),
+            Some(&Value::Array(vec![
+                Value::String("log".to_string()),
+                Value::String("txt".to_string()),
+            ]))
+        );
+
+        let mut retention: Table = HashMap::new();
+        retention.insert("file_size_mib".to_string(), Value::Integer(10));
+        retention.insert("last_write_h".to_string(), Value::Integer(5));
+        assert_eq!(table.get("retention"), Some(&Value::Table(retention)));
+
+        let mut entry_a: Table = HashMap::new();
+        entry_a.insert(
+            "path".to_string(),
+            Value::String("/var/log/app/*.log".to_string()),
+        );
+
+        let mut entry_b: Table = HashMap::new();
+        entry_b.insert(
+            "dir".to_string(),
+            Value::String("/var/log/containers".to_string()),
+        );
+        entry_b.insert("recursive".to_string(), Value::Bool(true));
+
+        assert_eq!(
+            table.get("files"),
+            Some(&Value::Array(vec![
+                Value::Table(entry_a),
+                Value::Table(entry_b)
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_invalid_indentation() {
+        let input = "retention:\n  file_size_mib: 10\n    last_write_h: 5\n";
+        assert!(parse_yaml(input).is_err());
+    }
+}
@@ -7,22 +7,20 @@ pub const YALC_VERSION: &'static str = "0.1.0";
 ///Default path for the config file
 pub const DEFAULT_CONFIG_PATH: &'static str = "/etc/yalc.toml";
 
-///Default toml config file content
-pub const DEFAULT_CONFIG_CONTENT: &'static str = r#"# Yalc log rotation config
-dry_run = false
-mode = "FileSize"
+///Name of the drop-in directory next to the config file
+pub const DROP_IN_DIR_NAME: &str = "yalc.d";
 
-keep_rotate = 3
+///Exit status for errors outside of the file tasks, like an invalid config
+pub const EXIT_ERROR: u8 = 1;
 
-missing_files_ok = true
-copy_truncate = true
+///Exit status when at least one file task has failed
+pub const EXIT_TASK_FAILURE: u8 = 2;
 
-file_list = [
-    "/var/log/test.log",
-    "/opt/app/logs/server.log"
-]
+///Exit status when the run was aborted after a failed file task
+pub const EXIT_ABORTED: u8 = 3;
 
-[retention]
-file_size_mib = 10
-last_write_h = 5
-"#;
+///Exit status for runs with warnings like missing or skipped files, only used with 'fail_on = "warn"'
+pub const EXIT_WARNING: u8 = 4;
+
+///Base of the exit status for runs that were interrupted by a signal
+pub const EXIT_SIGNAL_BASE: u8 = 128;
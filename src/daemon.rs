@@ -0,0 +1,170 @@
+//! Module for running yalc as a long running daemon
+//!
+//! The daemon executes the cleanup periodically based on the interval of
+//! the daemon config. Between the runs the config files are watched, so
+//! a changed config is applied without restarting the daemon. Invalid
+//! config updates are rejected and the previous config stays active.
+//! The status and metrics of the runs can be served over http for monitoring.
+//!
+pub mod config_watch;
+pub mod status_server;
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cleaner;
+use crate::command::{RunArg, report};
+use crate::config::{self, Config, config_diff};
+use crate::daemon::config_watch::ConfigWatcher;
+use crate::daemon::status_server::SharedStatus;
+use crate::notify;
+use crate::output::{self, run_id};
+use crate::platform;
+use crate::resolver;
+use crate::state;
+use crate::stats;
+
+/// Time between two checks for config file changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Holds everything that is required to load the config again
+struct ConfigSource {
+    path: PathBuf,
+    profile: Option<String>,
+    run_args: Vec<RunArg>,
+}
+
+impl ConfigSource {
+    /// Load the config and apply the env variables and cli args
+    fn load(&self) -> Result<Config, io::Error> {
+        let raw_config: Config = config::load_config(&self.path, self.profile.as_deref())?;
+        let config: Config = config::adjust_runner_config(raw_config, &self.run_args)?;
+
+        //A reloaded config can change the log level and the log file of the daemon
+        output::apply_config(&config)?;
+        Ok(config)
+    }
+}
+
+/// Run the cleanup periodically until the process receives SIGINT or SIGTERM.
+/// Returns an error when the initial config can not be loaded or the status endpoint can not be started.
+pub fn run_daemon(
+    config_path: &Path,
+    profile: Option<String>,
+    run_args: Vec<RunArg>,
+) -> Result<(), io::Error> {
+    let source = ConfigSource {
+        path: config_path.to_path_buf(),
+        profile,
+        run_args,
+    };
+
+    let mut config: Config = source.load()?;
+    let mut watcher = ConfigWatcher::new(config_path);
+    let status = SharedStatus::new();
+
+    if let Some(address) = config.daemon.status_listen {
+        status_server::start(address, status.clone())?;
+    }
+
+    output::print_status(&format!(
+        "Started yalc daemon with an interval of {} seconds",
+        config.daemon.interval_s
+    ));
+
+    loop {
+        let run_start = Instant::now();
+        let run_started_at: u64 = state::unix_time_now();
+
+        run_once(&config, &status);
+        status.set_next_run(run_started_at + config.daemon.interval_s);
+
+        //Wait for the next run and watch the config in the meantime
+        while run_start.elapsed() < Duration::from_secs(config.daemon.interval_s) {
+            if let Some(signal_number) = platform::shutdown_signal() {
+                output::print_status(&format!(
+                    "Stopped yalc daemon after {}",
+                    platform::signal_name(signal_number)
+                ));
+                return Ok(());
+            }
+
+            thread::sleep(WATCH_INTERVAL);
+
+            if config.daemon.reload
+                && watcher.has_changed()
+                && let Some(new_config) = reload_config(&source, &config)
+            {
+                if new_config.daemon.status_listen != config.daemon.status_listen {
+                    output::warn(
+                        "Warning: The change of daemon.status_listen is applied after a restart",
+                    );
+                }
+
+                config = new_config;
+                status.set_next_run(run_started_at + config.daemon.interval_s);
+            }
+        }
+    }
+}
+
+/// Execute a single cleanup run, errors are logged and do not stop the daemon
+fn run_once(config: &Config, status: &SharedStatus) {
+    let tasks = resolver::resolve_tasks(config);
+
+    //Every run of the daemon has its own id
+    let _run_scope = run_id::start();
+    status.run_started(&tasks);
+
+    //An aborted run only stops the current run, the next run starts as scheduled
+    match cleaner::run_cleanup(config, &tasks) {
+        Ok(report) => {
+            status.run_finished(&report);
+            report::print_run_report(&report);
+            stats::record_run(config, &report);
+            notify::notify_run(&config.notify, &report);
+        }
+        Err(e) => {
+            status.run_failed(&e.to_string());
+            output::error(&format!("Cleanup run failed: {}", e));
+        }
+    }
+}
+
+/// Load the changed config, returns None when the new config is rejected
+fn reload_config(source: &ConfigSource, active_config: &Config) -> Option<Config> {
+    output::print_status(&format!(
+        "Config change detected, reloading: {}",
+        source.path.display()
+    ));
+
+    match source.load() {
+        Ok(new_config) => {
+            let changes = config_diff::diff_configs(active_config, &new_config);
+
+            if changes.is_empty() {
+                output::print_status("Config reloaded without changed values");
+            } else {
+                output::print_status(&format!(
+                    "Config reloaded with {} changed values:",
+                    changes.len()
+                ));
+
+                for change in changes.iter() {
+                    output::print_status(&format!("  {}", change));
+                }
+            }
+
+            Some(new_config)
+        }
+        Err(e) => {
+            output::warn(&format!(
+                "Rejected invalid config update, keeping the active config: {}",
+                e
+            ));
+            None
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Module for detecting changes of the config files
+//!
+//! The std library has no file system notifications, so the modification
+//! times of the config file, the drop-in files and all included files are
+//! polled instead. A change is detected when a file was modified, added or
+//! removed, the included files are resolved again for every poll.
+//!
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::config::config_merge;
+
+/// Modification time of every watched file, None for missing files
+type Snapshot = Vec<(PathBuf, Option<SystemTime>)>;
+
+/// Watches the main config file, all files of its drop-in directory and their includes
+pub struct ConfigWatcher {
+    config_path: PathBuf,
+    snapshot: Snapshot,
+}
+
+impl ConfigWatcher {
+    /// Create a new watcher with the current state of the config files
+    pub fn new(config_path: &Path) -> ConfigWatcher {
+        ConfigWatcher {
+            config_path: config_path.to_path_buf(),
+            snapshot: take_snapshot(config_path),
+        }
+    }
+
+    /// Returns true when any config file has changed since the last call
+    pub fn has_changed(&mut self) -> bool {
+        let snapshot: Snapshot = take_snapshot(&self.config_path);
+
+        if snapshot != self.snapshot {
+            self.snapshot = snapshot;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Read the modification times of the config file, the drop-in files and the includes
+fn take_snapshot(config_path: &Path) -> Snapshot {
+    //Errors while loading the files are reported by the config reload
+    config_merge::list_config_files(config_path)
+        .into_iter()
+        .map(|path| {
+            let modified = modified_time(&path).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+/// Get the last modification time of a file
+fn modified_time(path: &Path) -> Result<SystemTime, io::Error> {
+    fs::metadata(path)?.modified()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_detect_changes() {
+        let dir = env::temp_dir().join(format!("yalc_test_watch_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let config_path = dir.join("yalc.toml");
+        fs::write(&config_path, "dry_run = true\n").unwrap();
+
+        let mut watcher = ConfigWatcher::new(&config_path);
+        assert!(!watcher.has_changed());
+
+        //Adding a drop-in file is a change
+        fs::create_dir_all(dir.join("yalc.d")).unwrap();
+        fs::write(dir.join("yalc.d/10-app.toml"), "keep_rotate = 2\n").unwrap();
+        assert!(watcher.has_changed());
+        assert!(!watcher.has_changed());
+
+        //Removing the config file is a change
+        fs::remove_file(&config_path).unwrap();
+        assert!(watcher.has_changed());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_detect_include_changes() {
+        let dir = env::temp_dir().join(format!("yalc_test_watch_include_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("yalc.d")).unwrap();
+
+        let config_path = dir.join("yalc.toml");
+        fs::write(&config_path, "include = [\"base.toml\"]\n").unwrap();
+        fs::write(dir.join("base.toml"), "include = [\"nested.toml\"]\n").unwrap();
+        fs::write(dir.join("nested.toml"), "keep_rotate = 2\n").unwrap();
+        fs::write(
+            dir.join("yalc.d/10-app.toml"),
+            "include = [\"../app.toml\"]\n",
+        )
+        .unwrap();
+        fs::write(dir.join("app.toml"), "keep_rotate = 3\n").unwrap();
+
+        let mut watcher = ConfigWatcher::new(&config_path);
+        assert!(!watcher.has_changed());
+
+        //Files included by the config and by the drop-in files are watched
+        let mtime = SystemTime::now() + std::time::Duration::from_secs(60);
+        for name in ["nested.toml", "app.toml"] {
+            let file = fs::File::options()
+                .write(true)
+                .open(dir.join(name))
+                .unwrap();
+            file.set_modified(mtime).unwrap();
+            assert!(watcher.has_changed());
+        }
+
+        //A file that is included later is watched from then on
+        fs::write(dir.join("nested.toml"), "include = [\"late.toml\"]\n").unwrap();
+        assert!(watcher.has_changed());
+        fs::write(dir.join("late.toml"), "keep_rotate = 4\n").unwrap();
+        assert!(watcher.has_changed());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
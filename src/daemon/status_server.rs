@@ -0,0 +1,526 @@
+//! Module for the http endpoint of the daemon
+//!
+//! Monitoring systems probe the daemon over http instead of parsing its
+//! log. The endpoint is disabled by default and listens on the address of
+//! 'daemon.status_listen', which is usually a loopback address:
+//!
+//! GET /healthz  Returns 'ok' while the daemon is running
+//! GET /status   Last run, next scheduled run and the errors of the last run as json
+//! GET /metrics  Run counters and the sizes of the managed files for Prometheus
+//!
+//! The sizes of the managed files are read for every scrape, so they also
+//! show the growth of the files between two runs. The managed files are
+//! the files of the last run, new files of a glob pattern appear after the
+//! next run.
+//!
+//! The requests are answered one after another by a background thread.
+//! Only the request line is evaluated, the endpoint is no general http server.
+//!
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use crate::cleaner::audit;
+use crate::cleaner::report::RunReport;
+use crate::output::json::JsonValue;
+use crate::output::{self, run_id};
+use crate::platform;
+use crate::resolver::FileTask;
+use crate::state;
+
+/// Time to wait for the request and the response of a connection, slow clients can not block
+/// the endpoint
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum size of the request line and headers that are read
+const MAX_REQUEST_SIZE: u64 = 8 * 1024;
+
+/// Represents the status of the daemon that is served by the endpoint
+#[derive(Debug, Default)]
+struct DaemonStatus {
+    /// Unix time in seconds when the daemon was started
+    started_at: u64,
+
+    /// Number of the runs since the daemon was started
+    runs: u64,
+
+    /// Id of the run that is executed right now, None between the runs
+    running: Option<String>,
+
+    /// Json fields of the last finished run, None before the first run finished
+    last_run: Option<Vec<(String, JsonValue)>>,
+
+    /// Unix time in seconds of the next scheduled run
+    next_run_at: Option<u64>,
+
+    /// Failed tasks and errors of the last run
+    last_errors: Vec<String>,
+
+    /// Log files of the last run, their sizes are read for every scrape
+    managed_files: Vec<PathBuf>,
+
+    /// Totals of all runs since the daemon was started
+    counters: RunCounters,
+}
+
+/// Represents the totals of the runs and the values of the last run for the metrics
+#[derive(Debug, Default)]
+struct RunCounters {
+    /// Runs that could not be executed or had a failed task
+    runs_failed: u64,
+
+    tasks_success: u64,
+    tasks_failure: u64,
+    bytes_freed: u64,
+
+    /// Unix time in seconds when the last finished run was started
+    last_run_started_at: Option<u64>,
+
+    last_run_duration: Duration,
+    last_run_exit_code: u8,
+}
+
+/// Status of the daemon that is shared between the daemon loop and the endpoint
+#[derive(Debug, Clone)]
+pub struct SharedStatus {
+    status: Arc<Mutex<DaemonStatus>>,
+}
+
+impl SharedStatus {
+    /// Create the status of a daemon that was started now
+    pub fn new() -> SharedStatus {
+        SharedStatus {
+            status: Arc::new(Mutex::new(DaemonStatus {
+                started_at: state::unix_time_now(),
+                ..DaemonStatus::default()
+            })),
+        }
+    }
+
+    /// Mark the start of a run, must be called within the scope of the run id
+    pub fn run_started(&self, tasks: &[FileTask]) {
+        let mut status = self.lock();
+        status.runs += 1;
+        status.running = Some(run_id::current().unwrap_or_default());
+        status.managed_files = tasks.iter().map(|task| task.path.clone()).collect();
+    }
+
+    /// Store the results of a finished run
+    pub fn run_finished(&self, report: &RunReport) {
+        let mut fields: Vec<(String, JsonValue)> = vec![(
+            "run_id".to_string(),
+            JsonValue::optional_string(report.run_id.clone()),
+        )];
+        fields.extend(report.total_fields());
+        fields.push((
+            "exit_code".to_string(),
+            JsonValue::uint(report.exit_code() as u64),
+        ));
+
+        let mut status = self.lock();
+        status.running = None;
+        status.last_run = Some(fields);
+        status.last_errors = run_errors(report);
+
+        let counters: &mut RunCounters = &mut status.counters;
+        counters.tasks_success += report.tasks_success as u64;
+        counters.tasks_failure += report.tasks_failure as u64;
+        counters.bytes_freed += report.bytes_freed();
+        counters.last_run_started_at = Some(report.started_at);
+        counters.last_run_duration = report.duration;
+        counters.last_run_exit_code = report.exit_code();
+
+        if report.exit_code() != 0 {
+            counters.runs_failed += 1;
+        }
+    }
+
+    /// Store the error of a run that could not be executed
+    pub fn run_failed(&self, error: &str) {
+        let mut status = self.lock();
+        status.running = None;
+        status.last_errors = vec![error.to_string()];
+        status.counters.runs_failed += 1;
+    }
+
+    /// Set the unix time in seconds of the next scheduled run
+    pub fn set_next_run(&self, next_run_at: u64) {
+        self.lock().next_run_at = Some(next_run_at);
+    }
+
+    /// A panic of the endpoint thread does not invalidate the status
+    fn lock(&self) -> MutexGuard<'_, DaemonStatus> {
+        self.status.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Start answering the requests on the address in a background thread.
+/// Returns an error when the address can not be bound, like a port that is already in use.
+pub fn start(address: SocketAddr, status: SharedStatus) -> Result<(), io::Error> {
+    let listener = TcpListener::bind(address).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Failed to bind the status endpoint to {}: {}", address, e),
+        )
+    })?;
+
+    output::print_status(&format!(
+        "Serving the daemon status at: http://{}/status",
+        address
+    ));
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            //A failed connection only affects the single request
+            let result = stream.and_then(|stream| handle_connection(stream, &status));
+
+            if let Err(e) = result {
+                output::debug(&format!("Status request failed: {}", e));
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Read the request of the connection and write the response
+fn handle_connection(mut stream: TcpStream, status: &SharedStatus) -> Result<(), io::Error> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_SIZE));
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    //The headers are read, so the client does not see a reset connection
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+
+    //The file sizes are only read for the metrics and without holding the lock,
+    //so a slow disk does not block the daemon
+    let file_sizes: Vec<(PathBuf, u64)> = match request_target(&request_line) {
+        ("GET", "/metrics") => read_file_sizes(status.lock().managed_files.clone()),
+        _ => Vec::new(),
+    };
+
+    let (status_line, content_type, body) = route(&request_line, &status.lock(), &file_sizes);
+
+    let response: String = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status_line,
+        content_type,
+        body.len(),
+        body
+    );
+
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
+
+/// Get the current sizes of the files that exist
+fn read_file_sizes(managed_files: Vec<PathBuf>) -> Vec<(PathBuf, u64)> {
+    managed_files
+        .into_iter()
+        .filter_map(|path| {
+            let size: u64 = fs::metadata(&path).ok()?.len();
+            Some((path, size))
+        })
+        .collect()
+}
+
+/// Get the method and the path of a request line, query parameters are not supported
+fn request_target(request_line: &str) -> (&str, &str) {
+    let mut parts = request_line.split_whitespace();
+    let method: &str = parts.next().unwrap_or_default();
+    let path: &str = parts
+        .next()
+        .and_then(|target| target.split('?').next())
+        .unwrap_or_default();
+
+    (method, path)
+}
+
+/// Get the status line, content type and body of the response to a request line.
+/// The file sizes are the current sizes of the managed files that exist.
+fn route(
+    request_line: &str,
+    status: &DaemonStatus,
+    file_sizes: &[(PathBuf, u64)],
+) -> (&'static str, &'static str, String) {
+    match request_target(request_line) {
+        ("GET", "/healthz") => ("200 OK", "text/plain", "ok\n".to_string()),
+        ("GET", "/status") => (
+            "200 OK",
+            "application/json",
+            format!("{}\n", status_json(status).to_pretty_string()),
+        ),
+        ("GET", "/metrics") => (
+            "200 OK",
+            "text/plain; version=0.0.4",
+            format_metrics(status, file_sizes),
+        ),
+        (_, "/healthz" | "/status" | "/metrics") => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_string(),
+        ),
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    }
+}
+
+/// Get the json document of the status
+fn status_json(status: &DaemonStatus) -> JsonValue {
+    JsonValue::Object(vec![
+        (
+            "pid".to_string(),
+            JsonValue::uint(std::process::id() as u64),
+        ),
+        (
+            "started_at".to_string(),
+            JsonValue::string(audit::format_utc_time(status.started_at)),
+        ),
+        ("runs".to_string(), JsonValue::uint(status.runs)),
+        (
+            "running".to_string(),
+            JsonValue::optional_string(status.running.clone()),
+        ),
+        (
+            "last_run".to_string(),
+            status
+                .last_run
+                .clone()
+                .map_or(JsonValue::Null, JsonValue::Object),
+        ),
+        (
+            "next_run_at".to_string(),
+            JsonValue::optional_string(status.next_run_at.map(audit::format_utc_time)),
+        ),
+        (
+            "last_errors".to_string(),
+            JsonValue::Array(
+                status
+                    .last_errors
+                    .iter()
+                    .map(|error| JsonValue::string(error.clone()))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+/// Format the metrics of the daemon in the Prometheus text exposition format
+fn format_metrics(status: &DaemonStatus, file_sizes: &[(PathBuf, u64)]) -> String {
+    let counters: &RunCounters = &status.counters;
+    let mut output = String::new();
+
+    let mut write_metric = |name: &str, kind: &str, help: &str, samples: &[(String, f64)]| {
+        let _ = writeln!(output, "# HELP {} {}", name, help);
+        let _ = writeln!(output, "# TYPE {} {}", name, kind);
+
+        for (labels, value) in samples.iter() {
+            let _ = writeln!(output, "{}{} {}", name, labels, value);
+        }
+    };
+
+    write_metric(
+        "yalc_daemon_start_time_seconds",
+        "gauge",
+        "Unix time when the daemon was started",
+        &[(String::new(), status.started_at as f64)],
+    );
+    write_metric(
+        "yalc_runs_total",
+        "counter",
+        "Number of started cleanup runs",
+        &[(String::new(), status.runs as f64)],
+    );
+    write_metric(
+        "yalc_runs_failed_total",
+        "counter",
+        "Number of runs that could not be executed or had a failed task",
+        &[(String::new(), counters.runs_failed as f64)],
+    );
+    write_metric(
+        "yalc_tasks_total",
+        "counter",
+        "Number of executed file tasks by their result",
+        &[
+            (
+                "{result=\"success\"}".to_string(),
+                counters.tasks_success as f64,
+            ),
+            (
+                "{result=\"failure\"}".to_string(),
+                counters.tasks_failure as f64,
+            ),
+        ],
+    );
+    write_metric(
+        "yalc_freed_bytes_total",
+        "counter",
+        "Number of bytes that the log files shrank by the runs",
+        &[(String::new(), counters.bytes_freed as f64)],
+    );
+
+    //The values of the last run are only known after the first run
+    if let Some(last_run_started_at) = counters.last_run_started_at {
+        write_metric(
+            "yalc_last_run_timestamp_seconds",
+            "gauge",
+            "Unix time when the last finished run was started",
+            &[(String::new(), last_run_started_at as f64)],
+        );
+        write_metric(
+            "yalc_last_run_duration_seconds",
+            "gauge",
+            "Wall time of the last finished run",
+            &[(String::new(), counters.last_run_duration.as_secs_f64())],
+        );
+        write_metric(
+            "yalc_last_run_exit_code",
+            "gauge",
+            "Exit status of the last finished run, 0 when all tasks were successful",
+            &[(String::new(), f64::from(counters.last_run_exit_code))],
+        );
+    }
+
+    if let Some(next_run_at) = status.next_run_at {
+        write_metric(
+            "yalc_next_run_timestamp_seconds",
+            "gauge",
+            "Unix time of the next scheduled run",
+            &[(String::new(), next_run_at as f64)],
+        );
+    }
+
+    let size_samples: Vec<(String, f64)> = file_sizes
+        .iter()
+        .map(|(path, size)| {
+            let label: String =
+                format!("{{path=\"{}\"}}", escape_label(&path.display().to_string()));
+            (label, *size as f64)
+        })
+        .collect();
+
+    write_metric(
+        "yalc_file_size_bytes",
+        "gauge",
+        "Current size of the managed log files",
+        &size_samples,
+    );
+
+    output
+}
+
+/// Escape a label value of the exposition format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Get the errors of the failed tasks and the reason of a stopped run
+fn run_errors(report: &RunReport) -> Vec<String> {
+    let mut errors: Vec<String> = report
+        .tasks
+        .iter()
+        .filter_map(|task| {
+            task.error
+                .as_ref()
+                .map(|error| format!("{}: {}", task.path.display(), error))
+        })
+        .collect();
+
+    if report.aborted {
+        errors.push("Run was aborted after a failed file task".to_string());
+    }
+
+    if let Some(signal_number) = report.interrupted_by {
+        errors.push(format!(
+            "Run was interrupted by {}",
+            platform::signal_name(signal_number)
+        ));
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route() {
+        let status = DaemonStatus {
+            started_at: 1709296200,
+            runs: 1,
+            next_run_at: Some(1709299800),
+            last_errors: vec!["/var/log/app.log: busy".to_string()],
+            ..DaemonStatus::default()
+        };
+
+        let (status_line, _, body) = route("GET /healthz HTTP/1.1\r\n", &status, &[]);
+        assert_eq!(status_line, "200 OK");
+        assert_eq!(body, "ok\n");
+
+        let (status_line, content_type, body) =
+            route("GET /status?full=1 HTTP/1.1\r\n", &status, &[]);
+        assert_eq!(status_line, "200 OK");
+        assert_eq!(content_type, "application/json");
+        assert!(body.contains("\"next_run_at\": \"2024-03-01T13:30:00Z\""));
+        assert!(body.contains("\"/var/log/app.log: busy\""));
+        assert!(body.contains("\"last_run\": null"));
+
+        assert_eq!(
+            route("POST /status HTTP/1.1", &status, &[]).0,
+            "405 Method Not Allowed"
+        );
+        assert_eq!(route("GET / HTTP/1.1", &status, &[]).0, "404 Not Found");
+        assert_eq!(route("", &status, &[]).0, "404 Not Found");
+    }
+    #[test]
+    fn test_format_metrics() {
+        let status = DaemonStatus {
+            runs: 2,
+            counters: RunCounters {
+                tasks_success: 5,
+                tasks_failure: 1,
+                ..RunCounters::default()
+            },
+            ..DaemonStatus::default()
+        };
+
+        let file_sizes = vec![(PathBuf::from("/var/log/a\"b.log"), 1024)];
+        let (status_line, content_type, body) =
+            route("GET /metrics HTTP/1.1", &status, &file_sizes);
+
+        assert_eq!(status_line, "200 OK");
+        assert!(content_type.starts_with("text/plain"));
+        assert!(body.contains("# TYPE yalc_runs_total counter\nyalc_runs_total 2\n"));
+        assert!(body.contains("yalc_tasks_total{result=\"failure\"} 1\n"));
+        assert!(body.contains("yalc_file_size_bytes{path=\"/var/log/a\\\"b.log\"} 1024\n"));
+
+        //Without a finished run there are no values of the last run
+        assert!(!body.contains("yalc_last_run_exit_code"));
+
+        let finished = DaemonStatus {
+            counters: RunCounters {
+                last_run_started_at: Some(1709296200),
+                last_run_duration: Duration::from_millis(1500),
+                ..RunCounters::default()
+            },
+            ..DaemonStatus::default()
+        };
+        let (_, _, body) = route("GET /metrics HTTP/1.1", &finished, &[]);
+        assert!(body.contains("yalc_last_run_duration_seconds 1.5\n"));
+        assert!(body.contains("yalc_last_run_timestamp_seconds 1709296200\n"));
+    }
+}
@@ -31,12 +31,93 @@ pub fn print_help() {
     println!("        Executes the log file cleanup process based on the current configuration.");
     println!("        This is the default command if no other command is provided.");
     println!();
+    println!("    daemon [OPTIONS]");
+    println!("        Executes the cleanup periodically with the interval of daemon.interval_s.");
+    println!(
+        "        Changes of the config file and drop-in directory are applied without restart"
+    );
+    println!("        when daemon.reload is enabled. Accepts the same options as the run command.");
+    println!(
+        "        With daemon.status_listen the paths /healthz, /status and /metrics are served"
+    );
+    println!("        over http, /metrics in the Prometheus text format.");
+    println!();
+    println!("    restore [OPTIONS] [FILE...]");
+    println!(
+        "        Reverts the most recent run of the files with the undo journal next to the state"
+    );
+    println!(
+        "        file. Without a FILE all recorded files are restored. A file that has grown since"
+    );
+    println!("        the run is not restored. Removed archives can not be restored.");
+    println!();
+    println!("    history [OPTIONS]");
+    println!(
+        "        List the recorded runs with their totals. Every run that is not a dry run is"
+    );
+    println!("        recorded in the statistics file next to the state file.");
+    println!();
+    println!("    stats [OPTIONS]");
+    println!("        Summarize the recorded runs of every file, like the number of cleanups and");
+    println!(
+        "        failures, the freed bytes and the growth rate per day. Files whose growth of"
+    );
+    println!("        the most recent day is more than twice their earlier rate are highlighted.");
+    println!(
+        "        The column Threshold forecasts when a file reaches its size threshold, files that"
+    );
+    println!("        reach it before the next expected run are printed in red with a warning.");
+    println!();
     println!("CONFIG SUBCOMMANDS");
     println!("    init");
-    println!("        Create a new default configuration file at the default config path.");
+    println!(
+        "        Create a new default configuration file. Without --config or YALC_CONFIG the"
+    );
+    println!(
+        "        file is created at /etc/yalc.toml for root and at the user config path otherwise."
+    );
     println!();
     println!("    check");
-    println!("        Check if the configuration file exists and is valid.");
+    println!(
+        "        Check if the configuration file exists and is valid. Unknown keys, deprecated"
+    );
+    println!(
+        "        keys and suspicious values like keep_rotate = 0 with copy_truncate are reported"
+    );
+    println!("        as warnings. With --strict the warnings also fail the check.");
+    println!();
+    println!("    migrate");
+    println!(
+        "        Upgrade the configuration file to the current schema version. The original file"
+    );
+    println!("        is kept with the .bak extension.");
+    println!();
+    println!("    example");
+    println!(
+        "        Print an example configuration with all supported keys and their documentation."
+    );
+    println!();
+    println!("GLOBAL OPTIONS");
+    println!("    --config PATH");
+    println!(
+        "        Path of the configuration file. When not provided the first existing file of"
+    );
+    println!("        YALC_CONFIG, $XDG_CONFIG_HOME/yalc/yalc.toml, ~/.config/yalc/yalc.toml and");
+    println!("        /etc/yalc.toml is used.");
+    println!();
+    println!("    --color MODE");
+    println!(
+        "        Color the errors, warnings and task results: 'auto', 'always' or 'never'. With"
+    );
+    println!(
+        "        'auto' the output is only colored for terminals and when NO_COLOR is not set."
+    );
+    println!();
+    println!("    --verbose");
+    println!(
+        "        Print the debug messages, twice also the trace messages. Has precedence over"
+    );
+    println!("        the config key log_level.");
     println!();
     println!("RUN OPTIONS");
     println!("    --dry, -d");
@@ -51,11 +132,113 @@ pub fn print_help() {
     println!(
         "        Truncate files instead of deleting them. This is useful for clearing files that"
     );
-    println!("        are still in use by a process.");
+    println!("        are still in use by a process. Applies to all files, also to entries that");
+    println!("        set copy_truncate = false.");
+    println!();
+    println!("    --profile, -p NAME");
+    println!(
+        "        Apply the values of the configuration profile NAME, defined in a [profile.NAME]"
+    );
+    println!("        section. The profile values overwrite the base configuration values.");
+    println!();
+    println!("    --on-error, -e POLICY");
+    println!(
+        "        Overwrite the configuration value on_error. With 'abort' the run stops at the"
+    );
+    println!("        first failed file, with 'continue' the remaining files are processed.");
+    println!();
+    println!("    --fail-on LEVEL");
+    println!(
+        "        Overwrite the configuration value fail_on. With 'warn' also missing, skipped,"
+    );
+    println!(
+        "        stale, deferred and unmanaged files make yalc exit with a non-zero status, with"
+    );
+    println!("        'error' only failed tasks and with 'never' the task results are ignored.");
+    println!();
+    println!("    --group, -g NAME");
+    println!(
+        "        Only process the files of the group NAME, defined in a [group.NAME] section."
+    );
+    println!("        The option can be repeated to select multiple groups.");
+    println!();
+    println!("    --match, -m PATTERN");
+    println!(
+        "        Only process the resolved files that match the glob PATTERN. Patterns without '/'"
+    );
+    println!("        are matched against the file name, others against the whole path.");
+    println!("        The option can be repeated, a file is processed when any pattern matches.");
+    println!();
+    println!("    --plan");
+    println!(
+        "        Print the planned actions of every file and the reasons of the decisions without"
+    );
+    println!("        changing any file.");
+    println!();
+    println!("    --output, -o FORMAT");
+    println!(
+        "        Format of the plan, 'text' or 'json'. With 'json' the status messages are written"
+    );
+    println!("        to stderr, so stdout only contains the json document. A run without --plan");
+    println!(
+        "        supports 'ndjson', which writes one json object per event like task_started,"
+    );
+    println!("        condition_met, action_executed and task_finished to stdout.");
+    println!();
+    println!("    --cron");
+    println!(
+        "        Print nothing when every task was successful. When a task has failed, the whole"
+    );
+    println!("        output of the run is printed, so cron only sends a mail for failed runs.");
+    println!();
+    println!("RESTORE OPTIONS");
+    println!("    --dry, -d");
+    println!("        Print the steps that would be reverted without changing any file.");
+    println!();
+    println!("    --profile, -p NAME");
+    println!("        Apply the values of the configuration profile NAME.");
+    println!();
+    println!("HISTORY AND STATS OPTIONS");
+    println!("    --limit, -l COUNT");
+    println!("        Only use the COUNT most recent recorded runs.");
+    println!();
+    println!("    --profile, -p NAME");
+    println!("        Apply the values of the configuration profile NAME.");
+    println!();
+    println!("ENVIRONMENT");
+    println!("    YALC_CONFIG");
+    println!("        Path of the configuration file, if not provided by the --config option.");
+    println!();
+    println!("    YALC_PROFILE");
+    println!("        Name of the configuration profile, if not selected by the --profile option.");
+    println!();
+    println!("    YALC_DRY_RUN, YALC_MODE, YALC_KEEP_ROTATE, YALC_MISSING_FILES_OK,");
+    println!("    YALC_COPY_TRUNCATE, YALC_FILE_SIZE_MIB, YALC_LAST_WRITE_H");
+    println!("        Overwrite the matching configuration values. Run options have precedence");
+    println!("        over environment variables.");
+    println!();
+    println!("EXIT STATUS");
+    println!("    0   All file tasks were successful");
+    println!("    1   The command failed, for example because of an invalid configuration");
+    println!("    2   At least one file task has failed");
+    println!("    3   The run was aborted after a failed file task");
+    println!("    4   The run has warnings and fail_on is set to 'warn'");
+    println!("    128+N   The run was interrupted by the signal N, like 143 for SIGTERM");
     println!();
     println!("EXAMPLES");
     println!("    $ yalc help");
     println!("    $ yalc -d");
     println!("    $ yalc config init");
     println!("    $ yalc run --trunc --ignore-miss");
+    println!("    $ yalc run --profile prod");
+    println!("    $ yalc run --on-error abort");
+    println!("    $ yalc run --cron --fail-on warn");
+    println!("    $ yalc run --group nginx --plan");
+    println!("    $ yalc run --match \"nginx*\"");
+    println!("    $ yalc run --plan --output json");
+    println!("    $ yalc run --cron");
+    println!("    $ yalc restore --dry /var/log/app.log");
+    println!("    $ yalc history --limit 10");
+    println!("    $ yalc config check --config ~/yalc.toml");
+    println!("    $ yalc config check --strict");
 }
@@ -0,0 +1,138 @@
+//! Module for the lock that prevents concurrent runs
+//!
+//! Overlapping runs, for example from cron and a manual call, would race
+//! on the same rotation sequence. Every run holds an exclusive lock on the
+//! lock file until it is finished. The lock is released by the kernel when
+//! the process exits, so a crashed run never blocks the following runs.
+//!
+use std::env;
+use std::fs::{self, File, TryLockError};
+use std::io::{self, ErrorKind, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::output;
+use crate::platform;
+
+/// Lock file of the root user
+const SYSTEM_LOCK_FILE: &str = "/run/yalc.lock";
+
+/// Time between two attempts to get the lock of another run
+const LOCK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Represents the acquired lock of a run, the lock is released when it is dropped
+#[derive(Debug)]
+pub struct RunLock {
+    file: File,
+}
+
+impl Drop for RunLock {
+    fn drop(&mut self) {
+        //The pid is removed before the lock is released, so it never names a finished run
+        let _ = self.file.set_len(0);
+    }
+}
+
+/// Get the lock file for the current user when no lock file is configured
+pub fn default_lock_path() -> PathBuf {
+    if platform::is_root_user() {
+        return PathBuf::from(SYSTEM_LOCK_FILE);
+    }
+
+    match env::var("XDG_RUNTIME_DIR")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+    {
+        Some(runtime_dir) => Path::new(&runtime_dir).join("yalc.lock"),
+        None => env::temp_dir().join(format!("yalc-{}.lock", platform::effective_user_id())),
+    }
+}
+
+/// Acquire the lock of the lock file, waits up to the wait time when another run holds the lock.
+/// The pid of the run is written into the lock file, so the blocking run can be reported.
+pub fn acquire(path: &Path, wait: Option<Duration>) -> Result<RunLock, io::Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file: File = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+
+    let wait_start = Instant::now();
+    let max_wait: Duration = wait.unwrap_or(Duration::ZERO);
+
+    loop {
+        match file.try_lock() {
+            Ok(()) => break,
+            Err(TryLockError::WouldBlock) if wait_start.elapsed() < max_wait => {
+                if wait_start.elapsed() < LOCK_POLL_INTERVAL {
+                    output::print_status(&format!(
+                        "Waiting up to {} seconds for the run{} to finish",
+                        max_wait.as_secs(),
+                        holder_description(path)
+                    ));
+                }
+
+                thread::sleep(LOCK_POLL_INTERVAL);
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(io::Error::new(
+                    ErrorKind::WouldBlock,
+                    format!(
+                        "Another yalc run{} holds the lock file '{}'",
+                        holder_description(path),
+                        path.display()
+                    ),
+                ));
+            }
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+    }
+
+    file.set_len(0)?;
+    file.rewind()?;
+    write!(file, "{}", std::process::id())?;
+
+    Ok(RunLock { file })
+}
+
+/// Describe the run that holds the lock by its pid, empty when the pid is unknown
+fn holder_description(path: &Path) -> String {
+    match fs::read_to_string(path) {
+        Ok(content) if !content.trim().is_empty() => format!(" (pid {})", content.trim()),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_lock() {
+        let dir = env::temp_dir().join(format!("yalc_test_lock_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let lock_path = dir.join("yalc.lock");
+
+        let lock = acquire(&lock_path, None).unwrap();
+        assert_eq!(
+            fs::read_to_string(&lock_path).unwrap(),
+            std::process::id().to_string()
+        );
+
+        //The lock is exclusive, even within the same process
+        let error = acquire(&lock_path, None).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::WouldBlock);
+
+        drop(lock);
+        assert!(fs::read_to_string(&lock_path).unwrap().is_empty());
+        assert!(acquire(&lock_path, None).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -3,25 +3,56 @@
 //!
 //! Main documentation: [GitHub Yalc](https://github.com/Henrik-Peters/Yalc)
 //!
-use crate::command::Command;
+use crate::command::{Command, GlobalArgs};
+use crate::constants::EXIT_ERROR;
 use std::env;
+use std::process::ExitCode;
 
 mod cleaner;
 mod command;
 mod config;
 mod constants;
+mod daemon;
 mod help;
+mod lock;
+mod notify;
+mod output;
+mod platform;
+mod resolver;
+mod state;
+mod stats;
 
-fn main() {
+fn main() -> ExitCode {
     //Get arguments passed to this program
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    //Options like the config path are valid for every command
+    let (command, global_args) = match GlobalArgs::extract_from(&mut args) {
+        Ok(global_args) => (Command::from_args(args), global_args),
+        Err(e) => {
+            output::error(&e.to_string());
+            (Command::Help, GlobalArgs::default())
+        }
+    };
+
+    if let Some(mode) = global_args.color {
+        output::set_color_mode(mode);
+    }
+
+    //The log level of the command line has precedence over the config
+    if let Some(level) = global_args.log_level() {
+        output::set_log_level_from_args(level);
+    }
 
     //Parse and execute command
-    let command = Command::from_args(args);
-    let res_command = command.execute();
+    let res_command = command.execute(&global_args);
 
     //Display the error when the command has failed
-    if let Err(e) = res_command {
-        eprintln!("Error: {}", e);
+    match res_command {
+        Ok(exit_code) => exit_code,
+        Err(e) => {
+            output::error(&format!("Error: {}", e));
+            ExitCode::from(EXIT_ERROR)
+        }
     }
 }
@@ -0,0 +1,444 @@
+//! Module for the notifications about the results of a run
+//!
+//! Unattended runs report their results to the configured channels, like
+//! an email with the summary of the run or a message in a chat room. Every
+//! channel is a notifier that receives the events it selected:
+//!
+//! run_finished:    Summary of the run, for every run or only for failed runs
+//! task_failed:     Error of a single failed file task
+//! budget_exceeded: The total budget was exceeded and archives were removed
+//!
+//! Runs fail with a failed task, an abort of the error policy or an
+//! interruption by a signal. A failed notification is logged and never
+//! changes the result of the run.
+//!
+pub mod email;
+pub mod matrix;
+pub mod slack;
+
+use std::io::{self, Write};
+use std::process::{Child, Command, Output, Stdio};
+
+use crate::cleaner::audit;
+use crate::cleaner::budget::BudgetExcess;
+use crate::cleaner::report::{RunReport, TaskReport};
+use crate::command::report;
+use crate::config::{NotifyConfig, NotifyEvent, NotifyFilter, NotifyOn};
+use crate::output::{self, table};
+use crate::platform;
+
+/// Program of curl that sends the notifications
+pub(crate) const CURL_PROGRAM: &str = "curl";
+
+/// Trait for all channels that can be notified about the events of a run
+pub trait Notifier {
+    /// Name of the channel used for logging
+    fn name(&self) -> &str;
+
+    /// Events of the run that are sent to the channel
+    fn filter(&self) -> &NotifyFilter;
+
+    /// Send a message with the title and the lines of its body to the channel
+    fn send(&self, title: &str, lines: &[String]) -> Result<(), io::Error>;
+}
+
+/// Create the notifiers of all configured channels
+pub fn create_notifiers(config: &NotifyConfig) -> Vec<Box<dyn Notifier + '_>> {
+    let mut notifiers: Vec<Box<dyn Notifier + '_>> = Vec::new();
+
+    if let Some(email) = &config.email {
+        notifiers.push(Box::new(email::EmailNotifier { email }));
+    }
+
+    if let Some(slack) = &config.slack {
+        notifiers.push(Box::new(slack::SlackNotifier { slack }));
+    }
+
+    if let Some(matrix) = &config.matrix {
+        notifiers.push(Box::new(matrix::MatrixNotifier { matrix }));
+    }
+
+    notifiers
+}
+
+/// Represents a single event of a run that channels can be notified about
+#[derive(Debug)]
+pub enum Event<'a> {
+    RunFinished(&'a RunReport),
+    TaskFailed(&'a TaskReport),
+    BudgetExceeded(&'a BudgetExcess),
+}
+
+impl Event<'_> {
+    /// Get the kind of the event that is selected in the config
+    pub fn kind(&self) -> NotifyEvent {
+        match self {
+            Event::RunFinished(_) => NotifyEvent::RunFinished,
+            Event::TaskFailed(_) => NotifyEvent::TaskFailed,
+            Event::BudgetExceeded(_) => NotifyEvent::BudgetExceeded,
+        }
+    }
+
+    /// Get the one line summary of the event
+    pub fn title(&self) -> String {
+        match self {
+            Event::RunFinished(report) => summary_title(report),
+            Event::TaskFailed(task) => format!(
+                "yalc on {}: task failed for '{}'",
+                platform::hostname(),
+                task.path.display()
+            ),
+            Event::BudgetExceeded(_) => {
+                format!("yalc on {}: total budget exceeded", platform::hostname())
+            }
+        }
+    }
+
+    /// Get the lines of the message body of the event
+    pub fn lines(&self) -> Vec<String> {
+        match self {
+            Event::RunFinished(report) => summary_lines(report),
+            Event::TaskFailed(task) => vec![
+                format!("File:     {}", task.path.display()),
+                format!("Trigger:  {}", task.trigger.as_deref().unwrap_or("-")),
+                format!("Duration: {}", table::format_duration(task.duration)),
+                format!("Error:    {}", task.error.as_deref().unwrap_or("-")),
+            ],
+            Event::BudgetExceeded(excess) => vec![
+                format!("Budget:   {}", table::format_bytes(excess.budget_bytes)),
+                format!("Used:     {}", table::format_bytes(excess.used_bytes)),
+                format!(
+                    "Removed:  {} archives [{}]",
+                    excess.removed_count,
+                    table::format_bytes(excess.removed_bytes)
+                ),
+            ],
+        }
+    }
+}
+
+/// Get the events of the run in the order they are sent
+fn run_events(report: &RunReport) -> Vec<Event<'_>> {
+    let mut events: Vec<Event<'_>> = report
+        .tasks
+        .iter()
+        .filter(|task| task.error.is_some())
+        .map(Event::TaskFailed)
+        .collect();
+
+    if let Some(excess) = &report.budget_exceeded {
+        events.push(Event::BudgetExceeded(excess));
+    }
+
+    events.push(Event::RunFinished(report));
+    events
+}
+
+/// Send the events of the run to every channel that is notified about them
+pub fn notify_run(config: &NotifyConfig, report: &RunReport) {
+    let notifiers: Vec<Box<dyn Notifier + '_>> = create_notifiers(config);
+    if notifiers.is_empty() {
+        return;
+    }
+
+    let events: Vec<Event<'_>> = run_events(report);
+
+    for notifier in notifiers.iter() {
+        for event in events.iter().filter(|e| accepts(notifier.filter(), e)) {
+            let title: String = event.title();
+            output::print_status(&format!(
+                "Sending {} notification: {}",
+                notifier.name(),
+                title
+            ));
+
+            let mut lines: Vec<String> = event.lines();
+            lines.push(String::new());
+            lines.push(run_line(report));
+
+            if let Err(e) = notifier.send(&title, &lines) {
+                output::error(&format!(
+                    "Failed to send the {} notification: {}",
+                    notifier.name(),
+                    e
+                ));
+            }
+        }
+    }
+}
+
+/// Get the line with the id and the start time of the run, so the message can be found in the logs
+fn run_line(report: &RunReport) -> String {
+    format!(
+        "Run: {} started at {}",
+        report.run_id.as_deref().unwrap_or("-"),
+        audit::format_utc_time(report.started_at)
+    )
+}
+
+/// Returns true when a channel with the filter is notified about the event
+fn accepts(filter: &NotifyFilter, event: &Event) -> bool {
+    if !filter.events.contains(&event.kind()) {
+        return false;
+    }
+
+    match event {
+        Event::RunFinished(report) => should_notify(filter.on, report),
+        Event::TaskFailed(_) | Event::BudgetExceeded(_) => true,
+    }
+}
+
+/// Returns true when a channel with the setting is notified about the run
+fn should_notify(on: NotifyOn, report: &RunReport) -> bool {
+    match on {
+        NotifyOn::Always => true,
+        NotifyOn::Failure => report.exit_code() != 0,
+    }
+}
+
+/// Get the one line summary of the run like 'yalc on web1: 1 of 3 tasks failed'
+pub fn summary_title(report: &RunReport) -> String {
+    let result: String = match (report.interrupted_by, report.aborted) {
+        (Some(signal_number), _) => format!(
+            "run interrupted by {}",
+            platform::signal_name(signal_number)
+        ),
+        (None, true) => format!("run aborted after {} failed tasks", report.tasks_failure),
+        (None, false) if report.tasks_failure > 0 => format!(
+            "{} of {} tasks failed",
+            report.tasks_failure, report.tasks_executed
+        ),
+        (None, false) if report.hook_failures > 0 => {
+            format!("{} postrotate hooks failed", report.hook_failures)
+        }
+        (None, false) => format!("all {} tasks successful", report.tasks_executed),
+    };
+
+    format!("yalc on {}: {}", platform::hostname(), result)
+}
+
+/// Get the lines of the run summary with the table of the tasks and the errors of the failed tasks
+pub fn summary_lines(report: &RunReport) -> Vec<String> {
+    let mut lines: Vec<String> = vec![
+        format!(
+            "Successful tasks: {}/{}",
+            report.tasks_success, report.tasks_executed
+        ),
+        format!(
+            "Failure tasks:    {}/{}",
+            report.tasks_failure, report.tasks_executed
+        ),
+        format!(
+            "Run duration:     {} [{} freed]",
+            table::format_duration(report.duration),
+            table::format_bytes(report.bytes_freed())
+        ),
+    ];
+
+    if report.hook_failures > 0 {
+        lines.push(format!(
+            "Failed hooks:     {} [postrotate hooks of the groups]",
+            report.hook_failures
+        ));
+    }
+
+    if report.tasks_warning() > 0 {
+        lines.push(format!(
+            "Warning tasks:    {} [missing or skipped files]",
+            report.tasks_warning()
+        ));
+    }
+
+    if report.tasks_stale() > 0 {
+        lines.push(format!(
+            "Stale files:      {} [no write within stale_after]",
+            report.tasks_stale()
+        ));
+    }
+
+    if !report.unmanaged_files.is_empty() {
+        lines.push(format!(
+            "Unmanaged files:  {} [matched by no entry]",
+            report.unmanaged_files.len()
+        ));
+    }
+
+    if !report.changes.is_empty() {
+        lines.push(format!(
+            "Changed files:    {} [compared to their previous run]",
+            report.changes.len()
+        ));
+    }
+
+    if !report.tasks.is_empty() {
+        lines.push(String::new());
+        lines.extend(report::task_table(&report.tasks));
+    }
+
+    let errors: Vec<String> = report
+        .tasks
+        .iter()
+        .filter_map(|task| {
+            task.error
+                .as_ref()
+                .map(|error| format!("{}: {}", task.path.display(), error))
+        })
+        .collect();
+
+    if !errors.is_empty() {
+        lines.push(String::new());
+        lines.push("Errors:".to_string());
+        lines.extend(errors);
+    }
+
+    if !report.unmanaged_files.is_empty() {
+        lines.push(String::new());
+        lines.push("Unmanaged files:".to_string());
+        lines.extend(report.unmanaged_files.iter().map(|file| {
+            format!(
+                "{} [{}]",
+                file.path.display(),
+                table::format_bytes(file.size)
+            )
+        }));
+    }
+
+    if !report.changes.is_empty() {
+        lines.push(String::new());
+        lines.push("Changed files:".to_string());
+        lines.extend(
+            report
+                .changes
+                .iter()
+                .map(|change| format!("{} [{}]", change.path.display(), change.kind)),
+        );
+    }
+
+    lines
+}
+
+/// Format a line of a curl config that is read from stdin with '--config -'.
+/// Secrets like webhook urls and tokens never appear in the arguments of the process.
+pub(crate) fn curl_config_line(option: &str, value: &str) -> String {
+    format!(
+        "{} = \"{}\"\n",
+        option,
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+/// Run curl with the input on stdin, the error contains the output of curl
+pub(crate) fn run_curl(mut command: Command, input: &str) -> Result<(), io::Error> {
+    let mut child: Child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Failed to start '{}': {}", CURL_PROGRAM, e),
+            )
+        })?;
+
+    //The input is closed after it was written, so curl sees the end of the input
+    if let Some(mut child_stdin) = child.stdin.take() {
+        child_stdin.write_all(input.as_bytes())?;
+    }
+
+    let output: Output = child.wait_with_output()?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    Err(io::Error::other(format!(
+        "'{}' failed with {}: {}",
+        CURL_PROGRAM,
+        output.status,
+        String::from_utf8_lossy(&output.stderr).trim()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_notify() {
+        let successful = RunReport {
+            tasks_executed: 2,
+            tasks_success: 2,
+            ..RunReport::default()
+        };
+        let failed = RunReport {
+            tasks_executed: 2,
+            tasks_success: 1,
+            tasks_failure: 1,
+            ..RunReport::default()
+        };
+
+        assert!(!should_notify(NotifyOn::Failure, &successful));
+        assert!(should_notify(NotifyOn::Failure, &failed));
+        assert!(should_notify(NotifyOn::Always, &successful));
+        assert!(summary_title(&failed).ends_with(": 1 of 2 tasks failed"));
+        assert_eq!(summary_lines(&failed)[1], "Failure tasks:    1/2");
+
+        let hook_failed = RunReport {
+            tasks_executed: 2,
+            tasks_success: 2,
+            hook_failures: 1,
+            ..RunReport::default()
+        };
+        assert!(should_notify(NotifyOn::Failure, &hook_failed));
+        assert!(summary_title(&hook_failed).ends_with(": 1 postrotate hooks failed"));
+
+        let run = RunReport {
+            run_id: Some("3f9a0c2e81d4b7a6".to_string()),
+            started_at: 1709296200,
+            ..RunReport::default()
+        };
+        assert_eq!(
+            run_line(&run),
+            "Run: 3f9a0c2e81d4b7a6 started at 2024-03-01T12:30:00Z"
+        );
+    }
+
+    #[test]
+    fn test_accepts_events() {
+        let excess = BudgetExcess {
+            budget_bytes: 300,
+            used_bytes: 400,
+            removed_count: 1,
+            removed_bytes: 100,
+        };
+        let successful = RunReport {
+            tasks_executed: 1,
+            tasks_success: 1,
+            budget_exceeded: Some(excess.clone()),
+            ..RunReport::default()
+        };
+        let filter = NotifyFilter {
+            on: NotifyOn::Failure,
+            events: vec![NotifyEvent::RunFinished, NotifyEvent::BudgetExceeded],
+        };
+
+        let events: Vec<Event<'_>> = run_events(&successful);
+        assert_eq!(events.len(), 2);
+        assert!(accepts(&filter, &events[0]));
+        assert!(!accepts(&filter, &events[1]));
+        assert!(!accepts(&NotifyFilter::default(), &events[0]));
+    }
+
+    #[test]
+    fn test_curl_config_line() {
+        assert_eq!(
+            curl_config_line("url", "https://example.com/hook"),
+            "url = \"https://example.com/hook\"\n"
+        );
+        assert_eq!(
+            curl_config_line("data-binary", "{\"text\":\"a\\nb\"}"),
+            "data-binary = \"{\\\"text\\\":\\\"a\\\\nb\\\"}\"\n"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Email notification with the messages of a run. The email is sent to the
+//! SMTP server with the installed curl, the login is read from a netrc
+//! file, so it never appears in the arguments of the process.
+//!
+use std::io;
+use std::process::Command;
+
+use crate::config::{EmailNotify, NotifyFilter};
+use crate::notify::{CURL_PROGRAM, Notifier, run_curl};
+
+/// Notifier that sends the messages as email to all recipients
+pub struct EmailNotifier<'a> {
+    pub email: &'a EmailNotify,
+}
+
+impl Notifier for EmailNotifier<'_> {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    fn filter(&self) -> &NotifyFilter {
+        &self.email.filter
+    }
+
+    fn send(&self, title: &str, lines: &[String]) -> Result<(), io::Error> {
+        let message: String = format_message(self.email, title, lines);
+
+        let mut command = Command::new(CURL_PROGRAM);
+        command
+            .arg("--silent")
+            .arg("--show-error")
+            .arg("--url")
+            .arg(&self.email.server)
+            .arg("--mail-from")
+            .arg(&self.email.from);
+
+        for recipient in self.email.to.iter() {
+            command.arg("--mail-rcpt").arg(recipient);
+        }
+
+        if let Some(netrc_file) = &self.email.netrc_file {
+            command.arg("--netrc-file").arg(netrc_file);
+        }
+
+        //The message is read from stdin
+        command.arg("--upload-file").arg("-");
+        run_curl(command, &message)
+    }
+}
+
+/// Format the email with its headers, the lines of the body are separated by CRLF
+fn format_message(email: &EmailNotify, subject: &str, lines: &[String]) -> String {
+    let mut message: String = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n",
+        email.from,
+        email.to.join(", "),
+        subject
+    );
+
+    for line in lines.iter() {
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_message() {
+        let email = EmailNotify {
+            server: "smtp://mail.example.com".to_string(),
+            from: "yalc@example.com".to_string(),
+            to: vec!["ops@example.com".to_string(), "dev@example.com".to_string()],
+            netrc_file: None,
+            filter: NotifyFilter::default(),
+        };
+
+        assert_eq!(
+            format_message(&email, "yalc on web1", &["Failure tasks: 1/2".to_string()]),
+            "From: yalc@example.com\r\nTo: ops@example.com, dev@example.com\r\nSubject: yalc on web1\r\nContent-Type: text/plain; charset=utf-8\r\n\r\nFailure tasks: 1/2\r\n"
+        );
+    }
+}
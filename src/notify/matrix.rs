@@ -0,0 +1,161 @@
+//! Matrix notification with the messages of a run. The message is sent to
+//! the room with the client-server API of the homeserver and the installed
+//! curl. The access token is read from a file and passed to curl as config
+//! on stdin, so it never appears in the arguments of the process.
+//!
+use std::fs;
+use std::io;
+use std::process::{self, Command};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{MatrixNotify, NotifyFilter};
+use crate::notify::{CURL_PROGRAM, Notifier, curl_config_line, run_curl};
+use crate::output::json::JsonValue;
+
+/// Number of the messages sent by this process, part of the unique transaction ids
+static MESSAGE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Notifier that sends the messages to a room of a Matrix homeserver
+pub struct MatrixNotifier<'a> {
+    pub matrix: &'a MatrixNotify,
+}
+
+impl Notifier for MatrixNotifier<'_> {
+    fn name(&self) -> &str {
+        "matrix"
+    }
+
+    fn filter(&self) -> &NotifyFilter {
+        &self.matrix.filter
+    }
+
+    fn send(&self, title: &str, lines: &[String]) -> Result<(), io::Error> {
+        let access_token: String = fs::read_to_string(&self.matrix.access_token_file)
+            .map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!(
+                        "Failed to read the access token '{}': {}",
+                        self.matrix.access_token_file, e
+                    ),
+                )
+            })?
+            .trim()
+            .to_string();
+
+        let config: String = [
+            curl_config_line("url", &message_url(self.matrix, &transaction_id())),
+            curl_config_line("request", "PUT"),
+            curl_config_line("header", "Content-Type: application/json"),
+            curl_config_line("header", &format!("Authorization: Bearer {}", access_token)),
+            curl_config_line("data-binary", &format_payload(title, lines)),
+        ]
+        .concat();
+
+        let mut command = Command::new(CURL_PROGRAM);
+        command
+            .arg("--fail")
+            .arg("--silent")
+            .arg("--show-error")
+            .arg("--config")
+            .arg("-");
+
+        run_curl(command, &config)
+    }
+}
+
+/// Get a transaction id that is unique for every message, the homeserver drops repeated ids
+fn transaction_id() -> String {
+    let unix_time: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    format!(
+        "yalc-{}-{}-{}",
+        unix_time,
+        process::id(),
+        MESSAGE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Get the url of the endpoint that sends a message event to the room
+fn message_url(matrix: &MatrixNotify, txn_id: &str) -> String {
+    format!(
+        "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
+        matrix.homeserver,
+        encode_path_segment(&matrix.room_id),
+        encode_path_segment(txn_id)
+    )
+}
+
+/// Percent-encode all bytes of a path segment except the unreserved characters
+fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+/// Format the text message event, the lines are shown as a code block to keep the tables aligned
+fn format_payload(title: &str, lines: &[String]) -> String {
+    let body: String = format!("{}\n\n{}", title, lines.join("\n"));
+    let formatted_body: String = format!(
+        "<strong>{}</strong><pre><code>{}</code></pre>",
+        escape_html(title),
+        escape_html(&lines.join("\n"))
+    );
+
+    JsonValue::Object(vec![
+        ("msgtype".to_string(), JsonValue::string("m.text")),
+        ("body".to_string(), JsonValue::string(body)),
+        (
+            "format".to_string(),
+            JsonValue::string("org.matrix.custom.html"),
+        ),
+        (
+            "formatted_body".to_string(),
+            JsonValue::string(formatted_body),
+        ),
+    ])
+    .to_compact_string()
+}
+
+/// Escape the characters with a meaning in html
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_url() {
+        let matrix = MatrixNotify {
+            homeserver: "https://matrix.example.com".to_string(),
+            room_id: "!abc123:example.com".to_string(),
+            access_token_file: "/etc/yalc/matrix-token".to_string(),
+            filter: NotifyFilter::default(),
+        };
+
+        assert_eq!(
+            message_url(&matrix, "yalc-1-2-3"),
+            "https://matrix.example.com/_matrix/client/v3/rooms/%21abc123%3Aexample.com/send/m.room.message/yalc-1-2-3"
+        );
+        assert_eq!(
+            format_payload("yalc on web1", &["a <b>".to_string()]),
+            "{\"msgtype\":\"m.text\",\"body\":\"yalc on web1\\n\\na <b>\",\"format\":\"org.matrix.custom.html\",\"formatted_body\":\"<strong>yalc on web1</strong><pre><code>a &lt;b&gt;</code></pre>\"}"
+        );
+    }
+}
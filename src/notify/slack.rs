@@ -0,0 +1,67 @@
+//! Slack notification with the messages of a run. The message is posted to
+//! an incoming webhook with the installed curl. The url of the webhook is
+//! its secret, so it is passed to curl as config on stdin and never appears
+//! in the arguments of the process.
+//!
+use std::io;
+use std::process::Command;
+
+use crate::config::{NotifyFilter, SlackNotify};
+use crate::notify::{CURL_PROGRAM, Notifier, curl_config_line, run_curl};
+use crate::output::json::JsonValue;
+
+/// Notifier that posts the messages to the webhook of a Slack channel
+pub struct SlackNotifier<'a> {
+    pub slack: &'a SlackNotify,
+}
+
+impl Notifier for SlackNotifier<'_> {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    fn filter(&self) -> &NotifyFilter {
+        &self.slack.filter
+    }
+
+    fn send(&self, title: &str, lines: &[String]) -> Result<(), io::Error> {
+        let config: String = [
+            curl_config_line("url", &self.slack.webhook_url),
+            curl_config_line("header", "Content-Type: application/json"),
+            curl_config_line("data-binary", &format_payload(title, lines)),
+        ]
+        .concat();
+
+        let mut command = Command::new(CURL_PROGRAM);
+        command
+            .arg("--fail")
+            .arg("--silent")
+            .arg("--show-error")
+            .arg("--config")
+            .arg("-");
+
+        run_curl(command, &config)
+    }
+}
+
+/// Format the json payload of the webhook, the lines are shown as a code block to keep the tables aligned
+fn format_payload(title: &str, lines: &[String]) -> String {
+    let text: String = format!("*{}*\n```\n{}\n```", title, lines.join("\n"));
+    JsonValue::Object(vec![("text".to_string(), JsonValue::string(text))]).to_compact_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_payload() {
+        assert_eq!(
+            format_payload(
+                "yalc on web1: total budget exceeded",
+                &["Budget: 1.0 GiB".to_string(), "Used: 1.5 GiB".to_string()]
+            ),
+            "{\"text\":\"*yalc on web1: total budget exceeded*\\n```\\nBudget: 1.0 GiB\\nUsed: 1.5 GiB\\n```\"}"
+        );
+    }
+}
@@ -0,0 +1,431 @@
+//! Module for the output of the commands
+//!
+//! Commands report their progress with status messages. Machine readable
+//! results like the json plan are printed to stdout, in this case the
+//! status messages are written to stderr, so stdout only contains the
+//! result and can be processed by other tools.
+//!
+//! Every message has a severity level. Errors and warnings are always
+//! written to stderr, the other levels use the stream of the status
+//! messages. Messages above the selected log level are dropped, so the
+//! debug and trace output only appears with '--verbose' or the config
+//! key 'log_level'. The printed messages are also appended to the log
+//! file of the config, so unattended runs leave a persistent record.
+//!
+//! Errors, warnings and the results of the tasks are colored when the
+//! stream is a terminal. The option '--color' and the 'NO_COLOR' env
+//! variable control the colors, the log file never contains colors.
+//! The messages can also be sent to the local syslog daemon. The lines of
+//! the log file and syslog have the time and the id of the current run.
+//!
+//! Cron jobs buffer the messages until the run has finished. The buffer
+//! is printed when the run has failed and discarded otherwise, the log
+//! file still receives every message right away.
+//!
+
+pub mod events;
+pub mod json;
+pub mod log_file;
+pub mod progress;
+pub mod run_id;
+pub mod syslog;
+pub mod table;
+
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use crate::config::Config;
+
+/// When set to true the status messages are written to stderr
+static STATUS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Most verbose level of the messages that are printed
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+
+/// When set to true the log level of the command line has precedence over the config
+static LOG_LEVEL_FROM_ARGS: AtomicBool = AtomicBool::new(false);
+
+/// Selected color mode, the numeric representation of ColorMode
+static COLOR_MODE: AtomicU8 = AtomicU8::new(ColorMode::Auto as u8);
+
+/// Buffered messages with their stream, None when the messages are printed right away
+static MESSAGE_BUFFER: Mutex<Option<Vec<(bool, String)>>> = Mutex::new(None);
+
+/// Enum representing when the messages are colored
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    /// Colors for terminals, unless the env variable 'NO_COLOR' is set
+    Auto = 0,
+
+    /// Colors for every stream, also for pipes and files
+    Always = 1,
+
+    /// No colors at all
+    Never = 2,
+}
+
+/// Custom error type for parsing ColorMode
+#[derive(Debug)]
+pub struct ParseColorModeError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseColorModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse ColorMode: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseColorModeError {}
+
+impl FromStr for ColorMode {
+    type Err = ParseColorModeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(ParseColorModeError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Enum representing the colors of the messages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Green,
+    Yellow,
+    Red,
+}
+
+impl Color {
+    /// Get the ANSI escape sequence that selects the color
+    fn ansi_code(&self) -> &'static str {
+        match self {
+            Color::Green => "\x1b[32m",
+            Color::Yellow => "\x1b[33m",
+            Color::Red => "\x1b[31m",
+        }
+    }
+}
+
+/// Enum representing the severity of a message, ordered from the most severe level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// Failures of a task or the whole run
+    Error = 0,
+
+    /// Problems that do not stop the run, like deprecated config keys
+    Warn = 1,
+
+    /// Progress of the run, the default level
+    Info = 2,
+
+    /// Decisions of the run that explain the progress
+    Debug = 3,
+
+    /// Internal details like the tokens of the config parser
+    Trace = 4,
+}
+
+/// Custom error type for parsing LogLevel
+#[derive(Debug)]
+pub struct ParseLogLevelError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseLogLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse LogLevel: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseLogLevelError {}
+
+impl FromStr for LogLevel {
+    type Err = ParseLogLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warn" | "warning" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            _ => Err(ParseLogLevelError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl LogLevel {
+    /// Get the level of the numeric representation, higher values are clamped to trace
+    fn from_u8(value: u8) -> LogLevel {
+        match value {
+            0 => LogLevel::Error,
+            1 => LogLevel::Warn,
+            2 => LogLevel::Info,
+            3 => LogLevel::Debug,
+            _ => LogLevel::Trace,
+        }
+    }
+
+    /// Get the level of the '--verbose' flags, every flag adds one level to info
+    pub fn from_verbosity(count: u8) -> LogLevel {
+        LogLevel::from_u8((LogLevel::Info as u8).saturating_add(count))
+    }
+}
+
+/// Enum representing the format of the command results
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// Readable text for terminals
+    Text,
+
+    /// Single json document
+    Json,
+
+    /// One json object per line for every event of a run
+    Ndjson,
+}
+
+/// Custom error type for parsing OutputFormat
+#[derive(Debug)]
+pub struct ParseOutputFormatError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseOutputFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse OutputFormat: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseOutputFormatError {}
+
+impl FromStr for OutputFormat {
+    type Err = ParseOutputFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            _ => Err(ParseOutputFormatError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Write the status messages to stderr, so stdout is kept free for the result
+pub fn redirect_status_to_stderr() {
+    STATUS_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Set the log level of the command line, the level of the config is ignored afterwards
+pub fn set_log_level_from_args(level: LogLevel) {
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+    LOG_LEVEL_FROM_ARGS.store(true, Ordering::Relaxed);
+}
+
+/// Set the log level of the config, a level of the command line is kept
+pub fn set_log_level(level: LogLevel) {
+    if !LOG_LEVEL_FROM_ARGS.load(Ordering::Relaxed) {
+        LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+    }
+}
+
+/// Apply the log level, the log file and the syslog output of the config
+pub fn apply_config(config: &Config) -> Result<(), io::Error> {
+    set_log_level(config.log_level);
+    log_file::open(
+        config.log_file.as_deref().map(Path::new),
+        config.log_file_max_size_bytes,
+    )?;
+    syslog::open(&config.syslog)
+}
+
+/// Returns true when messages of the level are printed
+pub fn is_enabled(level: LogLevel) -> bool {
+    level <= LogLevel::from_u8(LOG_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Print a message with a severity level, messages above the log level are dropped
+pub fn log(level: LogLevel, message: &str) {
+    let color: Option<Color> = match level {
+        LogLevel::Error => Some(Color::Red),
+        LogLevel::Warn => Some(Color::Yellow),
+        LogLevel::Info | LogLevel::Debug | LogLevel::Trace => None,
+    };
+
+    log_colored(level, message, color);
+}
+
+/// Print a message with a severity level and a color, the log file gets the message without color
+fn log_colored(level: LogLevel, message: &str, color: Option<Color>) {
+    if !is_enabled(level) {
+        return;
+    }
+
+    log_file::write(level, message);
+    syslog::write(level, message);
+
+    let to_stderr: bool = match level {
+        LogLevel::Error | LogLevel::Warn => true,
+        LogLevel::Info | LogLevel::Debug | LogLevel::Trace => {
+            STATUS_TO_STDERR.load(Ordering::Relaxed)
+        }
+    };
+
+    let text: String = match color {
+        Some(color) if use_color(to_stderr) => paint(message, color),
+        _ => message.to_string(),
+    };
+
+    if let Some(buffer) = MESSAGE_BUFFER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_mut()
+    {
+        buffer.push((to_stderr, text));
+        return;
+    }
+
+    write_message(to_stderr, &text);
+}
+
+/// Write a message to its stream
+fn write_message(to_stderr: bool, text: &str) {
+    match to_stderr {
+        true => eprintln!("{}", text),
+        false => println!("{}", text),
+    }
+}
+
+/// Buffer the messages instead of printing them, until the buffer is flushed or discarded
+pub fn buffer_messages() {
+    *MESSAGE_BUFFER.lock().unwrap_or_else(|e| e.into_inner()) = Some(Vec::new());
+}
+
+/// Print the buffered messages in their order, the next messages are printed right away
+pub fn flush_buffer() {
+    let buffer = MESSAGE_BUFFER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take();
+
+    for (to_stderr, text) in buffer.unwrap_or_default() {
+        write_message(to_stderr, &text);
+    }
+}
+
+/// Drop the buffered messages, the next messages are printed right away
+pub fn discard_buffer() {
+    MESSAGE_BUFFER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take();
+}
+
+/// Select when the messages are colored
+pub fn set_color_mode(mode: ColorMode) {
+    COLOR_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+/// Returns true when the messages of the stream are colored
+fn use_color(to_stderr: bool) -> bool {
+    match COLOR_MODE.load(Ordering::Relaxed) {
+        1 => true,
+        2 => false,
+        _ => {
+            //Any non-empty value of NO_COLOR disables the colors, see no-color.org
+            let no_color: bool = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+
+            !no_color
+                && match to_stderr {
+                    true => io::stderr().is_terminal(),
+                    false => io::stdout().is_terminal(),
+                }
+        }
+    }
+}
+
+/// Wrap the text into the escape sequences of the color
+fn paint(text: &str, color: Color) -> String {
+    format!("{}{}\x1b[0m", color.ansi_code(), text)
+}
+
+/// Print a status message in a color, like the result of a task
+pub fn print_colored(message: &str, color: Color) {
+    log_colored(LogLevel::Info, message, Some(color));
+}
+
+/// Print a message about a failure
+pub fn error(message: &str) {
+    log(LogLevel::Error, message);
+}
+
+/// Print a message about a problem that does not stop the run
+pub fn warn(message: &str) {
+    log(LogLevel::Warn, message);
+}
+
+/// Print a status message about the progress of a command
+pub fn print_status(message: &str) {
+    log(LogLevel::Info, message);
+}
+
+/// Print a message that explains a decision of the run
+pub fn debug(message: &str) {
+    log(LogLevel::Debug, message);
+}
+
+/// Print a message with internal details
+pub fn trace(message: &str) {
+    log(LogLevel::Trace, message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_level() {
+        assert_eq!("debug".parse::<LogLevel>().unwrap(), LogLevel::Debug);
+        assert_eq!("WARNING".parse::<LogLevel>().unwrap(), LogLevel::Warn);
+        assert!("verbose".parse::<LogLevel>().is_err());
+        assert!(LogLevel::Error < LogLevel::Trace);
+    }
+
+    #[test]
+    fn test_log_level_from_verbosity() {
+        assert_eq!(LogLevel::from_verbosity(0), LogLevel::Info);
+        assert_eq!(LogLevel::from_verbosity(1), LogLevel::Debug);
+        assert_eq!(LogLevel::from_verbosity(2), LogLevel::Trace);
+        assert_eq!(LogLevel::from_verbosity(u8::MAX), LogLevel::Trace);
+    }
+
+    #[test]
+    fn test_parse_color_mode() {
+        assert_eq!("auto".parse::<ColorMode>().unwrap(), ColorMode::Auto);
+        assert_eq!("Never".parse::<ColorMode>().unwrap(), ColorMode::Never);
+        assert!("rainbow".parse::<ColorMode>().is_err());
+        assert_eq!(paint("ok", Color::Green), "\x1b[32mok\x1b[0m");
+    }
+}
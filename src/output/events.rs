@@ -0,0 +1,86 @@
+//! Module for the event stream of a run
+//!
+//! With '--output ndjson' every event of a run is written to stdout as a
+//! single line with a json object. Log shippers and wrappers can consume
+//! the events without parsing the status messages, which are written to
+//! stderr in this mode:
+//!
+//! {"event":"task_finished","time":"2024-03-01T12:30:00Z","run_id":"3f9a0c2e81d4b7a6","task":1,"path":"/var/log/app.log","result":"rotated","error":null}
+//!
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::cleaner::audit;
+use crate::output::json::JsonValue;
+use crate::output::run_id;
+use crate::state;
+
+/// When set to true the events are written to stdout
+static EVENTS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Write the events of the run to stdout
+pub fn enable() {
+    EVENTS_ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Returns true when the events are written
+pub fn is_enabled() -> bool {
+    EVENTS_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Write an event with its fields as a single json line, nothing is written when events are disabled
+pub fn emit(event: &str, fields: Vec<(String, JsonValue)>) {
+    if !is_enabled() {
+        return;
+    }
+
+    println!(
+        "{}",
+        event_json(event, state::unix_time_now(), run_id::current(), fields).to_compact_string()
+    );
+}
+
+/// Create the json object of an event, the name, the time and the id of the run are the first keys
+fn event_json(
+    event: &str,
+    unix_s: u64,
+    run_id: Option<String>,
+    fields: Vec<(String, JsonValue)>,
+) -> JsonValue {
+    let mut entries: Vec<(String, JsonValue)> = vec![
+        ("event".to_string(), JsonValue::string(event)),
+        (
+            "time".to_string(),
+            JsonValue::string(audit::format_utc_time(unix_s)),
+        ),
+    ];
+
+    if let Some(run_id) = run_id {
+        entries.push(("run_id".to_string(), JsonValue::string(run_id)));
+    }
+    entries.extend(fields);
+
+    JsonValue::Object(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_json() {
+        let event = event_json(
+            "task_started",
+            0,
+            Some("3f9a0c2e81d4b7a6".to_string()),
+            vec![
+                ("task".to_string(), JsonValue::uint(1)),
+                ("path".to_string(), JsonValue::string("/var/log/app.log")),
+            ],
+        );
+
+        assert_eq!(
+            event.to_compact_string(),
+            "{\"event\":\"task_started\",\"time\":\"1970-01-01T00:00:00Z\",\"run_id\":\"3f9a0c2e81d4b7a6\",\"task\":1,\"path\":\"/var/log/app.log\"}"
+        );
+    }
+}
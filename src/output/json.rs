@@ -0,0 +1,186 @@
+//! Module for writing json documents
+//!
+//! Json is only written and never read, so a small value type is enough.
+//! The keys of objects are written in the order they were inserted, which
+//! keeps the documents stable and easy to diff between two runs.
+//!
+
+/// Enum representing a json value
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Create a string value
+    pub fn string(value: impl Into<String>) -> JsonValue {
+        JsonValue::String(value.into())
+    }
+
+    /// Create an integer value, values above the maximum are capped
+    pub fn uint(value: u64) -> JsonValue {
+        JsonValue::Integer(i64::try_from(value).unwrap_or(i64::MAX))
+    }
+
+    /// Create a string value or null when there is no value
+    pub fn optional_string(value: Option<impl Into<String>>) -> JsonValue {
+        value.map_or(JsonValue::Null, JsonValue::string)
+    }
+
+    /// Format the value as json with an indentation of two spaces
+    pub fn to_pretty_string(&self) -> String {
+        let mut output = String::new();
+        self.write_value(&mut output, 0);
+        output
+    }
+
+    /// Format the value as json on a single line without any whitespace
+    pub fn to_compact_string(&self) -> String {
+        let mut output = String::new();
+        self.write_compact(&mut output);
+        output
+    }
+
+    /// Write the value into the output without any whitespace
+    fn write_compact(&self, output: &mut String) {
+        match self {
+            JsonValue::Null | JsonValue::Bool(_) | JsonValue::Integer(_) | JsonValue::String(_) => {
+                self.write_value(output, 0)
+            }
+            JsonValue::Array(items) => {
+                output.push('[');
+
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        output.push(',');
+                    }
+                    item.write_compact(output);
+                }
+
+                output.push(']');
+            }
+            JsonValue::Object(entries) => {
+                output.push('{');
+
+                for (idx, (key, value)) in entries.iter().enumerate() {
+                    if idx > 0 {
+                        output.push(',');
+                    }
+                    write_string(output, key);
+                    output.push(':');
+                    value.write_compact(output);
+                }
+
+                output.push('}');
+            }
+        }
+    }
+
+    /// Write the value into the output, nested values are indented by the level
+    fn write_value(&self, output: &mut String, level: usize) {
+        let indent = |output: &mut String, level: usize| output.push_str(&"  ".repeat(level));
+
+        match self {
+            JsonValue::Null => output.push_str("null"),
+            JsonValue::Bool(value) => output.push_str(&value.to_string()),
+            JsonValue::Integer(value) => output.push_str(&value.to_string()),
+            JsonValue::String(value) => write_string(output, value),
+            JsonValue::Array(items) if items.is_empty() => output.push_str("[]"),
+            JsonValue::Object(entries) if entries.is_empty() => output.push_str("{}"),
+            JsonValue::Array(items) => {
+                output.push_str("[\n");
+
+                for (idx, item) in items.iter().enumerate() {
+                    indent(output, level + 1);
+                    item.write_value(output, level + 1);
+                    output.push_str(if idx + 1 < items.len() { ",\n" } else { "\n" });
+                }
+
+                indent(output, level);
+                output.push(']');
+            }
+            JsonValue::Object(entries) => {
+                output.push_str("{\n");
+
+                for (idx, (key, value)) in entries.iter().enumerate() {
+                    indent(output, level + 1);
+                    write_string(output, key);
+                    output.push_str(": ");
+                    value.write_value(output, level + 1);
+                    output.push_str(if idx + 1 < entries.len() { ",\n" } else { "\n" });
+                }
+
+                indent(output, level);
+                output.push('}');
+            }
+        }
+    }
+}
+
+/// Write a string with surrounding quotes, control characters are escaped
+fn write_string(output: &mut String, value: &str) {
+    output.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => output.push(c),
+        }
+    }
+
+    output.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_pretty_string() {
+        let value = JsonValue::Object(vec![
+            (
+                "path".to_string(),
+                JsonValue::string("/var/log/\"app\".log"),
+            ),
+            ("size".to_string(), JsonValue::uint(512)),
+            ("error".to_string(), JsonValue::Null),
+            (
+                "actions".to_string(),
+                JsonValue::Array(vec![JsonValue::Bool(true), JsonValue::string("a\tb\u{1}")]),
+            ),
+            ("notes".to_string(), JsonValue::Array(Vec::new())),
+        ]);
+
+        assert_eq!(
+            value.to_pretty_string(),
+            "{\n  \"path\": \"/var/log/\\\"app\\\".log\",\n  \"size\": 512,\n  \"error\": null,\n  \"actions\": [\n    true,\n    \"a\\tb\\u0001\"\n  ],\n  \"notes\": []\n}"
+        );
+    }
+
+    #[test]
+    fn test_json_compact_string() {
+        let value = JsonValue::Object(vec![
+            ("event".to_string(), JsonValue::string("task_finished")),
+            (
+                "notes".to_string(),
+                JsonValue::Array(vec![JsonValue::uint(1), JsonValue::Null]),
+            ),
+            ("actions".to_string(), JsonValue::Array(Vec::new())),
+        ]);
+
+        assert_eq!(
+            value.to_compact_string(),
+            "{\"event\":\"task_finished\",\"notes\":[1,null],\"actions\":[]}"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Module for the log file of yalc itself
+//!
+//! Cron jobs and timers often discard the console output of a run. With a
+//! log file every printed message is also appended to the configured file
+//! with its time, severity level and the id of the run:
+//!
+//! 2024-03-01T12:30:00Z INFO run=3f9a0c2e81d4b7a6 [1] Renaming original to '/var/log/app.log.0'
+//!
+//! The log file rotates itself: when the next message would exceed the
+//! maximum size, the file is renamed to '<log_file>.1' and a new file is
+//! started. Only a single rotation is kept.
+//!
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::cleaner::audit;
+use crate::output::{LogLevel, run_id};
+use crate::state;
+
+/// Mode of a newly created log file, only the owner and the group can read it
+const LOG_FILE_MODE: u32 = 0o640;
+
+/// Log file of the process, the messages are only printed when it is not opened
+static LOG_FILE: Mutex<Option<LogFile>> = Mutex::new(None);
+
+/// Represents the open log file with the number of bytes that it contains
+#[derive(Debug)]
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size_bytes: u64,
+}
+
+impl LogFile {
+    /// Open the log file for appending, it is created when it does not exist
+    fn open(path: &Path, max_size_bytes: u64) -> Result<LogFile, io::Error> {
+        let file: File = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .mode(LOG_FILE_MODE)
+            .open(path)
+            .map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("Failed to open log file '{}': {}", path.display(), e),
+                )
+            })?;
+
+        Ok(LogFile {
+            path: path.to_path_buf(),
+            size: file.metadata()?.len(),
+            file,
+            max_size_bytes,
+        })
+    }
+
+    /// Replace the previous rotation with the current file and start a new file
+    fn rotate(&mut self) -> Result<(), io::Error> {
+        fs::rename(&self.path, rotated_path(&self.path))?;
+        *self = LogFile::open(&self.path, self.max_size_bytes)?;
+        Ok(())
+    }
+}
+
+/// Get the path of the rotated log file like '/var/log/yalc.log.1'
+fn rotated_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.1", path.display()))
+}
+
+/// Open the log file, a previously opened log file is closed. Without a path no log file is used.
+pub fn open(path: Option<&Path>, max_size_bytes: u64) -> Result<(), io::Error> {
+    let log_file: Option<LogFile> = match path {
+        Some(path) => Some(LogFile::open(path, max_size_bytes)?),
+        None => None,
+    };
+
+    *LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()) = log_file;
+    Ok(())
+}
+
+/// Append a line with the time and the level of the message to the log file
+pub fn write(level: LogLevel, message: &str) {
+    let mut log_file = LOG_FILE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(log) = log_file.as_mut() else {
+        return;
+    };
+
+    let line: String = format!(
+        "{} {} {}{}\n",
+        audit::format_utc_time(state::unix_time_now()),
+        level_name(level),
+        run_id::field(),
+        message
+    );
+
+    //An empty file is never rotated, so a single large message is still written
+    if log.size > 0
+        && log.size + line.len() as u64 > log.max_size_bytes
+        && let Err(e) = log.rotate()
+    {
+        eprintln!("Failed to rotate the log file: {}", e);
+    }
+
+    match log.file.write_all(line.as_bytes()) {
+        Ok(()) => log.size += line.len() as u64,
+        Err(e) => eprintln!("Failed to write the log file: {}", e),
+    }
+}
+
+/// Get the name of the level in the log file like 'INFO'
+fn level_name(level: LogLevel) -> &'static str {
+    match level {
+        LogLevel::Error => "ERROR",
+        LogLevel::Warn => "WARN",
+        LogLevel::Info => "INFO",
+        LogLevel::Debug => "DEBUG",
+        LogLevel::Trace => "TRACE",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_log_file_rotation() {
+        let dir = env::temp_dir().join(format!("yalc_test_log_file_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("yalc.log");
+
+        //The log file is not used by the static of the process, so tests do not interfere
+        let mut log = LogFile::open(&path, 64).unwrap();
+        log.file.write_all(&[b'x'; 60]).unwrap();
+        log.size += 60;
+        log.rotate().unwrap();
+
+        assert_eq!(fs::metadata(rotated_path(&path)).unwrap().len(), 60);
+        assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+        assert_eq!(log.size, 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
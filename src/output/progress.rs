@@ -0,0 +1,205 @@
+//! Module for the progress of long file operations
+//!
+//! Copies and compressions of files with several gigabytes can take many
+//! minutes. The copy loops report their copied bytes, so operators can tell
+//! that the run is not hung. On a terminal a progress bar is drawn on
+//! stderr, otherwise a status line with the percentage is printed in a
+//! fixed interval, which also ends up in the log file:
+//!
+//! compress '/var/log/app.log.0' [########------------]  40% 1.1 GiB of 2.7 GiB
+//! compress '/var/log/app.log.0': 40% [1.1 GiB of 2.7 GiB]
+//!
+//! Operations that finish within the delay show no progress at all.
+//!
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::output::{self, LogLevel, table};
+
+/// Time after the start of an operation before its progress is shown
+const PROGRESS_DELAY: Duration = Duration::from_secs(2);
+
+/// Minimum time between two redraws of the progress bar
+const BAR_REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Time between two progress lines when stderr is not a terminal
+const LINE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Number of characters of the progress bar
+const BAR_WIDTH: usize = 20;
+
+/// Progress of the running operation, None when no operation is tracked
+static PROGRESS: Mutex<Option<Progress>> = Mutex::new(None);
+
+/// Represents the progress of a running file operation
+#[derive(Debug)]
+struct Progress {
+    /// Operation and file like "compress '/var/log/app.log.0'"
+    label: String,
+
+    /// Size of the source file in bytes, 0 when the size is unknown
+    total_bytes: u64,
+
+    copied_bytes: u64,
+    started: Instant,
+
+    /// Time when the progress was shown the last time, None before it was shown
+    last_shown: Option<Instant>,
+
+    /// True when the progress is drawn as bar on the terminal
+    draw_bar: bool,
+}
+
+/// Represents the tracked operation, the progress bar is removed when it is dropped
+#[derive(Debug)]
+pub struct ProgressScope {
+    _private: (),
+}
+
+//Implement the Drop trait
+impl Drop for ProgressScope {
+    fn drop(&mut self) {
+        let progress: Option<Progress> = PROGRESS.lock().unwrap_or_else(|e| e.into_inner()).take();
+
+        //The bar is cleared, so the next message starts at an empty line
+        if let Some(progress) = progress
+            && progress.draw_bar
+            && progress.last_shown.is_some()
+        {
+            eprint!("\r\x1b[K");
+            let _ = io::stderr().flush();
+        }
+    }
+}
+
+/// Track the progress of an operation on the source file until the scope is dropped
+pub fn start(operation: &str, source: &Path, total_bytes: u64) -> ProgressScope {
+    *PROGRESS.lock().unwrap_or_else(|e| e.into_inner()) = Some(Progress {
+        label: format!("{} '{}'", operation, source.display()),
+        total_bytes,
+        copied_bytes: 0,
+        started: Instant::now(),
+        last_shown: None,
+        draw_bar: io::stderr().is_terminal(),
+    });
+
+    ProgressScope { _private: () }
+}
+
+/// Add the copied bytes to the running operation and show its progress when it is due
+pub fn advance(bytes: u64) {
+    let mut progress = PROGRESS.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(progress) = progress.as_mut() else {
+        return;
+    };
+
+    progress.copied_bytes = progress.copied_bytes.saturating_add(bytes);
+
+    let now: Instant = Instant::now();
+    let interval: Duration = match progress.draw_bar {
+        true => BAR_REDRAW_INTERVAL,
+        false => LINE_INTERVAL,
+    };
+
+    let is_due: bool = match progress.last_shown {
+        None => now.duration_since(progress.started) >= PROGRESS_DELAY,
+        Some(last_shown) => now.duration_since(last_shown) >= interval,
+    };
+
+    if !is_due || !output::is_enabled(LogLevel::Info) {
+        return;
+    }
+    progress.last_shown = Some(now);
+
+    match progress.draw_bar {
+        true => {
+            eprint!("\r\x1b[K{}", format_bar(progress));
+            let _ = io::stderr().flush();
+        }
+        false => output::print_status(&format_line(progress)),
+    }
+}
+
+/// Get the percentage of the copied bytes, None when the size is unknown.
+/// Files can grow during the copy, so the percentage never exceeds 100.
+fn percent(progress: &Progress) -> Option<u64> {
+    match progress.total_bytes {
+        0 => None,
+        total_bytes => Some((progress.copied_bytes.saturating_mul(100) / total_bytes).min(100)),
+    }
+}
+
+/// Format the progress bar like "compress 'app.log' [####----]  50% 1.0 GiB of 2.0 GiB"
+fn format_bar(progress: &Progress) -> String {
+    let Some(percent) = percent(progress) else {
+        return format!(
+            "{} {}",
+            progress.label,
+            table::format_bytes(progress.copied_bytes)
+        );
+    };
+
+    let filled: usize = (percent as usize * BAR_WIDTH) / 100;
+    format!(
+        "{} [{}{}] {:>3}% {} of {}",
+        progress.label,
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        percent,
+        table::format_bytes(progress.copied_bytes),
+        table::format_bytes(progress.total_bytes)
+    )
+}
+
+/// Format the progress line like "compress 'app.log': 50% [1.0 GiB of 2.0 GiB]"
+fn format_line(progress: &Progress) -> String {
+    match percent(progress) {
+        Some(percent) => format!(
+            "{}: {}% [{} of {}]",
+            progress.label,
+            percent,
+            table::format_bytes(progress.copied_bytes),
+            table::format_bytes(progress.total_bytes)
+        ),
+        None => format!(
+            "{}: {} copied",
+            progress.label,
+            table::format_bytes(progress.copied_bytes)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_progress() {
+        let mut progress = Progress {
+            label: "compress 'app.log'".to_string(),
+            total_bytes: 2 * 1024 * 1024 * 1024,
+            copied_bytes: 1024 * 1024 * 1024,
+            started: Instant::now(),
+            last_shown: None,
+            draw_bar: false,
+        };
+
+        assert_eq!(
+            format_bar(&progress),
+            "compress 'app.log' [##########----------]  50% 1.0 GiB of 2.0 GiB"
+        );
+        assert_eq!(
+            format_line(&progress),
+            "compress 'app.log': 50% [1.0 GiB of 2.0 GiB]"
+        );
+
+        //A file that grew during the copy stays at 100%
+        progress.copied_bytes = 3 * 1024 * 1024 * 1024;
+        assert_eq!(percent(&progress), Some(100));
+
+        progress.total_bytes = 0;
+        assert_eq!(format_line(&progress), "compress 'app.log': 3.0 GiB copied");
+    }
+}
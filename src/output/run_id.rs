@@ -0,0 +1,100 @@
+//! Module for the unique id of a run
+//!
+//! Hosts that ship their logs to a central place need to tell the messages
+//! of concurrent runs apart. Every run gets a random looking id that is
+//! part of every line of the log file, the syslog messages, the events,
+//! the audit log and the notifications of the run:
+//!
+//! 2024-03-01T12:30:00Z INFO run=3f9a0c2e81d4b7a6 [1] Renaming original to '/var/log/app.log.0'
+//!
+//! The id is derived from the hostname, the process id, the current time
+//! and a counter, so the runs of the same daemon and of different hosts
+//! never share an id. Messages outside of a run, like the config check
+//! before the run, have no id.
+//!
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cleaner::sha256::Sha256;
+use crate::platform;
+
+/// Number of hex digits of a run id
+const RUN_ID_LENGTH: usize = 16;
+
+/// Id of the current run, None outside of a run
+static CURRENT_RUN_ID: Mutex<Option<String>> = Mutex::new(None);
+
+/// Number of the runs that were started by the process
+static RUN_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Represents the id of the current run, the messages have no id after it is dropped
+#[derive(Debug)]
+pub struct RunScope {
+    _private: (),
+}
+
+//Implement the Drop trait
+impl Drop for RunScope {
+    fn drop(&mut self) {
+        *CURRENT_RUN_ID.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// Start a run with a new id, the messages carry the id until the scope is dropped
+pub fn start() -> RunScope {
+    let unix_ns: u128 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos());
+
+    let run_id: String = generate_id(
+        &platform::hostname(),
+        std::process::id(),
+        unix_ns,
+        RUN_COUNTER.fetch_add(1, Ordering::Relaxed),
+    );
+
+    *CURRENT_RUN_ID.lock().unwrap_or_else(|e| e.into_inner()) = Some(run_id);
+    RunScope { _private: () }
+}
+
+/// Get the id of the current run, None outside of a run
+pub fn current() -> Option<String> {
+    CURRENT_RUN_ID
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Get the field of the current run like 'run=3f9a0c2e81d4b7a6 ', empty outside of a run
+pub fn field() -> String {
+    current()
+        .map(|run_id| format!("run={} ", run_id))
+        .unwrap_or_default()
+}
+
+/// Create the id of a run from the hash of its origin
+fn generate_id(hostname: &str, pid: u32, unix_ns: u128, counter: u64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}:{}:{}:{}", hostname, pid, unix_ns, counter).as_bytes());
+
+    let mut run_id: String = hasher.finish_hex();
+    run_id.truncate(RUN_ID_LENGTH);
+    run_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_id() {
+        let run_id: String = generate_id("web1", 42, 1709296200000000000, 0);
+
+        assert_eq!(run_id.len(), RUN_ID_LENGTH);
+        assert!(run_id.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(run_id, generate_id("web1", 42, 1709296200000000000, 0));
+        assert_ne!(run_id, generate_id("web2", 42, 1709296200000000000, 0));
+        assert_ne!(run_id, generate_id("web1", 42, 1709296200000000000, 1));
+    }
+}
@@ -0,0 +1,260 @@
+//! Module for sending the messages to the local syslog daemon
+//!
+//! With '[syslog]' every printed message is also sent to the socket of the
+//! local syslog daemon, so the activity of yalc ends up in the same log
+//! pipeline as the files it manages. The messages use the BSD format
+//! without a timestamp, the daemon adds the time of reception. Relays can
+//! delay the messages, so the text starts with the time of the message
+//! and the id of the run:
+//!
+//! <30>yalc[4711]: 2024-03-01T12:30:00Z run=3f9a0c2e81d4b7a6 [1] Renaming original to '/var/log/app.log.0'
+//!
+//! Errors and warnings are sent with the severities err and warning, the
+//! status messages with the configured priority.
+//!
+use std::fmt;
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::cleaner::audit;
+use crate::config::SyslogConfig;
+use crate::output::{LogLevel, run_id};
+use crate::state;
+
+/// Sockets of the local syslog daemon, the first socket that exists is used
+const SYSLOG_SOCKETS: [&str; 3] = ["/dev/log", "/var/run/syslog", "/var/run/log"];
+
+/// Connection to the syslog daemon, the messages are only printed when it is not opened
+static SYSLOG: Mutex<Option<Syslog>> = Mutex::new(None);
+
+/// Enum representing the syslog facilities that a program can use
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Facility {
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Cron = 9,
+    AuthPriv = 10,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// Custom error type for parsing Facility
+#[derive(Debug)]
+pub struct ParseFacilityError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseFacilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse Facility: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseFacilityError {}
+
+impl FromStr for Facility {
+    type Err = ParseFacilityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "user" => Ok(Facility::User),
+            "mail" => Ok(Facility::Mail),
+            "daemon" => Ok(Facility::Daemon),
+            "auth" => Ok(Facility::Auth),
+            "syslog" => Ok(Facility::Syslog),
+            "cron" => Ok(Facility::Cron),
+            "authpriv" => Ok(Facility::AuthPriv),
+            "local0" => Ok(Facility::Local0),
+            "local1" => Ok(Facility::Local1),
+            "local2" => Ok(Facility::Local2),
+            "local3" => Ok(Facility::Local3),
+            "local4" => Ok(Facility::Local4),
+            "local5" => Ok(Facility::Local5),
+            "local6" => Ok(Facility::Local6),
+            "local7" => Ok(Facility::Local7),
+            _ => Err(ParseFacilityError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Enum representing the syslog severities, ordered from the most severe value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Emergency = 0,
+    Alert = 1,
+    Critical = 2,
+    Error = 3,
+    Warning = 4,
+    Notice = 5,
+    Info = 6,
+    Debug = 7,
+}
+
+/// Custom error type for parsing Severity
+#[derive(Debug)]
+pub struct ParseSeverityError {
+    invalid_value: String,
+}
+
+//Implement the Display trait
+impl fmt::Display for ParseSeverityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse Severity: {}", self.invalid_value)
+    }
+}
+
+//Implement the std Error trait
+impl std::error::Error for ParseSeverityError {}
+
+impl FromStr for Severity {
+    type Err = ParseSeverityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "emerg" | "emergency" => Ok(Severity::Emergency),
+            "alert" => Ok(Severity::Alert),
+            "crit" | "critical" => Ok(Severity::Critical),
+            "err" | "error" => Ok(Severity::Error),
+            "warning" | "warn" => Ok(Severity::Warning),
+            "notice" => Ok(Severity::Notice),
+            "info" => Ok(Severity::Info),
+            "debug" => Ok(Severity::Debug),
+            _ => Err(ParseSeverityError {
+                invalid_value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Represents the socket of the syslog daemon with the facility and the priority of the messages
+#[derive(Debug)]
+struct Syslog {
+    socket: UnixDatagram,
+    facility: Facility,
+    priority: Severity,
+}
+
+/// Connect to the socket of the local syslog daemon, a previous connection is closed.
+/// Without an enabled syslog config no messages are sent.
+pub fn open(config: &SyslogConfig) -> Result<(), io::Error> {
+    let syslog: Option<Syslog> = match config.enabled {
+        true => Some(Syslog {
+            socket: connect()?,
+            facility: config.facility,
+            priority: config.priority,
+        }),
+        false => None,
+    };
+
+    *SYSLOG.lock().unwrap_or_else(|e| e.into_inner()) = syslog;
+    Ok(())
+}
+
+/// Connect to the first socket of the syslog daemon that exists
+fn connect() -> Result<UnixDatagram, io::Error> {
+    let socket_path: &str = SYSLOG_SOCKETS
+        .into_iter()
+        .find(|path| Path::new(path).exists())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "No syslog socket found, checked: {}",
+                    SYSLOG_SOCKETS.join(", ")
+                ),
+            )
+        })?;
+
+    let socket: UnixDatagram = UnixDatagram::unbound()?;
+    socket.connect(socket_path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Failed to connect to syslog '{}': {}", socket_path, e),
+        )
+    })?;
+
+    //A busy syslog daemon must never stall the run, messages are dropped when its queue is full
+    socket.set_nonblocking(true)?;
+
+    Ok(socket)
+}
+
+/// Send the message to the syslog daemon, a daemon that is not reachable or busy drops the message
+pub fn write(level: LogLevel, message: &str) {
+    let syslog = SYSLOG.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(syslog) = syslog.as_ref() else {
+        return;
+    };
+
+    let severity: Severity = match level {
+        LogLevel::Error => Severity::Error,
+        LogLevel::Warn => Severity::Warning,
+        LogLevel::Info => syslog.priority,
+        LogLevel::Debug | LogLevel::Trace => Severity::Debug,
+    };
+
+    let text: String = format!(
+        "{} {}{}",
+        audit::format_utc_time(state::unix_time_now()),
+        run_id::field(),
+        message
+    );
+
+    //The message can not be printed, every error message would be sent to syslog again
+    let _ = syslog
+        .socket
+        .send(format_message(syslog.facility, severity, process::id(), &text).as_bytes());
+}
+
+/// Format a message of the BSD syslog protocol without a timestamp
+fn format_message(facility: Facility, severity: Severity, pid: u32, message: &str) -> String {
+    format!(
+        "<{}>yalc[{}]: {}",
+        (facility as u8) * 8 + severity as u8,
+        pid,
+        message
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_message() {
+        assert_eq!(
+            format_message(Facility::Daemon, Severity::Info, 42, "All tasks done"),
+            "<30>yalc[42]: All tasks done"
+        );
+        assert_eq!(
+            format_message(Facility::Local7, Severity::Error, 1, "Task error"),
+            "<187>yalc[1]: Task error"
+        );
+    }
+
+    #[test]
+    fn test_parse_syslog_values() {
+        assert_eq!("LOCAL3".parse::<Facility>().unwrap(), Facility::Local3);
+        assert!("kernel".parse::<Facility>().is_err());
+        assert_eq!("notice".parse::<Severity>().unwrap(), Severity::Notice);
+        assert_eq!("warn".parse::<Severity>().unwrap(), Severity::Warning);
+        assert!("verbose".parse::<Severity>().is_err());
+    }
+}
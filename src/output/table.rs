@@ -0,0 +1,138 @@
+//! Module for aligned text tables
+//!
+//! The summary of a run is printed as table with one row per file. Every
+//! column is as wide as its widest cell, numbers are aligned to the right
+//! so their units line up:
+//!
+//! File              Action     Freed  Status
+//! ----------------  -------  -------  ------
+//! /var/log/app.log  rotated  2.0 KiB  ok
+//!
+use std::time::Duration;
+
+/// Enum representing the alignment of the cells of a column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Render the table into lines, the header is separated from the rows by dashes.
+/// Rows with fewer cells than columns are filled with empty cells.
+pub fn render(columns: &[(&str, Align)], rows: &[Vec<String>]) -> Vec<String> {
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(idx, (header, _))| {
+            rows.iter()
+                .filter_map(|row| row.get(idx))
+                .map(|cell| cell.chars().count())
+                .chain([header.chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let render_row = |cells: &[&str]| -> String {
+        let line: Vec<String> = columns
+            .iter()
+            .zip(widths.iter())
+            .enumerate()
+            .map(|(idx, ((_, align), width))| {
+                let cell: &str = cells.get(idx).copied().unwrap_or("");
+
+                match align {
+                    Align::Left => format!("{:<width$}", cell, width = width),
+                    Align::Right => format!("{:>width$}", cell, width = width),
+                }
+            })
+            .collect();
+
+        line.join("  ").trim_end().to_string()
+    };
+
+    let headers: Vec<&str> = columns.iter().map(|(header, _)| *header).collect();
+    let dashes: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+
+    let mut lines: Vec<String> = Vec::with_capacity(rows.len() + 2);
+    lines.push(render_row(&headers));
+    lines.push(render_row(
+        &dashes.iter().map(String::as_str).collect::<Vec<&str>>(),
+    ));
+
+    for row in rows.iter() {
+        lines.push(render_row(
+            &row.iter().map(String::as_str).collect::<Vec<&str>>(),
+        ));
+    }
+
+    lines
+}
+
+/// Format a number of bytes with a binary unit like '1.5 MiB'
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut value: f64 = bytes as f64 / 1024.0;
+    let mut unit: usize = 0;
+
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Format a throughput in bytes per second like '20.0 MiB/s'
+pub fn format_throughput(bytes_per_second: u64) -> String {
+    format!("{}/s", format_bytes(bytes_per_second))
+}
+
+/// Format a duration with milliseconds below one second like '250 ms' or '1.5 s'
+pub fn format_duration(duration: Duration) -> String {
+    match duration.as_millis() {
+        millis if millis < 1000 => format!("{} ms", millis),
+        _ => format!("{:.1} s", duration.as_secs_f64()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_table() {
+        let lines = render(
+            &[("File", Align::Left), ("Freed", Align::Right)],
+            &[
+                vec!["/var/log/app.log".to_string(), "2.0 KiB".to_string()],
+                vec!["a.log".to_string(), "0 B".to_string()],
+            ],
+        );
+
+        assert_eq!(
+            lines,
+            vec![
+                "File                Freed",
+                "----------------  -------",
+                "/var/log/app.log  2.0 KiB",
+                "a.log                 0 B",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_units() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+        assert_eq!(format_duration(Duration::from_millis(250)), "250 ms");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1.5 s");
+        assert_eq!(format_throughput(20 * 1024 * 1024), "20.0 MiB/s");
+    }
+}
@@ -0,0 +1,375 @@
+//! Module for platform specific system calls
+//!
+//! The rust standard library does not cover every system call that is
+//! required by yalc. This module wraps the required libc functions, so
+//! all unsafe code of yalc is located in this module.
+//!
+pub mod priority;
+
+use std::ffi::{CString, c_char, c_int};
+use std::fs::{File, Metadata};
+use std::io;
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+use std::mem::MaybeUninit;
+use std::os::fd::AsRawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// Prefix of the libc 'passwd' struct, only the leading fields are read
+#[repr(C)]
+#[allow(dead_code)] //Only created by libc
+struct Passwd {
+    pw_name: *mut c_char,
+    pw_passwd: *mut c_char,
+    pw_uid: u32,
+    pw_gid: u32,
+}
+
+/// Prefix of the libc 'group' struct, only the leading fields are read
+#[repr(C)]
+#[allow(dead_code)] //Only created by libc
+struct Group {
+    gr_name: *mut c_char,
+    gr_passwd: *mut c_char,
+    gr_gid: u32,
+}
+
+/// The libc 'statvfs' struct of 64 bit linux systems, other systems use another layout
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+#[repr(C)]
+#[allow(dead_code)] //Only created by libc
+struct StatVfs {
+    f_bsize: u64,
+    f_frsize: u64,
+    f_blocks: u64,
+    f_bfree: u64,
+    f_bavail: u64,
+    f_files: u64,
+    f_ffree: u64,
+    f_favail: u64,
+    f_fsid: u64,
+    f_flag: u64,
+    f_namemax: u64,
+    f_spare: [c_int; 6],
+}
+
+unsafe extern "C" {
+    fn geteuid() -> u32;
+    fn gethostname(name: *mut c_char, len: usize) -> c_int;
+    fn getpwnam(name: *const c_char) -> *mut Passwd;
+    fn getgrnam(name: *const c_char) -> *mut Group;
+    fn kill(pid: c_int, sig: c_int) -> c_int;
+    #[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+    fn statvfs(path: *const c_char, buf: *mut StatVfs) -> c_int;
+    fn signal(signum: c_int, handler: usize) -> usize;
+    fn ioctl(fd: c_int, request: u64, ...) -> c_int;
+    fn copy_file_range(
+        fd_in: c_int,
+        off_in: *mut i64,
+        fd_out: c_int,
+        off_out: *mut i64,
+        len: usize,
+        flags: u32,
+    ) -> isize;
+}
+
+/// Request number of the linux 'FICLONE' ioctl
+const FICLONE: u64 = 0x40049409;
+
+/// Value of the libc 'SIG_DFL' handler
+const SIG_DFL: usize = 0;
+
+/// Number of the received shutdown signal, zero when no signal was received
+static SHUTDOWN_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Represents the size and the free space of a filesystem
+#[derive(Debug)]
+pub struct FilesystemSpace {
+    pub total_bytes: u64,
+
+    /// Bytes that are available for unprivileged users
+    pub available_bytes: u64,
+}
+
+impl FilesystemSpace {
+    /// Percentage of the available bytes, 100 for filesystems without a size
+    pub fn free_percent(&self) -> u64 {
+        match self.total_bytes {
+            0 => 100,
+            total => ((self.available_bytes as u128 * 100) / total as u128) as u64,
+        }
+    }
+}
+
+/// Names and numbers of the signals that can be sent to processes
+const SIGNALS: &[(&str, c_int)] = &[
+    ("HUP", 1),
+    ("INT", 2),
+    ("QUIT", 3),
+    ("USR1", 10),
+    ("USR2", 12),
+    ("TERM", 15),
+    ("CONT", 18),
+    ("WINCH", 28),
+];
+
+/// Returns true when the process is running with root privileges
+pub fn is_root_user() -> bool {
+    //SAFETY: geteuid has no preconditions and can not fail
+    unsafe { geteuid() == 0 }
+}
+
+/// Get the effective user id of the process
+pub fn effective_user_id() -> u32 {
+    //SAFETY: geteuid has no preconditions and can not fail
+    unsafe { geteuid() }
+}
+
+/// Get the host name of the system, 'localhost' when it can not be read
+pub fn hostname() -> String {
+    let mut buffer: [u8; 256] = [0; 256];
+
+    //SAFETY: The buffer is valid for its length, the result is only read on success
+    let result: c_int = unsafe { gethostname(buffer.as_mut_ptr() as *mut c_char, buffer.len()) };
+
+    //Truncated names are not guaranteed to contain the terminating null byte
+    let length: usize = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+
+    match result == 0 && length > 0 {
+        true => String::from_utf8_lossy(&buffer[..length]).to_string(),
+        false => "localhost".to_string(),
+    }
+}
+
+/// Get the size of a file in bytes, the allocated blocks are used when `allocated` is set.
+/// Sparse files use less disk space than their length, files with holes are common for
+/// logs that are truncated while the writer keeps its offset.
+pub fn file_size(metadata: &Metadata, allocated: bool) -> u64 {
+    match allocated {
+        //The block count of stat is always in units of 512 bytes
+        true => metadata.blocks().saturating_mul(512),
+        false => metadata.len(),
+    }
+}
+
+/// Get the user id of a user name or numeric id, None for unknown users
+pub fn lookup_user_id(user: &str) -> Option<u32> {
+    if let Ok(uid) = user.parse::<u32>() {
+        return Some(uid);
+    }
+
+    let name = CString::new(user).ok()?;
+
+    //SAFETY: The name is a valid C string, the result is checked for null
+    //before it is read and the static result buffer is not kept
+    unsafe {
+        let passwd: *mut Passwd = getpwnam(name.as_ptr());
+        passwd.as_ref().map(|p| p.pw_uid)
+    }
+}
+
+/// Get the group id of a group name or numeric id, None for unknown groups
+pub fn lookup_group_id(group: &str) -> Option<u32> {
+    if let Ok(gid) = group.parse::<u32>() {
+        return Some(gid);
+    }
+
+    let name = CString::new(group).ok()?;
+
+    //SAFETY: The name is a valid C string, the result is checked for null
+    //before it is read and the static result buffer is not kept
+    unsafe {
+        let group: *mut Group = getgrnam(name.as_ptr());
+        group.as_ref().map(|g| g.gr_gid)
+    }
+}
+
+/// Get the number of a signal name like 'HUP' or 'SIGHUP', None for unknown signals
+pub fn signal_number(name: &str) -> Option<i32> {
+    let upper_name: String = name.to_uppercase();
+    let short_name: &str = upper_name.strip_prefix("SIG").unwrap_or(&upper_name);
+
+    SIGNALS
+        .iter()
+        .find(|(signal_name, _)| *signal_name == short_name)
+        .map(|(_, number)| *number)
+}
+
+/// Get the name of a signal number like 'SIGTERM'
+pub fn signal_name(number: i32) -> String {
+    match SIGNALS
+        .iter()
+        .find(|(_, signal_number)| *signal_number == number)
+    {
+        Some((name, _)) => format!("SIG{}", name),
+        None => format!("signal {}", number),
+    }
+}
+
+/// Handler for SIGINT and SIGTERM, only stores the signal
+extern "C" fn handle_shutdown_signal(signal_number: c_int) {
+    SHUTDOWN_SIGNAL.store(signal_number, Ordering::SeqCst);
+
+    //A second signal terminates the process right away
+    //SAFETY: signal is async-signal-safe and the default handler has no preconditions
+    unsafe {
+        signal(signal_number, SIG_DFL);
+    }
+}
+
+/// Install the handlers for SIGINT and SIGTERM, the signals are polled with shutdown_signal
+pub fn install_shutdown_handler() {
+    let handler = handle_shutdown_signal as extern "C" fn(c_int) as usize;
+
+    for signal_number in [2, 15] {
+        //SAFETY: The handler only performs async-signal-safe operations
+        unsafe {
+            signal(signal_number, handler);
+        }
+    }
+}
+
+/// Get the received SIGINT or SIGTERM, None when no shutdown was requested
+pub fn shutdown_signal() -> Option<i32> {
+    match SHUTDOWN_SIGNAL.load(Ordering::SeqCst) {
+        0 => None,
+        signal_number => Some(signal_number),
+    }
+}
+
+/// Send a signal to a process, the error contains the reason of the failed send
+pub fn send_signal(pid: i32, signal: i32) -> Result<(), io::Error> {
+    //SAFETY: kill has no memory preconditions, invalid arguments result in an error code
+    let result: c_int = unsafe { kill(pid, signal) };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Share the data blocks of the source with the destination on filesystems like btrfs and XFS.
+/// The destination must be empty, the error is EOPNOTSUPP or EXDEV without reflink support.
+pub fn clone_file(source: &File, dest: &File) -> Result<(), io::Error> {
+    //SAFETY: Both file descriptors are valid while the references are borrowed
+    let result: c_int = unsafe { ioctl(dest.as_raw_fd(), FICLONE, source.as_raw_fd()) };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Copy up to length bytes from the current offset of the source to the current offset
+/// of the destination within the kernel. Returns the number of copied bytes, 0 at the end.
+pub fn copy_file_chunk(source: &File, dest: &File, length: usize) -> Result<u64, io::Error> {
+    //SAFETY: Both file descriptors are valid and null offsets use the file offsets
+    let result: isize = unsafe {
+        copy_file_range(
+            source.as_raw_fd(),
+            std::ptr::null_mut(),
+            dest.as_raw_fd(),
+            std::ptr::null_mut(),
+            length,
+            0,
+        )
+    };
+
+    u64::try_from(result).map_err(|_| io::Error::last_os_error())
+}
+
+/// Get the space of the filesystem that contains the path
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+pub fn filesystem_space(path: &Path) -> Result<FilesystemSpace, io::Error> {
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut stat: MaybeUninit<StatVfs> = MaybeUninit::uninit();
+
+    //SAFETY: The path is a valid C string and the buffer has the size of the libc struct
+    let result: c_int = unsafe { statvfs(c_path.as_ptr(), stat.as_mut_ptr()) };
+
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    //SAFETY: The buffer was initialized by the successful statvfs call
+    let stat: StatVfs = unsafe { stat.assume_init() };
+    let block_size: u64 = stat.f_frsize;
+
+    Ok(FilesystemSpace {
+        total_bytes: stat.f_blocks.saturating_mul(block_size),
+        available_bytes: stat.f_bavail.saturating_mul(block_size),
+    })
+}
+
+/// Get the space of the filesystem that contains the path.
+/// The layout of the statvfs struct is only known for 64 bit linux systems.
+#[cfg(not(all(target_os = "linux", target_pointer_width = "64")))]
+pub fn filesystem_space(path: &Path) -> Result<FilesystemSpace, io::Error> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "The free space of '{}' can not be read on this platform",
+            path.display()
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_ids() {
+        assert_eq!(lookup_user_id("root"), Some(0));
+        assert_eq!(lookup_user_id("1234"), Some(1234));
+        assert_eq!(lookup_user_id("yalc-unknown-user"), None);
+        assert_eq!(lookup_group_id("root"), Some(0));
+        assert_eq!(lookup_group_id("yalc-unknown-group"), None);
+    }
+
+    #[test]
+    fn test_hostname() {
+        let name = hostname();
+        assert!(!name.is_empty());
+        assert!(!name.contains('\0'));
+    }
+
+    #[test]
+    fn test_file_size() {
+        let path = std::env::temp_dir().join(format!("yalc_test_sparse_{}", std::process::id()));
+        let file = File::create(&path).unwrap();
+
+        //A file that only consists of a hole does not allocate the full length
+        file.set_len(8 * 1024 * 1024).unwrap();
+        let metadata = file.metadata().unwrap();
+        assert_eq!(file_size(&metadata, false), 8 * 1024 * 1024);
+        assert!(file_size(&metadata, true) < 8 * 1024 * 1024);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_signal_number() {
+        assert_eq!(signal_number("HUP"), Some(1));
+        assert_eq!(signal_number("sigusr1"), Some(10));
+        assert_eq!(signal_number("KILLALL"), None);
+        assert_eq!(signal_name(15), "SIGTERM");
+
+        //Signal 0 only checks that the own process exists
+        assert!(send_signal(std::process::id() as i32, 0).is_ok());
+    }
+
+    #[test]
+    fn test_filesystem_space() {
+        let space = filesystem_space(Path::new("/")).unwrap();
+        assert!(space.available_bytes <= space.total_bytes);
+        assert!(space.free_percent() <= 100);
+
+        assert!(filesystem_space(Path::new("/yalc/missing/path")).is_err());
+    }
+}
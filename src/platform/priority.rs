@@ -0,0 +1,130 @@
+//! Module for the scheduling priority of yalc
+//!
+//! Rotations and compressions of large logs compete with the services on
+//! the same machine. The CPU priority is lowered with setpriority and the
+//! I/O priority with the linux ioprio_set system call. Child processes like
+//! hooks and compressors inherit both priorities from yalc.
+//!
+use std::ffi::{c_int, c_long};
+use std::io::{self, ErrorKind};
+
+use crate::config::{IoClass, PriorityConfig};
+use crate::output;
+
+unsafe extern "C" {
+    fn setpriority(which: c_int, who: c_int, prio: c_int) -> c_int;
+    fn syscall(number: c_long, ...) -> c_long;
+}
+
+/// Value of 'PRIO_PROCESS' for setpriority
+const PRIO_PROCESS: c_int = 0;
+
+/// Value of 'IOPRIO_WHO_PROCESS' for ioprio_set
+const IOPRIO_WHO_PROCESS: c_int = 1;
+
+/// Level of the best-effort class when no level is configured
+const IO_DEFAULT_LEVEL: u8 = 4;
+
+/// Bit offset of the class within an I/O priority value
+const IOPRIO_CLASS_SHIFT: u32 = 13;
+
+/// Number of the ioprio_set system call, the numbers differ between the architectures
+#[cfg(target_arch = "x86_64")]
+const SYS_IOPRIO_SET: Option<c_long> = Some(251);
+
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+const SYS_IOPRIO_SET: Option<c_long> = Some(30);
+
+#[cfg(target_arch = "x86")]
+const SYS_IOPRIO_SET: Option<c_long> = Some(289);
+
+#[cfg(target_arch = "arm")]
+const SYS_IOPRIO_SET: Option<c_long> = Some(314);
+
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "x86",
+    target_arch = "arm"
+)))]
+const SYS_IOPRIO_SET: Option<c_long> = None;
+
+/// Get the I/O priority value of a class and a level like the kernel macro IOPRIO_PRIO_VALUE
+fn ioprio_value(class: IoClass, level: u8) -> c_int {
+    let class_number: c_int = match class {
+        IoClass::BestEffort => 2,
+        IoClass::Idle => 3,
+    };
+
+    (class_number << IOPRIO_CLASS_SHIFT) | c_int::from(level)
+}
+
+/// Set the nice value of the process, children inherit the value
+pub fn set_nice(nice: u8) -> Result<(), io::Error> {
+    //SAFETY: setpriority has no memory preconditions, the result is checked
+    let result: c_int = unsafe { setpriority(PRIO_PROCESS, 0, c_int::from(nice)) };
+
+    match result {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Set the I/O scheduling class and level of the process, the level is ignored for idle
+pub fn set_io_priority(class: IoClass, level: u8) -> Result<(), io::Error> {
+    let Some(sys_ioprio_set) = SYS_IOPRIO_SET else {
+        return Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "The I/O priority is not supported on this architecture",
+        ));
+    };
+
+    //SAFETY: ioprio_set only reads the integer arguments, the result is checked
+    let result: c_long = unsafe {
+        syscall(
+            sys_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            ioprio_value(class, level),
+        )
+    };
+
+    match result {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Apply the configured priorities, failures are reported as warnings.
+/// Unprivileged users can only lower the priorities of the process.
+pub fn apply_priority(config: &PriorityConfig) {
+    if let Some(nice) = config.nice
+        && let Err(e) = set_nice(nice)
+    {
+        output::warn(&format!(
+            "Warning: Failed to set the nice value {}: {}",
+            nice, e
+        ));
+    }
+
+    if let Some(class) = config.io_class
+        && let Err(e) = set_io_priority(class, config.io_level.unwrap_or(IO_DEFAULT_LEVEL))
+    {
+        output::warn(&format!(
+            "Warning: Failed to set the I/O priority {:?}: {}",
+            class, e
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ioprio_value() {
+        assert_eq!(ioprio_value(IoClass::BestEffort, 7), 0x4007);
+        assert_eq!(ioprio_value(IoClass::Idle, 0), 0x6000);
+    }
+}
@@ -0,0 +1,556 @@
+//! Module for resolving the configured file entries to concrete files
+//!
+//! The config can contain file paths with glob patterns and directories.
+//! These entries are expanded at run time, so new log files are picked up
+//! automatically. The resolved file tasks are the input for the cleanup.
+//!
+//! The directories of the glob and directory entries are also watched for
+//! files that no entry matches, like the new log file of a service whose
+//! name changed. These files are reported, so the gaps of the config are
+//! visible before they fill the disk.
+//!
+
+pub mod glob;
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::cleaner::compression::COMPRESSED_EXTENSIONS;
+use crate::cleaner::manifest::MANIFEST_SUFFIX;
+use crate::config::{Config, DirSource, FileOverrides, FileSource, GroupConfig, SignalTarget};
+use crate::output;
+
+/// Represents a single log file that is processed by a cleanup task
+#[derive(Debug, Clone)]
+pub struct FileTask {
+    /// Path of the concrete log file
+    pub path: PathBuf,
+
+    /// Directory for the rotated files, next to the log file when not set
+    pub olddir: Option<PathBuf>,
+
+    /// Name of the group with the shared settings and hooks of the file
+    pub group: Option<String>,
+
+    /// Process that is signaled after the file was rotated
+    pub signal: Option<SignalTarget>,
+
+    /// Processing order of the file inside of its group, higher priorities first
+    pub priority: i64,
+
+    /// Rotation settings of the file entry and its group that replace the global values
+    pub overrides: FileOverrides,
+}
+
+/// Represents a file in a watched directory that is matched by no entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnmanagedFile {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// Resolve all file paths of the config to a list of file tasks.
+/// Files that are matched by multiple entries are only added once.
+/// The tasks are ordered group by group, files without a group come first.
+pub fn resolve_tasks(config: &Config) -> Vec<FileTask> {
+    let mut tasks: Vec<FileTask> = Vec::new();
+    let mut known_paths: HashSet<PathBuf> = HashSet::new();
+
+    //Collect the paths of the simple file list and all file entries
+    let mut resolved_tasks: Vec<FileTask> = Vec::new();
+
+    for pattern in config.file_list.iter() {
+        resolved_tasks.extend(resolve_pattern(pattern).into_iter().map(|path| FileTask {
+            path,
+            olddir: None,
+            group: None,
+            signal: None,
+            priority: 0,
+            overrides: FileOverrides::default(),
+        }));
+    }
+
+    for entry in config.files.iter() {
+        let paths: Vec<PathBuf> = match &entry.source {
+            FileSource::Path(pattern) => resolve_pattern(pattern),
+            FileSource::Dir(source) => resolve_dir(source),
+        };
+
+        //The entry inherits all settings from its group that it does not set itself
+        let overrides: FileOverrides = match find_group(config, entry.group.as_deref()) {
+            Some(group) => entry.overrides.with_fallback(&group.overrides),
+            None => entry.overrides.clone(),
+        };
+
+        resolved_tasks.extend(paths.into_iter().map(|path| FileTask {
+            path,
+            olddir: entry.olddir.as_ref().map(PathBuf::from),
+            group: entry.group.clone(),
+            signal: entry.signal.clone(),
+            priority: entry.priority.unwrap_or(0),
+            overrides: overrides.clone(),
+        }));
+    }
+
+    //The paths of the groups come last, so file entries can refine single files of a group
+    for group in config.groups.iter() {
+        for pattern in group.paths.iter() {
+            resolved_tasks.extend(resolve_pattern(pattern).into_iter().map(|path| FileTask {
+                path,
+                olddir: None,
+                group: Some(group.name.clone()),
+                signal: None,
+                priority: 0,
+                overrides: group.overrides.clone(),
+            }));
+        }
+    }
+
+    //The first entry that matches a file defines the options of the task
+    for task in resolved_tasks {
+        if known_paths.insert(task.path.clone()) {
+            tasks.push(task);
+        }
+    }
+
+    //Only the files of the groups selected with '--group' are processed
+    if !config.selected_groups.is_empty() {
+        tasks.retain(|task| {
+            task.group
+                .as_ref()
+                .is_some_and(|group| config.selected_groups.contains(group))
+        });
+    }
+
+    //The sort is stable, so the files of a group keep the order of the config
+    tasks.sort_by_key(|task| {
+        task.group
+            .as_ref()
+            .and_then(|name| config.groups.iter().position(|g| &g.name == name))
+            .map_or(0, |position| position + 1)
+    });
+
+    tasks
+}
+
+/// Find the group with the name, None for files without a group
+fn find_group<'a>(config: &'a Config, name: Option<&str>) -> Option<&'a GroupConfig> {
+    let name: &str = name?;
+    config.groups.iter().find(|group| group.name == name)
+}
+
+/// Resolve a single configured path which can contain glob patterns.
+/// Paths without glob patterns are kept even if the file does not exist.
+fn resolve_pattern(pattern: &str) -> Vec<PathBuf> {
+    if !glob::is_glob_pattern(pattern) {
+        return vec![PathBuf::from(pattern)];
+    }
+
+    match glob::expand(pattern) {
+        Ok(mut paths) => {
+            //Directories and rotated files matched by the pattern are no log files
+            paths.retain(|path| path.is_file() && !is_rotation_artifact(path));
+            output::print_status(&format!(
+                "Pattern '{}' matched {} files",
+                pattern,
+                paths.len()
+            ));
+            paths
+        }
+        Err(e) => {
+            output::error(&format!("Failed to resolve pattern '{}': {}", pattern, e));
+            Vec::new()
+        }
+    }
+}
+
+/// Resolve a directory entry to all files that match the entry filters
+fn resolve_dir(source: &DirSource) -> Vec<PathBuf> {
+    match scan_dir(source) {
+        Ok(paths) => {
+            output::print_status(&format!(
+                "Directory '{}' contains {} files",
+                source.dir,
+                paths.len()
+            ));
+            paths
+        }
+        Err(e) => {
+            output::error(&format!("Failed to scan directory '{}': {}", source.dir, e));
+            Vec::new()
+        }
+    }
+}
+
+/// Find the files of at least the minimum size in the directories of the glob and directory
+/// entries that are matched by no entry. Rotated files, archives and manifests are not reported.
+pub fn find_unmanaged_files(
+    config: &Config,
+    tasks: &[FileTask],
+    min_size_bytes: u64,
+) -> Vec<UnmanagedFile> {
+    let managed_paths: HashSet<&Path> = tasks.iter().map(|task| task.path.as_path()).collect();
+    let managed_names: Vec<&OsStr> = tasks
+        .iter()
+        .filter_map(|task| task.path.file_name())
+        .collect();
+
+    let mut unmanaged_paths: BTreeSet<PathBuf> = BTreeSet::new();
+
+    for (dir, max_depth) in watched_dirs(config) {
+        let paths: Vec<PathBuf> = match walk_dir(&dir, max_depth) {
+            Ok(paths) => paths,
+            Err(e) => {
+                output::debug(&format!(
+                    "Failed to check directory '{}' for unmanaged files: {}",
+                    dir.display(),
+                    e
+                ));
+                continue;
+            }
+        };
+
+        unmanaged_paths.extend(paths.into_iter().filter(|path| {
+            !managed_paths.contains(path.as_path()) && !is_archive_file(path, &managed_names)
+        }));
+    }
+
+    unmanaged_paths
+        .into_iter()
+        .filter_map(|path| {
+            let size: u64 = fs::metadata(&path).ok()?.len();
+            (size >= min_size_bytes).then_some(UnmanagedFile { path, size })
+        })
+        .collect()
+}
+
+/// Get the directories of the glob and directory entries with their maximum scan depth
+fn watched_dirs(config: &Config) -> BTreeMap<PathBuf, u64> {
+    let mut dirs: BTreeMap<PathBuf, u64> = BTreeMap::new();
+    let mut add_dir = |dir: PathBuf, max_depth: u64| {
+        let depth: &mut u64 = dirs.entry(dir).or_insert(max_depth);
+        *depth = (*depth).max(max_depth);
+    };
+
+    let patterns = config
+        .file_list
+        .iter()
+        .chain(config.groups.iter().flat_map(|group| group.paths.iter()))
+        .chain(config.files.iter().filter_map(|entry| match &entry.source {
+            FileSource::Path(pattern) => Some(pattern),
+            FileSource::Dir(_) => None,
+        }));
+
+    //Only glob patterns watch their directory, a plain path selects a single file
+    for pattern in patterns.filter(|pattern| glob::is_glob_pattern(pattern)) {
+        let Some(parent) = Path::new(pattern).parent() else {
+            continue;
+        };
+        let parent: String = parent.display().to_string();
+
+        match glob::is_glob_pattern(&parent) {
+            true => glob::expand(&parent)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|path| path.is_dir())
+                .for_each(|dir| add_dir(dir, 1)),
+            false => add_dir(PathBuf::from(parent), 1),
+        }
+    }
+
+    for entry in config.files.iter() {
+        if let FileSource::Dir(source) = &entry.source {
+            add_dir(PathBuf::from(&source.dir), dir_max_depth(source));
+        }
+    }
+
+    dirs
+}
+
+/// Returns true for files that belong to the rotation of a log file, like
+/// 'app.log.2.gz', 'app.log-20240301.gz' or 'app.log.manifest.toml'
+fn is_archive_file(path: &Path, managed_names: &[&OsStr]) -> bool {
+    if is_rotation_artifact(path) {
+        return true;
+    }
+
+    let is_compressed: bool = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| COMPRESSED_EXTENSIONS.contains(&ext));
+
+    let file_name: &str = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+
+    is_compressed
+        || file_name.ends_with(MANIFEST_SUFFIX)
+        || managed_names.iter().any(|name| {
+            name.to_str().is_some_and(|name| {
+                file_name
+                    .strip_prefix(name)
+                    .is_some_and(|suffix| suffix.starts_with(['.', '-']))
+            })
+        })
+}
+
+/// Get the maximum scan depth of a dir source, files directly in the directory have the depth 1
+fn dir_max_depth(source: &DirSource) -> u64 {
+    match (source.recursive, source.max_depth) {
+        (false, _) => 1,
+        (true, Some(depth)) => depth,
+        (true, None) => u64::MAX,
+    }
+}
+
+/// Scan the directory of a dir source for matching files.
+fn scan_dir(source: &DirSource) -> Result<Vec<PathBuf>, io::Error> {
+    let mut paths: Vec<PathBuf> = walk_dir(Path::new(&source.dir), dir_max_depth(source))?;
+
+    paths.retain(|path| {
+        has_matching_extension(path, &source.extensions) && !is_rotation_artifact(path)
+    });
+
+    Ok(paths)
+}
+
+/// Get all files of a directory up to the maximum depth.
+///
+/// Symbolic links to directories are followed, but every directory is
+/// scanned at most once so symlink loops can not cause an endless scan.
+/// Sub directories that can not be read are logged and skipped. Paths are sorted.
+fn walk_dir(root_dir: &Path, max_depth: u64) -> Result<Vec<PathBuf>, io::Error> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut visited_dirs: HashSet<PathBuf> = HashSet::new();
+
+    //Directories that still have to be scanned with their depth
+    let mut pending_dirs: Vec<(PathBuf, u64)> = vec![(root_dir.to_path_buf(), 1)];
+    visited_dirs.insert(fs::canonicalize(root_dir)?);
+
+    while let Some((dir, depth)) = pending_dirs.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if depth == 1 => return Err(e),
+            Err(e) => {
+                output::warn(&format!("Skipping directory '{}': {}", dir.display(), e));
+                continue;
+            }
+        };
+
+        for entry in entries {
+            let path: PathBuf = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    output::warn(&format!("Skipping entry of '{}': {}", dir.display(), e));
+                    continue;
+                }
+            };
+
+            //Follow symbolic links to check the type of the target
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(_) => continue, //Skip broken symbolic links
+            };
+
+            if metadata.is_dir() {
+                if depth >= max_depth {
+                    continue;
+                }
+
+                match fs::canonicalize(&path) {
+                    Ok(canonical) => {
+                        if visited_dirs.insert(canonical) {
+                            pending_dirs.push((path, depth + 1));
+                        }
+                    }
+                    Err(e) => {
+                        output::warn(&format!("Skipping directory '{}': {}", path.display(), e))
+                    }
+                }
+            } else if metadata.is_file() {
+                paths.push(path);
+            }
+        }
+    }
+
+    paths.sort();
+    Ok(paths)
+}
+
+/// Returns true when the file has one of the extensions or the extension list is empty
+fn has_matching_extension(path: &Path, extensions: &[String]) -> bool {
+    if extensions.is_empty() {
+        return true;
+    }
+
+    match path.extension() {
+        Some(ext) => extensions.iter().any(|e| ext == e.as_str()),
+        None => false,
+    }
+}
+
+/// Returns true for files created by a previous rotation like 'app.log.2'
+/// or 'app.log.2.gz'. These files must never be rotated again as a new log file.
+fn is_rotation_artifact(path: &Path) -> bool {
+    let is_index = |ext: &str| !ext.is_empty() && ext.chars().all(|c| c.is_ascii_digit());
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if COMPRESSED_EXTENSIONS.contains(&ext) => {
+            //Check the index before the extension of the compressed archive
+            let stem: &Path = Path::new(path.file_stem().unwrap_or_default());
+            stem.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(is_index)
+        }
+        Some(ext) => is_index(ext),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform;
+    use std::env;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_is_rotation_artifact() {
+        assert!(is_rotation_artifact(Path::new("/var/log/app.log.0")));
+        assert!(is_rotation_artifact(Path::new("/var/log/app.log.12")));
+        assert!(!is_rotation_artifact(Path::new("/var/log/app.log")));
+        assert!(!is_rotation_artifact(Path::new("/var/log/app")));
+        assert!(is_rotation_artifact(Path::new("/var/log/app.log.3.gz")));
+        assert!(!is_rotation_artifact(Path::new("/var/log/app.tar.gz")));
+    }
+
+    #[test]
+    fn test_is_archive_file() {
+        let managed_names: Vec<&OsStr> = vec![OsStr::new("app.log")];
+
+        assert!(is_archive_file(
+            Path::new("/var/log/app.log-20240301"),
+            &managed_names
+        ));
+        assert!(is_archive_file(
+            Path::new("/var/log/app.log.manifest.toml"),
+            &managed_names
+        ));
+        assert!(is_archive_file(
+            Path::new("/var/log/old.tar.gz"),
+            &managed_names
+        ));
+        assert!(!is_archive_file(
+            Path::new("/var/log/app.logger"),
+            &managed_names
+        ));
+        assert!(!is_archive_file(
+            Path::new("/var/log/worker.out"),
+            &managed_names
+        ));
+    }
+
+    #[test]
+    fn test_scan_dir() {
+        let dir = env::temp_dir().join(format!("yalc_test_scan_dir_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+
+        fs::write(dir.join("top.log"), "").unwrap();
+        fs::write(dir.join("top.log.0"), "").unwrap();
+        fs::write(dir.join("notes.md"), "").unwrap();
+        fs::write(dir.join("a/mid.txt"), "").unwrap();
+        fs::write(dir.join("a/b/deep.log"), "").unwrap();
+
+        //Symlink loop back to the scanned directory
+        std::os::unix::fs::symlink(&dir, dir.join("a/loop")).unwrap();
+
+        let mut source = DirSource {
+            dir: dir.display().to_string(),
+            recursive: false,
+            extensions: vec!["log".to_string(), "txt".to_string()],
+            max_depth: None,
+        };
+
+        assert_eq!(scan_dir(&source).unwrap(), vec![dir.join("top.log")]);
+
+        source.recursive = true;
+        assert_eq!(
+            scan_dir(&source).unwrap(),
+            vec![
+                dir.join("a/b/deep.log"),
+                dir.join("a/mid.txt"),
+                dir.join("top.log"),
+            ]
+        );
+
+        source.max_depth = Some(2);
+        assert_eq!(
+            scan_dir(&source).unwrap(),
+            vec![dir.join("a/mid.txt"), dir.join("top.log")]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_skip_rotation_artifacts() {
+        let dir = env::temp_dir().join(format!("yalc_test_artifacts_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        for name in ["app.log", "app.log.0", "app.log.1.gz", "app.tar.gz"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let source = DirSource {
+            dir: dir.display().to_string(),
+            recursive: false,
+            extensions: vec![],
+            max_depth: None,
+        };
+
+        let expected: Vec<PathBuf> = vec![dir.join("app.log"), dir.join("app.tar.gz")];
+        assert_eq!(scan_dir(&source).unwrap(), expected);
+
+        let mut matched: Vec<PathBuf> = resolve_pattern(&format!("{}/app*", dir.display()));
+        matched.sort();
+        assert_eq!(matched, expected);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_dir_unreadable_subdir() {
+        //Root can read every directory, so the skip can not be tested
+        if platform::is_root_user() {
+            return;
+        }
+
+        let dir = env::temp_dir().join(format!("yalc_test_scan_locked_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("locked")).unwrap();
+        fs::write(dir.join("top.log"), "").unwrap();
+        fs::write(dir.join("locked/inner.log"), "").unwrap();
+        fs::set_permissions(dir.join("locked"), fs::Permissions::from_mode(0o000)).unwrap();
+
+        let source = DirSource {
+            dir: dir.display().to_string(),
+            recursive: true,
+            extensions: vec![],
+            max_depth: None,
+        };
+
+        //The unreadable directory is skipped, the other files are still found
+        let paths: Vec<PathBuf> = scan_dir(&source).unwrap();
+        assert!(paths.contains(&dir.join("top.log")));
+        assert!(!paths.contains(&dir.join("locked/inner.log")));
+
+        fs::set_permissions(dir.join("locked"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+//! Module for matching and expanding glob patterns
+//!
+//! Supports the wildcards '*' and '?' and character classes like '[a-z]'
+//! or '[!0-9]'. Wildcards never match the path separator '/', so every
+//! path component of a pattern is matched separately.
+//!
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Returns true when the pattern contains any glob wildcard chars
+pub fn is_glob_pattern(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// Returns true when the whole text is matched by the glob pattern
+pub fn matches(pattern: &str, text: &str) -> bool {
+    let pattern_chars: Vec<char> = pattern.chars().collect();
+    let text_chars: Vec<char> = text.chars().collect();
+
+    match_chars(&pattern_chars, &text_chars)
+}
+
+/// Match a char sequence against a pattern char sequence.
+///
+/// The last seen '*' wildcard is remembered, on a mismatch the
+/// wildcard consumes one more char and the matching continues.
+/// This results in a runtime of O(n*m) in the worst case.
+fn match_chars(pattern: &[char], text: &[char]) -> bool {
+    let mut p: usize = 0;
+    let mut t: usize = 0;
+
+    //Pattern index after the last star and text index where the star started
+    let mut last_star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                '*' => {
+                    last_star = Some((p + 1, t));
+                    p += 1;
+                    continue;
+                }
+                '?' if text[t] != '/' => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                '[' => {
+                    if let Some((is_match, class_len)) = match_class(&pattern[p..], text[t]) {
+                        if is_match {
+                            p += class_len;
+                            t += 1;
+                            continue;
+                        }
+                    } else if text[t] == '[' {
+                        //Unclosed classes are matched as literal char
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+                c if c == text[t] => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        //Mismatch, let the last star consume one more char
+        match last_star {
+            Some((star_p, star_t)) if text[star_t] != '/' => {
+                p = star_p;
+                t = star_t + 1;
+                last_star = Some((star_p, star_t + 1));
+            }
+            _ => return false,
+        }
+    }
+
+    //Remaining stars can match the empty sequence
+    while p < pattern.len() && pattern[p] == '*' {
+        p += 1;
+    }
+
+    p == pattern.len()
+}
+
+/// Match a single char against a character class like '[a-z]'.
+///
+/// # Returns
+/// - `Some((bool, usize))`: If the char matched and the length of the class in the pattern.
+/// - `None`: When the class is not closed by a ']' char.
+///
+fn match_class(pattern: &[char], c: char) -> Option<(bool, usize)> {
+    let mut idx: usize = 1;
+    let negated: bool = matches!(pattern.get(idx), Some('!') | Some('^'));
+
+    if negated {
+        idx += 1;
+    }
+
+    let mut is_match: bool = false;
+    let mut first: bool = true;
+
+    while let Some(&class_char) = pattern.get(idx) {
+        //A closing bracket directly at the start is a literal char
+        if class_char == ']' && !first {
+            let matched: bool = (is_match != negated) && c != '/';
+            return Some((matched, idx + 1));
+        }
+
+        //Check for a range like 'a-z'
+        if pattern.get(idx + 1) == Some(&'-') && pattern.get(idx + 2).is_some_and(|&e| e != ']') {
+            let range_end: char = pattern[idx + 2];
+
+            if class_char <= c && c <= range_end {
+                is_match = true;
+            }
+
+            idx += 3;
+        } else {
+            if class_char == c {
+                is_match = true;
+            }
+
+            idx += 1;
+        }
+
+        first = false;
+    }
+
+    None
+}
+
+/// Expand a glob pattern to all existing paths that match the pattern.
+///
+/// Each path component is matched against the directory entries of the
+/// previous components. Hidden entries are only matched when the pattern
+/// component starts with a dot. The resulting paths are sorted.
+pub fn expand(pattern: &str) -> Result<Vec<PathBuf>, io::Error> {
+    let components: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+
+    let root: PathBuf = if pattern.starts_with('/') {
+        PathBuf::from("/")
+    } else {
+        PathBuf::from(".")
+    };
+
+    let mut candidates: Vec<PathBuf> = vec![root];
+
+    for (idx_component, component) in components.iter().enumerate() {
+        let is_last: bool = idx_component == components.len() - 1;
+        let mut next_candidates: Vec<PathBuf> = Vec::new();
+
+        for candidate in candidates.iter() {
+            if !is_glob_pattern(component) {
+                //Literal components are simply appended
+                let path = candidate.join(component);
+
+                if path.exists() {
+                    next_candidates.push(path);
+                }
+                continue;
+            }
+
+            if !candidate.is_dir() {
+                continue;
+            }
+
+            for entry in fs::read_dir(candidate)? {
+                let entry = entry?;
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+
+                if name.starts_with('.') && !component.starts_with('.') {
+                    continue; //Skip hidden entries
+                }
+
+                if matches(component, &name) {
+                    next_candidates.push(entry.path());
+                }
+            }
+        }
+
+        //Only directories can contain the next path components
+        if !is_last {
+            next_candidates.retain(|p| p.is_dir());
+        }
+
+        candidates = next_candidates;
+    }
+
+    //Remove the leading './' of relative patterns
+    let mut paths: Vec<PathBuf> = candidates
+        .into_iter()
+        .map(|p| p.strip_prefix(".").map(PathBuf::from).unwrap_or(p))
+        .collect();
+
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_is_glob_pattern() {
+        assert!(is_glob_pattern("/var/log/*.log"));
+        assert!(is_glob_pattern("/var/log/app?.log"));
+        assert!(is_glob_pattern("/var/log/app[0-9].log"));
+        assert!(!is_glob_pattern("/var/log/app.log"));
+    }
+
+    #[test]
+    fn test_matches_wildcards() {
+        assert!(matches("*.log", "server.log"));
+        assert!(matches("*.log", ".log"));
+        assert!(matches("server*", "server.log"));
+        assert!(matches("s*r.l?g", "server.log"));
+        assert!(matches("*", ""));
+        assert!(!matches("*.log", "server.txt"));
+        assert!(!matches("?.log", "ab.log"));
+
+        //Wildcards never match the path separator
+        assert!(!matches("*.log", "app/server.log"));
+        assert!(matches("*/*.log", "app/server.log"));
+    }
+
+    #[test]
+    fn test_matches_classes() {
+        assert!(matches("app[0-9].log", "app1.log"));
+        assert!(matches("app[abc].log", "appb.log"));
+        assert!(!matches("app[abc].log", "appd.log"));
+        assert!(matches("app[!0-9].log", "appx.log"));
+        assert!(!matches("app[!0-9].log", "app5.log"));
+        assert!(matches("app[]].log", "app].log"));
+
+        //Unclosed classes are literal chars
+        assert!(matches("app[.log", "app[.log"));
+    }
+
+    #[test]
+    fn test_expand() {
+        let dir = env::temp_dir().join(format!("yalc_test_glob_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("app")).unwrap();
+        fs::create_dir_all(dir.join("db")).unwrap();
+
+        fs::write(dir.join("app/a.log"), "").unwrap();
+        fs::write(dir.join("app/b.log"), "").unwrap();
+        fs::write(dir.join("app/.hidden.log"), "").unwrap();
+        fs::write(dir.join("app/c.txt"), "").unwrap();
+        fs::write(dir.join("db/d.log"), "").unwrap();
+
+        let pattern = format!("{}/*/*.log", dir.display());
+        let paths = expand(&pattern).unwrap();
+
+        assert_eq!(
+            paths,
+            vec![
+                dir.join("app/a.log"),
+                dir.join("app/b.log"),
+                dir.join("db/d.log"),
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,345 @@
+//! Module for the persistent state of the managed files
+//!
+//! Some decisions depend on earlier runs, like the time of the last rotation
+//! of a file. Every run records the size and the action of each managed file,
+//! so later runs can compare against it. The state is stored as toml file and
+//! loaded at the start of every run. The file is replaced atomically when it
+//! is saved, so an interrupted run never leaves a partially written state
+//! behind.
+//!
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::toml_parser::{self, Table, TopLevelTable, Value};
+use crate::config::toml_writer;
+use crate::platform;
+
+/// State file of the root user
+const SYSTEM_STATE_FILE: &str = "/var/lib/yalc/state.toml";
+
+/// Name of the array of tables with the state of every file
+const FILE_KEY: &str = "file";
+
+/// Enum representing the result of the last run for a file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileAction {
+    /// The file was rotated
+    Rotated,
+
+    /// The file was removed, because no rotations are kept
+    Removed,
+
+    /// The file was trimmed in place
+    Trimmed,
+
+    /// The file was checked without changing it
+    Skipped,
+
+    /// The file did not exist
+    Missing,
+
+    /// The task of the file failed
+    Failed,
+}
+
+impl FileAction {
+    /// Name of the action in the state file
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileAction::Rotated => "rotated",
+            FileAction::Removed => "removed",
+            FileAction::Trimmed => "trimmed",
+            FileAction::Skipped => "skipped",
+            FileAction::Missing => "missing",
+            FileAction::Failed => "failed",
+        }
+    }
+
+    /// Get the action of a name from the state file, None for unknown names
+    pub fn from_name(name: &str) -> Option<FileAction> {
+        [
+            FileAction::Rotated,
+            FileAction::Removed,
+            FileAction::Trimmed,
+            FileAction::Skipped,
+            FileAction::Missing,
+            FileAction::Failed,
+        ]
+        .into_iter()
+        .find(|action| action.name() == name)
+    }
+}
+
+/// Represents the state of a single managed file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileState {
+    /// Unix time in seconds of the last rotation
+    pub last_rotation: Option<u64>,
+
+    /// Unix time in seconds of the last run that processed the file
+    pub last_seen: Option<u64>,
+
+    /// Size of the file in bytes before the last run changed it
+    pub last_size: Option<u64>,
+
+    /// Result of the last run for the file
+    pub last_action: Option<FileAction>,
+
+    /// Fingerprint of the file content directly after the last rotation
+    pub fingerprint: Option<String>,
+}
+
+/// Represents the loaded state of all managed files
+#[derive(Debug)]
+pub struct StateStore {
+    path: PathBuf,
+    files: BTreeMap<PathBuf, FileState>,
+
+    /// When set to true the state differs from the state file
+    changed: bool,
+}
+
+/// Get the default path of the state file.
+/// Users without root privileges store the state in their home directory.
+pub fn default_state_path() -> PathBuf {
+    if platform::is_root_user() {
+        return PathBuf::from(SYSTEM_STATE_FILE);
+    }
+
+    let lookup = |name: &str| env::var(name).ok().filter(|dir| !dir.is_empty());
+
+    match (lookup("XDG_STATE_HOME"), lookup("HOME")) {
+        (Some(state_dir), _) => Path::new(&state_dir).join("yalc/state.toml"),
+        (None, Some(home_dir)) => Path::new(&home_dir).join(".local/state/yalc/state.toml"),
+        (None, None) => PathBuf::from(SYSTEM_STATE_FILE),
+    }
+}
+
+/// Get the current unix time in seconds
+pub fn unix_time_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+impl StateStore {
+    /// Create an empty state that is saved to the path
+    pub fn new(path: &Path) -> StateStore {
+        StateStore {
+            path: path.to_path_buf(),
+            files: BTreeMap::new(),
+            changed: false,
+        }
+    }
+
+    /// Load the state file, a missing file results in an empty state
+    pub fn load(path: &Path) -> Result<StateStore, io::Error> {
+        let mut store = StateStore::new(path);
+
+        if !path.exists() {
+            return Ok(store);
+        }
+
+        let root: TopLevelTable = toml_parser::load_toml_table(path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Failed to load state file '{}': {}", path.display(), e),
+            )
+        })?;
+
+        let Some(Value::Array(entries)) = root.get(FILE_KEY) else {
+            return Ok(store);
+        };
+
+        for entry in entries.iter() {
+            if let Value::Table(table) = entry
+                && let Some(Value::String(file_path)) = table.get("path")
+            {
+                store
+                    .files
+                    .insert(PathBuf::from(file_path), parse_file_state(table));
+            }
+        }
+
+        Ok(store)
+    }
+
+    /// Get the path of the state file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the state of a file, None for files without a state
+    pub fn get(&self, file_path: &Path) -> Option<&FileState> {
+        self.files.get(file_path)
+    }
+
+    /// Store that the file was rotated at the given unix time
+    pub fn record_rotation(&mut self, file_path: &Path, time: u64) {
+        self.files
+            .entry(file_path.to_path_buf())
+            .or_default()
+            .last_rotation = Some(time);
+        self.changed = true;
+    }
+
+    /// Store the fingerprint of the file after a rotation, None for files that no longer exist
+    pub fn record_fingerprint(&mut self, file_path: &Path, fingerprint: Option<String>) {
+        self.files
+            .entry(file_path.to_path_buf())
+            .or_default()
+            .fingerprint = fingerprint;
+        self.changed = true;
+    }
+
+    /// Store the result of a run for the file, the size is None for missing files
+    pub fn record_run(&mut self, file_path: &Path, action: FileAction, size: Option<u64>) {
+        let state: &mut FileState = self.files.entry(file_path.to_path_buf()).or_default();
+        state.last_seen = Some(unix_time_now());
+        state.last_action = Some(action);
+
+        if size.is_some() {
+            state.last_size = size;
+        }
+
+        self.changed = true;
+    }
+
+    /// Returns true when the state must be saved
+    pub fn has_changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Write the state file, the parent directory is created when it is missing
+    pub fn save(&self) -> Result<(), io::Error> {
+        let entries: Vec<Value> = self
+            .files
+            .iter()
+            .map(|(file_path, state)| Value::Table(format_file_state(file_path, state)))
+            .collect();
+
+        let mut root: TopLevelTable = TopLevelTable::new();
+        root.insert(FILE_KEY.to_string(), Value::Array(entries));
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        //The state is written to a temporary file first, so it is replaced atomically
+        let tmp_path: PathBuf = self.path.with_extension("toml.tmp");
+        fs::write(&tmp_path, toml_writer::to_toml_string(&root))?;
+        fs::rename(&tmp_path, &self.path)
+    }
+}
+
+/// Read the state values of a file entry, invalid values are ignored
+fn parse_file_state(table: &Table) -> FileState {
+    let get_uint = |key: &str| match table.get(key) {
+        Some(Value::Integer(value)) => u64::try_from(*value).ok(),
+        _ => None,
+    };
+
+    let last_action: Option<FileAction> = match table.get("last_action") {
+        Some(Value::String(name)) => FileAction::from_name(name),
+        _ => None,
+    };
+
+    let fingerprint: Option<String> = match table.get("fingerprint") {
+        Some(Value::String(fingerprint)) => Some(fingerprint.clone()),
+        _ => None,
+    };
+
+    FileState {
+        last_rotation: get_uint("last_rotation"),
+        last_seen: get_uint("last_seen"),
+        last_size: get_uint("last_size"),
+        last_action,
+        fingerprint,
+    }
+}
+
+/// Convert the state of a file into a toml table
+fn format_file_state(file_path: &Path, state: &FileState) -> Table {
+    let mut table: Table = Table::new();
+    table.insert(
+        "path".to_string(),
+        Value::String(file_path.display().to_string()),
+    );
+
+    let uint_values = [
+        ("last_rotation", state.last_rotation),
+        ("last_seen", state.last_seen),
+        ("last_size", state.last_size),
+    ];
+
+    for (key, value) in uint_values {
+        if let Some(value) = value {
+            table.insert(
+                key.to_string(),
+                Value::Integer(i64::try_from(value).unwrap_or(i64::MAX)),
+            );
+        }
+    }
+
+    if let Some(action) = state.last_action {
+        table.insert(
+            "last_action".to_string(),
+            Value::String(action.name().to_string()),
+        );
+    }
+
+    if let Some(fingerprint) = &state.fingerprint {
+        table.insert(
+            "fingerprint".to_string(),
+            Value::String(fingerprint.clone()),
+        );
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_roundtrip() {
+        let dir = env::temp_dir().join(format!("yalc_test_state_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        //The parent directory is created with the first save
+        let state_path = dir.join("nested/state.toml");
+        let mut store = StateStore::load(&state_path).unwrap();
+        assert!(store.get(Path::new("/var/log/app.log")).is_none());
+
+        store.record_rotation(Path::new("/var/log/app.log"), 1700000000);
+        store.record_run(
+            Path::new("/var/log/app.log"),
+            FileAction::Rotated,
+            Some(512),
+        );
+        store.record_fingerprint(Path::new("/var/log/app.log"), Some("0-1-abc".to_string()));
+        store.record_run(Path::new("/var/log/gone.log"), FileAction::Missing, None);
+        store.save().unwrap();
+
+        let loaded = StateStore::load(&state_path).unwrap();
+        assert!(!loaded.has_changed());
+
+        let state = loaded.get(Path::new("/var/log/app.log")).unwrap();
+        assert_eq!(state.last_rotation, Some(1700000000));
+        assert_eq!(state.last_size, Some(512));
+        assert_eq!(state.last_action, Some(FileAction::Rotated));
+        assert!(state.last_seen.is_some());
+        assert_eq!(state.fingerprint.as_deref(), Some("0-1-abc"));
+
+        let state = loaded.get(Path::new("/var/log/gone.log")).unwrap();
+        assert_eq!(state.last_action, Some(FileAction::Missing));
+        assert_eq!(state.last_size, None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,527 @@
+//! Module for the persistent statistics of the runs
+//!
+//! The state only keeps the last run of every file. To answer questions
+//! about earlier runs, like how often a file was rotated or how fast it
+//! grows, every run appends its totals and the outcome of each file to a
+//! statistics file next to the state file. Each record is a single line:
+//!
+//! run 1709296200 1520 0 2 0 1048576
+//! file 1709296200 rotated 1048576 1048576 1210 /var/log/app.log
+//! file 1709296200 skipped 2048 0 3 /var/log/other.log
+//!
+//! A run line has the start time, the duration in milliseconds, the exit
+//! status, the executed and failed tasks and the freed bytes. The file
+//! lines of a run follow its run line with the start time of the run, the
+//! action, the size before the task, the freed bytes, the duration and the
+//! path. Once the file exceeds its maximum size, the oldest runs are
+//! removed, so the statistics never grow without bounds.
+//!
+//! The summary of a run compares every file with its previous recorded
+//! outcome, so a file that starts failing stands out of the repeated lines.
+//!
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::cleaner;
+use crate::cleaner::report::RunReport;
+use crate::config::Config;
+use crate::output;
+use crate::state::FileAction;
+
+/// First line of the statistics file with the version of the format
+const STATS_HEADER: &str = "# yalc stats v1";
+
+/// Mode of a newly created statistics file, only the owner and the group can read it
+const STATS_FILE_MODE: u32 = 0o640;
+
+/// Represents the recorded totals of a single run with its files
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    /// Unix time in seconds when the run was started
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub exit_code: u8,
+    pub tasks_executed: usize,
+    pub tasks_failure: usize,
+    pub bytes_freed: u64,
+    pub files: Vec<FileRecord>,
+}
+
+/// Represents the recorded outcome of a single file of a run
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileRecord {
+    pub path: PathBuf,
+    pub action: FileAction,
+
+    /// Size of the file in bytes before the task, None for a missing file
+    pub size: Option<u64>,
+
+    pub bytes_freed: u64,
+    pub duration_ms: u64,
+}
+
+/// Enum representing how the outcome of a file differs from its previous run
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ChangeKind {
+    /// The task of the file failed after a successful previous run or for a new file
+    NewlyFailing,
+
+    /// The task of the file succeeded after a failed previous run
+    Recovered,
+
+    /// The file was cleaned up after it was skipped in the previous run
+    StartedTriggering,
+
+    /// The file was skipped after it was cleaned up in the previous run
+    StoppedTriggering,
+}
+
+//Implement the Display trait
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name: &str = match self {
+            ChangeKind::NewlyFailing => "newly failing",
+            ChangeKind::Recovered => "recovered",
+            ChangeKind::StartedTriggering => "started triggering",
+            ChangeKind::StoppedTriggering => "stopped triggering",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Represents a file whose outcome differs from its previous run
+#[derive(Debug, PartialEq, Clone)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+}
+
+impl RunRecord {
+    /// Create the record of a run, files of a dry run are not recorded
+    pub fn from_report(report: &RunReport) -> RunRecord {
+        RunRecord {
+            started_at: report.started_at,
+            duration_ms: report.duration.as_millis() as u64,
+            exit_code: report.exit_code(),
+            tasks_executed: report.tasks_executed,
+            tasks_failure: report.tasks_failure,
+            bytes_freed: report.bytes_freed(),
+            files: report
+                .tasks
+                .iter()
+                .filter(|task| !task.dry_run)
+                .map(|task| FileRecord {
+                    path: task.path.clone(),
+                    action: match task.error {
+                        Some(_) => FileAction::Failed,
+                        None => task.action,
+                    },
+                    size: task.size,
+                    bytes_freed: task.bytes_freed,
+                    duration_ms: task.duration.as_millis() as u64,
+                })
+                .collect(),
+        }
+    }
+
+    /// Format the run line and the lines of its files
+    fn to_lines(&self) -> String {
+        let mut lines: String = format!(
+            "run {} {} {} {} {} {}\n",
+            self.started_at,
+            self.duration_ms,
+            self.exit_code,
+            self.tasks_executed,
+            self.tasks_failure,
+            self.bytes_freed
+        );
+
+        for file in self.files.iter() {
+            lines.push_str(&format!(
+                "file {} {} {} {} {} {}\n",
+                self.started_at,
+                file.action.name(),
+                file.size.map_or("-".to_string(), |size| size.to_string()),
+                file.bytes_freed,
+                file.duration_ms,
+                escape_path(&file.path.display().to_string())
+            ));
+        }
+
+        lines
+    }
+}
+
+/// Record the run in the statistics file of the config, a failure is logged and never fails the run.
+/// Dry runs change no file, so they are not recorded.
+pub fn record_run(config: &Config, report: &RunReport) {
+    if config.dry_run || config.stats_max_size_bytes == 0 {
+        return;
+    }
+
+    let path: PathBuf = stats_path(&cleaner::state_path(config));
+
+    if let Err(e) = append(
+        &path,
+        &RunRecord::from_report(report),
+        config.stats_max_size_bytes,
+    ) {
+        output::error(&format!(
+            "Failed to save stats file '{}': {}",
+            path.display(),
+            e
+        ));
+    }
+}
+
+/// Compare the files of the run with their previous recorded runs.
+/// Dry runs and configs without statistics have no changes, a failed read is logged.
+pub fn changes_since_previous(config: &Config, report: &RunReport) -> Vec<FileChange> {
+    if config.dry_run || config.stats_max_size_bytes == 0 {
+        return Vec::new();
+    }
+
+    let path: PathBuf = stats_path(&cleaner::state_path(config));
+
+    match load(&path) {
+        Ok(records) => compare_files(&records, &RunRecord::from_report(report)),
+        Err(e) => {
+            output::warn(&format!("Warning: {}", e));
+            Vec::new()
+        }
+    }
+}
+
+/// Compare every file of the current run with the last recorded run of the same file.
+/// Runs of selected groups only contain some files, so the runs of a file can be far apart.
+fn compare_files(records: &[RunRecord], current: &RunRecord) -> Vec<FileChange> {
+    let mut previous: HashMap<&Path, FileAction> = HashMap::new();
+
+    for file in records.iter().flat_map(|record| record.files.iter()) {
+        previous.insert(&file.path, file.action);
+    }
+
+    current
+        .files
+        .iter()
+        .filter_map(|file| {
+            let previous_action: Option<FileAction> = previous.get(file.path.as_path()).copied();
+
+            let kind: ChangeKind = match (previous_action, file.action) {
+                (Some(FileAction::Failed), FileAction::Failed) => return None,
+                (_, FileAction::Failed) => ChangeKind::NewlyFailing,
+                (Some(FileAction::Failed), _) => ChangeKind::Recovered,
+                (Some(previous_action), action) => {
+                    match (is_triggered(previous_action)?, is_triggered(action)?) {
+                        (false, true) => ChangeKind::StartedTriggering,
+                        (true, false) => ChangeKind::StoppedTriggering,
+                        _ => return None,
+                    }
+                }
+                (None, _) => return None,
+            };
+
+            Some(FileChange {
+                path: file.path.clone(),
+                kind,
+            })
+        })
+        .collect()
+}
+
+/// Returns true when the file was cleaned up, None for missing and failed files
+fn is_triggered(action: FileAction) -> Option<bool> {
+    match action {
+        FileAction::Rotated | FileAction::Removed | FileAction::Trimmed => Some(true),
+        FileAction::Skipped => Some(false),
+        FileAction::Missing | FileAction::Failed => None,
+    }
+}
+
+/// Get the path of the statistics file next to the state file like '/var/lib/yalc/state.stats'
+pub fn stats_path(state_path: &Path) -> PathBuf {
+    state_path.with_extension("stats")
+}
+
+/// Load all records of the statistics file in the order they were recorded.
+/// A missing file results in no records, invalid lines are ignored.
+pub fn load(path: &Path) -> Result<Vec<RunRecord>, io::Error> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(parse_records(&content)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(io::Error::new(
+            e.kind(),
+            format!("Failed to read stats file '{}': {}", path.display(), e),
+        )),
+    }
+}
+
+/// Append the record of a run, the oldest runs are removed once the file exceeds its maximum size
+pub fn append(path: &Path, record: &RunRecord, max_size_bytes: u64) -> Result<(), io::Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .mode(STATS_FILE_MODE)
+        .open(path)?;
+
+    let mut lines: String = String::new();
+    if file.metadata()?.len() == 0 {
+        lines.push_str(STATS_HEADER);
+        lines.push('\n');
+    }
+    lines.push_str(&record.to_lines());
+
+    //The run is written with a single append, so a concurrent reader never sees half a run
+    file.write_all(lines.as_bytes())?;
+
+    if file.metadata()?.len() > max_size_bytes {
+        compact(path, max_size_bytes)?;
+    }
+
+    Ok(())
+}
+
+/// Remove the oldest runs until the file is at most half of its maximum size.
+/// The margin avoids a rewrite of the whole file for every following run.
+fn compact(path: &Path, max_size_bytes: u64) -> Result<(), io::Error> {
+    let records: Vec<RunRecord> = load(path)?;
+    let mut kept_lines: Vec<String> = Vec::new();
+    let mut kept_bytes: u64 = (STATS_HEADER.len() + 1) as u64;
+
+    //The newest run is always kept, even when it exceeds the size on its own
+    for record in records.iter().rev() {
+        let lines: String = record.to_lines();
+        kept_bytes += lines.len() as u64;
+
+        if !kept_lines.is_empty() && kept_bytes > max_size_bytes / 2 {
+            break;
+        }
+        kept_lines.push(lines);
+    }
+
+    kept_lines.push(format!("{}\n", STATS_HEADER));
+    kept_lines.reverse();
+
+    //The statistics are written to a temporary file first, so they are replaced atomically
+    let tmp_path: PathBuf = path.with_extension("stats.tmp");
+    fs::write(&tmp_path, kept_lines.concat())?;
+    fs::rename(&tmp_path, path)
+}
+
+/// Parse the run lines and the following file lines of the statistics
+fn parse_records(content: &str) -> Vec<RunRecord> {
+    let mut records: Vec<RunRecord> = Vec::new();
+
+    for line in content.lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(values) = line.strip_prefix("run ") {
+            if let Some(record) = parse_run_line(values) {
+                records.push(record);
+            }
+        } else if let Some(values) = line.strip_prefix("file ")
+            && let Some(record) = records.last_mut()
+            && let Some((started_at, file)) = parse_file_line(values)
+            && started_at == record.started_at
+        {
+            record.files.push(file);
+        }
+    }
+
+    records
+}
+
+/// Parse the values of a run line, None for an invalid line
+fn parse_run_line(values: &str) -> Option<RunRecord> {
+    let values: Vec<&str> = values.split(' ').collect();
+    if values.len() != 6 {
+        return None;
+    }
+
+    Some(RunRecord {
+        started_at: values[0].parse().ok()?,
+        duration_ms: values[1].parse().ok()?,
+        exit_code: values[2].parse().ok()?,
+        tasks_executed: values[3].parse().ok()?,
+        tasks_failure: values[4].parse().ok()?,
+        bytes_freed: values[5].parse().ok()?,
+        files: Vec::new(),
+    })
+}
+
+/// Parse the values of a file line with the start time of its run, None for an invalid line
+fn parse_file_line(values: &str) -> Option<(u64, FileRecord)> {
+    //The path is the last value and can contain spaces
+    let values: Vec<&str> = values.splitn(6, ' ').collect();
+    if values.len() != 6 {
+        return None;
+    }
+
+    let size: Option<u64> = match values[2] {
+        "-" => None,
+        size => Some(size.parse().ok()?),
+    };
+
+    Some((
+        values[0].parse().ok()?,
+        FileRecord {
+            path: PathBuf::from(unescape_path(values[5])),
+            action: FileAction::from_name(values[1])?,
+            size,
+            bytes_freed: values[3].parse().ok()?,
+            duration_ms: values[4].parse().ok()?,
+        },
+    ))
+}
+
+/// Escape the backslashes and line breaks of a path, so every record stays on a single line
+fn escape_path(path: &str) -> String {
+    path.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Revert the escaping of a recorded path
+fn unescape_path(escaped: &str) -> String {
+    let mut path = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            path.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => path.push('\n'),
+            Some('r') => path.push('\r'),
+            Some(other) => path.push(other),
+            None => path.push('\\'),
+        }
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn run_record(started_at: u64) -> RunRecord {
+        RunRecord {
+            started_at,
+            duration_ms: 1520,
+            exit_code: 0,
+            tasks_executed: 2,
+            tasks_failure: 0,
+            bytes_freed: 1024,
+            files: vec![
+                FileRecord {
+                    path: PathBuf::from("/var/log/my app.log"),
+                    action: FileAction::Rotated,
+                    size: Some(1024),
+                    bytes_freed: 1024,
+                    duration_ms: 12,
+                },
+                FileRecord {
+                    path: PathBuf::from("/var/log/odd\\name\n.log"),
+                    action: FileAction::Missing,
+                    size: None,
+                    bytes_freed: 0,
+                    duration_ms: 0,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_parse_records() {
+        let record: RunRecord = run_record(1709296200);
+        let content: String = format!(
+            "{}\n{}run invalid\nfile 1 rotated 1 1 1 /var/log/orphan.log\n",
+            STATS_HEADER,
+            record.to_lines()
+        );
+
+        assert_eq!(parse_records(&content), vec![record]);
+    }
+
+    #[test]
+    fn test_append_with_retention() {
+        let dir = env::temp_dir().join(format!("yalc_test_stats_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let path: PathBuf = stats_path(&dir.join("state.toml"));
+        let record_size: u64 = run_record(0).to_lines().len() as u64;
+
+        for started_at in 0..10 {
+            append(&path, &run_record(started_at), 4 * record_size).unwrap();
+        }
+
+        //The file is compacted to half of its maximum size, the newest runs are kept
+        let records: Vec<RunRecord> = load(&path).unwrap();
+        assert!(fs::metadata(&path).unwrap().len() <= 4 * record_size);
+        assert_eq!(records.last().unwrap().started_at, 9);
+        assert!(records.len() < 4);
+
+        assert!(load(&dir.join("missing.stats")).unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_compare_files() {
+        let file = |path: &str, action: FileAction| FileRecord {
+            path: PathBuf::from(path),
+            action,
+            size: Some(1024),
+            bytes_freed: 0,
+            duration_ms: 0,
+        };
+
+        let mut first: RunRecord = run_record(1);
+        first.files = vec![
+            file("/var/log/a.log", FileAction::Skipped),
+            file("/var/log/b.log", FileAction::Rotated),
+            file("/var/log/c.log", FileAction::Failed),
+        ];
+
+        //The second run only contains a selected group
+        let mut second: RunRecord = run_record(2);
+        second.files = vec![file("/var/log/d.log", FileAction::Rotated)];
+
+        let mut current: RunRecord = run_record(3);
+        current.files = vec![
+            file("/var/log/a.log", FileAction::Rotated),
+            file("/var/log/b.log", FileAction::Skipped),
+            file("/var/log/c.log", FileAction::Skipped),
+            file("/var/log/d.log", FileAction::Failed),
+            file("/var/log/e.log", FileAction::Skipped),
+        ];
+
+        let kinds: Vec<ChangeKind> = compare_files(&[first, second], &current)
+            .into_iter()
+            .map(|change| change.kind)
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                ChangeKind::StartedTriggering,
+                ChangeKind::StoppedTriggering,
+                ChangeKind::Recovered,
+                ChangeKind::NewlyFailing
+            ]
+        );
+    }
+}